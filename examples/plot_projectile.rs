@@ -1,9 +1,8 @@
 use ray_tracer_challenge_2::canvas::Canvas;
 use ray_tracer_challenge_2::color::Color;
 use ray_tracer_challenge_2::space::{Point, Vector};
+use ray_tracer_challenge_2::Float;
 use std::error::Error;
-use std::fs::OpenOptions;
-use std::io::BufWriter;
 
 const OUTPUT_PATH: &str = "output/projectile.ppm";
 
@@ -19,11 +18,10 @@ struct Projectile {
     velocity: Vector,
 }
 
-fn tick(env: &Env, projectile: Projectile) -> Projectile {
-    Projectile {
-        position: projectile.position + projectile.velocity,
-        velocity: projectile.velocity + env.gravity + env.wind,
-    }
+fn tick(env: &Env, mut projectile: Projectile) -> Projectile {
+    projectile.position += projectile.velocity;
+    projectile.velocity += env.gravity + env.wind;
+    projectile
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
@@ -41,25 +39,17 @@ fn main() -> Result<(), Box<dyn Error>> {
     let mut canvas = Canvas::new(900, 550);
 
     while projectile.position.y() >= 1.0 {
-        let canvas_x = projectile.position.x().round() as usize;
-        let canvas_y = canvas.height - projectile.position.y().round() as usize;
+        // Points that leave the frame are clipped rather than wrapping round.
+        let canvas_x = projectile.position.x();
+        let canvas_y = canvas.height as Float - projectile.position.y();
 
-        canvas.plot_point(
-            &Point::new(canvas_x as f64, canvas_y as f64, 0.0),
-            &projectile_color,
-        );
+        canvas.plot_point(&Point::new(canvas_x, canvas_y, 0.0), &projectile_color);
 
         projectile = tick(&environment, projectile);
     }
 
-    let mut file = BufWriter::new(
-        OpenOptions::new()
-            .write(true)
-            .create(true)
-            .open(OUTPUT_PATH)?,
-    );
     println!("Writing file... {}", OUTPUT_PATH);
-    canvas.write_ppm(&mut file)?;
+    canvas.save(OUTPUT_PATH)?;
     println!("Done.");
     Ok(())
 }
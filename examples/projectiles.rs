@@ -13,11 +13,10 @@ struct Projectile {
     velocity: Vector,
 }
 
-fn tick(env: &Env, projectile: Projectile) -> Projectile {
-    Projectile {
-        position: projectile.position + projectile.velocity,
-        velocity: projectile.velocity + env.gravity + env.wind,
-    }
+fn tick(env: &Env, mut projectile: Projectile) -> Projectile {
+    projectile.position += projectile.velocity;
+    projectile.velocity += env.gravity + env.wind;
+    projectile
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
@@ -30,10 +29,10 @@ fn main() -> Result<(), Box<dyn Error>> {
         wind: Vector::new(-0.01, 0.0, 0.0),
     };
 
-    println!("{:?}", projectile.position);
+    println!("{:.3}", projectile.position);
     while projectile.position.y() >= 0.0 {
         projectile = tick(&environment, projectile);
-        println!("{:?}", projectile.position);
+        println!("{:.3}", projectile.position);
     }
 
     Ok(())
@@ -1,4 +1,4 @@
-use std::{error::Error, fs::OpenOptions, io::BufWriter, time::Instant};
+use std::{error::Error, time::Instant};
 
 use ray_tracer_challenge_2::{
     canvas::Canvas,
@@ -6,8 +6,9 @@ use ray_tracer_challenge_2::{
     ray::{Intersections, Ray},
     shape::{Shape, Sphere},
     space::Point,
+    Float,
 };
-//use ray_tracer_challenge_2::matrix::Matrix;
+//use ray_tracer_challenge_2::matrix4::Matrix4;
 
 const OUTPUT_PATH: &str = "output/projection.ppm";
 
@@ -17,20 +18,20 @@ fn main() -> Result<(), Box<dyn Error>> {
     let wall_size = 7.0;
 
     let canvas_pixels = 512;
-    let pixel_size = wall_size / canvas_pixels as f64;
+    let pixel_size = wall_size / canvas_pixels as Float;
     let half = wall_size / 2.0;
 
-    let mut canvas = Canvas::new(canvas_pixels as usize, canvas_pixels as usize);
+    let mut canvas = Canvas::new(canvas_pixels, canvas_pixels);
     let color = Color::new(1.0, 0.0, 0.0);
     let shape: Shape = Sphere::new().into();
-    //*shape.transformation() = Matrix::scaling(0.5, 1.0, 1.0);
+    //*shape.transformation() = Matrix4::scaling(0.5, 1.0, 1.0);
 
     let before = Instant::now();
 
     for y in 0..canvas_pixels {
-        let world_y = half - pixel_size * y as f64;
+        let world_y = half - pixel_size * y as Float;
         for x in 0..canvas_pixels {
-            let world_x = -half + pixel_size * x as f64;
+            let world_x = -half + pixel_size * x as Float;
 
             let position = Point::new(world_x, world_y, wall_z);
 
@@ -43,14 +44,8 @@ fn main() -> Result<(), Box<dyn Error>> {
         }
     }
     println!("Generated pixels in {:.2?}", before.elapsed());
-    let mut file = BufWriter::new(
-        OpenOptions::new()
-            .write(true)
-            .create(true)
-            .open(OUTPUT_PATH)?,
-    );
     println!("Writing file... {}", OUTPUT_PATH);
-    canvas.write_ppm(&mut file)?;
+    canvas.save(OUTPUT_PATH)?;
     println!("Done.");
 
     Ok(())
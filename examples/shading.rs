@@ -1,4 +1,4 @@
-use std::{error::Error, fs::OpenOptions, io::BufWriter, time::Instant};
+use std::{error::Error, time::Instant};
 
 use ray_tracer_challenge_2::{
     canvas::Canvas,
@@ -7,6 +7,7 @@ use ray_tracer_challenge_2::{
     ray::{Intersections, Ray},
     shape::{Shape, Sphere},
     space::Point,
+    Float,
 };
 
 const OUTPUT_PATH: &str = "output/shading.ppm";
@@ -17,10 +18,10 @@ fn main() -> Result<(), Box<dyn Error>> {
     let wall_size = 7.0;
 
     let canvas_pixels = 512;
-    let pixel_size = wall_size / canvas_pixels as f64;
+    let pixel_size = wall_size / canvas_pixels as Float;
     let half = wall_size / 2.0;
 
-    let mut canvas = Canvas::new(canvas_pixels as usize, canvas_pixels as usize);
+    let mut canvas = Canvas::new(canvas_pixels, canvas_pixels);
     let mut shape = Sphere::new();
     let material = shape.material_mut();
     material.ambient = 0.5;
@@ -35,9 +36,9 @@ fn main() -> Result<(), Box<dyn Error>> {
     let before = Instant::now();
 
     for y in 0..canvas_pixels {
-        let world_y = half - pixel_size * y as f64;
+        let world_y = half - pixel_size * y as Float;
         for x in 0..canvas_pixels {
-            let world_x = -half + pixel_size * x as f64;
+            let world_x = -half + pixel_size * x as Float;
 
             let position = Point::new(world_x, world_y, wall_z);
 
@@ -49,22 +50,18 @@ fn main() -> Result<(), Box<dyn Error>> {
             if let Some(hit) = is.hit() {
                 let point = r.position(hit.t);
                 let normal = shape.normal_at(&point);
-                let eye = r.direction * -1.0;
-                let color = shape.material().lighting(&light, &point, &eye, &normal);
+                let eye = -r.direction;
+                let color = shape
+                    .material()
+                    .lighting(&shape, &light, &point, &eye, &normal, false);
 
                 canvas.write_pixel(x, y, color)
             }
         }
     }
     println!("Generated pixels in {:.2?}", before.elapsed());
-    let mut file = BufWriter::new(
-        OpenOptions::new()
-            .write(true)
-            .create(true)
-            .open(OUTPUT_PATH)?,
-    );
     println!("Writing file... {}", OUTPUT_PATH);
-    canvas.write_ppm(&mut file)?;
+    canvas.save(OUTPUT_PATH)?;
     println!("Done.");
 
     Ok(())
@@ -0,0 +1,453 @@
+use std::fs;
+use std::io::{BufWriter, Error, ErrorKind, Result, Write};
+use std::path::Path;
+
+use crate::consts::TAU;
+
+use crate::camera::{Camera, RenderOptions};
+use crate::canvas::Canvas;
+use crate::color::Color;
+use crate::gif::write_animated_gif;
+use crate::materials::Material;
+use crate::matrix4::Matrix4;
+use crate::shape::Shape;
+use crate::space::{Point, Vector};
+use crate::world::World;
+use crate::Float;
+
+/// Values that can be blended between keyframes.
+pub trait Interpolate: Clone {
+    /// The value a fraction `t` of the way from `self`, at 0.0, to `other`,
+    /// at 1.0.
+    fn interpolate(&self, other: &Self, t: Float) -> Self;
+}
+
+impl Interpolate for Float {
+    fn interpolate(&self, other: &Self, t: Float) -> Self {
+        self + (other - self) * t
+    }
+}
+
+impl Interpolate for Color {
+    fn interpolate(&self, other: &Self, t: Float) -> Self {
+        self.lerp(*other, t)
+    }
+}
+
+impl Interpolate for Point {
+    fn interpolate(&self, other: &Self, t: Float) -> Self {
+        *self + (*other - *self) * t
+    }
+}
+
+impl Interpolate for Vector {
+    fn interpolate(&self, other: &Self, t: Float) -> Self {
+        *self + (*other - *self) * t
+    }
+}
+
+/// Matrices are blended element by element, like [`Motion`], so keyframes
+/// for a rotation need to be close together to stay rigid.
+///
+/// [`Motion`]: crate::shape::Motion
+impl Interpolate for Matrix4 {
+    fn interpolate(&self, other: &Self, t: Float) -> Self {
+        self.lerp(other, t)
+    }
+}
+
+/// Colors and the numeric properties are blended. Patterns can't be, so the
+/// earlier keyframe's pattern is kept until the later one is reached.
+impl Interpolate for Material {
+    fn interpolate(&self, other: &Self, t: Float) -> Self {
+        Self {
+            color: self.color.interpolate(&other.color, t),
+            ambient: self.ambient.interpolate(&other.ambient, t),
+            diffuse: self.diffuse.interpolate(&other.diffuse, t),
+            specular: self.specular.interpolate(&other.specular, t),
+            shininess: self.shininess.interpolate(&other.shininess, t),
+            reflective: self.reflective.interpolate(&other.reflective, t),
+            transparency: self.transparency.interpolate(&other.transparency, t),
+            refractive_index: self
+                .refractive_index
+                .interpolate(&other.refractive_index, t),
+            ..self.clone()
+        }
+    }
+}
+
+/// A value that changes over time, given at a number of key times and
+/// interpolated linearly between them. Before the first key and after the
+/// last the value holds steady.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Keyframes<T> {
+    keys: Vec<(Float, T)>,
+}
+
+impl<T: Interpolate> Keyframes<T> {
+    /// Panics if `keys` is empty.
+    pub fn new(keys: impl IntoIterator<Item = (Float, T)>) -> Self {
+        let mut keys: Vec<_> = keys.into_iter().collect();
+        assert!(!keys.is_empty(), "keyframes need at least one key");
+        keys.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+        Self { keys }
+    }
+
+    /// The time of the last key.
+    pub fn end(&self) -> Float {
+        self.keys[self.keys.len() - 1].0
+    }
+
+    pub fn value_at(&self, time: Float) -> T {
+        let next = self.keys.partition_point(|(t, _)| *t <= time);
+        if next == 0 {
+            return self.keys[0].1.clone();
+        }
+        if next == self.keys.len() {
+            return self.keys[next - 1].1.clone();
+        }
+
+        let (t0, from) = &self.keys[next - 1];
+        let (t1, to) = &self.keys[next];
+        from.interpolate(to, (time - t0) / (t1 - t0))
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Track {
+    Transform(String, Keyframes<Matrix4>),
+    Material(String, Keyframes<Material>),
+    CameraTransform(Keyframes<Matrix4>),
+    FieldOfView(Keyframes<Float>),
+}
+
+/// A set of keyframed changes to a scene: the transformations and materials
+/// of named objects and the camera's view. Objects are found by the names
+/// given with [`Shape::set_name`], searching inside groups.
+///
+/// [`Shape::set_name`]: crate::shape::Shape::set_name
+#[derive(Clone, Debug, Default)]
+pub struct Animation {
+    tracks: Vec<Track>,
+}
+
+impl Animation {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn animate_transform(&mut self, object: impl Into<String>, keys: Keyframes<Matrix4>) {
+        self.tracks.push(Track::Transform(object.into(), keys));
+    }
+
+    pub fn animate_material(&mut self, object: impl Into<String>, keys: Keyframes<Material>) {
+        self.tracks.push(Track::Material(object.into(), keys));
+    }
+
+    pub fn animate_camera(&mut self, keys: Keyframes<Matrix4>) {
+        self.tracks.push(Track::CameraTransform(keys));
+    }
+
+    pub fn animate_field_of_view(&mut self, keys: Keyframes<Float>) {
+        self.tracks.push(Track::FieldOfView(keys));
+    }
+
+    /// The time of the last key in any track, or 0.0 for an empty animation.
+    pub fn duration(&self) -> Float {
+        self.tracks
+            .iter()
+            .map(|track| match track {
+                Track::Transform(_, keys) | Track::CameraTransform(keys) => keys.end(),
+                Track::Material(_, keys) => keys.end(),
+                Track::FieldOfView(keys) => keys.end(),
+            })
+            .fold(0.0, Float::max)
+    }
+
+    /// Pose `world` and `camera` as they are at `time`.
+    ///
+    /// Panics if an animated object isn't in the world, or a transformation
+    /// isn't invertible.
+    pub fn apply(&self, time: Float, world: &mut World, camera: &mut Camera) {
+        for track in &self.tracks {
+            match track {
+                Track::Transform(name, keys) => animate_object(world, name, |object| {
+                    object.set_transform(keys.value_at(time))
+                }),
+                Track::Material(name, keys) => animate_object(world, name, |object| {
+                    *object.material_mut() = keys.value_at(time)
+                }),
+                Track::CameraTransform(keys) => camera.set_transform(keys.value_at(time)),
+                Track::FieldOfView(keys) => camera.set_field_of_view(keys.value_at(time)),
+            }
+        }
+    }
+
+    /// Render `frames` frames spread evenly over the animation, from time 0.0
+    /// to its [`Animation::duration`], saving them in `dir` as
+    /// `frame0000.png`, `frame0001.png` and so on. The directory is created
+    /// if need be.
+    pub fn render_sequence(
+        &self,
+        world: &World,
+        camera: &Camera,
+        frames: usize,
+        dir: impl AsRef<Path>,
+    ) -> Result<()> {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir)?;
+
+        let (mut world, mut camera) = (world.clone(), camera.clone());
+        let step = if frames > 1 {
+            self.duration() / (frames - 1) as Float
+        } else {
+            0.0
+        };
+        for frame in 0..frames {
+            self.apply(frame as Float * step, &mut world, &mut camera);
+            render(&camera, &world).save(dir.join(format!("frame{frame:04}.png")))?;
+        }
+        Ok(())
+    }
+}
+
+/// Render each of `frames` in the scene `scene` builds for it, saving them
+/// to files named by `pattern` in the format given by its extension, as for
+/// [`Canvas::save`]. The first run of `#` characters in the pattern is
+/// replaced by the frame number, padded with zeros to the same width, so
+/// `"out/frame_####.ppm"` names frame 1 `out/frame_0001.ppm`. Missing
+/// directories are created.
+///
+/// Fails with [`ErrorKind::InvalidInput`] if `pattern` has no `#`.
+pub fn render_frames(
+    frames: impl IntoIterator<Item = usize>,
+    mut scene: impl FnMut(usize) -> (World, Camera),
+    pattern: impl AsRef<str>,
+) -> Result<()> {
+    let pattern = pattern.as_ref();
+    let Some(start) = pattern.find('#') else {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("frame pattern has no # for the frame number: {pattern}"),
+        ));
+    };
+    let width = pattern[start..].bytes().take_while(|&b| b == b'#').count();
+    let (before, after) = (&pattern[..start], &pattern[start + width..]);
+
+    for frame in frames {
+        let path = format!("{before}{frame:0width$}{after}");
+        if let Some(dir) = Path::new(&path).parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let (world, camera) = scene(frame);
+        render(&camera, &world).save(&path)?;
+    }
+    Ok(())
+}
+
+impl Camera {
+    /// Render `frames` views with the camera circling the world's y axis
+    /// once, as if the scene were spinning on a turntable in front of it.
+    /// The first frame is the camera's own view.
+    pub fn render_turntable(&self, world: &World, frames: usize) -> Vec<Canvas> {
+        let mut camera = self.clone();
+        let view = *camera.transformation();
+        (0..frames)
+            .map(|frame| {
+                let angle = TAU * frame as Float / frames as Float;
+                camera.set_transform(view * Matrix4::rotation_y(angle));
+                render(&camera, world)
+            })
+            .collect()
+    }
+
+    /// Render a turntable, as for [`Camera::render_turntable`], and save it
+    /// to `path` as a looping animated GIF that takes `seconds` to turn once.
+    pub fn save_turntable_gif(
+        &self,
+        world: &World,
+        frames: usize,
+        seconds: Float,
+        path: impl AsRef<Path>,
+    ) -> Result<()> {
+        let images = self.render_turntable(world, frames);
+        // GIF delays are in hundredths of a second.
+        let delay = (seconds * 100.0 / frames as Float).round() as u16;
+        let mut file = BufWriter::new(fs::File::create(path)?);
+        write_animated_gif(&mut file, &images, delay)?;
+        file.flush()
+    }
+}
+
+/// Render a frame, in parallel if possible.
+fn render(camera: &Camera, world: &World) -> Canvas {
+    camera.render_with_options(world, &RenderOptions::default())
+}
+
+fn animate_object(world: &mut World, name: &str, update: impl FnOnce(&mut Shape)) {
+    if !world.update_object(name, update) {
+        panic!("no object named {name:?} to animate");
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::consts::PI;
+
+    use super::*;
+    use crate::fixtures::default_world;
+    use crate::shape::Sphere;
+
+    #[test]
+    fn test_keyframes_interpolate() {
+        let keys = Keyframes::new([(2.0, 10.0), (0.0, 0.0), (3.0, 10.0)]);
+        assert_eq!(keys.value_at(0.0), 0.0);
+        assert_eq!(keys.value_at(0.5), 2.5);
+        assert_eq!(keys.value_at(2.0), 10.0);
+        assert_eq!(keys.value_at(2.5), 10.0);
+        assert_eq!(keys.end(), 3.0);
+    }
+
+    #[test]
+    fn test_keyframes_hold_outside_keys() {
+        let keys = Keyframes::new([
+            (1.0, Color::new(1.0, 0.0, 0.0)),
+            (2.0, Color::new(0.0, 0.0, 1.0)),
+        ]);
+        assert_eq!(keys.value_at(-5.0), Color::new(1.0, 0.0, 0.0));
+        assert_eq!(keys.value_at(1.5), Color::new(0.5, 0.0, 0.5));
+        assert_eq!(keys.value_at(7.0), Color::new(0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_keyframes_need_a_key() {
+        Keyframes::<Float>::new([]);
+    }
+
+    #[test]
+    fn test_interpolate_material() {
+        let mut shiny = Material::new();
+        shiny.reflective = 1.0;
+        shiny.color = Color::new(0.0, 1.0, 0.0);
+        let m = Material::new().interpolate(&shiny, 0.25);
+        assert_eq!(m.reflective, 0.25);
+        assert_eq!(m.color, Color::new(0.75, 1.0, 0.75));
+        assert_eq!(m.diffuse, Material::new().diffuse);
+    }
+
+    #[test]
+    fn test_apply_animation() {
+        let mut world = default_world();
+        let mut ball: Shape = Sphere::new().into();
+        ball.set_name("ball");
+        world.add_object(ball);
+        let mut camera = Camera::new(11, 11, PI / 2.0);
+
+        let mut animation = Animation::new();
+        animation.animate_transform(
+            "ball",
+            Keyframes::new([
+                (0.0, Matrix4::identity()),
+                (2.0, Matrix4::translation(4.0, 0.0, 0.0)),
+            ]),
+        );
+        animation.animate_field_of_view(Keyframes::new([(0.0, PI / 2.0), (4.0, PI / 4.0)]));
+        assert_eq!(animation.duration(), 4.0);
+
+        animation.apply(1.0, &mut world, &mut camera);
+        let ball = world.find_object("ball").unwrap();
+        assert_eq!(ball.transformation(), &Matrix4::translation(2.0, 0.0, 0.0));
+        assert_eq!(camera.field_of_view(), 7.0 * PI / 16.0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_apply_animation_to_missing_object() {
+        let mut animation = Animation::new();
+        animation.animate_material("ghost", Keyframes::new([(0.0, Material::new())]));
+        animation.apply(0.0, &mut default_world(), &mut Camera::new(1, 1, PI / 2.0));
+    }
+
+    #[test]
+    fn test_render_turntable() {
+        let world = default_world();
+        let mut camera = Camera::new(5, 5, PI / 3.0);
+        camera.set_transform(Matrix4::translation(0.0, 0.0, -5.0));
+        let frames = camera.render_turntable(&world, 4);
+        assert_eq!(frames.len(), 4);
+        assert_eq!(frames[0], camera.render(&world));
+
+        let mut quarter = camera.clone();
+        quarter.set_transform(Matrix4::translation(0.0, 0.0, -5.0) * Matrix4::rotation_y(PI / 2.0));
+        assert_eq!(frames[1], quarter.render(&world));
+    }
+
+    #[test]
+    fn test_save_turntable_gif() {
+        let path = std::env::temp_dir().join(format!("turntable-{}.gif", std::process::id()));
+        let camera = Camera::new(4, 3, PI / 2.0);
+        camera
+            .save_turntable_gif(&default_world(), 3, 1.5, &path)
+            .unwrap();
+        let bytes = fs::read(&path).unwrap();
+        assert_eq!(&bytes[..6], b"GIF89a");
+        // Three frames of half a second each.
+        let delay = [0x21, 0xf9, 4, 0x04, 50, 0];
+        assert_eq!(bytes.windows(6).filter(|w| *w == delay).count(), 3);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_render_frames() {
+        let dir = std::env::temp_dir().join(format!("frames-{}", std::process::id()));
+        let pattern = dir.join("nested").join("shot_###.ppm");
+        let mut seen = vec![];
+        render_frames(
+            8..11,
+            |frame| {
+                seen.push(frame);
+                (default_world(), Camera::new(3, 2, PI / 2.0))
+            },
+            pattern.to_str().unwrap(),
+        )
+        .unwrap();
+        assert_eq!(seen, [8, 9, 10]);
+
+        let mut names: Vec<_> = fs::read_dir(dir.join("nested"))
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        names.sort();
+        assert_eq!(names, ["shot_008.ppm", "shot_009.ppm", "shot_010.ppm"]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_render_frames_needs_frame_number() {
+        let err = render_frames(0..1, |_| unreachable!(), "frame.ppm").unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn test_render_sequence() {
+        let dir = std::env::temp_dir().join(format!("animation-{}", std::process::id()));
+        let mut animation = Animation::new();
+        animation.animate_camera(Keyframes::new([
+            (0.0, Matrix4::translation(0.0, 0.0, 5.0)),
+            (1.0, Matrix4::translation(0.0, 0.0, 10.0)),
+        ]));
+        let camera = Camera::new(5, 5, PI / 2.0);
+        animation
+            .render_sequence(&default_world(), &camera, 3, &dir)
+            .unwrap();
+
+        let mut names: Vec<_> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        names.sort();
+        assert_eq!(names, ["frame0000.png", "frame0001.png", "frame0002.png"]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
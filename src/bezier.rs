@@ -0,0 +1,158 @@
+use crate::shape::{Group, Triangle};
+use crate::space::Point;
+use crate::Float;
+use crate::EPSILON;
+
+/// A bicubic Bézier patch: a curved surface shaped by a 4x4 grid of control
+/// points, passing through the four corner ones. Patches are rendered by
+/// tessellating them into triangles.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BezierPatch {
+    /// Indexed by row (along v), then column (along u).
+    pub control: [[Point; 4]; 4],
+}
+
+/// The cubic Bernstein polynomials at `t`, weighting each control point.
+fn bernstein(t: Float) -> [Float; 4] {
+    let s = 1.0 - t;
+    [s * s * s, 3.0 * t * s * s, 3.0 * t * t * s, t * t * t]
+}
+
+impl BezierPatch {
+    pub fn new(control: [[Point; 4]; 4]) -> Self {
+        Self { control }
+    }
+
+    /// The point on the surface at the parameters `u` and `v`, each from 0.0
+    /// to 1.0.
+    pub fn point_at(&self, u: Float, v: Float) -> Point {
+        let (bu, bv) = (bernstein(u), bernstein(v));
+        let (mut x, mut y, mut z) = (0.0, 0.0, 0.0);
+        for (row, weight_v) in self.control.iter().zip(bv) {
+            for (p, weight_u) in row.iter().zip(bu) {
+                let weight = weight_u * weight_v;
+                x += p.x() * weight;
+                y += p.y() * weight;
+                z += p.z() * weight;
+            }
+        }
+        Point::new(x, y, z)
+    }
+
+    /// Approximate the patch with `resolution` x `resolution` grid squares of
+    /// two triangles each. Triangles that collapse to a line or a point, as
+    /// they do where a patch's edge is pinched, are left out.
+    ///
+    /// Panics if `resolution` is zero.
+    pub fn tessellate(&self, resolution: usize) -> Group {
+        assert!(resolution > 0, "tessellation resolution must be at least 1");
+
+        let step = 1.0 / resolution as Float;
+        let grid: Vec<Vec<Point>> = (0..=resolution)
+            .map(|row| {
+                (0..=resolution)
+                    .map(|col| self.point_at(col as Float * step, row as Float * step))
+                    .collect()
+            })
+            .collect();
+
+        let mut group = Group::new();
+        for row in 0..resolution {
+            for col in 0..resolution {
+                let (a, b) = (grid[row][col], grid[row][col + 1]);
+                let (c, d) = (grid[row + 1][col], grid[row + 1][col + 1]);
+                for (p1, p2, p3) in [(a, b, d), (a, d, c)] {
+                    // Compare the area with the edges' lengths, so that fine
+                    // tessellations and small patches keep their triangles.
+                    let (e1, e2) = (p2 - p1, p3 - p1);
+                    if e1.cross(e2).magnitude() > EPSILON * e1.magnitude() * e2.magnitude() {
+                        group.add_child(Triangle::new(p1, p2, p3));
+                    }
+                }
+            }
+        }
+        group
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ray::{Intersections, Ray};
+    use crate::shape::Shape;
+    use crate::space::Vector;
+
+    /// A flat patch covering 0 to 3 in x and y.
+    fn flat_patch() -> BezierPatch {
+        let mut control = [[Point::origin(); 4]; 4];
+        for (row, points) in control.iter_mut().enumerate() {
+            for (col, p) in points.iter_mut().enumerate() {
+                *p = Point::new(col as Float, row as Float, 0.0);
+            }
+        }
+        BezierPatch::new(control)
+    }
+
+    #[test]
+    fn test_patch_passes_through_corners() {
+        let mut patch = flat_patch();
+        patch.control[1][1] = Point::new(1.0, 1.0, 5.0);
+        assert_eq!(patch.point_at(0.0, 0.0), Point::new(0.0, 0.0, 0.0));
+        assert_eq!(patch.point_at(1.0, 0.0), Point::new(3.0, 0.0, 0.0));
+        assert_eq!(patch.point_at(0.0, 1.0), Point::new(0.0, 3.0, 0.0));
+        assert_eq!(patch.point_at(1.0, 1.0), Point::new(3.0, 3.0, 0.0));
+    }
+
+    #[test]
+    fn test_flat_patch_point() {
+        let patch = flat_patch();
+        assert_eq!(patch.point_at(0.5, 0.5), Point::new(1.5, 1.5, 0.0));
+        assert_eq!(patch.point_at(0.25, 0.0), Point::new(0.75, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_raised_control_point_bends_surface() {
+        let mut patch = flat_patch();
+        for row in 1..3 {
+            for col in 1..3 {
+                patch.control[row][col] = Point::new(col as Float, row as Float, 4.0);
+            }
+        }
+        // Each inner point's weight is 3 * 0.5^3 = 0.375 per axis.
+        assert_eq!(patch.point_at(0.5, 0.5), Point::new(1.5, 1.5, 2.25));
+    }
+
+    #[test]
+    fn test_tessellate() {
+        let patch = flat_patch();
+        let mesh: Shape = patch.tessellate(4).into();
+        let Shape::Group(group) = &mesh else {
+            unreachable!()
+        };
+        assert_eq!(group.children().len(), 32);
+
+        let r = Ray::new(Point::new(1.0, 2.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let mut xs = Intersections::new();
+        mesh.intersect(&r, &mut xs);
+        assert_eq!(xs.hit().map(|i| i.t), Some(5.0));
+    }
+
+    #[test]
+    fn test_tessellate_skips_degenerate_triangles() {
+        let mut patch = flat_patch();
+        // Pinch the whole bottom edge to a single point.
+        patch.control[0] = [Point::new(1.5, 0.0, 0.0); 4];
+        let group = patch.tessellate(2);
+        assert_eq!(group.children().len(), 6);
+    }
+
+    #[test]
+    fn test_tessellate_small_patch() {
+        let mut patch = flat_patch();
+        for p in patch.control.iter_mut().flatten() {
+            *p = Point::new(p.x() * 0.001, p.y() * 0.001, 0.0);
+        }
+        assert_eq!(patch.tessellate(4).children().len(), 32);
+    }
+}
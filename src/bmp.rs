@@ -0,0 +1,106 @@
+use crate::canvas::Canvas;
+use crate::tonemap::OutputOptions;
+use std::io::{prelude::*, Result};
+
+/// The sizes of the file header and the BITMAPINFOHEADER that follows it.
+const FILE_HEADER_SIZE: u32 = 14;
+const INFO_HEADER_SIZE: u32 = 40;
+
+/// 72 DPI, in pixels per metre.
+const RESOLUTION: u32 = 2835;
+
+impl Canvas {
+    /// Write the canvas as an uncompressed 24-bit BMP image, clamping colors
+    /// to the range 0.0 to 1.0 as for PPM.
+    pub fn write_bmp(&self, sink: &mut impl Write) -> Result<()> {
+        self.write_bmp_with_options(sink, &OutputOptions::default())
+    }
+
+    pub fn write_bmp_with_options(
+        &self,
+        sink: &mut impl Write,
+        options: &OutputOptions,
+    ) -> Result<()> {
+        // Each row is padded to a multiple of four bytes.
+        let row_size = (self.width * 3).next_multiple_of(4);
+        let image_size = (row_size * self.height) as u32;
+        let offset = FILE_HEADER_SIZE + INFO_HEADER_SIZE;
+
+        let mut header = Vec::with_capacity(offset as usize);
+        header.extend_from_slice(b"BM");
+        header.extend_from_slice(&(offset + image_size).to_le_bytes());
+        header.extend_from_slice(&[0; 4]);
+        header.extend_from_slice(&offset.to_le_bytes());
+
+        header.extend_from_slice(&INFO_HEADER_SIZE.to_le_bytes());
+        header.extend_from_slice(&(self.width as i32).to_le_bytes());
+        // A positive height means rows are stored from the bottom up.
+        header.extend_from_slice(&(self.height as i32).to_le_bytes());
+        // One color plane, 24 bits per pixel, no compression.
+        header.extend_from_slice(&1_u16.to_le_bytes());
+        header.extend_from_slice(&24_u16.to_le_bytes());
+        header.extend_from_slice(&0_u32.to_le_bytes());
+        header.extend_from_slice(&image_size.to_le_bytes());
+        header.extend_from_slice(&RESOLUTION.to_le_bytes());
+        header.extend_from_slice(&RESOLUTION.to_le_bytes());
+        // No palette.
+        header.extend_from_slice(&[0; 8]);
+        sink.write_all(&header)?;
+
+        let canvas = self.for_output(options);
+        let mut bytes = Vec::with_capacity(row_size);
+        for row in (0..self.height).rev() {
+            bytes.clear();
+            for col in 0..self.width {
+                let (r, g, b) = canvas.pixel_at(col, row).to_u8();
+                bytes.extend_from_slice(&[b, g, r]);
+            }
+            bytes.resize(row_size, 0);
+            sink.write_all(&bytes)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::color::Color;
+
+    #[test]
+    fn test_bmp() {
+        let mut c = Canvas::new(3, 2);
+        c.write_pixel(0, 0, Color::new(1.0, 0.0, 0.2));
+        c.write_pixel(2, 1, Color::new(1.5, 0.5, -0.5));
+        let mut bytes = Vec::new();
+        c.write_bmp(&mut bytes).unwrap();
+
+        // Two rows of nine bytes, each padded to twelve.
+        assert_eq!(bytes.len(), 54 + 24);
+        assert_eq!(&bytes[0..2], b"BM");
+        assert_eq!(&bytes[2..6], &78_u32.to_le_bytes());
+        assert_eq!(&bytes[10..14], &54_u32.to_le_bytes());
+        assert_eq!(&bytes[14..18], &40_u32.to_le_bytes());
+        assert_eq!(&bytes[18..22], &3_i32.to_le_bytes());
+        assert_eq!(&bytes[22..26], &2_i32.to_le_bytes());
+        assert_eq!(&bytes[26..30], &[1, 0, 24, 0]);
+        assert_eq!(&bytes[34..38], &24_u32.to_le_bytes());
+
+        // The bottom row comes first, in blue, green, red order.
+        assert_eq!(&bytes[54..66], &[0, 0, 0, 0, 0, 0, 0, 128, 255, 0, 0, 0]);
+        assert_eq!(&bytes[66..78], &[51, 0, 255, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_bmp_exposure() {
+        let mut c = Canvas::new(1, 1);
+        c.write_pixel(0, 0, Color::new(2.0, 1.0, 0.0));
+        let mut bytes = Vec::new();
+        let options = OutputOptions {
+            exposure: -1.0,
+            ..OutputOptions::default()
+        };
+        c.write_bmp_with_options(&mut bytes, &options).unwrap();
+        assert_eq!(&bytes[54..57], &[0, 128, 255]);
+    }
+}
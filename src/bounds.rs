@@ -0,0 +1,339 @@
+use crate::matrix4::Matrix4;
+use crate::ray::{intersect_box, Ray};
+use crate::space::Point;
+use crate::Float;
+
+/// An axis-aligned box enclosing a shape, used to skip intersecting whole
+/// groups of shapes that a ray can't possibly hit.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BoundingBox {
+    pub min: Point,
+    pub max: Point,
+}
+
+impl BoundingBox {
+    pub fn new(min: Point, max: Point) -> Self {
+        Self { min, max }
+    }
+
+    /// A box containing nothing, which grows to fit whatever is added to it.
+    pub fn empty() -> Self {
+        Self::new(
+            Point::new(Float::INFINITY, Float::INFINITY, Float::INFINITY),
+            Point::new(
+                Float::NEG_INFINITY,
+                Float::NEG_INFINITY,
+                Float::NEG_INFINITY,
+            ),
+        )
+    }
+
+    /// A box containing everything, for shapes with no finite extent.
+    pub fn infinite() -> Self {
+        Self::new(
+            Point::new(
+                Float::NEG_INFINITY,
+                Float::NEG_INFINITY,
+                Float::NEG_INFINITY,
+            ),
+            Point::new(Float::INFINITY, Float::INFINITY, Float::INFINITY),
+        )
+    }
+
+    pub fn is_finite(&self) -> bool {
+        (0..3).all(|i| self.min.get(i).is_finite() && self.max.get(i).is_finite())
+    }
+
+    pub fn add_point(&mut self, point: &Point) {
+        self.min = Point::new(
+            self.min.x().min(point.x()),
+            self.min.y().min(point.y()),
+            self.min.z().min(point.z()),
+        );
+        self.max = Point::new(
+            self.max.x().max(point.x()),
+            self.max.y().max(point.y()),
+            self.max.z().max(point.z()),
+        );
+    }
+
+    pub fn add_box(&mut self, other: &BoundingBox) {
+        self.add_point(&other.min);
+        self.add_point(&other.max);
+    }
+
+    pub fn contains_point(&self, point: &Point) -> bool {
+        (0..3).all(|i| self.min.get(i) <= point.get(i) && point.get(i) <= self.max.get(i))
+    }
+
+    pub fn contains_box(&self, other: &BoundingBox) -> bool {
+        self.contains_point(&other.min) && self.contains_point(&other.max)
+    }
+
+    /// The box enclosing this one once transformed by `matrix`. Infinite
+    /// boxes stay infinite.
+    pub fn transform(&self, matrix: &Matrix4) -> BoundingBox {
+        if !self.is_finite() {
+            return *self;
+        }
+
+        let mut bounds = BoundingBox::empty();
+        for x in [self.min.x(), self.max.x()] {
+            for y in [self.min.y(), self.max.y()] {
+                for z in [self.min.z(), self.max.z()] {
+                    bounds.add_point(&(matrix * Point::new(x, y, z)));
+                }
+            }
+        }
+        bounds
+    }
+
+    /// Whether `ray` passes through the box.
+    pub fn intersects(&self, ray: &Ray) -> bool {
+        self.intersect(ray).is_some()
+    }
+
+    /// The distances along `ray` at which it enters and leaves the box, if it
+    /// passes through at all.
+    pub fn intersect(&self, ray: &Ray) -> Option<(Float, Float)> {
+        intersect_box(ray, &self.min, &self.max)
+    }
+
+    /// Cut the box in half across its longest axis.
+    pub fn split(&self) -> (BoundingBox, BoundingBox) {
+        let extent: Vec<Float> = (0..3).map(|i| self.max.get(i) - self.min.get(i)).collect();
+        let axis = if extent[0] >= extent[1] && extent[0] >= extent[2] {
+            0
+        } else if extent[1] >= extent[2] {
+            1
+        } else {
+            2
+        };
+
+        let mut mid_min = self.min.to_array();
+        let mut mid_max = self.max.to_array();
+        let middle = self.min.get(axis) + extent[axis] / 2.0;
+        mid_min[axis] = middle;
+        mid_max[axis] = middle;
+
+        (
+            BoundingBox::new(self.min, Point::from(mid_max)),
+            BoundingBox::new(Point::from(mid_min), self.max),
+        )
+    }
+
+    /// Cut the box in half across all three axes. Bit 0 of each octant's
+    /// index is set if it's in the upper half on the x axis, bit 1 for the y
+    /// axis and bit 2 for the z axis.
+    pub fn octants(&self) -> [BoundingBox; 8] {
+        let (min, max) = (self.min.to_array(), self.max.to_array());
+        std::array::from_fn(|octant| {
+            let (mut low, mut high) = (min, max);
+            for axis in 0..3 {
+                let middle = min[axis] + (max[axis] - min[axis]) / 2.0;
+                if octant & (1 << axis) == 0 {
+                    high[axis] = middle;
+                } else {
+                    low[axis] = middle;
+                }
+            }
+            BoundingBox::new(Point::from(low), Point::from(high))
+        })
+    }
+}
+
+impl Default for BoundingBox {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::consts::{PI, SQRT_2};
+
+    use super::*;
+    use crate::space::Vector;
+
+    #[test]
+    fn test_octants() {
+        let b = BoundingBox::new(Point::new(-1.0, 0.0, 2.0), Point::new(1.0, 4.0, 6.0));
+        let octants = b.octants();
+        assert_eq!(
+            octants[0],
+            BoundingBox::new(Point::new(-1.0, 0.0, 2.0), Point::new(0.0, 2.0, 4.0))
+        );
+        assert_eq!(
+            octants[0b101],
+            BoundingBox::new(Point::new(0.0, 0.0, 4.0), Point::new(1.0, 2.0, 6.0))
+        );
+        assert!(octants.iter().all(|octant| b.contains_box(octant)));
+    }
+
+    #[test]
+    fn test_empty_box() {
+        let b = BoundingBox::empty();
+        assert_eq!(b.min.x(), Float::INFINITY);
+        assert_eq!(b.max.z(), Float::NEG_INFINITY);
+        assert!(!b.contains_point(&Point::origin()));
+    }
+
+    #[test]
+    fn test_add_points() {
+        let mut b = BoundingBox::empty();
+        b.add_point(&Point::new(-5.0, 2.0, 0.0));
+        b.add_point(&Point::new(7.0, 0.0, -3.0));
+        assert_eq!(b.min, Point::new(-5.0, 0.0, -3.0));
+        assert_eq!(b.max, Point::new(7.0, 2.0, 0.0));
+    }
+
+    #[test]
+    fn test_add_box() {
+        let mut b1 = BoundingBox::new(Point::new(-5.0, -2.0, 0.0), Point::new(7.0, 4.0, 4.0));
+        let b2 = BoundingBox::new(Point::new(8.0, -7.0, -2.0), Point::new(14.0, 2.0, 8.0));
+        b1.add_box(&b2);
+        assert_eq!(b1.min, Point::new(-5.0, -7.0, -2.0));
+        assert_eq!(b1.max, Point::new(14.0, 4.0, 8.0));
+    }
+
+    #[test]
+    fn test_contains_point() {
+        let b = BoundingBox::new(Point::new(5.0, -2.0, 0.0), Point::new(11.0, 4.0, 7.0));
+        let cases = [
+            (Point::new(5.0, -2.0, 0.0), true),
+            (Point::new(11.0, 4.0, 7.0), true),
+            (Point::new(8.0, 1.0, 3.0), true),
+            (Point::new(3.0, 0.0, 3.0), false),
+            (Point::new(8.0, -4.0, 3.0), false),
+            (Point::new(8.0, 1.0, -1.0), false),
+            (Point::new(13.0, 1.0, 3.0), false),
+            (Point::new(8.0, 5.0, 3.0), false),
+            (Point::new(8.0, 1.0, 8.0), false),
+        ];
+        for (point, expected) in cases {
+            assert_eq!(b.contains_point(&point), expected, "{:?}", point);
+        }
+    }
+
+    #[test]
+    fn test_contains_box() {
+        let b = BoundingBox::new(Point::new(5.0, -2.0, 0.0), Point::new(11.0, 4.0, 7.0));
+        let cases = [
+            (Point::new(5.0, -2.0, 0.0), Point::new(11.0, 4.0, 7.0), true),
+            (Point::new(6.0, -1.0, 1.0), Point::new(10.0, 3.0, 6.0), true),
+            (
+                Point::new(4.0, -3.0, -1.0),
+                Point::new(10.0, 3.0, 6.0),
+                false,
+            ),
+            (
+                Point::new(6.0, -1.0, 1.0),
+                Point::new(12.0, 5.0, 8.0),
+                false,
+            ),
+        ];
+        for (min, max, expected) in cases {
+            assert_eq!(b.contains_box(&BoundingBox::new(min, max)), expected);
+        }
+    }
+
+    #[test]
+    fn test_transform_box() {
+        let b = BoundingBox::new(Point::new(-1.0, -1.0, -1.0), Point::new(1.0, 1.0, 1.0));
+        let m = Matrix4::rotation_x(PI / 4.0) * Matrix4::rotation_y(PI / 4.0);
+        let b2 = b.transform(&m);
+        assert_eq!(b2.min, Point::new(-SQRT_2, -1.70711, -1.70711));
+        assert_eq!(b2.max, Point::new(SQRT_2, 1.70711, 1.70711));
+    }
+
+    #[test]
+    fn test_transform_infinite_box() {
+        let b = BoundingBox::infinite().transform(&Matrix4::rotation_y(PI / 4.0));
+        assert_eq!(b.min.x(), Float::NEG_INFINITY);
+        assert_eq!(b.max.y(), Float::INFINITY);
+    }
+
+    #[test]
+    fn test_intersect_cubic_box() {
+        let b = BoundingBox::new(Point::new(-1.0, -1.0, -1.0), Point::new(1.0, 1.0, 1.0));
+        let cases = [
+            (Point::new(5.0, 0.5, 0.0), Vector::new(-1.0, 0.0, 0.0), true),
+            (Point::new(-5.0, 0.5, 0.0), Vector::new(1.0, 0.0, 0.0), true),
+            (Point::new(0.5, 5.0, 0.0), Vector::new(0.0, -1.0, 0.0), true),
+            (Point::new(0.5, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0), true),
+            (Point::new(0.0, 0.5, 0.0), Vector::new(0.0, 0.0, 1.0), true),
+            (
+                Point::new(-2.0, 0.0, 0.0),
+                Vector::new(2.0, 4.0, 6.0),
+                false,
+            ),
+            (
+                Point::new(0.0, -2.0, 0.0),
+                Vector::new(6.0, 2.0, 4.0),
+                false,
+            ),
+            (
+                Point::new(2.0, 0.0, 2.0),
+                Vector::new(0.0, 0.0, -1.0),
+                false,
+            ),
+            (
+                Point::new(2.0, 2.0, 0.0),
+                Vector::new(-1.0, 0.0, 0.0),
+                false,
+            ),
+        ];
+        for (origin, direction, expected) in cases {
+            let r = Ray::new(origin, direction.normalize());
+            assert_eq!(b.intersects(&r), expected, "{:?}", r);
+        }
+    }
+
+    #[test]
+    fn test_intersect_non_cubic_box() {
+        let b = BoundingBox::new(Point::new(5.0, -2.0, 0.0), Point::new(11.0, 4.0, 7.0));
+        let cases = [
+            (
+                Point::new(15.0, 1.0, 2.0),
+                Vector::new(-1.0, 0.0, 0.0),
+                true,
+            ),
+            (Point::new(7.0, 6.0, 5.0), Vector::new(0.0, -1.0, 0.0), true),
+            (
+                Point::new(9.0, -1.0, -8.0),
+                Vector::new(2.0, 4.0, 6.0),
+                false,
+            ),
+            (
+                Point::new(8.0, 2.0, 12.0),
+                Vector::new(0.0, 0.0, -1.0),
+                true,
+            ),
+            (
+                Point::new(12.0, 5.0, 4.0),
+                Vector::new(-1.0, 0.0, 0.0),
+                false,
+            ),
+        ];
+        for (origin, direction, expected) in cases {
+            let r = Ray::new(origin, direction.normalize());
+            assert_eq!(b.intersects(&r), expected, "{:?}", r);
+        }
+    }
+
+    #[test]
+    fn test_split() {
+        let b = BoundingBox::new(Point::new(-1.0, -4.0, -5.0), Point::new(9.0, 6.0, 5.0));
+        let (left, right) = b.split();
+        assert_eq!(left.min, Point::new(-1.0, -4.0, -5.0));
+        assert_eq!(left.max, Point::new(4.0, 6.0, 5.0));
+        assert_eq!(right.min, Point::new(4.0, -4.0, -5.0));
+        assert_eq!(right.max, Point::new(9.0, 6.0, 5.0));
+
+        let b = BoundingBox::new(Point::new(-1.0, -2.0, -3.0), Point::new(5.0, 3.0, 7.0));
+        let (left, right) = b.split();
+        assert_eq!(left.max, Point::new(5.0, 3.0, 2.0));
+        assert_eq!(right.min, Point::new(-1.0, -2.0, 2.0));
+    }
+}
@@ -0,0 +1,841 @@
+use crate::consts::PI;
+
+use crate::canvas::Canvas;
+use crate::color::Color;
+use crate::error::{MatrixError, Result};
+use crate::matrix4::Matrix4;
+use crate::ray::{Intersections, Ray};
+use crate::sampler::{pixel_key, Sampler, Sequence};
+use crate::space::Point;
+use crate::tile::Tile;
+use crate::world::World;
+use crate::Float;
+
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+/// How the camera maps pixels to ray directions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Projection {
+    /// A pinhole camera projecting onto a flat canvas, as in the book.
+    #[default]
+    Perspective,
+    /// An equidistant fisheye: a ray's angle from the view direction grows in
+    /// proportion to its pixel's distance from the image center, with the
+    /// field of view spanning the longer side of the image. Fields of view
+    /// beyond 180 degrees see behind the camera.
+    Fisheye,
+    /// A 360 degree panorama, with longitude across the image and latitude
+    /// down it. The field of view is ignored. The layout matches
+    /// [`UvMapping::Spherical`], so a render from the origin can be used
+    /// directly as a skybox texture; this puts the +z axis, behind the
+    /// default view direction, at the center of the image.
+    ///
+    /// [`UvMapping::Spherical`]: crate::uv::UvMapping::Spherical
+    Equirectangular,
+}
+
+/// Options for [`Camera::render_with_options`].
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct RenderOptions {
+    /// How many threads to trace rays on. `None` uses rayon's global pool,
+    /// normally one thread per CPU, and `Some(1)` renders on the calling
+    /// thread, which can make debugging easier. Without the `rayon` feature
+    /// rendering is always single-threaded.
+    pub threads: Option<usize>,
+}
+
+/// A camera's transformation, with its inverse cached as it's needed for
+/// every ray. It's serialized as just the transformation.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "Matrix4", from = "Matrix4")
+)]
+struct View {
+    transformation: Matrix4,
+    /// `None` if the transformation is singular.
+    inverse: Option<Matrix4>,
+}
+
+impl From<Matrix4> for View {
+    fn from(transformation: Matrix4) -> Self {
+        Self {
+            transformation,
+            inverse: transformation.inverse().ok(),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<View> for Matrix4 {
+    fn from(value: View) -> Self {
+        value.transformation
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Camera {
+    hsize: usize,
+    vsize: usize,
+    field_of_view: Float,
+    #[cfg_attr(feature = "serde", serde(rename = "transformation"))]
+    view: View,
+    half_width: Float,
+    half_height: Float,
+    pixel_size: Float,
+    sampler: Sampler,
+    samples: usize,
+    sequence: Sequence,
+    shutter: (Float, Float),
+    projection: Projection,
+}
+
+impl Camera {
+    pub fn new(hsize: usize, vsize: usize, field_of_view: Float) -> Self {
+        let mut camera = Self {
+            hsize,
+            vsize,
+            field_of_view,
+            view: Matrix4::identity().into(),
+            half_width: 0.0,
+            half_height: 0.0,
+            pixel_size: 0.0,
+            sampler: Sampler::default(),
+            samples: 1,
+            sequence: Sequence::default(),
+            shutter: (0.0, 0.0),
+            projection: Projection::default(),
+        };
+        camera.set_field_of_view(field_of_view);
+        camera
+    }
+
+    pub fn hsize(&self) -> usize {
+        self.hsize
+    }
+
+    pub fn vsize(&self) -> usize {
+        self.vsize
+    }
+
+    pub fn field_of_view(&self) -> Float {
+        self.field_of_view
+    }
+
+    pub fn set_field_of_view(&mut self, field_of_view: Float) {
+        let half_view = (field_of_view / 2.0).tan();
+        let aspect = self.hsize as Float / self.vsize as Float;
+        let (half_width, half_height) = if aspect >= 1.0 {
+            (half_view, half_view / aspect)
+        } else {
+            (half_view * aspect, half_view)
+        };
+
+        self.field_of_view = field_of_view;
+        self.half_width = half_width;
+        self.half_height = half_height;
+        self.pixel_size = half_width * 2.0 / self.hsize as Float;
+    }
+
+    pub fn transformation(&self) -> &Matrix4 {
+        &self.view.transformation
+    }
+
+    /// Set the camera's transformation, precomputing its inverse. Rendering
+    /// panics if it isn't invertible, but see [`Camera::try_render`].
+    pub fn set_transform(&mut self, transformation: Matrix4) {
+        self.view = transformation.into();
+    }
+
+    pub fn sampler(&self) -> Sampler {
+        self.sampler
+    }
+
+    pub fn set_sampler(&mut self, sampler: Sampler) {
+        self.sampler = sampler;
+    }
+
+    /// The number of samples taken along each axis of a pixel, so each pixel
+    /// is the average of `samples * samples` rays.
+    pub fn samples(&self) -> usize {
+        self.samples
+    }
+
+    pub fn set_samples(&mut self, samples: usize) {
+        assert!(samples > 0, "samples must be positive");
+        self.samples = samples;
+    }
+
+    pub fn sequence(&self) -> &Sequence {
+        &self.sequence
+    }
+
+    /// Set where the sampler's random choices come from. Each pixel gets its
+    /// own [fork](Sequence::fork) of `sequence`, so changing the seed of a
+    /// random sequence gives a different but repeatable pattern of noise.
+    pub fn set_sequence(&mut self, sequence: Sequence) {
+        self.sequence = sequence;
+    }
+
+    /// The times the shutter opens and closes. Rays are cast at times spread
+    /// across this interval, blurring any shapes with [`Motion`].
+    ///
+    /// [`Motion`]: crate::shape::Motion
+    pub fn shutter(&self) -> (Float, Float) {
+        self.shutter
+    }
+
+    pub fn set_shutter(&mut self, open: Float, close: Float) {
+        self.shutter = (open, close);
+    }
+
+    pub fn projection(&self) -> Projection {
+        self.projection
+    }
+
+    pub fn set_projection(&mut self, projection: Projection) {
+        self.projection = projection;
+    }
+
+    /// Panics if the camera's transformation isn't invertible.
+    fn inverse_transformation(&self) -> &Matrix4 {
+        self.view
+            .inverse
+            .as_ref()
+            .expect("camera transformation must be invertible")
+    }
+
+    /// The ray through the center of the pixel at (`px`, `py`), cast as the
+    /// shutter opens.
+    pub fn ray_for_pixel(&self, px: usize, py: usize) -> Ray {
+        self.ray_at(px as Float + 0.5, py as Float + 0.5)
+    }
+
+    /// The ray through the point (`x`, `y`) on the canvas, measured in pixels
+    /// from its top left corner, cast as the shutter opens. The center of
+    /// the pixel at (`px`, `py`) is at (`px + 0.5`, `py + 0.5`).
+    pub fn ray_at(&self, x: Float, y: Float) -> Ray {
+        self.ray_through(x, y, self.shutter.0)
+    }
+
+    /// The rays the camera averages for the pixel at (`px`, `py`), placed by
+    /// its sampler and spread over the time the shutter is open.
+    pub fn rays_for_pixel(&self, px: usize, py: usize) -> Vec<Ray> {
+        let mut rays = vec![];
+        self.for_each_ray_for_pixel(px, py, |ray| rays.push(ray));
+        rays
+    }
+
+    /// Call `visit` with each of [`Camera::rays_for_pixel`] in turn, without
+    /// collecting them.
+    fn for_each_ray_for_pixel(&self, px: usize, py: usize, mut visit: impl FnMut(Ray)) {
+        let key = pixel_key(px, py);
+        let offsets = self
+            .sampler
+            .offsets_from(self.samples, &mut self.sequence.fork(key));
+        // Fork differently for the times, so time and position aren't linked.
+        let times = self
+            .sampler
+            .times_from(self.samples, &mut self.sequence.fork(!key));
+        let (open, close) = self.shutter;
+        for ((dx, dy), t) in offsets.iter().zip(times) {
+            let time = open + (close - open) * t;
+            visit(self.ray_through(px as Float + dx, py as Float + dy, time));
+        }
+    }
+
+    /// The ray through the point (`x`, `y`) on the canvas, measured in pixels
+    /// from its top left corner, cast at `time`.
+    fn ray_through(&self, x: Float, y: Float, time: Float) -> Ray {
+        let target = match self.projection {
+            Projection::Perspective => self.perspective_target(x, y),
+            Projection::Fisheye => self.fisheye_target(x, y),
+            Projection::Equirectangular => self.equirectangular_target(x, y),
+        };
+
+        // Transform the target point and the origin, then compute the ray's
+        // direction vector.
+        let inverse = self.inverse_transformation();
+        let pixel = inverse * target;
+        let origin = inverse * Point::origin();
+        let direction = (pixel - origin).normalize();
+
+        Ray::with_time(origin, direction, time)
+    }
+
+    /// The untransformed point the ray through (`x`, `y`) passes through, on
+    /// a canvas at z = -1.
+    fn perspective_target(&self, x: Float, y: Float) -> Point {
+        // Offset from the edge of the canvas to the point
+        let xoffset = x * self.pixel_size;
+        let yoffset = y * self.pixel_size;
+
+        // Untransformed coordinates of the pixel in world space. The camera
+        // looks toward -z, so +x is to the *left*.
+        let world_x = self.half_width - xoffset;
+        let world_y = self.half_height - yoffset;
+        Point::new(world_x, world_y, -1.0)
+    }
+
+    /// The untransformed point one unit from the origin in the direction of
+    /// the fisheye ray through (`x`, `y`).
+    fn fisheye_target(&self, x: Float, y: Float) -> Point {
+        let angle_per_pixel = self.field_of_view / usize::max(self.hsize, self.vsize) as Float;
+
+        // Angular offsets from the view direction, with +x to the left as for
+        // the perspective projection.
+        let ax = (self.hsize as Float / 2.0 - x) * angle_per_pixel;
+        let ay = (self.vsize as Float / 2.0 - y) * angle_per_pixel;
+        let theta = (ax * ax + ay * ay).sqrt();
+        if theta == 0.0 {
+            return Point::new(0.0, 0.0, -1.0);
+        }
+
+        let s = theta.sin() / theta;
+        Point::new(ax * s, ay * s, -theta.cos())
+    }
+
+    /// The untransformed point one unit from the origin in the direction of
+    /// the panoramic ray through (`x`, `y`): the inverse of
+    /// [`spherical_map`](crate::uv::spherical_map).
+    fn equirectangular_target(&self, x: Float, y: Float) -> Point {
+        let u = x / self.hsize as Float;
+        let v = y / self.vsize as Float;
+
+        // Angle around the y axis, measured from +z, and down from +y.
+        let theta = PI * (1.0 - 2.0 * u);
+        let phi = PI * v;
+        Point::new(phi.sin() * theta.sin(), phi.cos(), phi.sin() * theta.cos())
+    }
+
+    /// The color of the pixel at (`px`, `py`): the average of the samples
+    /// placed by the camera's sampler.
+    pub(crate) fn color_at_pixel(&self, world: &World, px: usize, py: usize) -> Color {
+        let mut xs = Intersections::new();
+        let mut total = Color::new(0.0, 0.0, 0.0);
+        let mut count = 0;
+        self.for_each_ray_for_pixel(px, py, |ray| {
+            total += world.color_at_with(&ray, &mut xs);
+            count += 1;
+        });
+        total / count as Float
+    }
+
+    /// Render `world` as for [`Camera::render_with_options`], but fail with
+    /// [`MatrixError::Singular`] if the camera's transformation can't be
+    /// inverted rather than panicking partway through.
+    ///
+    /// [`MatrixError::Singular`]: crate::error::MatrixError::Singular
+    pub fn try_render(&self, world: &World) -> Result<Canvas> {
+        if self.view.inverse.is_none() {
+            return Err(MatrixError::Singular.into());
+        }
+        Ok(self.render_with_options(world, &RenderOptions::default()))
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub fn render(&self, world: &World) -> Canvas {
+        let mut image = Canvas::new(self.hsize, self.vsize);
+        for y in 0..self.vsize {
+            for x in 0..self.hsize {
+                image.write_pixel(x, y, self.color_at_pixel(world, x, y));
+            }
+        }
+        image
+    }
+
+    /// Render `world` with each row of the image traced on a separate rayon
+    /// task. Produces the same image as [`Camera::render`].
+    #[cfg(feature = "rayon")]
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub fn render_parallel(&self, world: &World) -> Canvas {
+        let mut image = Canvas::new(self.hsize, self.vsize);
+        image.par_rows_mut().enumerate().for_each(|(y, row)| {
+            for (x, pixel) in row.iter_mut().enumerate() {
+                *pixel = self.color_at_pixel(world, x, y);
+            }
+        });
+        image
+    }
+
+    /// Render `world`, in parallel if possible, as configured by `options`.
+    /// Produces the same image as [`Camera::render`] whatever the number of
+    /// threads.
+    ///
+    /// Panics if a thread pool of the requested size can't be started.
+    pub fn render_with_options(&self, world: &World, options: &RenderOptions) -> Canvas {
+        #[cfg(feature = "rayon")]
+        match options.threads {
+            None => self.render_parallel(world),
+            Some(1) => self.render(world),
+            Some(threads) => rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .expect("couldn't start render threads")
+                .install(|| self.render_parallel(world)),
+        }
+        #[cfg(not(feature = "rayon"))]
+        {
+            let _ = options;
+            self.render(world)
+        }
+    }
+
+    /// Render just the pixels covered by `tile`, as a tile-sized canvas.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip(self, world))
+    )]
+    pub fn render_tile(&self, world: &World, tile: &Tile) -> Canvas {
+        let mut image = Canvas::new(tile.width, tile.height);
+        for (x, y) in tile.pixels() {
+            image.write_pixel(x - tile.x, y - tile.y, self.color_at_pixel(world, x, y));
+        }
+        image
+    }
+
+    /// Render `world` one `tile_size` square block at a time, calling
+    /// `on_tile` as each block is finished so partial results can be shown
+    /// or saved.
+    pub fn render_tiled(
+        &self,
+        world: &World,
+        tile_size: usize,
+        mut on_tile: impl FnMut(&Tile, &Canvas),
+    ) -> Canvas {
+        let mut image = Canvas::new(self.hsize, self.vsize);
+        for tile in Tile::grid(self.hsize, self.vsize, tile_size) {
+            let rendered = self.render_tile(world, &tile);
+            on_tile(&tile, &rendered);
+            image.blit(&rendered, tile.x, tile.y);
+        }
+        image
+    }
+
+    /// Like [`Camera::render_tiled`], but with tiles traced in parallel.
+    /// `on_tile` is called from the worker threads, in no particular order.
+    #[cfg(feature = "rayon")]
+    pub fn render_tiled_parallel(
+        &self,
+        world: &World,
+        tile_size: usize,
+        on_tile: impl Fn(&Tile, &Canvas) + Sync,
+    ) -> Canvas {
+        let rendered: Vec<(Tile, Canvas)> = Tile::grid(self.hsize, self.vsize, tile_size)
+            .into_par_iter()
+            .map(|tile| {
+                let rendered = self.render_tile(world, &tile);
+                on_tile(&tile, &rendered);
+                (tile, rendered)
+            })
+            .collect();
+
+        let mut image = Canvas::new(self.hsize, self.vsize);
+        for (tile, tile_image) in rendered {
+            image.blit(&tile_image, tile.x, tile.y);
+        }
+        image
+    }
+
+    /// Render `world` in a series of increasingly detailed passes, calling
+    /// `on_pass` with the whole image after each one: useful for previewing
+    /// slow scenes.
+    ///
+    /// The first pass traces one pixel in every `initial_block` square block
+    /// and fills the block with its color. Each following pass halves the
+    /// block size, tracing only pixels that haven't been traced yet, until
+    /// the last pass traces at full resolution. The final image is identical
+    /// to [`Camera::render`], for the same total number of rays.
+    pub fn render_progressive(
+        &self,
+        world: &World,
+        initial_block: usize,
+        mut on_pass: impl FnMut(&Canvas),
+    ) -> Canvas {
+        let mut image = Canvas::new(self.hsize, self.vsize);
+        let mut traced = vec![false; self.hsize * self.vsize];
+        let mut block = initial_block.max(1);
+
+        loop {
+            for y in (0..self.vsize).step_by(block) {
+                for x in (0..self.hsize).step_by(block) {
+                    if traced[y * self.hsize + x] {
+                        continue;
+                    }
+                    let color = self.color_at_pixel(world, x, y);
+                    traced[y * self.hsize + x] = true;
+
+                    for by in y..usize::min(y + block, self.vsize) {
+                        for bx in x..usize::min(x + block, self.hsize) {
+                            if (bx, by) == (x, y) || !traced[by * self.hsize + bx] {
+                                image.write_pixel(bx, by, color);
+                            }
+                        }
+                    }
+                }
+            }
+            on_pass(&image);
+
+            if block == 1 {
+                return image;
+            }
+            block /= 2;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::consts::FRAC_1_SQRT_2;
+
+    use super::*;
+    use crate::assert_approx_eq;
+    use crate::error::{Error, MatrixError};
+    use crate::fixtures::{default_camera, default_world};
+    use crate::lighting::PointLight;
+    use crate::shape::{Motion, Sphere};
+    use crate::space::Vector;
+    use crate::testlib::approx_equals_fail;
+    use crate::uv::spherical_map;
+
+    #[test]
+    fn test_camera_construction() {
+        let c = Camera::new(160, 120, PI / 2.0);
+        assert_eq!(c.hsize, 160);
+        assert_eq!(c.vsize, 120);
+        assert_approx_eq!(c.field_of_view, PI / 2.0);
+        assert_eq!(*c.transformation(), Matrix4::identity());
+    }
+
+    #[test]
+    fn test_pixel_size_horizontal_canvas() {
+        let c = Camera::new(200, 125, PI / 2.0);
+        assert_approx_eq!(c.pixel_size, 0.01);
+    }
+
+    #[test]
+    fn test_pixel_size_vertical_canvas() {
+        let c = Camera::new(125, 200, PI / 2.0);
+        assert_approx_eq!(c.pixel_size, 0.01);
+    }
+
+    #[test]
+    fn test_ray_through_center() {
+        let c = Camera::new(201, 101, PI / 2.0);
+        let r = c.ray_for_pixel(100, 50);
+        assert_eq!(r.origin, Point::origin());
+        assert_eq!(r.direction, Vector::new(0.0, 0.0, -1.0));
+    }
+
+    #[test]
+    fn test_ray_through_corner() {
+        let c = Camera::new(201, 101, PI / 2.0);
+        let r = c.ray_for_pixel(0, 0);
+        assert_eq!(r.origin, Point::origin());
+        assert_eq!(r.direction, Vector::new(0.66519, 0.33259, -0.66851));
+    }
+
+    #[test]
+    fn test_ray_transformed_camera() {
+        let mut c = Camera::new(201, 101, PI / 2.0);
+        c.set_transform(Matrix4::rotation_y(PI / 4.0) * Matrix4::translation(0.0, -2.0, 5.0));
+        let r = c.ray_for_pixel(100, 50);
+        let hsq = (2.0 as Float).sqrt() / 2.0;
+        assert_eq!(r.origin, Point::new(0.0, 2.0, -5.0));
+        assert_eq!(r.direction, Vector::new(hsq, 0.0, -hsq));
+    }
+
+    #[test]
+    fn test_ray_at_subpixel_point() {
+        let c = Camera::new(201, 101, PI / 2.0);
+        assert_eq!(c.ray_at(100.5, 50.5), c.ray_for_pixel(100, 50));
+        assert_eq!(c.ray_at(0.5, 0.5), c.ray_for_pixel(0, 0));
+        assert_eq!(
+            c.ray_at(0.0, 50.5).direction,
+            Vector::new(FRAC_1_SQRT_2, 0.0, -FRAC_1_SQRT_2)
+        );
+    }
+
+    #[test]
+    fn test_rays_for_pixel_follow_sampler() {
+        let mut c = Camera::new(201, 101, PI / 2.0);
+        c.set_sampler(Sampler::Grid);
+        c.set_samples(2);
+        let expected: Vec<_> = [(5.25, 4.25), (5.75, 4.25), (5.25, 4.75), (5.75, 4.75)]
+            .iter()
+            .map(|&(x, y)| c.ray_at(x, y))
+            .collect();
+        assert_eq!(c.rays_for_pixel(5, 4), expected);
+    }
+
+    #[test]
+    fn test_default_projection() {
+        let c = Camera::new(201, 101, PI / 2.0);
+        assert_eq!(c.projection(), Projection::Perspective);
+    }
+
+    #[test]
+    fn test_fisheye_ray_through_center() {
+        let mut c = Camera::new(201, 101, PI);
+        c.set_projection(Projection::Fisheye);
+        let r = c.ray_for_pixel(100, 50);
+        assert_eq!(r.origin, Point::origin());
+        assert_eq!(r.direction, Vector::new(0.0, 0.0, -1.0));
+    }
+
+    #[test]
+    fn test_fisheye_angle_proportional_to_distance() {
+        // One pixel is PI / 200 radians, so the edges are 90 degrees off-axis.
+        let mut c = Camera::new(201, 101, PI * 201.0 / 200.0);
+        c.set_projection(Projection::Fisheye);
+        assert_eq!(
+            c.ray_for_pixel(200, 50).direction,
+            Vector::new(-1.0, 0.0, 0.0)
+        );
+        assert_eq!(c.ray_for_pixel(0, 50).direction, Vector::new(1.0, 0.0, 0.0));
+
+        let r = c.ray_for_pixel(100, 0);
+        let (sin, cos) = (PI * 50.0 / 200.0).sin_cos();
+        assert_eq!(r.direction, Vector::new(0.0, sin, -cos));
+    }
+
+    #[test]
+    fn test_fisheye_sees_behind_camera() {
+        let mut c = Camera::new(101, 101, 2.0 * PI * 101.0 / 100.0);
+        c.set_projection(Projection::Fisheye);
+        assert!(c.ray_for_pixel(0, 50).direction.z() > 0.0);
+    }
+
+    #[test]
+    fn test_fisheye_transformed_camera() {
+        let mut c = Camera::new(201, 101, PI);
+        c.set_projection(Projection::Fisheye);
+        c.set_transform(Matrix4::rotation_y(PI / 4.0) * Matrix4::translation(0.0, -2.0, 5.0));
+        let r = c.ray_for_pixel(100, 50);
+        let hsq = (2.0 as Float).sqrt() / 2.0;
+        assert_eq!(r.origin, Point::new(0.0, 2.0, -5.0));
+        assert_eq!(r.direction, Vector::new(hsq, 0.0, -hsq));
+    }
+
+    #[test]
+    fn test_equirectangular_ray_through_center() {
+        let mut c = Camera::new(201, 101, PI / 2.0);
+        c.set_projection(Projection::Equirectangular);
+        let r = c.ray_for_pixel(100, 50);
+        assert_eq!(r.origin, Point::origin());
+        assert_eq!(r.direction, Vector::new(0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn test_equirectangular_round_trips_spherical_map() {
+        let mut c = Camera::new(40, 20, PI / 2.0);
+        c.set_projection(Projection::Equirectangular);
+        for (px, py) in [(0, 0), (7, 3), (20, 10), (33, 15), (39, 19)] {
+            let r = c.ray_for_pixel(px, py);
+            let (u, v) = spherical_map(&(Point::origin() + r.direction));
+            assert_approx_eq!(u, (px as Float + 0.5) / 40.0);
+            assert_approx_eq!(v, 1.0 - (py as Float + 0.5) / 20.0);
+        }
+    }
+
+    #[test]
+    fn test_render() {
+        let w = default_world();
+        let image = default_camera().render(&w);
+        assert_eq!(image.pixel_at(5, 5), Color::new(0.38066, 0.47583, 0.2855));
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_render_parallel_matches_render() {
+        let w = default_world();
+        let c = default_camera();
+        let image = c.render_parallel(&w);
+        assert_eq!(image.pixel_at(5, 5), Color::new(0.38066, 0.47583, 0.2855));
+        assert_eq!(image, c.render(&w));
+    }
+
+    #[test]
+    fn test_try_render() {
+        let w = default_world();
+        let mut c = default_camera();
+        assert_eq!(c.try_render(&w).unwrap(), c.render(&w));
+
+        c.set_transform(Matrix4::scaling(1.0, 0.0, 1.0));
+        assert!(matches!(
+            c.try_render(&w),
+            Err(Error::Matrix(MatrixError::Singular))
+        ));
+    }
+
+    #[test]
+    fn test_render_with_options_matches_render() {
+        let w = default_world();
+        let c = default_camera();
+        let expected = c.render(&w);
+        for threads in [None, Some(1), Some(3)] {
+            let options = RenderOptions { threads };
+            assert_eq!(c.render_with_options(&w, &options), expected);
+        }
+    }
+
+    #[test]
+    fn test_render_tile() {
+        let w = default_world();
+        let c = default_camera();
+        let image = c.render(&w);
+        let tile = Tile::new(4, 3, 5, 4);
+        let tile_image = c.render_tile(&w, &tile);
+
+        assert_eq!(tile_image.width, 5);
+        assert_eq!(tile_image.height, 4);
+        for (x, y) in tile.pixels() {
+            assert_eq!(tile_image.pixel_at(x - 4, y - 3), image.pixel_at(x, y));
+        }
+    }
+
+    #[test]
+    fn test_render_tiled_matches_render() {
+        let w = default_world();
+        let c = default_camera();
+        let mut tiles = vec![];
+        let image = c.render_tiled(&w, 4, |tile, tile_image| {
+            assert_eq!(
+                (tile.width, tile.height),
+                (tile_image.width, tile_image.height)
+            );
+            tiles.push(*tile);
+        });
+
+        assert_eq!(image, c.render(&w));
+        assert_eq!(tiles, Tile::grid(11, 11, 4));
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_render_tiled_parallel_matches_render() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let w = default_world();
+        let c = default_camera();
+        let count = AtomicUsize::new(0);
+        let image = c.render_tiled_parallel(&w, 3, |_, _| {
+            count.fetch_add(1, Ordering::Relaxed);
+        });
+
+        assert_eq!(image, c.render(&w));
+        assert_eq!(count.into_inner(), 16);
+    }
+
+    #[test]
+    fn test_render_progressive() {
+        let w = default_world();
+        let c = default_camera();
+        let mut passes = vec![];
+        let image = c.render_progressive(&w, 8, |pass| passes.push(pass.clone()));
+
+        // Blocks of 8, 4, 2 then single pixels
+        assert_eq!(passes.len(), 4);
+        assert_eq!(image, c.render(&w));
+        assert_eq!(&image, passes.last().unwrap());
+
+        // The first pass fills each block with the color of its corner.
+        let first = &passes[0];
+        let full = c.render(&w);
+        assert_eq!(first.pixel_at(0, 0), full.pixel_at(0, 0));
+        assert_eq!(first.pixel_at(7, 7), full.pixel_at(0, 0));
+        assert_eq!(first.pixel_at(7, 8), full.pixel_at(0, 8));
+        assert_eq!(first.pixel_at(10, 10), full.pixel_at(8, 8));
+        assert_eq!(first.pixel_at(5, 5), full.pixel_at(0, 0));
+        assert_ne!(first.pixel_at(5, 5), full.pixel_at(5, 5));
+    }
+
+    #[test]
+    fn test_render_progressive_block_not_power_of_two() {
+        let w = default_world();
+        let c = default_camera();
+        let mut count = 0;
+        let image = c.render_progressive(&w, 5, |_| count += 1);
+
+        assert_eq!(count, 3);
+        assert_eq!(image, c.render(&w));
+    }
+
+    #[test]
+    fn test_default_sampling() {
+        let c = Camera::new(160, 120, PI / 2.0);
+        assert_eq!(c.samples(), 1);
+        assert_eq!(c.sampler(), Sampler::Jittered);
+    }
+
+    #[test]
+    fn test_multisampled_pixel_is_average_of_samples() {
+        let w = default_world();
+        let mut c = default_camera();
+        c.set_sampler(Sampler::Grid);
+        c.set_samples(2);
+
+        let expected = [(5.25, 4.25), (5.75, 4.25), (5.25, 4.75), (5.75, 4.75)]
+            .iter()
+            .map(|&(x, y)| w.color_at(&c.ray_through(x, y, 0.0)))
+            .sum::<Color>()
+            / 4.0;
+        assert_eq!(c.render(&w).pixel_at(5, 4), expected);
+    }
+
+    #[test]
+    fn test_rays_for_pixel_from_sequence() {
+        let mut c = Camera::new(201, 101, PI / 2.0);
+        c.set_samples(2);
+        let default_rays = c.rays_for_pixel(5, 4);
+        c.set_sequence(Sequence::random(0));
+        assert_eq!(c.rays_for_pixel(5, 4), default_rays);
+        c.set_sequence(Sequence::random(1));
+        assert_ne!(c.rays_for_pixel(5, 4), default_rays);
+
+        c.set_sequence(Sequence::fixed([0.5]));
+        let expected: Vec<_> = [(5.25, 4.25), (5.75, 4.25), (5.25, 4.75), (5.75, 4.75)]
+            .iter()
+            .map(|&(x, y)| c.ray_at(x, y))
+            .collect();
+        assert_eq!(c.rays_for_pixel(5, 4), expected);
+    }
+
+    #[test]
+    fn test_jittered_render_is_repeatable() {
+        let w = default_world();
+        let mut c = default_camera();
+        c.set_samples(3);
+        assert_eq!(c.render(&w), c.render(&w));
+        assert_eq!(c.render_tiled(&w, 4, |_, _| {}), c.render(&w));
+    }
+
+    #[test]
+    fn test_shutter_blurs_moving_shapes() {
+        let mut w = World::new();
+        w.set_light(PointLight::new(
+            Point::new(0.0, 0.0, -10.0),
+            Color::new(1.0, 1.0, 1.0),
+        ));
+        let mut s = Sphere::with_transform(Matrix4::translation(-3.0, 0.0, 0.0));
+        s.material_mut().ambient = 1.0;
+        s.material_mut().diffuse = 0.0;
+        s.material_mut().specular = 0.0;
+        s.set_motion(Motion::new(Matrix4::translation(3.0, 0.0, 0.0), 0.0, 1.0));
+        w.add_object(s);
+
+        let mut c = default_camera();
+        c.set_samples(4);
+        c.set_shutter(0.0, 1.0);
+        let blurred = c.render(&w).pixel_at(5, 5);
+        assert!(blurred.red() > 0.1 && blurred.red() < 0.9);
+
+        // With the shutter closed every ray sees the sphere where it starts.
+        c.set_shutter(0.0, 0.0);
+        assert_eq!(c.render(&w).pixel_at(5, 5), Color::new(0.0, 0.0, 0.0));
+    }
+}
@@ -1,5 +1,27 @@
-use crate::{color::Color, space::Tuple};
+use crate::Float;
+use crate::{color::Color, error, space::Tuple};
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use std::fs::File;
+use std::io::{BufWriter, Error, ErrorKind, Result, Write};
+use std::path::Path;
 
+/// How [`Canvas::blit_with`] combines the pixels it copies with those
+/// already on the canvas.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BlendMode {
+    /// Overwrite the existing pixels.
+    Replace,
+    /// Mix with the existing pixels, with the copied image given this
+    /// opacity from 0.0 (invisible) to 1.0 (the same as `Replace`).
+    Alpha(Float),
+    /// Add to the existing pixels, for combining separately rendered light.
+    Additive,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Canvas {
     pub width: usize,
     pub height: usize,
@@ -15,36 +37,236 @@ impl Canvas {
         }
     }
 
+    /// Build a canvas from row-major pixel data.
+    ///
+    /// Panics if `pixels` doesn't hold exactly `width * height` colors.
+    pub fn from_pixels(width: usize, height: usize, pixels: Vec<Color>) -> Self {
+        assert_eq!(pixels.len(), width * height);
+        Self {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    /// Set every pixel to `color`.
+    pub fn fill(&mut self, color: Color) {
+        self.pixels.fill(color);
+    }
+
+    /// Set every pixel to black.
+    pub fn clear(&mut self) {
+        self.fill(Color::new(0.0, 0.0, 0.0));
+    }
+
+    /// Panics if (`x`, `y`) is outside the canvas.
     pub fn write_pixel(&mut self, x: usize, y: usize, color: Color) {
-        self.pixels[y * self.width + x] = color;
+        self.try_write_pixel(x, y, color)
+            .unwrap_or_else(|e| panic!("{e}"));
+    }
+
+    /// Like [`Canvas::write_pixel`], but fails with
+    /// [`Error::PixelOutOfBounds`](error::Error::PixelOutOfBounds) rather
+    /// than panicking.
+    pub fn try_write_pixel(&mut self, x: usize, y: usize, color: Color) -> error::Result<()> {
+        let index = self.index(x, y).ok_or_else(|| self.out_of_bounds(x, y))?;
+        self.pixels[index] = color;
+        Ok(())
+    }
+
+    /// Write the pixel at (`x`, `y`) if it's on the canvas, and do nothing
+    /// if it isn't, for plotting things that may stray off the edges.
+    pub fn write_pixel_clipped(&mut self, x: isize, y: isize, color: Color) {
+        if let (Ok(x), Ok(y)) = (usize::try_from(x), usize::try_from(y)) {
+            if let Some(index) = self.index(x, y) {
+                self.pixels[index] = color;
+            }
+        }
     }
 
+    /// Panics if (`x`, `y`) is outside the canvas.
     pub fn pixel_at(&self, x: usize, y: usize) -> Color {
-        self.pixels[y * self.width + x]
+        self.get_pixel(x, y)
+            .unwrap_or_else(|| panic!("{}", self.out_of_bounds(x, y)))
     }
 
-    pub fn plot_point(&mut self, point: &Tuple, color: &Color) {
-        // TODO: Write tests for this function.
-        let x = point.x().round() as usize;
-        let y = point.y().round() as usize;
+    /// The pixel at (`x`, `y`), or `None` if it's outside the canvas.
+    pub fn get_pixel(&self, x: usize, y: usize) -> Option<Color> {
+        self.index(x, y).map(|index| self.pixels[index])
+    }
+
+    fn index(&self, x: usize, y: usize) -> Option<usize> {
+        (x < self.width && y < self.height).then(|| y * self.width + x)
+    }
+
+    fn out_of_bounds(&self, x: usize, y: usize) -> error::Error {
+        error::Error::PixelOutOfBounds {
+            x,
+            y,
+            width: self.width,
+            height: self.height,
+        }
+    }
+
+    /// Iterate over the pixels in row-major order.
+    pub fn pixels(&self) -> std::slice::Iter<'_, Color> {
+        self.pixels.iter()
+    }
+
+    /// Iterate mutably over the pixels in row-major order.
+    pub fn pixels_mut(&mut self) -> std::slice::IterMut<'_, Color> {
+        self.pixels.iter_mut()
+    }
 
-        let xmin = isize::max(0, x as isize - 1) as usize;
-        let ymin = isize::max(0, y as isize - 1) as usize;
+    /// Iterate mutably over the pixels along with their (x, y) coordinates.
+    pub fn enumerate_pixels_mut(&mut self) -> impl Iterator<Item = (usize, usize, &mut Color)> {
+        let width = self.width;
+        self.pixels
+            .iter_mut()
+            .enumerate()
+            .map(move |(i, pixel)| (i % width, i / width, pixel))
+    }
 
-        let xmax = usize::min(x + 1, self.width - 1);
-        let ymax = usize::min(y + 1, self.height - 1);
+    /// Iterate mutably over the rows of pixels, top to bottom.
+    pub fn rows_mut(&mut self) -> std::slice::ChunksExactMut<'_, Color> {
+        // chunks_exact_mut panics on a zero chunk size, and a zero-width
+        // canvas has no pixels to split anyway.
+        self.pixels.chunks_exact_mut(self.width.max(1))
+    }
 
-        for px in xmin..xmax + 1 {
-            for py in ymin..ymax + 1 {
-                self.write_pixel(px, py, *color);
+    /// Like [`Canvas::rows_mut`], but as a rayon parallel iterator so each
+    /// row can be written from a different thread.
+    #[cfg(feature = "rayon")]
+    pub fn par_rows_mut(&mut self) -> rayon::slice::ChunksExactMut<'_, Color> {
+        self.pixels.par_chunks_exact_mut(self.width.max(1))
+    }
+
+    /// Copy `other` onto this canvas with its top left corner at (`x`, `y`).
+    /// Anything falling outside the canvas is cropped.
+    pub fn blit(&mut self, other: &Canvas, x: usize, y: usize) {
+        self.blit_with(other, x, y, BlendMode::Replace);
+    }
+
+    /// Like [`Canvas::blit`], combining the pixels according to `mode`.
+    pub fn blit_with(&mut self, other: &Canvas, x: usize, y: usize, mode: BlendMode) {
+        let width = other.width.min(self.width.saturating_sub(x));
+        let height = other.height.min(self.height.saturating_sub(y));
+
+        for oy in 0..height {
+            for ox in 0..width {
+                let src = other.pixel_at(ox, oy);
+                let dst = self.pixel_at(x + ox, y + oy);
+                let color = match mode {
+                    BlendMode::Replace => src,
+                    BlendMode::Alpha(alpha) => dst.lerp(src, alpha),
+                    BlendMode::Additive => dst + src,
+                };
+                self.write_pixel(x + ox, y + oy, color);
             }
         }
     }
+
+    /// Shrink the canvas by `factor` in each direction, averaging each
+    /// `factor` x `factor` block of pixels into one. Rendering at a multiple
+    /// of the target size and then downsampling gives anti-aliased output.
+    ///
+    /// If the size isn't a multiple of `factor`, the blocks along the right
+    /// and bottom edges are smaller and average only the pixels they cover.
+    ///
+    /// Panics if `factor` is zero.
+    pub fn downsample(&self, factor: usize) -> Canvas {
+        assert!(factor > 0, "downsample factor must be at least 1");
+        let mut image = Canvas::new(self.width.div_ceil(factor), self.height.div_ceil(factor));
+        for (x, y, pixel) in image.enumerate_pixels_mut() {
+            let xs = x * factor..((x + 1) * factor).min(self.width);
+            let ys = y * factor..((y + 1) * factor).min(self.height);
+            *pixel = Color::average(
+                ys.flat_map(|sy| xs.clone().map(move |sx| (sx, sy)))
+                    .map(|(sx, sy)| self.pixel_at(sx, sy)),
+            );
+        }
+        image
+    }
+
+    /// The pixels as 8-bit RGBA bytes, row by row from the top, all fully
+    /// opaque. This is the layout HTML canvas `ImageData` and most GUI
+    /// framebuffers expect.
+    pub fn to_rgba8(&self) -> Vec<u8> {
+        self.pixels()
+            .flat_map(|pixel| {
+                let (r, g, b) = pixel.to_u8();
+                [r, g, b, 255]
+            })
+            .collect()
+    }
+
+    /// Like [`Canvas::to_rgba8`], but with every pixel given coverage
+    /// `alpha` and its color premultiplied by it, for compositing the image
+    /// over whatever is behind it.
+    pub fn to_rgba8_premultiplied(&self, alpha: Float) -> Vec<u8> {
+        let alpha = alpha.clamp(0.0, 1.0);
+        let (a, _, _) = Color::new(alpha, 0.0, 0.0).to_u8();
+        self.pixels()
+            .flat_map(|pixel| {
+                let (r, g, b) = (pixel.clamp(0.0, 1.0) * alpha).to_u8();
+                [r, g, b, a]
+            })
+            .collect()
+    }
+
+    /// Draw a 3x3 square centred on `point`'s x and y, clipped to the
+    /// canvas.
+    pub fn plot_point(&mut self, point: &Tuple, color: &Color) {
+        let x = point.x().round() as isize;
+        let y = point.y().round() as isize;
+
+        for px in x - 1..=x + 1 {
+            for py in y - 1..=y + 1 {
+                self.write_pixel_clipped(px, py, *color);
+            }
+        }
+    }
+
+    /// Write the canvas to `path`, choosing the format from the file's
+    /// extension: `ppm`, `png`, `bmp`, `tga`, `gif`, `hdr` or `pfm`. Any
+    /// existing file is replaced.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, fields(path = %path.as_ref().display()))
+    )]
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase());
+        let write = match extension.as_deref() {
+            Some("ppm") => Self::write_ppm,
+            Some("png") => Self::write_png,
+            Some("bmp") => Self::write_bmp,
+            Some("tga") => Self::write_tga,
+            Some("gif") => Self::write_gif,
+            Some("hdr") => Self::write_hdr,
+            Some("pfm") => Self::write_pfm,
+            _ => {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("unsupported image format: {}", path.display()),
+                ))
+            }
+        };
+
+        let mut file = BufWriter::new(File::create(path)?);
+        write(self, &mut file)?;
+        file.flush()
+    }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::space::Point;
+    use std::fs;
 
     #[test]
     fn test_new_canvas() {
@@ -60,6 +282,58 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_fill_and_clear() {
+        let mut c = Canvas::new(4, 3);
+        let sky = Color::new(0.5, 0.7, 1.0);
+        c.fill(sky);
+        assert!(c.pixels.iter().all(|&p| p == sky));
+
+        c.clear();
+        assert!(c.pixels.iter().all(|&p| p == Color::new(0.0, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn test_blit() {
+        let mut c = Canvas::new(4, 3);
+        let mut tile = Canvas::new(2, 2);
+        tile.fill(Color::new(1.0, 0.5, 0.0));
+        c.blit(&tile, 1, 1);
+
+        assert_eq!(c.pixel_at(0, 0), Color::new(0.0, 0.0, 0.0));
+        assert_eq!(c.pixel_at(1, 1), Color::new(1.0, 0.5, 0.0));
+        assert_eq!(c.pixel_at(2, 2), Color::new(1.0, 0.5, 0.0));
+        assert_eq!(c.pixel_at(3, 2), Color::new(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_blit_crops_to_canvas() {
+        let mut c = Canvas::new(3, 3);
+        let mut tile = Canvas::new(4, 4);
+        tile.fill(Color::new(1.0, 1.0, 1.0));
+        c.blit(&tile, 2, 1);
+        c.blit(&tile, 5, 5);
+
+        assert_eq!(c.pixel_at(2, 2), Color::new(1.0, 1.0, 1.0));
+        assert_eq!(c.pixel_at(1, 2), Color::new(0.0, 0.0, 0.0));
+        assert_eq!(c.pixel_at(2, 0), Color::new(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_blit_alpha_and_additive() {
+        let mut c = Canvas::new(1, 1);
+        c.fill(Color::new(0.2, 0.4, 0.6));
+        let mut overlay = Canvas::new(1, 1);
+        overlay.fill(Color::new(1.0, 0.0, 0.0));
+
+        let mut alpha = c.clone();
+        alpha.blit_with(&overlay, 0, 0, BlendMode::Alpha(0.25));
+        assert_eq!(alpha.pixel_at(0, 0), Color::new(0.4, 0.3, 0.45));
+
+        c.blit_with(&overlay, 0, 0, BlendMode::Additive);
+        assert_eq!(c.pixel_at(0, 0), Color::new(1.2, 0.4, 0.6));
+    }
+
     #[test]
     fn test_write_pixel() {
         let mut c = Canvas::new(10, 20);
@@ -69,4 +343,190 @@ mod test {
         c.write_pixel(2, 3, red);
         assert_eq!(c.pixel_at(2, 3), Color::new(1.0, 0.0, 0.0));
     }
+
+    #[test]
+    fn test_try_write_pixel() {
+        let mut c = Canvas::new(10, 20);
+        let red = Color::new(1.0, 0.0, 0.0);
+        assert!(c.try_write_pixel(9, 19, red).is_ok());
+        assert_eq!(c.pixel_at(9, 19), red);
+
+        // Would land on the next row if x weren't checked.
+        assert!(matches!(
+            c.try_write_pixel(10, 3, red),
+            Err(error::Error::PixelOutOfBounds {
+                x: 10,
+                y: 3,
+                width: 10,
+                height: 20
+            })
+        ));
+        assert!(c.try_write_pixel(0, 20, red).is_err());
+        assert_eq!(c.get_pixel(0, 4), Some(Color::new(0.0, 0.0, 0.0)));
+    }
+
+    #[test]
+    #[should_panic(expected = "(10, 3) is outside the 10x20 canvas")]
+    fn test_write_pixel_out_of_bounds() {
+        Canvas::new(10, 20).write_pixel(10, 3, Color::new(1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_write_pixel_clipped() {
+        let mut c = Canvas::new(3, 2);
+        let red = Color::new(1.0, 0.0, 0.0);
+        c.write_pixel_clipped(-1, 0, red);
+        c.write_pixel_clipped(3, 1, red);
+        c.write_pixel_clipped(1, 5, red);
+        assert_eq!(c, Canvas::new(3, 2));
+
+        c.write_pixel_clipped(2, 1, red);
+        assert_eq!(c.pixel_at(2, 1), red);
+    }
+
+    #[test]
+    fn test_get_pixel() {
+        let mut c = Canvas::new(3, 2);
+        c.write_pixel(2, 1, Color::new(0.5, 0.5, 0.5));
+        assert_eq!(c.get_pixel(2, 1), Some(Color::new(0.5, 0.5, 0.5)));
+        assert_eq!(c.get_pixel(3, 0), None);
+        assert_eq!(c.get_pixel(0, 2), None);
+    }
+
+    #[test]
+    fn test_plot_point_clips_at_edges() {
+        let mut c = Canvas::new(4, 4);
+        let red = Color::new(1.0, 0.0, 0.0);
+        c.plot_point(&Point::new(0.0, 3.2, 0.0), &red);
+        let plotted: Vec<_> = (0..4)
+            .flat_map(|y| (0..4).map(move |x| (x, y)))
+            .filter(|&(x, y)| c.pixel_at(x, y) == red)
+            .collect();
+        assert_eq!(plotted, [(0, 2), (1, 2), (0, 3), (1, 3)]);
+
+        // Entirely off the canvas.
+        c.plot_point(&Point::new(-5.0, 10.0, 0.0), &Color::new(0.0, 1.0, 0.0));
+        assert_eq!(c.pixels().filter(|&&p| p == red).count(), 4);
+    }
+
+    #[test]
+    fn test_save_picks_format_from_extension() {
+        let mut c = Canvas::new(3, 2);
+        c.fill(Color::new(0.5, 0.25, 1.0));
+        let dir = std::env::temp_dir();
+
+        let path = dir.join(format!("canvas-save-{}.ppm", std::process::id()));
+        c.save(&path).unwrap();
+        let mut expected = vec![];
+        c.write_ppm(&mut expected).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), expected);
+        fs::remove_file(&path).unwrap();
+
+        let path = dir.join(format!("canvas-save-{}.PNG", std::process::id()));
+        c.save(&path).unwrap();
+        let mut expected = vec![];
+        c.write_png(&mut expected).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), expected);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_save_rejects_unknown_extension() {
+        let c = Canvas::new(1, 1);
+        let path = std::env::temp_dir().join(format!("canvas-save-{}.xyz", std::process::id()));
+        let err = c.save(&path).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_pixel_iterators() {
+        let mut c = Canvas::new(3, 2);
+        for (x, y, pixel) in c.enumerate_pixels_mut() {
+            *pixel = Color::new(x as Float, y as Float, 0.0);
+        }
+        assert_eq!(c.pixel_at(2, 1), Color::new(2.0, 1.0, 0.0));
+        assert_eq!(c.pixels().nth(4), Some(&Color::new(1.0, 1.0, 0.0)));
+
+        for pixel in c.pixels_mut() {
+            *pixel *= 2.0;
+        }
+        assert_eq!(c.pixel_at(2, 1), Color::new(4.0, 2.0, 0.0));
+    }
+
+    #[test]
+    fn test_rows_mut() {
+        let mut c = Canvas::new(3, 2);
+        assert_eq!(c.rows_mut().len(), 2);
+        for (y, row) in c.rows_mut().enumerate() {
+            assert_eq!(row.len(), 3);
+            row[1] = Color::new(y as Float, 0.0, 0.0);
+        }
+        assert_eq!(c.pixel_at(1, 1), Color::new(1.0, 0.0, 0.0));
+        assert_eq!(Canvas::new(0, 4).rows_mut().count(), 0);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_par_rows_mut() {
+        let mut c = Canvas::new(4, 3);
+        c.par_rows_mut().enumerate().for_each(|(y, row)| {
+            for (x, pixel) in row.iter_mut().enumerate() {
+                *pixel = Color::new(x as Float, y as Float, 0.0);
+            }
+        });
+        assert_eq!(c.pixel_at(3, 2), Color::new(3.0, 2.0, 0.0));
+    }
+
+    #[test]
+    fn test_to_rgba8() {
+        let mut c = Canvas::new(2, 1);
+        c.write_pixel(0, 0, Color::new(1.5, 0.0, -0.5));
+        c.write_pixel(1, 0, Color::new(0.2, 0.4, 1.0));
+        assert_eq!(c.to_rgba8(), vec![255, 0, 0, 255, 51, 102, 255, 255]);
+    }
+
+    #[test]
+    fn test_to_rgba8_premultiplied() {
+        let mut c = Canvas::new(2, 1);
+        c.write_pixel(0, 0, Color::new(1.5, 0.0, 0.4));
+        c.write_pixel(1, 0, Color::new(0.2, 0.4, 1.0));
+        assert_eq!(
+            c.to_rgba8_premultiplied(0.5),
+            vec![128, 0, 51, 128, 26, 51, 128, 128]
+        );
+        assert_eq!(c.to_rgba8_premultiplied(1.0), c.to_rgba8());
+    }
+
+    #[test]
+    fn test_downsample_averages_blocks() {
+        let mut c = Canvas::new(4, 2);
+        c.write_pixel(0, 0, Color::new(1.0, 0.0, 0.0));
+        c.write_pixel(1, 1, Color::new(0.0, 1.0, 0.0));
+        c.write_pixel(2, 0, Color::new(0.4, 0.4, 0.4));
+        let small = c.downsample(2);
+        assert_eq!(small.width, 2);
+        assert_eq!(small.height, 1);
+        assert_eq!(small.pixel_at(0, 0), Color::new(0.25, 0.25, 0.0));
+        assert_eq!(small.pixel_at(1, 0), Color::new(0.1, 0.1, 0.1));
+    }
+
+    #[test]
+    fn test_downsample_partial_blocks() {
+        let mut c = Canvas::new(3, 3);
+        c.fill(Color::new(0.5, 0.5, 0.5));
+        c.write_pixel(2, 0, Color::new(1.0, 1.0, 1.0));
+        let small = c.downsample(2);
+        assert_eq!(small.width, 2);
+        assert_eq!(small.height, 2);
+        assert_eq!(small.pixel_at(1, 0), Color::new(0.75, 0.75, 0.75));
+        assert_eq!(small.pixel_at(1, 1), Color::new(0.5, 0.5, 0.5));
+    }
+
+    #[test]
+    fn test_downsample_by_one_is_a_copy() {
+        let mut c = Canvas::new(2, 2);
+        c.write_pixel(1, 0, Color::new(0.2, 0.3, 0.4));
+        assert_eq!(c.downsample(1), c);
+    }
 }
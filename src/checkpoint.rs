@@ -0,0 +1,450 @@
+use std::ffi::OsString;
+use std::fs::{self, File};
+use std::io::{prelude::*, BufReader, BufWriter, Error, ErrorKind, Result};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use crate::camera::Camera;
+use crate::canvas::Canvas;
+use crate::color::Color;
+use crate::tile::Tile;
+use crate::world::World;
+use crate::Float;
+
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+const MAGIC: &[u8; 8] = b"RTCHECK1";
+
+/// The most pixels a checkpoint may hold, a 16384 x 16384 image, and the
+/// longest either side may be, so a corrupt header can't ask for an
+/// enormous allocation or loop.
+const MAX_PIXELS: usize = 1 << 28;
+const MAX_SIDE: usize = 1 << 16;
+
+/// A partly rendered image, and which of its tiles are finished, that can be
+/// saved to disk and picked up again later.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Checkpoint {
+    image: Canvas,
+    tile_size: usize,
+    tiles: Vec<Tile>,
+    done: Vec<bool>,
+}
+
+impl Checkpoint {
+    /// A checkpoint for a `width` by `height` render split into tiles of
+    /// `tile_size` pixels square, none of them finished.
+    pub fn new(width: usize, height: usize, tile_size: usize) -> Self {
+        let tiles = Tile::grid(width, height, tile_size);
+        Self {
+            image: Canvas::new(width, height),
+            tile_size,
+            done: vec![false; tiles.len()],
+            tiles,
+        }
+    }
+
+    pub fn tile_size(&self) -> usize {
+        self.tile_size
+    }
+
+    /// The image so far. Unfinished tiles are black.
+    pub fn image(&self) -> &Canvas {
+        &self.image
+    }
+
+    pub fn into_image(self) -> Canvas {
+        self.image
+    }
+
+    /// The tiles still to be rendered, in rows from the top left.
+    pub fn remaining_tiles(&self) -> Vec<Tile> {
+        self.tiles
+            .iter()
+            .zip(&self.done)
+            .filter(|(_, &done)| !done)
+            .map(|(tile, _)| *tile)
+            .collect()
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.done.iter().all(|&done| done)
+    }
+
+    /// Record `tile` as finished, copying `rendered` into the image.
+    ///
+    /// Panics if `tile` isn't one of the checkpoint's tiles.
+    pub fn complete(&mut self, tile: &Tile, rendered: &Canvas) {
+        let index = self
+            .tiles
+            .iter()
+            .position(|t| t == tile)
+            .expect("tile isn't part of the checkpoint");
+        self.image.blit(rendered, tile.x, tile.y);
+        self.done[index] = true;
+    }
+
+    /// Write the checkpoint in a simple binary format, keeping every pixel
+    /// exactly so a resumed render matches an uninterrupted one.
+    pub fn write(&self, sink: &mut impl Write) -> Result<()> {
+        sink.write_all(MAGIC)?;
+        for n in [self.image.width, self.image.height, self.tile_size] {
+            sink.write_all(&(n as u64).to_le_bytes())?;
+        }
+        let done: Vec<u8> = self.done.iter().map(|&done| done as u8).collect();
+        sink.write_all(&done)?;
+
+        let mut bytes = Vec::with_capacity(self.image.width * 24);
+        for row in 0..self.image.height {
+            bytes.clear();
+            for col in 0..self.image.width {
+                let pixel = self.image.pixel_at(col, row);
+                for f in [pixel.red(), pixel.green(), pixel.blue()] {
+                    // Always f64, so checkpoints work with or without the
+                    // `f32` feature.
+                    #[allow(clippy::useless_conversion)]
+                    bytes.extend_from_slice(&f64::from(f).to_le_bytes());
+                }
+            }
+            sink.write_all(&bytes)?;
+        }
+        Ok(())
+    }
+
+    /// Read a checkpoint written by [`Checkpoint::write`].
+    pub fn read(source: &mut impl Read) -> Result<Self> {
+        let mut magic = [0; 8];
+        source.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "not a render checkpoint",
+            ));
+        }
+        let width = read_size(source)?;
+        let height = read_size(source)?;
+        let tile_size = read_size(source)?;
+        if tile_size == 0 {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "checkpoint has no tiles",
+            ));
+        }
+        if width > MAX_SIDE || height > MAX_SIDE || width * height > MAX_PIXELS {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "checkpoint is too large",
+            ));
+        }
+        if (width == 0) != (height == 0) {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "checkpoint has a side of zero pixels",
+            ));
+        }
+
+        let mut checkpoint = Self::new(width, height, tile_size);
+        let mut done = vec![0; checkpoint.done.len()];
+        source.read_exact(&mut done)?;
+        checkpoint.done = done.iter().map(|&done| done != 0).collect();
+
+        let mut bytes = [0; 8];
+        for pixel in checkpoint.image.pixels_mut() {
+            let mut components = [0.0; 3];
+            for c in &mut components {
+                source.read_exact(&mut bytes)?;
+                *c = f64::from_le_bytes(bytes) as Float;
+            }
+            *pixel = Color::new(components[0], components[1], components[2]);
+        }
+        Ok(checkpoint)
+    }
+
+    /// Save the checkpoint to `path`. The file is written alongside and then
+    /// moved into place, so an interruption never leaves it half written.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, fields(path = %path.as_ref().display()))
+    )]
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let mut partial = OsString::from(path);
+        partial.push(".partial");
+
+        let mut file = BufWriter::new(File::create(&partial)?);
+        self.write(&mut file)?;
+        file.flush()?;
+        drop(file);
+        fs::rename(&partial, path)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        Self::read(&mut BufReader::new(File::open(path)?))
+    }
+}
+
+fn read_size(source: &mut impl Read) -> Result<usize> {
+    let mut bytes = [0; 8];
+    source.read_exact(&mut bytes)?;
+    usize::try_from(u64::from_le_bytes(bytes))
+        .map_err(|_| Error::new(ErrorKind::InvalidData, "checkpoint is too large"))
+}
+
+impl Camera {
+    /// Render the tiles `checkpoint` hasn't finished yet, in parallel if
+    /// possible, calling `on_progress` each time some are finished so the
+    /// checkpoint can be saved. Stops at the first error from `on_progress`.
+    ///
+    /// Panics if the checkpoint isn't the same size as the camera's image.
+    pub fn resume_render(
+        &self,
+        world: &World,
+        checkpoint: &mut Checkpoint,
+        mut on_progress: impl FnMut(&Checkpoint) -> Result<()>,
+    ) -> Result<()> {
+        assert_eq!(
+            (checkpoint.image.width, checkpoint.image.height),
+            (self.hsize(), self.vsize()),
+            "checkpoint doesn't match the camera's image size"
+        );
+
+        #[cfg(feature = "rayon")]
+        let batch_size = rayon::current_num_threads();
+        #[cfg(not(feature = "rayon"))]
+        let batch_size = 1;
+
+        for batch in checkpoint.remaining_tiles().chunks(batch_size) {
+            #[cfg(feature = "rayon")]
+            let tiles = batch.par_iter();
+            #[cfg(not(feature = "rayon"))]
+            let tiles = batch.iter();
+            let rendered: Vec<Canvas> = tiles.map(|tile| self.render_tile(world, tile)).collect();
+
+            for (tile, tile_image) in batch.iter().zip(&rendered) {
+                checkpoint.complete(tile, tile_image);
+            }
+            on_progress(checkpoint)?;
+        }
+        Ok(())
+    }
+
+    /// Render `world` in `tile_size` square tiles, saving the image so far to
+    /// `path` whenever `interval` has passed since it was last saved. If
+    /// `path` already holds a checkpoint from an interrupted render, the
+    /// render carries on from there. The checkpoint is removed once the
+    /// render is finished.
+    ///
+    /// Fails with [`ErrorKind::InvalidInput`] if the checkpoint at `path` was
+    /// made for a different image size or tile size.
+    pub fn render_with_checkpoints(
+        &self,
+        world: &World,
+        tile_size: usize,
+        path: impl AsRef<Path>,
+        interval: Duration,
+    ) -> Result<Canvas> {
+        let path = path.as_ref();
+        let mut checkpoint = if path.exists() {
+            let checkpoint = Checkpoint::load(path)?;
+            let expected = (self.hsize(), self.vsize(), tile_size);
+            let found = (
+                checkpoint.image.width,
+                checkpoint.image.height,
+                checkpoint.tile_size,
+            );
+            if found != expected {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("checkpoint {} is for a different render", path.display()),
+                ));
+            }
+            checkpoint
+        } else {
+            Checkpoint::new(self.hsize(), self.vsize(), tile_size)
+        };
+
+        let mut last_saved = Instant::now();
+        self.resume_render(world, &mut checkpoint, |checkpoint| {
+            if last_saved.elapsed() >= interval && !checkpoint.is_complete() {
+                checkpoint.save(path)?;
+                last_saved = Instant::now();
+            }
+            Ok(())
+        })?;
+
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+        Ok(checkpoint.into_image())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::fixtures::{default_camera, default_world};
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("{name}-{}.checkpoint", std::process::id()))
+    }
+
+    #[test]
+    fn test_new_checkpoint() {
+        let checkpoint = Checkpoint::new(5, 3, 2);
+        assert_eq!(checkpoint.remaining_tiles(), Tile::grid(5, 3, 2));
+        assert!(!checkpoint.is_complete());
+        assert_eq!(checkpoint.image(), &Canvas::new(5, 3));
+    }
+
+    #[test]
+    fn test_complete_tile() {
+        let mut checkpoint = Checkpoint::new(5, 3, 2);
+        let mut rendered = Canvas::new(1, 2);
+        rendered.fill(Color::new(1.0, 0.0, 0.0));
+        checkpoint.complete(&Tile::new(4, 0, 1, 2), &rendered);
+
+        assert_eq!(checkpoint.remaining_tiles().len(), 5);
+        assert!(!checkpoint
+            .remaining_tiles()
+            .contains(&Tile::new(4, 0, 1, 2)));
+        assert_eq!(checkpoint.image().pixel_at(4, 1), Color::new(1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    #[should_panic(expected = "tile isn't part of the checkpoint")]
+    fn test_complete_unknown_tile() {
+        let mut checkpoint = Checkpoint::new(5, 3, 2);
+        checkpoint.complete(&Tile::new(1, 0, 2, 2), &Canvas::new(2, 2));
+    }
+
+    #[test]
+    fn test_write_read_round_trip() {
+        let mut checkpoint = Checkpoint::new(3, 2, 2);
+        let mut rendered = Canvas::new(2, 2);
+        rendered.write_pixel(1, 0, Color::new(0.1, -2.5, 1e10));
+        checkpoint.complete(&Tile::new(0, 0, 2, 2), &rendered);
+
+        let mut bytes = Vec::new();
+        checkpoint.write(&mut bytes).unwrap();
+        assert_eq!(bytes.len(), 8 + 24 + 2 + 6 * 24);
+        assert_eq!(Checkpoint::read(&mut &bytes[..]).unwrap(), checkpoint);
+    }
+
+    #[test]
+    fn test_read_invalid() {
+        let err = Checkpoint::read(&mut &b"RTCHECK0"[..]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+        let mut bytes = Vec::new();
+        Checkpoint::new(3, 2, 2).write(&mut bytes).unwrap();
+        bytes.pop();
+        let err = Checkpoint::read(&mut &bytes[..]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn test_read_too_large() {
+        for (width, height) in [(u64::MAX, 2), (1 << 20, 1 << 20), (1 << 17, 1)] {
+            let err = Checkpoint::read(&mut &header(width, height)[..]).unwrap_err();
+            assert_eq!(err.to_string(), "checkpoint is too large");
+        }
+    }
+
+    #[test]
+    fn test_read_corrupt_header() {
+        // No pixels to allocate, but a long loop over empty rows of tiles.
+        for (width, height) in [(0, u64::MAX), (0, 1 << 16), (1, 0)] {
+            let err = Checkpoint::read(&mut &header(width, height)[..]).unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::InvalidData);
+        }
+    }
+
+    fn header(width: u64, height: u64) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        for n in [width, height, 16] {
+            bytes.extend_from_slice(&n.to_le_bytes());
+        }
+        bytes
+    }
+
+    #[test]
+    fn test_resume_render_matches_render() {
+        let w = default_world();
+        let c = default_camera();
+        let mut checkpoint = Checkpoint::new(11, 11, 4);
+        let mut calls = 0;
+        c.resume_render(&w, &mut checkpoint, |_| {
+            calls += 1;
+            Ok(())
+        })
+        .unwrap();
+
+        assert!(checkpoint.is_complete());
+        assert!(calls > 0);
+        assert_eq!(checkpoint.into_image(), c.render(&w));
+    }
+
+    #[test]
+    fn test_resume_render_stops_on_error() {
+        let w = default_world();
+        let c = default_camera();
+        let mut checkpoint = Checkpoint::new(11, 11, 4);
+        let err = c
+            .resume_render(&w, &mut checkpoint, |_| Err(Error::other("interrupted")))
+            .unwrap_err();
+        assert_eq!(err.to_string(), "interrupted");
+        assert!(checkpoint.remaining_tiles().len() < 9);
+    }
+
+    #[test]
+    fn test_render_with_checkpoints() {
+        let w = default_world();
+        let c = default_camera();
+        let path = temp_path("render");
+        let image = c
+            .render_with_checkpoints(&w, 4, &path, Duration::ZERO)
+            .unwrap();
+        assert_eq!(image, c.render(&w));
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_render_with_checkpoints_resumes() {
+        let w = default_world();
+        let c = default_camera();
+        let path = temp_path("resume");
+
+        // Finish the first tile with a color the render wouldn't produce, so
+        // we can tell it wasn't rendered again.
+        let mut checkpoint = Checkpoint::new(11, 11, 4);
+        let mut rendered = Canvas::new(4, 4);
+        rendered.fill(Color::new(1.0, 0.0, 1.0));
+        checkpoint.complete(&Tile::new(0, 0, 4, 4), &rendered);
+        checkpoint.save(&path).unwrap();
+
+        let image = c
+            .render_with_checkpoints(&w, 4, &path, Duration::from_secs(3600))
+            .unwrap();
+        let full = c.render(&w);
+        assert_eq!(image.pixel_at(3, 3), Color::new(1.0, 0.0, 1.0));
+        assert_eq!(image.pixel_at(5, 5), full.pixel_at(5, 5));
+        assert_eq!(image.pixel_at(10, 2), full.pixel_at(10, 2));
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_render_with_mismatched_checkpoint() {
+        let w = default_world();
+        let c = default_camera();
+        let path = temp_path("mismatch");
+        Checkpoint::new(11, 11, 2).save(&path).unwrap();
+
+        let err = c
+            .render_with_checkpoints(&w, 4, &path, Duration::ZERO)
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        assert!(path.exists());
+        fs::remove_file(&path).unwrap();
+    }
+}
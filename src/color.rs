@@ -1,29 +1,167 @@
-use crate::approx_equal;
-use std::ops::{Add, Mul, Sub};
+use crate::Float;
+use crate::{approx_equal, fmt_components};
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Sub, SubAssign};
+
+/// An 8x8 Bayer matrix, ordering thresholds so that neighbouring pixels
+/// round in different directions.
+const BAYER: [[u8; 8]; 8] = [
+    [0, 32, 8, 40, 2, 34, 10, 42],
+    [48, 16, 56, 24, 50, 18, 58, 26],
+    [12, 44, 4, 36, 14, 46, 6, 38],
+    [60, 28, 52, 20, 62, 30, 54, 22],
+    [3, 35, 11, 43, 1, 33, 9, 41],
+    [51, 19, 59, 27, 49, 17, 57, 25],
+    [15, 47, 7, 39, 13, 45, 5, 37],
+    [63, 31, 55, 23, 61, 29, 53, 21],
+];
+
+/// How far to nudge a value at pixel (`x`, `y`) before rounding it, for
+/// ordered dithering: between -0.5 and 0.5 of a quantization step.
+pub(crate) fn dither_offset(x: usize, y: usize) -> Float {
+    (BAYER[y % 8][x % 8] as Float + 0.5) / 64.0 - 0.5
+}
 
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Color {
-    r: f64,
-    g: f64,
-    b: f64,
+    r: Float,
+    g: Float,
+    b: Float,
 }
 
 impl Color {
-    pub fn new(r: f64, g: f64, b: f64) -> Self {
+    pub fn new(r: Float, g: Float, b: Float) -> Self {
         Self { r, g, b }
     }
 
-    pub fn red(self) -> f64 {
+    pub fn red(self) -> Float {
         self.r
     }
 
-    pub fn green(self) -> f64 {
+    pub fn green(self) -> Float {
         self.g
     }
 
-    pub fn blue(self) -> f64 {
+    pub fn blue(self) -> Float {
         self.b
     }
+
+    /// Limit each component to the range `min` to `max`.
+    pub fn clamp(self, min: Float, max: Float) -> Self {
+        Self::new(
+            self.r.clamp(min, max),
+            self.g.clamp(min, max),
+            self.b.clamp(min, max),
+        )
+    }
+
+    /// Linearly interpolate towards `other`: `self` when `t` is 0.0 and
+    /// `other` when it's 1.0.
+    pub fn lerp(self, other: Color, t: Float) -> Self {
+        self + (other - self) * t
+    }
+
+    /// The perceived brightness of the color, weighting each component by
+    /// the Rec. 709 coefficients. Grays have the luminance of any component.
+    pub fn luminance(self) -> Float {
+        0.2126 * self.r + 0.7152 * self.g + 0.0722 * self.b
+    }
+
+    /// The mean of `colors`, or black if there are none.
+    pub fn average(colors: impl IntoIterator<Item = Color>) -> Self {
+        let (total, count) = colors
+            .into_iter()
+            .fold((Color::new(0.0, 0.0, 0.0), 0), |(total, count), c| {
+                (total + c, count + 1)
+            });
+        if count == 0 {
+            total
+        } else {
+            total / count as Float
+        }
+    }
+
+    /// A color from 8-bit components, where 255 is full intensity.
+    pub fn from_u8(r: u8, g: u8, b: u8) -> Self {
+        Self::new(r as Float / 255.0, g as Float / 255.0, b as Float / 255.0)
+    }
+
+    /// Parse a web-style hex color: `#rrggbb` or the shorthand `#rgb`, with
+    /// or without the `#`. Returns `None` if `hex` isn't in either form.
+    pub fn from_hex(hex: &str) -> Option<Self> {
+        let digits = hex.strip_prefix('#').unwrap_or(hex);
+        if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+
+        let component = |s: &str| u8::from_str_radix(s, 16).ok();
+        match digits.len() {
+            6 => Some(Self::from_u8(
+                component(&digits[0..2])?,
+                component(&digits[2..4])?,
+                component(&digits[4..6])?,
+            )),
+            // Each digit is repeated, so "f80" is "ff8800".
+            3 => Some(Self::from_u8(
+                component(&digits[0..1])? * 17,
+                component(&digits[1..2])? * 17,
+                component(&digits[2..3])? * 17,
+            )),
+            _ => None,
+        }
+    }
+
+    /// Encode linear light with the sRGB transfer curve, for display.
+    /// Negative components become 0.0.
+    pub fn to_srgb(self) -> Self {
+        let encode = |c: Float| {
+            let c = c.max(0.0);
+            if c <= 0.0031308 {
+                12.92 * c
+            } else {
+                1.055 * c.powf(1.0 / 2.4) - 0.055
+            }
+        };
+        Self::new(encode(self.r), encode(self.g), encode(self.b))
+    }
+
+    /// A color from sRGB-encoded components, such as one picked in a paint
+    /// program or read from an image file, decoded to linear light. The
+    /// inverse of [`Color::to_srgb`].
+    pub fn from_srgb(r: Float, g: Float, b: Float) -> Self {
+        let decode = |c: Float| {
+            let c = c.max(0.0);
+            if c <= 0.04045 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            }
+        };
+        Self::new(decode(r), decode(g), decode(b))
+    }
+
+    /// The color as 8-bit components, clamped to the range 0 to 255.
+    pub fn to_u8(self) -> (u8, u8, u8) {
+        let convert = |f: Float| (f * 255.0).round().clamp(0.0, 255.0) as u8;
+        (convert(self.r), convert(self.g), convert(self.b))
+    }
+
+    /// Like [`Color::to_u8`], but with ordered dithering for a pixel at
+    /// (`x`, `y`), so that over an area the components average out to the
+    /// unquantized color instead of banding in smooth gradients.
+    pub fn to_u8_dithered(self, x: usize, y: usize) -> (u8, u8, u8) {
+        let offset = dither_offset(x, y);
+        let convert = |f: Float| (f * 255.0 + offset).round().clamp(0.0, 255.0) as u8;
+        (convert(self.r), convert(self.g), convert(self.b))
+    }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_components(f, "rgb", &[self.r, self.g, self.b])
+    }
 }
 
 impl PartialEq for Color {
@@ -56,10 +194,10 @@ impl Sub for Color {
     }
 }
 
-impl Mul<f64> for Color {
+impl Mul<Float> for Color {
     type Output = Self;
 
-    fn mul(self, rhs: f64) -> Self::Output {
+    fn mul(self, rhs: Float) -> Self::Output {
         Self::Output {
             r: self.r * rhs,
             g: self.g * rhs,
@@ -79,12 +217,141 @@ impl Mul for Color {
     }
 }
 
+impl Div<Float> for Color {
+    type Output = Self;
+
+    fn div(self, rhs: Float) -> Self::Output {
+        Self::Output {
+            r: self.r / rhs,
+            g: self.g / rhs,
+            b: self.b / rhs,
+        }
+    }
+}
+
+impl Mul<Color> for Float {
+    type Output = Color;
+
+    fn mul(self, rhs: Color) -> Self::Output {
+        rhs * self
+    }
+}
+
+// Reference variants, delegating to the by-value operators above.
+
+impl Add for &Color {
+    type Output = Color;
+    fn add(self, rhs: Self) -> Self::Output {
+        *self + *rhs
+    }
+}
+
+impl Sub for &Color {
+    type Output = Color;
+    fn sub(self, rhs: Self) -> Self::Output {
+        *self - *rhs
+    }
+}
+
+impl Mul for &Color {
+    type Output = Color;
+    fn mul(self, rhs: Self) -> Self::Output {
+        *self * *rhs
+    }
+}
+
+impl Mul<Float> for &Color {
+    type Output = Color;
+    fn mul(self, rhs: Float) -> Self::Output {
+        *self * rhs
+    }
+}
+
+impl Mul<&Color> for Float {
+    type Output = Color;
+    fn mul(self, rhs: &Color) -> Self::Output {
+        *rhs * self
+    }
+}
+
+impl Div<Float> for &Color {
+    type Output = Color;
+    fn div(self, rhs: Float) -> Self::Output {
+        *self / rhs
+    }
+}
+
+impl Sum for Color {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Color::new(0.0, 0.0, 0.0), |total, c| total + c)
+    }
+}
+
+impl<'a> Sum<&'a Color> for Color {
+    fn sum<I: Iterator<Item = &'a Color>>(iter: I) -> Self {
+        iter.copied().sum()
+    }
+}
+
+// Assignment operators, delegating to the by-value operators above.
+
+impl AddAssign for Color {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl SubAssign for Color {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+impl MulAssign<Float> for Color {
+    fn mul_assign(&mut self, rhs: Float) {
+        *self = *self * rhs;
+    }
+}
+
+impl MulAssign<Color> for Color {
+    fn mul_assign(&mut self, rhs: Color) {
+        *self = *self * rhs;
+    }
+}
+
+impl DivAssign<Float> for Color {
+    fn div_assign(&mut self, rhs: Float) {
+        *self = *self / rhs;
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
     use crate::assert_approx_eq;
     use crate::testlib::approx_equals_fail;
 
+    #[test]
+    fn test_color_assignment_operators() {
+        let mut c = Color::new(0.5, 0.5, 0.5);
+        c += Color::new(0.5, 0.0, 0.25);
+        c -= Color::new(0.0, 0.25, 0.0);
+        assert_eq!(c, Color::new(1.0, 0.25, 0.75));
+        c *= 2.0;
+        c *= Color::new(0.5, 1.0, 0.0);
+        c /= 4.0;
+        assert_eq!(c, Color::new(0.25, 0.125, 0.0));
+    }
+
+    #[test]
+    fn test_color_display() {
+        assert_eq!(Color::new(0.5, 0.5, 1.0).to_string(), "rgb(0.5, 0.5, 1)");
+        assert_eq!(
+            format!("{:.1}", Color::new(0.5, 0.25, 1.0)),
+            "rgb(0.5, 0.2, 1.0)"
+        );
+    }
+
     #[test]
     fn test_color_construction() {
         let c = Color::new(-0.5, 0.4, 1.7);
@@ -121,4 +388,148 @@ mod test {
 
         assert_eq!(c1 * c2, Color::new(0.9, 0.2, 0.04));
     }
+
+    #[test]
+    fn test_color_from_u8() {
+        assert_eq!(Color::from_u8(255, 0, 51), Color::new(1.0, 0.0, 0.2));
+    }
+
+    #[test]
+    fn test_color_from_hex() {
+        assert_eq!(Color::from_hex("#ff0033"), Some(Color::new(1.0, 0.0, 0.2)));
+        assert_eq!(Color::from_hex("FF0033"), Some(Color::new(1.0, 0.0, 0.2)));
+        assert_eq!(Color::from_hex("#f03"), Some(Color::new(1.0, 0.0, 0.2)));
+    }
+
+    #[test]
+    fn test_color_from_invalid_hex() {
+        assert_eq!(Color::from_hex("#ff003"), None);
+        assert_eq!(Color::from_hex("#gg0033"), None);
+        assert_eq!(Color::from_hex("#+f+0+3"), None);
+        assert_eq!(Color::from_hex(""), None);
+        assert_eq!(Color::from_hex("#ffé03"), None);
+    }
+
+    #[test]
+    fn test_color_to_u8() {
+        assert_eq!(Color::new(1.0, 0.0, 0.2).to_u8(), (255, 0, 51));
+        assert_eq!(Color::new(1.5, -0.5, 0.5).to_u8(), (255, 0, 128));
+    }
+
+    #[test]
+    fn test_color_to_srgb() {
+        let c = Color::new(0.0, 0.002, 0.5).to_srgb();
+        assert_eq!(c, Color::new(0.0, 0.02584, 0.73536));
+        assert_eq!(Color::new(1.0, -1.0, 0.18).to_srgb().to_u8(), (255, 0, 118));
+    }
+
+    #[test]
+    fn test_color_srgb_round_trip() {
+        let c = Color::new(0.001, 0.2, 0.9);
+        let encoded = c.to_srgb();
+        assert_eq!(
+            Color::from_srgb(encoded.red(), encoded.green(), encoded.blue()),
+            c
+        );
+        assert_eq!(
+            Color::from_srgb(0.5, 0.5, 0.5),
+            Color::new(0.21404, 0.21404, 0.21404)
+        );
+    }
+
+    #[test]
+    fn test_color_to_u8_dithered() {
+        let c = Color::new(100.25 / 255.0, 1.0, -0.1);
+        let mut total = 0;
+        for y in 0..8 {
+            for x in 0..8 {
+                let (r, g, b) = c.to_u8_dithered(x, y);
+                assert!(r == 100 || r == 101);
+                assert_eq!((g, b), (255, 0));
+                total += r as usize;
+            }
+        }
+        assert_eq!(total, 100 * 64 + 16);
+        assert_eq!(c.to_u8_dithered(3, 5), c.to_u8_dithered(11, 13));
+    }
+
+    #[test]
+    fn test_color_hex_round_trip() {
+        let c = Color::from_hex("#12abef").unwrap();
+        assert_eq!(c.to_u8(), (0x12, 0xab, 0xef));
+    }
+
+    #[test]
+    fn test_color_clamp() {
+        let c = Color::new(-0.5, 0.4, 1.7);
+        assert_eq!(c.clamp(0.0, 1.0), Color::new(0.0, 0.4, 1.0));
+        assert_eq!(c.clamp(0.5, 0.6), Color::new(0.5, 0.5, 0.6));
+    }
+
+    #[test]
+    fn test_color_lerp() {
+        let a = Color::new(0.0, 0.5, 1.0);
+        let b = Color::new(1.0, 0.5, 0.0);
+        assert_eq!(a.lerp(b, 0.0), a);
+        assert_eq!(a.lerp(b, 1.0), b);
+        assert_eq!(a.lerp(b, 0.25), Color::new(0.25, 0.5, 0.75));
+    }
+
+    #[test]
+    fn test_color_divide_scalar() {
+        let c1 = Color::new(0.2, 0.3, 0.4);
+
+        assert_eq!(c1 / 2.0, Color::new(0.1, 0.15, 0.2));
+    }
+
+    #[test]
+    fn test_color_sum() {
+        let colors = [
+            Color::new(0.1, 0.2, 0.3),
+            Color::new(0.4, 0.5, 0.6),
+            Color::new(0.7, 0.8, 0.9),
+        ];
+        assert_eq!(colors.iter().sum::<Color>(), Color::new(1.2, 1.5, 1.8));
+        assert_eq!(colors.into_iter().sum::<Color>(), Color::new(1.2, 1.5, 1.8));
+        assert_eq!(
+            std::iter::empty::<Color>().sum::<Color>(),
+            Color::new(0.0, 0.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn test_color_average() {
+        let colors = [Color::new(0.1, 0.2, 0.3), Color::new(0.5, 0.6, 0.7)];
+        assert_eq!(Color::average(colors), Color::new(0.3, 0.4, 0.5));
+        assert_eq!(Color::average([]), Color::new(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_color_luminance() {
+        assert_approx_eq!(Color::new(0.4, 0.4, 0.4).luminance(), 0.4);
+        assert_approx_eq!(Color::new(1.0, 0.0, 0.0).luminance(), 0.2126);
+        assert_approx_eq!(Color::new(0.0, 1.0, 1.0).luminance(), 0.7874);
+    }
+
+    #[test]
+    fn test_scalar_multiply_color() {
+        let c1 = Color::new(0.2, 0.3, 0.4);
+
+        assert_eq!(2.0 * c1, Color::new(0.4, 0.6, 0.8));
+        assert_eq!(2.0 * &c1, Color::new(0.4, 0.6, 0.8));
+    }
+
+    #[test]
+    fn test_color_reference_operators() {
+        let c1 = Color::new(0.9, 0.6, 0.75);
+        let c2 = Color::new(0.7, 0.1, 0.25);
+
+        let (r1, r2) = (&c1, &c2);
+
+        assert_eq!(r1 + r2, c1 + c2);
+        assert_eq!(r1 - r2, c1 - c2);
+        assert_eq!(r1 * r2, c1 * c2);
+        assert_eq!(r1 * 2.0, c1 * 2.0);
+        assert_eq!(r1 / 2.0, c1 / 2.0);
+    }
 }
@@ -0,0 +1,167 @@
+use crate::canvas::Canvas;
+use crate::color::Color;
+use crate::Float;
+
+/// Settings for [`Canvas::denoise`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DenoiseOptions {
+    /// How far, in pixels, to look for neighbours to average with.
+    pub radius: usize,
+    /// How quickly neighbours' weights fall off with distance, in pixels.
+    pub spatial_sigma: Float,
+    /// How different a neighbour's color can be and still be averaged in.
+    /// Smaller values keep edges sharper but remove less noise.
+    pub color_sigma: Float,
+    /// Like `color_sigma`, for the difference between pixels in each guide
+    /// image.
+    pub guide_sigma: Float,
+}
+
+impl Default for DenoiseOptions {
+    fn default() -> Self {
+        Self {
+            radius: 3,
+            spatial_sigma: 1.5,
+            color_sigma: 0.2,
+            guide_sigma: 0.1,
+        }
+    }
+}
+
+fn distance_squared(a: Color, b: Color) -> Float {
+    let d = a - b;
+    d.red() * d.red() + d.green() * d.green() + d.blue() * d.blue()
+}
+
+impl Canvas {
+    /// Smooth out sampling noise with a bilateral filter, which averages each
+    /// pixel with those around it of similar color, so edges stay sharp.
+    pub fn denoise(&self, options: &DenoiseOptions) -> Canvas {
+        self.denoise_guided(&[], options)
+    }
+
+    /// Like [`Canvas::denoise`], but also only averaging pixels that are
+    /// similar in each of `guides`: noise-free images of the same size, such
+    /// as [`Camera::render_normals`] and [`Camera::render_depth`]. This keeps
+    /// edges between surfaces that noise would otherwise hide.
+    ///
+    /// Panics if a guide isn't the same size as the canvas.
+    ///
+    /// [`Camera::render_normals`]: crate::camera::Camera::render_normals
+    /// [`Camera::render_depth`]: crate::camera::Camera::render_depth
+    pub fn denoise_guided(&self, guides: &[&Canvas], options: &DenoiseOptions) -> Canvas {
+        for guide in guides {
+            assert!(
+                guide.width == self.width && guide.height == self.height,
+                "denoising guides must be the same size as the canvas"
+            );
+        }
+
+        let spatial = -0.5 / (options.spatial_sigma * options.spatial_sigma);
+        let color = -0.5 / (options.color_sigma * options.color_sigma);
+        let guide = -0.5 / (options.guide_sigma * options.guide_sigma);
+        let radius = options.radius as isize;
+
+        let mut image = Canvas::new(self.width, self.height);
+        for (x, y, pixel) in image.enumerate_pixels_mut() {
+            let center = self.pixel_at(x, y);
+            let mut total = Color::new(0.0, 0.0, 0.0);
+            let mut total_weight = 0.0;
+            for dy in -radius..=radius {
+                for dx in -radius..=radius {
+                    let (Some(nx), Some(ny)) = (x.checked_add_signed(dx), y.checked_add_signed(dy))
+                    else {
+                        continue;
+                    };
+                    if nx >= self.width || ny >= self.height {
+                        continue;
+                    }
+
+                    let neighbour = self.pixel_at(nx, ny);
+                    let mut exponent = spatial * (dx * dx + dy * dy) as Float
+                        + color * distance_squared(center, neighbour);
+                    for g in guides {
+                        exponent += guide * distance_squared(g.pixel_at(x, y), g.pixel_at(nx, ny));
+                    }
+                    let weight = exponent.exp();
+                    total += neighbour * weight;
+                    total_weight += weight;
+                }
+            }
+            // The pixel itself always has weight 1.0, so this never divides
+            // by zero.
+            *pixel = total / total_weight;
+        }
+        image
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Mid gray with alternate pixels brighter and darker.
+    fn noisy_gray(width: usize, height: usize) -> Canvas {
+        let mut c = Canvas::new(width, height);
+        for (x, y, pixel) in c.enumerate_pixels_mut() {
+            let v = if (x + y) % 2 == 0 { 0.55 } else { 0.45 };
+            *pixel = Color::new(v, v, v);
+        }
+        c
+    }
+
+    fn variance(c: &Canvas) -> Float {
+        let mean = Color::average(c.pixels().copied()).red();
+        c.pixels().map(|p| (p.red() - mean).powi(2)).sum::<Float>() / (c.width * c.height) as Float
+    }
+
+    #[test]
+    fn test_denoise_smooths_noise() {
+        let c = noisy_gray(8, 8);
+        let denoised = c.denoise(&DenoiseOptions::default());
+        assert!(variance(&denoised) < variance(&c) / 10.0);
+        assert!((denoised.pixel_at(4, 4).red() - 0.5).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_denoise_keeps_edges() {
+        let mut c = Canvas::new(8, 4);
+        for (x, _, pixel) in c.enumerate_pixels_mut() {
+            if x >= 4 {
+                *pixel = Color::new(1.0, 1.0, 1.0);
+            }
+        }
+        let denoised = c.denoise(&DenoiseOptions::default());
+        assert!(denoised.pixel_at(3, 2).red() < 0.01);
+        assert!(denoised.pixel_at(4, 2).red() > 0.99);
+    }
+
+    #[test]
+    fn test_guides_keep_hidden_edges() {
+        // A bright pixel just left of an edge that only the guide shows.
+        let mut c = noisy_gray(8, 4);
+        c.write_pixel(3, 2, Color::new(1.0, 1.0, 1.0));
+        let mut guide = Canvas::new(8, 4);
+        for (x, _, pixel) in guide.enumerate_pixels_mut() {
+            if x >= 4 {
+                *pixel = Color::new(0.0, 0.0, 1.0);
+            }
+        }
+        let options = DenoiseOptions {
+            color_sigma: 1.0,
+            ..DenoiseOptions::default()
+        };
+
+        let unguided = c.denoise(&options).pixel_at(4, 2).red();
+        let guided = c.denoise_guided(&[&guide], &options).pixel_at(4, 2).red();
+        assert!(guided < unguided);
+        assert!((guided - 0.5).abs() < 0.02);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_guide_size_must_match() {
+        Canvas::new(4, 4).denoise_guided(&[&Canvas::new(2, 2)], &DenoiseOptions::default());
+    }
+}
@@ -0,0 +1,312 @@
+use std::io::{Error, ErrorKind, Result};
+
+use crate::camera::Camera;
+use crate::canvas::Canvas;
+use crate::checkpoint::Checkpoint;
+use crate::tile::Tile;
+use crate::world::World;
+
+/// A share of a render to send to a worker: a run of tiles, and the hash of
+/// the scene they belong to so a worker with a different scene refuses it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Chunk {
+    pub id: usize,
+    pub scene_hash: u64,
+    pub tiles: Vec<Tile>,
+}
+
+/// The tiles of a [`Chunk`], rendered by a worker.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChunkResult {
+    pub id: usize,
+    pub scene_hash: u64,
+    pub tiles: Vec<(Tile, Canvas)>,
+}
+
+/// Identifies a camera and world, so chunks are only rendered and merged for
+/// the scene they came from. The hash is stable between runs and machines
+/// using the same version of this crate, but not across versions.
+pub fn scene_hash(camera: &Camera, world: &World) -> u64 {
+    // FNV-1a over the scene's debug output, which includes every field and
+    // prints floats exactly, but leaves out indexes built on first use.
+    format!("{camera:?}{world:?}")
+        .bytes()
+        .fold(0xcbf29ce484222325, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+        })
+}
+
+/// A render split into [`Chunk`]s for a farm of workers, which each render
+/// their chunks with [`Camera::render_chunk`] and send back the results to
+/// be merged. Chunks and results are plain data, serializable with the
+/// `serde` feature, so they can be passed over any transport.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RenderJob {
+    chunks: Vec<Chunk>,
+    merged: Vec<bool>,
+    checkpoint: Checkpoint,
+}
+
+impl RenderJob {
+    /// Split the render of `world` by `camera` into `chunk_count` chunks of
+    /// `tile_size` square tiles, or one per tile if there are fewer tiles.
+    /// Chunks differ in size by at most a tile, the first ones being larger.
+    pub fn new(camera: &Camera, world: &World, tile_size: usize, chunk_count: usize) -> Self {
+        assert!(chunk_count > 0, "chunk count must be positive");
+
+        let scene_hash = scene_hash(camera, world);
+        let checkpoint = Checkpoint::new(camera.hsize(), camera.vsize(), tile_size);
+        let tiles = checkpoint.remaining_tiles();
+        let chunk_count = chunk_count.min(tiles.len());
+        let mut rest = &tiles[..];
+        let chunks: Vec<Chunk> = (0..chunk_count)
+            .map(|id| {
+                let size = tiles.len() / chunk_count + usize::from(id < tiles.len() % chunk_count);
+                let (chunk, others) = rest.split_at(size);
+                rest = others;
+                Chunk {
+                    id,
+                    scene_hash,
+                    tiles: chunk.to_vec(),
+                }
+            })
+            .collect();
+
+        Self {
+            merged: vec![false; chunks.len()],
+            chunks,
+            checkpoint,
+        }
+    }
+
+    pub fn chunks(&self) -> &[Chunk] {
+        &self.chunks
+    }
+
+    /// The chunks without results yet, to hand out again if a worker is lost.
+    pub fn remaining_chunks(&self) -> Vec<&Chunk> {
+        self.chunks
+            .iter()
+            .zip(&self.merged)
+            .filter(|(_, &merged)| !merged)
+            .map(|(chunk, _)| chunk)
+            .collect()
+    }
+
+    /// Copy a worker's rendered tiles into the image. Merging a chunk again
+    /// replaces its tiles.
+    ///
+    /// Fails with [`ErrorKind::InvalidData`] if the result is for another
+    /// scene or doesn't hold exactly the tiles of its chunk.
+    pub fn merge(&mut self, result: &ChunkResult) -> Result<()> {
+        let chunk = self
+            .chunks
+            .get(result.id)
+            .ok_or_else(|| invalid(format!("no chunk {}", result.id)))?;
+        if result.scene_hash != chunk.scene_hash {
+            return Err(invalid(format!("chunk {} is for another scene", result.id)));
+        }
+        let matches = result.tiles.len() == chunk.tiles.len()
+            && result
+                .tiles
+                .iter()
+                .zip(&chunk.tiles)
+                .all(|((tile, image), expected)| {
+                    tile == expected && (image.width, image.height) == (tile.width, tile.height)
+                });
+        if !matches {
+            return Err(invalid(format!("chunk {} has the wrong tiles", result.id)));
+        }
+
+        for (tile, image) in &result.tiles {
+            self.checkpoint.complete(tile, image);
+        }
+        self.merged[result.id] = true;
+        Ok(())
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.merged.iter().all(|&merged| merged)
+    }
+
+    /// The image so far. Tiles from chunks without results are black.
+    pub fn image(&self) -> &Canvas {
+        self.checkpoint.image()
+    }
+
+    pub fn into_image(self) -> Canvas {
+        self.checkpoint.into_image()
+    }
+}
+
+fn invalid(message: String) -> Error {
+    Error::new(ErrorKind::InvalidData, message)
+}
+
+impl Camera {
+    /// Render the tiles of `chunk`, on a worker with the same camera and
+    /// world as the [`RenderJob`] it came from.
+    ///
+    /// Fails with [`ErrorKind::InvalidInput`] if the chunk is for a different
+    /// scene.
+    pub fn render_chunk(&self, world: &World, chunk: &Chunk) -> Result<ChunkResult> {
+        if scene_hash(self, world) != chunk.scene_hash {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("chunk {} is for another scene", chunk.id),
+            ));
+        }
+
+        let tiles = chunk
+            .tiles
+            .iter()
+            .map(|tile| (*tile, self.render_tile(world, tile)))
+            .collect();
+        Ok(ChunkResult {
+            id: chunk.id,
+            scene_hash: chunk.scene_hash,
+            tiles,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::consts::PI;
+
+    use super::*;
+    use crate::color::Color;
+    use crate::fixtures::{default_camera, default_world};
+    use crate::lighting::PointLight;
+    use crate::space::Point;
+    use crate::world::Acceleration;
+
+    #[test]
+    fn test_scene_hash() {
+        let w = default_world();
+        let c = default_camera();
+        assert_eq!(scene_hash(&c, &w), scene_hash(&c.clone(), &w.clone()));
+
+        let mut other = w.clone();
+        other.set_light(PointLight::new(
+            Point::new(-10.0, 10.0, -10.0),
+            Color::new(0.5, 0.5, 0.5),
+        ));
+        assert_ne!(scene_hash(&c, &w), scene_hash(&c, &other));
+        assert_ne!(scene_hash(&c, &w), scene_hash(&Camera::new(11, 11, PI), &w));
+    }
+
+    #[test]
+    fn test_scene_hash_ignores_built_index() {
+        let mut w = default_world();
+        w.set_acceleration(Acceleration::octree());
+        let c = default_camera();
+        let before = scene_hash(&c, &w);
+        w.intersect(&c.ray_for_pixel(5, 5));
+        assert_eq!(scene_hash(&c, &w), before);
+    }
+
+    #[test]
+    fn test_split_into_chunks() {
+        let job = RenderJob::new(&default_camera(), &default_world(), 4, 4);
+        // 9 tiles in chunks of 3, 2, 2 and 2.
+        assert_eq!(job.chunks().len(), 4);
+        assert_eq!(job.chunks()[1].id, 1);
+        assert_eq!(job.chunks()[1].tiles, Tile::grid(11, 11, 4)[3..5]);
+
+        let job = RenderJob::new(&default_camera(), &default_world(), 4, 20);
+        assert_eq!(job.chunks().len(), 9);
+    }
+
+    #[test]
+    fn test_split_into_even_chunks() {
+        // A row of 10 one-pixel tiles.
+        let c = Camera::new(10, 1, PI / 2.0);
+        let job = RenderJob::new(&c, &default_world(), 1, 4);
+        let sizes: Vec<_> = job.chunks().iter().map(|chunk| chunk.tiles.len()).collect();
+        assert_eq!(sizes, [3, 3, 2, 2]);
+
+        let tiles: Vec<_> = job
+            .chunks()
+            .iter()
+            .flat_map(|chunk| chunk.tiles.clone())
+            .collect();
+        assert_eq!(tiles, Tile::grid(10, 1, 1));
+    }
+
+    #[test]
+    fn test_split_empty_image() {
+        let c = Camera::new(0, 0, PI / 2.0);
+        let job = RenderJob::new(&c, &default_world(), 4, 4);
+        assert!(job.chunks().is_empty());
+        assert!(job.is_complete());
+    }
+
+    #[test]
+    fn test_render_and_merge_chunks() {
+        let w = default_world();
+        let c = default_camera();
+        let mut job = RenderJob::new(&c, &w, 4, 2);
+        let chunks = job.chunks().to_vec();
+
+        // Results can come back in any order.
+        for chunk in chunks.iter().rev() {
+            assert!(!job.is_complete());
+            let result = c.render_chunk(&w, chunk).unwrap();
+            job.merge(&result).unwrap();
+            assert!(!job.remaining_chunks().contains(&chunk));
+        }
+        assert!(job.is_complete());
+        assert_eq!(job.into_image(), c.render(&w));
+    }
+
+    #[test]
+    fn test_render_chunk_for_another_scene() {
+        let w = default_world();
+        let job = RenderJob::new(&default_camera(), &w, 4, 2);
+        let err = Camera::new(11, 11, PI)
+            .render_chunk(&w, &job.chunks()[0])
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn test_merge_invalid_results() {
+        let w = default_world();
+        let c = default_camera();
+        let mut job = RenderJob::new(&c, &w, 4, 2);
+        let result = c.render_chunk(&w, &job.chunks()[0]).unwrap();
+
+        let mut wrong_id = result.clone();
+        wrong_id.id = 5;
+        let mut wrong_scene = result.clone();
+        wrong_scene.scene_hash ^= 1;
+        let mut missing_tile = result.clone();
+        missing_tile.tiles.pop();
+        let mut wrong_size = result.clone();
+        wrong_size.tiles[0].1 = Canvas::new(1, 1);
+
+        for bad in [wrong_id, wrong_scene, missing_tile, wrong_size] {
+            assert_eq!(job.merge(&bad).unwrap_err().kind(), ErrorKind::InvalidData);
+        }
+        assert_eq!(job.remaining_chunks().len(), 2);
+        assert_eq!(job.image(), &Canvas::new(11, 11));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_chunks_serialize() {
+        let w = default_world();
+        let c = default_camera();
+        let job = RenderJob::new(&c, &w, 4, 2);
+        let json = serde_json::to_string(&job.chunks()[0]).unwrap();
+        let chunk: Chunk = serde_json::from_str(&json).unwrap();
+        assert_eq!(chunk, job.chunks()[0]);
+
+        let result = c.render_chunk(&w, &chunk).unwrap();
+        let json = serde_json::to_string(&result).unwrap();
+        assert_eq!(serde_json::from_str::<ChunkResult>(&json).unwrap(), result);
+    }
+}
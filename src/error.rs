@@ -0,0 +1,96 @@
+use std::io;
+
+/// The ways fallible operations in this crate can fail.
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum Error {
+    #[error("{row}, {col} is out of bounds for a {rows}x{cols} matrix")]
+    MatrixIndexOutOfBounds {
+        row: usize,
+        col: usize,
+        rows: usize,
+        cols: usize,
+    },
+    #[error("a {rows}x{cols} matrix needs {} values, not {len}", rows * cols)]
+    MatrixSizeMismatch {
+        rows: usize,
+        cols: usize,
+        len: usize,
+    },
+    #[error(transparent)]
+    Matrix(#[from] MatrixError),
+    #[error("({x}, {y}) is outside the {width}x{height} canvas")]
+    PixelOutOfBounds {
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+    },
+    #[error("quad edges must not be parallel")]
+    ParallelQuadEdges,
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[cfg(feature = "gltf")]
+    #[error(transparent)]
+    Gltf(#[from] ::gltf::Error),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Why a matrix couldn't be inverted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum MatrixError {
+    /// Its determinant is zero, or close enough that the inverse would be
+    /// rounding noise.
+    #[error("matrix is singular")]
+    Singular,
+    #[error("only square matrices can be inverted, not a {rows}x{cols} matrix")]
+    NotSquare { rows: usize, cols: usize },
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_error_messages() {
+        let e = Error::MatrixIndexOutOfBounds {
+            row: 4,
+            col: 1,
+            rows: 4,
+            cols: 4,
+        };
+        assert_eq!(e.to_string(), "4, 1 is out of bounds for a 4x4 matrix");
+
+        let e = Error::MatrixSizeMismatch {
+            rows: 2,
+            cols: 3,
+            len: 5,
+        };
+        assert_eq!(e.to_string(), "a 2x3 matrix needs 6 values, not 5");
+
+        let e: Error = MatrixError::NotSquare { rows: 2, cols: 3 }.into();
+        assert_eq!(
+            e.to_string(),
+            "only square matrices can be inverted, not a 2x3 matrix"
+        );
+    }
+
+    #[test]
+    fn test_pixel_error_message() {
+        let e = Error::PixelOutOfBounds {
+            x: 10,
+            y: 2,
+            width: 10,
+            height: 5,
+        };
+        assert_eq!(e.to_string(), "(10, 2) is outside the 10x5 canvas");
+    }
+
+    #[test]
+    fn test_from_io_error() {
+        let e: Error = io::Error::new(io::ErrorKind::NotFound, "missing").into();
+        assert!(matches!(e, Error::Io(_)));
+        assert_eq!(e.to_string(), "missing");
+    }
+}
@@ -0,0 +1,37 @@
+//! Ready-made scenes for examples and tests, enabled with the
+//! `test-fixtures` feature.
+
+use crate::camera::Camera;
+use crate::color::Color;
+use crate::consts::PI;
+use crate::matrix4::Matrix4;
+use crate::scene::SceneBuilder;
+use crate::space::{Point, Vector};
+use crate::world::World;
+
+/// The book's default world: a white point light at (-10, 10, -10) shining
+/// on two spheres at the origin, a unit sphere colored (0.8, 1.0, 0.6) with
+/// diffuse 0.7 and specular 0.2, and inside it one half its size.
+pub fn default_world() -> World {
+    SceneBuilder::new()
+        .light(Point::new(-10.0, 10.0, -10.0), Color::new(1.0, 1.0, 1.0))
+        .sphere(|s| {
+            s.colored(Color::new(0.8, 1.0, 0.6))
+                .diffuse(0.7)
+                .specular(0.2)
+        })
+        .sphere(|s| s.scaled(0.5, 0.5, 0.5))
+        .build()
+}
+
+/// The camera the book renders the default world with: 11 x 11 pixels with a
+/// field of view of π/2, at (0, 0, -5) looking at the origin.
+pub fn default_camera() -> Camera {
+    let mut c = Camera::new(11, 11, PI / 2.0);
+    c.set_transform(Matrix4::view_transform(
+        Point::new(0.0, 0.0, -5.0),
+        Point::origin(),
+        Vector::new(0.0, 1.0, 0.0),
+    ));
+    c
+}
@@ -0,0 +1,330 @@
+use std::collections::HashMap;
+use std::io::{prelude::*, Error, ErrorKind, Result};
+
+use crate::canvas::Canvas;
+use crate::color::{dither_offset, Color};
+use crate::tonemap::OutputOptions;
+use crate::Float;
+
+/// Levels per channel in the fixed palette: a 6x6x6 color cube.
+const LEVELS: usize = 6;
+
+/// Codes are 8 bits to start with, one per palette entry.
+const MIN_CODE_SIZE: u32 = 8;
+
+/// The most codes an LZW dictionary can hold.
+const MAX_CODES: u16 = 4096;
+
+/// The palette index for a pixel at (`x`, `y`), dithering between the
+/// nearest levels of the color cube so gradients don't band.
+fn palette_index(color: Color, x: usize, y: usize) -> u8 {
+    let offset = dither_offset(x, y);
+    let max = (LEVELS - 1) as Float;
+    let level = |c: Float| (c * max + offset).round().clamp(0.0, max) as usize;
+    (level(color.red()) * LEVELS * LEVELS + level(color.green()) * LEVELS + level(color.blue()))
+        as u8
+}
+
+/// The 256 entry color table: the color cube, padded with black.
+fn palette() -> Vec<u8> {
+    let mut table = vec![0; 256 * 3];
+    let step = 255 / (LEVELS - 1);
+    for i in 0..LEVELS * LEVELS * LEVELS {
+        table[i * 3] = (i / (LEVELS * LEVELS) * step) as u8;
+        table[i * 3 + 1] = (i / LEVELS % LEVELS * step) as u8;
+        table[i * 3 + 2] = (i % LEVELS * step) as u8;
+    }
+    table
+}
+
+/// Packs variable-length codes into bytes, least significant bit first.
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    pending: u32,
+    bits: u32,
+}
+
+impl BitWriter {
+    fn write(&mut self, code: u16, size: u32) {
+        self.pending |= (code as u32) << self.bits;
+        self.bits += size;
+        while self.bits >= 8 {
+            self.bytes.push(self.pending as u8);
+            self.pending >>= 8;
+            self.bits -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.bits > 0 {
+            self.bytes.push(self.pending as u8);
+        }
+        self.bytes
+    }
+}
+
+/// Compress palette indices with GIF's variant of LZW, starting over with a
+/// clear code whenever the dictionary fills up.
+fn lzw_compress(indices: &[u8]) -> Vec<u8> {
+    let clear = 1 << MIN_CODE_SIZE;
+    let end = clear + 1;
+    let mut dictionary: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut next = end + 1;
+    let mut size = MIN_CODE_SIZE + 1;
+
+    let mut out = BitWriter::default();
+    out.write(clear, size);
+    let mut prefix: Option<u16> = None;
+    for &index in indices {
+        let Some(p) = prefix else {
+            prefix = Some(index as u16);
+            continue;
+        };
+        if let Some(&code) = dictionary.get(&(p, index)) {
+            prefix = Some(code);
+            continue;
+        }
+
+        out.write(p, size);
+        if next < MAX_CODES {
+            dictionary.insert((p, index), next);
+            next += 1;
+            if next > 1 << size && size < 12 {
+                size += 1;
+            }
+        } else {
+            out.write(clear, size);
+            dictionary.clear();
+            next = end + 1;
+            size = MIN_CODE_SIZE + 1;
+        }
+        prefix = Some(index as u16);
+    }
+    if let Some(p) = prefix {
+        out.write(p, size);
+    }
+    out.write(end, size);
+    out.finish()
+}
+
+/// Write `data` as a series of sub-blocks of up to 255 bytes, ending with an
+/// empty one.
+fn write_sub_blocks(sink: &mut impl Write, data: &[u8]) -> Result<()> {
+    for block in data.chunks(255) {
+        sink.write_all(&[block.len() as u8])?;
+        sink.write_all(block)?;
+    }
+    sink.write_all(&[0])
+}
+
+/// Write `frames` as an animated GIF that loops forever, showing each frame
+/// for `frame_delay` hundredths of a second.
+///
+/// GIF images have at most 256 colors, so colors are clamped to the range
+/// 0.0 to 1.0 and dithered to a fixed palette of 216. A lone frame makes a
+/// still image.
+///
+/// Fails with [`ErrorKind::InvalidInput`] if there are no frames, they aren't
+/// all the same size, or they're more than 65535 pixels wide or high.
+pub fn write_animated_gif(
+    sink: &mut impl Write,
+    frames: &[Canvas],
+    frame_delay: u16,
+) -> Result<()> {
+    let invalid = |message| Error::new(ErrorKind::InvalidInput, message);
+    let first = frames
+        .first()
+        .ok_or_else(|| invalid("no frames to write"))?;
+    if frames
+        .iter()
+        .any(|f| f.width != first.width || f.height != first.height)
+    {
+        return Err(invalid("frames must all be the same size"));
+    }
+    let width = u16::try_from(first.width).map_err(|_| invalid("frames too large for GIF"))?;
+    let height = u16::try_from(first.height).map_err(|_| invalid("frames too large for GIF"))?;
+
+    sink.write_all(b"GIF89a")?;
+    sink.write_all(&width.to_le_bytes())?;
+    sink.write_all(&height.to_le_bytes())?;
+    // A global color table of 256 entries, with no background or aspect
+    // ratio.
+    sink.write_all(&[0xf7, 0, 0])?;
+    sink.write_all(&palette())?;
+
+    if frames.len() > 1 {
+        // Loop forever.
+        sink.write_all(&[0x21, 0xff, 11])?;
+        sink.write_all(b"NETSCAPE2.0")?;
+        sink.write_all(&[3, 1, 0, 0, 0])?;
+    }
+
+    for frame in frames {
+        // Graphic control: leave each frame in place and wait.
+        sink.write_all(&[0x21, 0xf9, 4, 0x04])?;
+        sink.write_all(&frame_delay.to_le_bytes())?;
+        sink.write_all(&[0, 0])?;
+
+        // The frame covers the whole image and uses the global colors.
+        sink.write_all(&[0x2c, 0, 0, 0, 0])?;
+        sink.write_all(&width.to_le_bytes())?;
+        sink.write_all(&height.to_le_bytes())?;
+        sink.write_all(&[0])?;
+
+        let mut indices = Vec::with_capacity(frame.width * frame.height);
+        for row in 0..frame.height {
+            for col in 0..frame.width {
+                indices.push(palette_index(frame.pixel_at(col, row), col, row));
+            }
+        }
+        sink.write_all(&[MIN_CODE_SIZE as u8])?;
+        write_sub_blocks(sink, &lzw_compress(&indices))?;
+    }
+
+    sink.write_all(&[0x3b])
+}
+
+impl Canvas {
+    /// Write the canvas as a still GIF image, as for [`write_animated_gif`].
+    pub fn write_gif(&self, sink: &mut impl Write) -> Result<()> {
+        self.write_gif_with_options(sink, &OutputOptions::default())
+    }
+
+    pub fn write_gif_with_options(
+        &self,
+        sink: &mut impl Write,
+        options: &OutputOptions,
+    ) -> Result<()> {
+        let canvas = self.for_output(options);
+        write_animated_gif(sink, std::slice::from_ref(&*canvas), 0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::tonemap::ToneMap;
+
+    /// Reverse `lzw_compress`, following the decoder's rules for growing the
+    /// code size.
+    fn lzw_decompress(data: &[u8]) -> Vec<u8> {
+        let clear = 1 << MIN_CODE_SIZE;
+        let end = clear + 1;
+        let (mut bit, mut size) = (0, MIN_CODE_SIZE + 1);
+        let mut table: Vec<Vec<u8>> = vec![];
+        let mut previous: Option<Vec<u8>> = None;
+        let mut out = vec![];
+        loop {
+            let mut code = 0_usize;
+            for i in 0..size as usize {
+                let b = (data[(bit + i) / 8] >> ((bit + i) % 8)) & 1;
+                code |= (b as usize) << i;
+            }
+            bit += size as usize;
+
+            if code == clear {
+                table = (0..=255).map(|i| vec![i]).chain([vec![], vec![]]).collect();
+                size = MIN_CODE_SIZE + 1;
+                previous = None;
+                continue;
+            }
+            if code == end {
+                return out;
+            }
+            let entry = match (table.get(code), &previous) {
+                (Some(entry), _) => entry.clone(),
+                (None, Some(p)) => [p.clone(), vec![p[0]]].concat(),
+                (None, None) => panic!("bad code"),
+            };
+            if let Some(p) = previous {
+                if table.len() < MAX_CODES as usize {
+                    table.push([p, vec![entry[0]]].concat());
+                    if table.len() == 1 << size && size < 12 {
+                        size += 1;
+                    }
+                }
+            }
+            out.extend_from_slice(&entry);
+            previous = Some(entry);
+        }
+    }
+
+    #[test]
+    fn test_lzw_round_trip() {
+        let repetitive: Vec<u8> = (0..5000).map(|i| (i % 7) as u8).collect();
+        assert_eq!(lzw_decompress(&lzw_compress(&repetitive)), repetitive);
+
+        // Enough varied data to fill the dictionary and start over.
+        let varied: Vec<u8> = (0..20000_u32)
+            .map(|i| (i.wrapping_mul(2654435761) >> 13) as u8)
+            .collect();
+        assert_eq!(lzw_decompress(&lzw_compress(&varied)), varied);
+
+        assert!(lzw_decompress(&lzw_compress(&[])).is_empty());
+    }
+
+    #[test]
+    fn test_palette_index() {
+        assert_eq!(palette_index(Color::new(0.0, 0.0, 0.0), 3, 2), 0);
+        assert_eq!(palette_index(Color::new(1.0, 1.0, 1.0), 3, 2), 215);
+        assert_eq!(palette_index(Color::new(1.0, 0.0, 0.6), 0, 0), 183);
+        assert_eq!(&palette()[183 * 3..184 * 3], &[255, 0, 153]);
+    }
+
+    #[test]
+    fn test_animated_gif() {
+        let mut red = Canvas::new(3, 2);
+        red.fill(Color::new(1.0, 0.0, 0.0));
+        let blue = Canvas::new(3, 2);
+        let mut bytes = Vec::new();
+        write_animated_gif(&mut bytes, &[red, blue], 10).unwrap();
+
+        assert_eq!(&bytes[..13], b"GIF89a\x03\x00\x02\x00\xf7\x00\x00");
+        let rest = &bytes[13 + 768..];
+        assert_eq!(&rest[3..14], b"NETSCAPE2.0");
+        assert_eq!(&rest[19..27], &[0x21, 0xf9, 4, 0x04, 10, 0, 0, 0]);
+        assert_eq!(&rest[27..37], &[0x2c, 0, 0, 0, 0, 3, 0, 2, 0, 0]);
+        assert_eq!(rest[37], 8);
+        let len = rest[38] as usize;
+        assert_eq!(
+            lzw_decompress(&rest[39..39 + len]),
+            [180; 6],
+            "the first frame is all red"
+        );
+        assert_eq!(bytes.last(), Some(&0x3b));
+    }
+
+    #[test]
+    fn test_still_gif() {
+        let mut bytes = Vec::new();
+        Canvas::new(2, 2).write_gif(&mut bytes).unwrap();
+        let rest = &bytes[13 + 768..];
+        assert_eq!(&rest[..4], &[0x21, 0xf9, 4, 0x04]);
+    }
+
+    #[test]
+    fn test_gif_exposure() {
+        let c = Canvas::from_pixels(2, 1, vec![Color::new(2.0, 1.0, 0.0); 2]);
+        let options = OutputOptions {
+            exposure: -1.0,
+            ..OutputOptions::default()
+        };
+        let mut bytes = Vec::new();
+        c.write_gif_with_options(&mut bytes, &options).unwrap();
+        let mut expected = Vec::new();
+        c.tone_mapped(-1.0, ToneMap::None)
+            .write_gif(&mut expected)
+            .unwrap();
+        assert_eq!(bytes, expected);
+    }
+
+    #[test]
+    fn test_gif_frames_must_match() {
+        let err = write_animated_gif(&mut Vec::new(), &[], 0).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        let frames = [Canvas::new(2, 2), Canvas::new(2, 3)];
+        let err = write_animated_gif(&mut Vec::new(), &frames, 0).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    }
+}
@@ -0,0 +1,321 @@
+//! Import scenes from glTF 2.0 files.
+
+use std::io::{self, prelude::*, ErrorKind};
+use std::path::Path;
+
+use ::gltf::{buffer, mesh::Mode, Gltf, Node, Primitive};
+
+use crate::color::Color;
+use crate::error::Result;
+use crate::materials::Material;
+use crate::matrix4::Matrix4;
+use crate::shape::{Group, Shape, Triangle};
+use crate::space::{Point, Vector};
+use crate::Float;
+
+/// Read a scene from a binary glTF file, or a text one with its buffers
+/// embedded. See [`load`].
+pub fn read(source: &mut impl Read) -> Result<Shape> {
+    let mut data = vec![];
+    source.read_to_end(&mut data)?;
+    import(Gltf::from_slice(&data)?, None)
+}
+
+/// Load a glTF scene from `path`, as a group with a group for each of its
+/// nodes, named and transformed like them. A node's mesh becomes a group of
+/// triangles for each of its primitives, coloured with the primitive's
+/// base color and smooth if it has normals.
+///
+/// Only triangle meshes, as lists, strips or fans, transforms and base colors
+/// are read, so points and lines, cameras, lights, textures and animations
+/// are all skipped. A node whose transform can't be inverted, such as a
+/// scale of zero, fails with [`MatrixError::Singular`]. Build a
+/// kd-tree for each large mesh with [`Shape::build_kd_tree`] before
+/// rendering.
+///
+/// [`MatrixError::Singular`]: crate::error::MatrixError::Singular
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(skip_all, fields(path = %path.as_ref().display()))
+)]
+pub fn load(path: impl AsRef<Path>) -> Result<Shape> {
+    let path = path.as_ref();
+    import(Gltf::open(path)?, path.parent())
+}
+
+fn import(gltf: Gltf, base: Option<&Path>) -> Result<Shape> {
+    let Gltf { document, blob } = gltf;
+    let buffers = ::gltf::import_buffers(&document, base, blob)?;
+
+    let mut group = Group::new();
+    if let Some(scene) = document.default_scene().or(document.scenes().next()) {
+        for node in scene.nodes() {
+            group.add_child(read_node(&node, &buffers)?);
+        }
+    }
+    Ok(group.into())
+}
+
+fn read_node(node: &Node, buffers: &[buffer::Data]) -> Result<Shape> {
+    // glTF matrices are stored column by column.
+    let m = node.transform().matrix();
+    let transform = std::array::from_fn(|row| std::array::from_fn(|col| m[col][row] as Float));
+
+    let mut group = Group::new();
+    if let Some(mesh) = node.mesh() {
+        for primitive in mesh.primitives() {
+            if matches!(
+                primitive.mode(),
+                Mode::Triangles | Mode::TriangleStrip | Mode::TriangleFan
+            ) {
+                group.add_child(read_primitive(&primitive, buffers)?);
+            }
+        }
+    }
+    for child in node.children() {
+        group.add_child(read_node(&child, buffers)?);
+    }
+
+    let mut shape: Shape = group.into();
+    shape.try_set_transform(Matrix4::new(transform))?;
+    if let Some(name) = node.name() {
+        shape.set_name(name);
+    }
+    Ok(shape)
+}
+
+fn read_primitive(primitive: &Primitive, buffers: &[buffer::Data]) -> Result<Group> {
+    let reader = primitive.reader(|buffer| buffers.get(buffer.index()).map(|data| &data[..]));
+    let points: Vec<Point> = match reader.read_positions() {
+        Some(positions) => positions
+            .map(|[x, y, z]| Point::new(x as Float, y as Float, z as Float))
+            .collect(),
+        None => vec![],
+    };
+    let normals: Option<Vec<Vector>> = reader.read_normals().map(|normals| {
+        normals
+            .map(|[x, y, z]| Vector::new(x as Float, y as Float, z as Float))
+            .collect()
+    });
+    let indices: Vec<usize> = match reader.read_indices() {
+        Some(indices) => indices.into_u32().map(|index| index as usize).collect(),
+        None => (0..points.len()).collect(),
+    };
+
+    let mut material = Material::new();
+    let [r, g, b, _] = primitive
+        .material()
+        .pbr_metallic_roughness()
+        .base_color_factor();
+    material.color = Color::new(r as Float, g as Float, b as Float);
+
+    let mut group = Group::new();
+    for corners in triangles(primitive.mode(), &indices) {
+        if let Some(&missing) = corners.iter().find(|&&index| index >= points.len()) {
+            let message = format!("glTF primitive refers to missing vertex {missing}");
+            return Err(io::Error::new(ErrorKind::InvalidData, message).into());
+        }
+        let [a, b, c] = corners;
+        let mut triangle = match &normals {
+            Some(normals) if normals.len() == points.len() => Triangle::with_normals(
+                points[a], points[b], points[c], normals[a], normals[b], normals[c],
+            ),
+            _ => Triangle::new(points[a], points[b], points[c]),
+        };
+        *triangle.material_mut() = material.clone();
+        group.add_child(triangle);
+    }
+    Ok(group)
+}
+
+/// The corners of each triangle in a primitive drawn with `mode`, keeping
+/// the winding of the first throughout strips and fans.
+fn triangles(mode: Mode, indices: &[usize]) -> Vec<[usize; 3]> {
+    match mode {
+        Mode::Triangles => indices
+            .chunks_exact(3)
+            .map(|corners| [corners[0], corners[1], corners[2]])
+            .collect(),
+        Mode::TriangleStrip => indices
+            .windows(3)
+            .enumerate()
+            .map(|(i, corners)| match i % 2 {
+                0 => [corners[0], corners[1], corners[2]],
+                _ => [corners[0], corners[2], corners[1]],
+            })
+            .collect(),
+        Mode::TriangleFan => indices
+            .get(1..)
+            .unwrap_or_default()
+            .windows(2)
+            .map(|corners| [corners[0], corners[1], indices[0]])
+            .collect(),
+        _ => vec![],
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::error::{Error, MatrixError};
+
+    /// A binary glTF file with the given JSON and binary chunks.
+    fn glb(json: &str, bin: &[u8]) -> Vec<u8> {
+        let mut json = json.as_bytes().to_vec();
+        json.resize(json.len().next_multiple_of(4), b' ');
+        let mut bin = bin.to_vec();
+        bin.resize(bin.len().next_multiple_of(4), 0);
+
+        let mut glb = vec![];
+        let length = 12 + 8 + json.len() + 8 + bin.len();
+        for word in [0x4654_6c67, 2, length as u32] {
+            glb.extend_from_slice(&u32::to_le_bytes(word));
+        }
+        for (chunk, kind) in [(&json, 0x4e4f_534a_u32), (&bin, 0x004e_4942)] {
+            glb.extend_from_slice(&(chunk.len() as u32).to_le_bytes());
+            glb.extend_from_slice(&kind.to_le_bytes());
+            glb.extend_from_slice(chunk);
+        }
+        glb
+    }
+
+    /// A red primitive of four vertices drawn with `mode`, in a node with
+    /// the given transform inside a named parent.
+    fn primitive_glb(mode: u32, indices: &[u16], transform: &str) -> Vec<u8> {
+        let json = r#"{
+            "asset": {"version": "2.0"},
+            "scene": 0,
+            "scenes": [{"nodes": [0]}],
+            "nodes": [
+                {"name": "parent", "children": [1]},
+                {"mesh": 0, TRANSFORM}
+            ],
+            "meshes": [{"primitives": [
+                {"attributes": {"POSITION": 0}, "indices": 1, "material": 0, "mode": MODE}
+            ]}],
+            "materials": [{"pbrMetallicRoughness": {"baseColorFactor": [1, 0, 0, 1]}}],
+            "buffers": [{"byteLength": BUFFER_LENGTH}],
+            "bufferViews": [
+                {"buffer": 0, "byteOffset": 0, "byteLength": 48},
+                {"buffer": 0, "byteOffset": 48, "byteLength": INDEX_LENGTH}
+            ],
+            "accessors": [
+                {"bufferView": 0, "componentType": 5126, "count": 4, "type": "VEC3",
+                 "min": [-1, -1, 0], "max": [1, 1, 0]},
+                {"bufferView": 1, "componentType": 5123, "count": COUNT, "type": "SCALAR"}
+            ]
+        }"#
+        .replace("TRANSFORM", transform)
+        .replace("MODE", &mode.to_string())
+        .replace("BUFFER_LENGTH", &(48 + 2 * indices.len()).to_string())
+        .replace("INDEX_LENGTH", &(2 * indices.len()).to_string())
+        .replace("COUNT", &indices.len().to_string());
+        let mut bin = vec![];
+        for value in [
+            0.0f32, 1.0, 0.0, -1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, -1.0, 0.0,
+        ] {
+            bin.extend_from_slice(&value.to_le_bytes());
+        }
+        for index in indices {
+            bin.extend_from_slice(&index.to_le_bytes());
+        }
+        glb(&json, &bin)
+    }
+
+    const MOVED_UP: &str = r#""translation": [0, 2, 0]"#;
+
+    /// One triangle, in a node moved up by 2.
+    fn triangle_glb(indices: [u16; 3]) -> Vec<u8> {
+        primitive_glb(4, &indices, MOVED_UP)
+    }
+
+    /// The points of the triangles in the first primitive of `scene`.
+    fn primitive_points(scene: &Shape) -> Vec<(Point, Point, Point)> {
+        let Some(Shape::Group(parent)) = scene.find("parent") else {
+            panic!("expected a group");
+        };
+        let Shape::Group(node) = &parent.children()[0] else {
+            panic!("expected a group");
+        };
+        let Shape::Group(primitive) = &node.children()[0] else {
+            panic!("expected a group");
+        };
+        primitive
+            .children()
+            .iter()
+            .map(|child| match child {
+                Shape::Triangle(triangle) => triangle.points(),
+                _ => unreachable!(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_read_nodes_and_meshes() {
+        let scene = read(&mut triangle_glb([0, 1, 2]).as_slice()).unwrap();
+        let parent = scene.find("parent").unwrap();
+        let Shape::Group(parent) = parent else {
+            panic!("expected a group");
+        };
+        let Shape::Group(node) = &parent.children()[0] else {
+            panic!("expected a group");
+        };
+        assert_eq!(*node.transformation(), Matrix4::translation(0.0, 2.0, 0.0));
+        let Shape::Group(primitive) = &node.children()[0] else {
+            panic!("expected a group");
+        };
+        let triangle = &primitive.children()[0];
+        assert_eq!(triangle.material().color, Color::new(1.0, 0.0, 0.0));
+        assert_eq!(
+            triangle.world_to_object(&Point::new(0.0, 3.0, 0.0)),
+            Point::new(0.0, 1.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn test_read_invalid() {
+        let error = read(&mut triangle_glb([0, 1, 5]).as_slice()).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "glTF primitive refers to missing vertex 5"
+        );
+        let error = read(&mut &b"not gltf"[..]).unwrap_err();
+        assert!(matches!(error, Error::Gltf(_)));
+    }
+
+    #[test]
+    fn test_read_strips_and_fans() {
+        let p = [
+            Point::new(0.0, 1.0, 0.0),
+            Point::new(-1.0, 0.0, 0.0),
+            Point::new(1.0, 0.0, 0.0),
+            Point::new(0.0, -1.0, 0.0),
+        ];
+        let strip = read(&mut primitive_glb(5, &[0, 1, 2, 3], MOVED_UP).as_slice()).unwrap();
+        assert_eq!(
+            primitive_points(&strip),
+            [(p[0], p[1], p[2]), (p[1], p[3], p[2])]
+        );
+        let fan = read(&mut primitive_glb(6, &[0, 1, 3, 2], MOVED_UP).as_slice()).unwrap();
+        assert_eq!(
+            primitive_points(&fan),
+            [(p[1], p[3], p[0]), (p[3], p[2], p[0])]
+        );
+        // Points aren't read.
+        let points = read(&mut primitive_glb(0, &[0, 1, 2, 3], MOVED_UP).as_slice()).unwrap();
+        let Some(Shape::Group(parent)) = points.find("parent") else {
+            panic!("expected a group");
+        };
+        let Shape::Group(node) = &parent.children()[0] else {
+            panic!("expected a group");
+        };
+        assert!(node.children().is_empty());
+    }
+
+    #[test]
+    fn test_read_singular_node_transform() {
+        let glb = primitive_glb(4, &[0, 1, 2], r#""scale": [0, 0, 0]"#);
+        let error = read(&mut glb.as_slice()).unwrap_err();
+        assert!(matches!(error, Error::Matrix(MatrixError::Singular)));
+    }
+}
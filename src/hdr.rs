@@ -0,0 +1,210 @@
+use crate::canvas::Canvas;
+use crate::color::Color;
+use crate::Float;
+use std::io::{prelude::*, Result};
+
+/// Encode a color in Radiance's shared-exponent RGBE format. Negative and
+/// NaN components are stored as zero, and infinite or too large ones as the
+/// largest value the format holds, 255/256 x 2^127.
+fn rgbe(color: Color) -> [u8; 4] {
+    let largest = (2 as Float).powi(127) * (255.0 / 256.0);
+    let channel = |c: Float| c.max(0.0).min(largest);
+    let r = channel(color.red());
+    let g = channel(color.green());
+    let b = channel(color.blue());
+    let v = r.max(g).max(b);
+    if v < 1e-32 {
+        return [0, 0, 0, 0];
+    }
+
+    // Find e such that v / 2^e is in [0.5, 1), correcting for rounding in log2.
+    let mut e = v.log2().floor() as i32 + 1;
+    if v / (2 as Float).powi(e) >= 1.0 {
+        e += 1;
+    }
+    let scale = (2 as Float).powi(8 - e);
+    [
+        (r * scale) as u8,
+        (g * scale) as u8,
+        (b * scale) as u8,
+        (e + 128) as u8,
+    ]
+}
+
+/// Run-length encode one channel of a scanline, as described in the Radiance
+/// file format: runs of three or more identical bytes are stored as a count
+/// (with the high bit set) and the byte, everything else as literal spans.
+fn write_rle_channel(sink: &mut impl Write, data: &[u8]) -> Result<()> {
+    const MIN_RUN: usize = 3;
+    const MAX_RUN: usize = 127;
+    const MAX_LITERAL: usize = 128;
+
+    let mut cur = 0;
+    while cur < data.len() {
+        // Find the next run long enough to be worth encoding.
+        let mut run_start = cur;
+        let mut run_len = 0;
+        while run_start < data.len() {
+            run_len = data[run_start..]
+                .iter()
+                .take(MAX_RUN)
+                .take_while(|&&b| b == data[run_start])
+                .count();
+            if run_len >= MIN_RUN {
+                break;
+            }
+            run_start += run_len;
+            run_len = 0;
+        }
+
+        // Everything before it goes out as literals.
+        while cur < run_start {
+            let count = usize::min(run_start - cur, MAX_LITERAL);
+            sink.write_all(&[count as u8])?;
+            sink.write_all(&data[cur..cur + count])?;
+            cur += count;
+        }
+
+        if run_len >= MIN_RUN {
+            sink.write_all(&[128 + run_len as u8, data[run_start]])?;
+            cur += run_len;
+        }
+    }
+    Ok(())
+}
+
+impl Canvas {
+    /// Write the canvas as a Radiance HDR (.hdr) image.
+    ///
+    /// Unlike PPM, colors are not clamped to 1.0, so bright highlights
+    /// survive for tone mapping in other tools. Negative components are
+    /// written as zero.
+    pub fn write_hdr(&self, sink: &mut impl Write) -> Result<()> {
+        writeln!(sink, "#?RADIANCE")?;
+        writeln!(sink, "FORMAT=32-bit_rle_rgbe")?;
+        writeln!(sink)?;
+        writeln!(sink, "-Y {} +X {}", self.height, self.width)?;
+
+        // Run-length encoding is only defined for these widths.
+        let rle = (8..0x8000).contains(&self.width);
+
+        for row in 0..self.height {
+            let pixels: Vec<[u8; 4]> = (0..self.width)
+                .map(|col| rgbe(self.pixel_at(col, row)))
+                .collect();
+
+            if rle {
+                sink.write_all(&[2, 2, (self.width >> 8) as u8, (self.width & 0xff) as u8])?;
+                for channel in 0..4 {
+                    let data: Vec<u8> = pixels.iter().map(|p| p[channel]).collect();
+                    write_rle_channel(sink, &data)?;
+                }
+            } else {
+                for pixel in pixels {
+                    sink.write_all(&pixel)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn header_len(bytes: &[u8]) -> usize {
+        // The header ends with the resolution line.
+        let mut newlines = 0;
+        for (i, &b) in bytes.iter().enumerate() {
+            if b == b'\n' {
+                newlines += 1;
+                if newlines == 4 {
+                    return i + 1;
+                }
+            }
+        }
+        panic!("Incomplete header");
+    }
+
+    #[test]
+    fn test_hdr_header() {
+        let c = Canvas::new(5, 3);
+        let mut bytes = Vec::new();
+        c.write_hdr(&mut bytes).unwrap();
+        let header = std::str::from_utf8(&bytes[..header_len(&bytes)]).unwrap();
+        assert_eq!(header, "#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y 3 +X 5\n");
+    }
+
+    #[test]
+    fn test_rgbe() {
+        assert_eq!(rgbe(Color::new(0.0, 0.0, 0.0)), [0, 0, 0, 0]);
+        assert_eq!(rgbe(Color::new(1.0, 0.5, 0.25)), [128, 64, 32, 129]);
+        assert_eq!(rgbe(Color::new(-1.0, 0.5, 0.25)), [0, 128, 64, 128]);
+    }
+
+    #[test]
+    fn test_rgbe_does_not_clamp() {
+        assert_eq!(rgbe(Color::new(4.0, 2.0, 1.0)), [128, 64, 32, 131]);
+        assert_eq!(rgbe(Color::new(0.0, 1000.0, 0.0)), [0, 250, 0, 138]);
+    }
+
+    #[test]
+    fn test_rgbe_clamps_out_of_range() {
+        assert_eq!(
+            rgbe(Color::new(Float::INFINITY, 1.0, Float::NAN)),
+            [255, 0, 0, 255]
+        );
+        assert_eq!(
+            rgbe(Color::new(Float::MAX, Float::MAX, Float::MAX)),
+            [255, 255, 255, 255]
+        );
+        assert_eq!(
+            rgbe(Color::new(Float::NAN, Float::NEG_INFINITY, 0.0)),
+            [0, 0, 0, 0]
+        );
+    }
+
+    #[test]
+    fn test_hdr_flat_scanlines_for_narrow_images() {
+        let mut c = Canvas::new(2, 1);
+        c.write_pixel(0, 0, Color::new(1.0, 0.5, 0.25));
+        c.write_pixel(1, 0, Color::new(4.0, 2.0, 1.0));
+        let mut bytes = Vec::new();
+        c.write_hdr(&mut bytes).unwrap();
+        assert_eq!(
+            &bytes[header_len(&bytes)..],
+            &[128, 64, 32, 129, 128, 64, 32, 131]
+        );
+    }
+
+    #[test]
+    fn test_hdr_rle_scanlines() {
+        let mut c = Canvas::new(10, 1);
+        for col in 0..10 {
+            c.write_pixel(col, 0, Color::new(1.0, 0.5, 0.25));
+        }
+        c.write_pixel(9, 0, Color::new(4.0, 2.0, 1.0));
+        let mut bytes = Vec::new();
+        c.write_hdr(&mut bytes).unwrap();
+        let data = &bytes[header_len(&bytes)..];
+        assert_eq!(&data[..4], &[2, 2, 0, 10]);
+        // Red, green and blue are uniform, the exponent changes at the end.
+        assert_eq!(&data[4..6], &[128 + 10, 128]);
+        assert_eq!(&data[6..8], &[128 + 10, 64]);
+        assert_eq!(&data[8..10], &[128 + 10, 32]);
+        assert_eq!(&data[10..], &[128 + 9, 129, 1, 131]);
+    }
+
+    #[test]
+    fn test_rle_long_literals_are_split() {
+        let data: Vec<u8> = (0..200).map(|i| i as u8).collect();
+        let mut bytes = Vec::new();
+        write_rle_channel(&mut bytes, &data).unwrap();
+        assert_eq!(bytes[0], 128);
+        assert_eq!(&bytes[1..129], &data[..128]);
+        assert_eq!(bytes[129], 72);
+        assert_eq!(&bytes[130..], &data[128..]);
+    }
+}
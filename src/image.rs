@@ -0,0 +1,62 @@
+use ::image::{DynamicImage, Rgb, RgbImage};
+
+use crate::canvas::Canvas;
+use crate::color::Color;
+use crate::Float;
+
+impl Canvas {
+    /// Convert an image from the `image` crate, in any of its pixel formats,
+    /// to a canvas. Components are scaled to the range 0.0 to 1.0 and any
+    /// alpha channel is dropped.
+    pub fn from_image(image: &DynamicImage) -> Canvas {
+        let rgb = image.to_rgb32f();
+        let pixels = rgb
+            .pixels()
+            .map(|&Rgb([r, g, b])| Color::new(r as Float, g as Float, b as Float))
+            .collect();
+        Canvas::from_pixels(rgb.width() as usize, rgb.height() as usize, pixels)
+    }
+}
+
+impl From<&Canvas> for RgbImage {
+    fn from(value: &Canvas) -> Self {
+        RgbImage::from_fn(value.width as u32, value.height as u32, |x, y| {
+            let (r, g, b) = value.pixel_at(x as usize, y as usize).to_u8();
+            Rgb([r, g, b])
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ::image::{Rgba, RgbaImage};
+
+    #[test]
+    fn test_canvas_to_rgb_image() {
+        let mut c = Canvas::new(3, 2);
+        c.write_pixel(2, 1, Color::new(1.5, 0.2, -0.5));
+        let image = RgbImage::from(&c);
+        assert_eq!(image.dimensions(), (3, 2));
+        assert_eq!(image.get_pixel(2, 1), &Rgb([255, 51, 0]));
+        assert_eq!(image.get_pixel(0, 0), &Rgb([0, 0, 0]));
+    }
+
+    #[test]
+    fn test_canvas_from_image() {
+        let mut image = RgbaImage::new(2, 3);
+        image.put_pixel(1, 2, Rgba([255, 51, 0, 128]));
+        let c = Canvas::from_image(&DynamicImage::ImageRgba8(image));
+        assert_eq!(c.width, 2);
+        assert_eq!(c.height, 3);
+        assert_eq!(c.pixel_at(1, 2), Color::new(1.0, 0.2, 0.0));
+    }
+
+    #[test]
+    fn test_image_round_trip() {
+        let mut c = Canvas::new(2, 2);
+        c.write_pixel(0, 1, Color::new(0.2, 0.4, 0.6));
+        let image = DynamicImage::ImageRgb8(RgbImage::from(&c));
+        assert_eq!(Canvas::from_image(&image), c);
+    }
+}
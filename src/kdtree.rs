@@ -0,0 +1,237 @@
+use std::fmt;
+
+use crate::bounds::BoundingBox;
+use crate::ray::Ray;
+use crate::space::Point;
+use crate::Float;
+
+/// How deep a [`KdTree`] built by
+/// [`Group::build_kd_tree`](crate::shape::Group::build_kd_tree) may nest.
+pub const DEFAULT_MAX_DEPTH: usize = 24;
+
+/// A spatial index over a list of small objects, found by their positions in
+/// the list, such as the triangles of a mesh.
+///
+/// Space is split in two across the longest axis, at the median of the
+/// objects' centres, and each half again while it holds more than a few
+/// objects. An object crossing a split stays with the node above it, which
+/// for a mesh of many small triangles leaves few objects that every ray
+/// passing through has to test.
+#[derive(Clone, PartialEq)]
+pub struct KdTree {
+    bounds: BoundingBox,
+    root: Node,
+    /// Objects without finite bounds, which every ray has to test.
+    unbounded: Vec<usize>,
+    leaf_size: usize,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Node {
+    /// In a leaf, all the node's objects. Otherwise, those crossing the
+    /// split.
+    objects: Vec<usize>,
+    split: Option<Split>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Split {
+    axis: usize,
+    position: Float,
+    /// Below and above the split.
+    children: Box<[Node; 2]>,
+}
+
+impl KdTree {
+    /// Index objects with the given bounds, splitting nodes with more than
+    /// `leaf_size` objects until they're `max_depth` levels deep.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip(bounds))
+    )]
+    pub fn new(
+        bounds: impl IntoIterator<Item = BoundingBox>,
+        max_depth: usize,
+        leaf_size: usize,
+    ) -> Self {
+        let mut total = BoundingBox::empty();
+        let mut entries = vec![];
+        let mut unbounded = vec![];
+        for (index, object_bounds) in bounds.into_iter().enumerate() {
+            if object_bounds.is_finite() {
+                total.add_box(&object_bounds);
+                entries.push((index, object_bounds));
+            } else {
+                unbounded.push(index);
+            }
+        }
+
+        Self {
+            root: Node::new(entries, &total, max_depth, leaf_size),
+            bounds: total,
+            unbounded,
+            leaf_size,
+        }
+    }
+
+    /// How many objects a node may hold before it's split.
+    pub fn leaf_size(&self) -> usize {
+        self.leaf_size
+    }
+
+    /// Call `visit` with each object that `ray` might hit. Each object is
+    /// visited at most once, in no particular order.
+    pub fn visit(&self, ray: &Ray, mut visit: impl FnMut(usize)) {
+        for &index in &self.unbounded {
+            visit(index);
+        }
+        self.root.visit(&self.bounds, ray, &mut visit);
+    }
+}
+
+/// Only the settings are printed, as the nodes follow from the objects and
+/// would swamp the debug output of the group holding the tree.
+impl fmt::Debug for KdTree {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KdTree")
+            .field("leaf_size", &self.leaf_size)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Node {
+    fn new(
+        mut entries: Vec<(usize, BoundingBox)>,
+        cell: &BoundingBox,
+        depth: usize,
+        leaf_size: usize,
+    ) -> Self {
+        let leaf = |entries: Vec<(usize, BoundingBox)>| Self {
+            objects: entries.into_iter().map(|(index, _)| index).collect(),
+            split: None,
+        };
+        if entries.len() <= leaf_size || depth == 0 {
+            return leaf(entries);
+        }
+
+        let axis = (0..3)
+            .max_by(|&a, &b| extent(cell, a).total_cmp(&extent(cell, b)))
+            .unwrap();
+        let centre = |(_, bounds): &(usize, BoundingBox)| {
+            (bounds.min.get(axis) + bounds.max.get(axis)) / 2.0
+        };
+        let middle = entries.len() / 2;
+        entries.select_nth_unstable_by(middle, |a, b| centre(a).total_cmp(&centre(b)));
+        let position = centre(&entries[middle]);
+
+        let (mut below, mut above, mut crossing) = (vec![], vec![], vec![]);
+        for entry in entries {
+            if entry.1.max.get(axis) <= position {
+                below.push(entry);
+            } else if entry.1.min.get(axis) >= position {
+                above.push(entry);
+            } else {
+                crossing.push(entry);
+            }
+        }
+        // Splitting is no use if everything crosses the split.
+        if below.is_empty() && above.is_empty() {
+            return leaf(crossing);
+        }
+
+        let (below_cell, above_cell) = halves(cell, axis, position);
+        Self {
+            objects: crossing.into_iter().map(|(index, _)| index).collect(),
+            split: Some(Split {
+                axis,
+                position,
+                children: Box::new([
+                    Node::new(below, &below_cell, depth - 1, leaf_size),
+                    Node::new(above, &above_cell, depth - 1, leaf_size),
+                ]),
+            }),
+        }
+    }
+
+    fn visit(&self, cell: &BoundingBox, ray: &Ray, visit: &mut dyn FnMut(usize)) {
+        if !cell.intersects(ray) {
+            return;
+        }
+        for &index in &self.objects {
+            visit(index);
+        }
+        if let Some(split) = &self.split {
+            let (below, above) = halves(cell, split.axis, split.position);
+            split.children[0].visit(&below, ray, visit);
+            split.children[1].visit(&above, ray, visit);
+        }
+    }
+}
+
+fn extent(cell: &BoundingBox, axis: usize) -> Float {
+    cell.max.get(axis) - cell.min.get(axis)
+}
+
+/// `cell` cut in two across `axis` at `position`.
+fn halves(cell: &BoundingBox, axis: usize, position: Float) -> (BoundingBox, BoundingBox) {
+    let mut below_max = cell.max.to_array();
+    let mut above_min = cell.min.to_array();
+    below_max[axis] = position;
+    above_min[axis] = position;
+    (
+        BoundingBox::new(cell.min, Point::from(below_max)),
+        BoundingBox::new(Point::from(above_min), cell.max),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::space::Vector;
+
+    fn visited(tree: &KdTree, ray: &Ray) -> Vec<usize> {
+        let mut indexes = vec![];
+        tree.visit(ray, |index| indexes.push(index));
+        indexes.sort();
+        indexes
+    }
+
+    #[test]
+    fn test_ray_visits_only_nearby_objects() {
+        // A strip of 1000 thin slivers along the x axis.
+        let slivers: Vec<BoundingBox> = (0..1000)
+            .map(|i| {
+                let x = i as Float * 0.1;
+                BoundingBox::new(Point::new(x, 0.0, -0.5), Point::new(x + 0.05, 1.0, 0.5))
+            })
+            .collect();
+        let tree = KdTree::new(slivers, DEFAULT_MAX_DEPTH, 4);
+
+        let ray = Ray::new(Point::new(50.01, 0.5, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let indexes = visited(&tree, &ray);
+        assert!(indexes.contains(&500));
+        assert!(indexes.len() < 20);
+
+        let ray = Ray::new(Point::new(50.01, 2.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(visited(&tree, &ray), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn test_crossing_and_unbounded_objects() {
+        let boxes = [
+            BoundingBox::new(Point::new(0.0, 0.0, 0.0), Point::new(1.0, 1.0, 1.0)),
+            BoundingBox::new(Point::new(0.5, 0.0, 0.0), Point::new(3.5, 1.0, 1.0)),
+            BoundingBox::new(Point::new(3.0, 0.0, 0.0), Point::new(4.0, 1.0, 1.0)),
+            BoundingBox::infinite(),
+        ];
+        let tree = KdTree::new(boxes, DEFAULT_MAX_DEPTH, 1);
+
+        let ray = Ray::new(Point::new(0.25, 0.5, -5.0), Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(visited(&tree, &ray), [0, 1, 3]);
+        let ray = Ray::new(Point::new(3.75, 0.5, -5.0), Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(visited(&tree, &ray), [1, 2, 3]);
+        // Along the whole row, everything is visited once.
+        let ray = Ray::new(Point::new(-1.0, 0.5, 0.5), Vector::new(1.0, 0.0, 0.0));
+        assert_eq!(visited(&tree, &ray), [0, 1, 2, 3]);
+    }
+}
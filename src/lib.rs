@@ -1,19 +1,100 @@
+pub mod animation;
+pub mod bezier;
+pub mod bmp;
+pub mod bounds;
+pub mod camera;
 pub mod canvas;
+pub mod checkpoint;
 pub mod color;
+pub mod denoise;
+pub mod distributed;
+pub mod error;
+#[cfg(any(test, feature = "test-fixtures"))]
+pub mod fixtures;
+pub mod gif;
+#[cfg(feature = "gltf")]
+pub mod gltf;
+pub mod hdr;
+#[cfg(feature = "image")]
+pub mod image;
+pub mod kdtree;
 pub mod lighting;
 pub mod materials;
 pub mod matrix;
+pub mod matrix2;
+pub mod matrix3;
+pub mod matrix4;
+pub mod noise;
+pub mod obj;
+pub mod octree;
+pub mod passes;
+pub mod patterns;
+pub mod pfm;
+pub mod ply;
+pub mod png;
 pub mod ppm;
+#[cfg(feature = "python")]
+mod python;
 pub mod ray;
+pub mod render_handle;
+pub mod sampler;
+pub mod scene;
+pub mod sdf;
 pub mod shape;
 pub mod space;
+pub mod stereo;
+pub mod stl;
+pub mod terrain;
+pub mod tga;
+pub mod tile;
+pub mod tonemap;
+pub mod transform;
+pub mod uv;
 pub mod world;
 
 #[cfg(test)]
 mod testlib;
 
-const EPSILON: f64 = 0.00001;
+/// The floating point type used for coordinates, colors and everything built
+/// on them: `f64`, or `f32` with the `f32` feature to save memory at the
+/// cost of precision.
+#[cfg(not(feature = "f32"))]
+pub type Float = f64;
+#[cfg(feature = "f32")]
+pub type Float = f32;
 
-fn approx_equal(a: f64, b: f64) -> bool {
-    (a - b).abs() < EPSILON
+/// Mathematical constants of type [`Float`].
+#[cfg(feature = "f32")]
+pub use std::f32::consts;
+/// Mathematical constants of type [`Float`].
+#[cfg(not(feature = "f32"))]
+pub use std::f64::consts;
+
+#[cfg(not(feature = "f32"))]
+const EPSILON: Float = 0.00001;
+// f32 only has around seven significant digits.
+#[cfg(feature = "f32")]
+const EPSILON: Float = 0.0001;
+
+/// Whether `a` and `b` are within [`EPSILON`] of each other. Equal infinities,
+/// as in empty bounding boxes, count as equal too.
+fn approx_equal(a: Float, b: Float) -> bool {
+    a == b || (a - b).abs() < EPSILON
+}
+
+/// Write `components` as `name(a, b, c)`, passing any precision or width
+/// the formatter was given on to each component.
+fn fmt_components(
+    f: &mut std::fmt::Formatter<'_>,
+    name: &str,
+    components: &[Float],
+) -> std::fmt::Result {
+    write!(f, "{name}(")?;
+    for (i, component) in components.iter().enumerate() {
+        if i > 0 {
+            write!(f, ", ")?;
+        }
+        std::fmt::Display::fmt(component, f)?;
+    }
+    write!(f, ")")
 }
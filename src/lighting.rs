@@ -1,6 +1,13 @@
-use crate::{color::Color, space::Point};
+use crate::Float;
+use crate::{
+    color::Color,
+    sampler::{Sampler, Sequence},
+    space::{Point, Vector},
+    EPSILON,
+};
 
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PointLight {
     position: Point,
     intensity: Color,
@@ -23,6 +30,172 @@ impl PointLight {
     }
 }
 
+/// A flat rectangular light, which casts soft shadows. It spans the
+/// parallelogram from `corner` along the edges `u` and `v`.
+///
+/// Shading samples `samples * samples` points on the light, placed by
+/// `sampler` over a grid of cells across the light. Each is weighted by the
+/// solid angle its cell covers as seen from the shaded point, so the parts of
+/// the light nearest the point and facing it most directly count most.
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AreaLight {
+    corner: Point,
+    u: Vector,
+    v: Vector,
+    intensity: Color,
+    samples: usize,
+    sampler: Sampler,
+    sequence: Sequence,
+}
+
+impl AreaLight {
+    pub fn new(corner: Point, u: Vector, v: Vector, intensity: Color) -> Self {
+        Self {
+            corner,
+            u,
+            v,
+            intensity,
+            samples: 4,
+            sampler: Sampler::Jittered,
+            sequence: Sequence::default(),
+        }
+    }
+
+    pub fn intensity(&self) -> Color {
+        self.intensity
+    }
+
+    pub fn corner(&self) -> Point {
+        self.corner
+    }
+
+    pub fn edges(&self) -> (Vector, Vector) {
+        (self.u, self.v)
+    }
+
+    /// The middle of the light.
+    pub fn position(&self) -> Point {
+        self.point_at(0.5, 0.5)
+    }
+
+    /// The number of samples taken along each edge of the light.
+    pub fn samples(&self) -> usize {
+        self.samples
+    }
+
+    pub fn set_samples(&mut self, samples: usize) {
+        assert!(samples > 0, "samples must be positive");
+        self.samples = samples;
+    }
+
+    pub fn sampler(&self) -> Sampler {
+        self.sampler
+    }
+
+    pub fn set_sampler(&mut self, sampler: Sampler) {
+        self.sampler = sampler;
+    }
+
+    pub fn sequence(&self) -> &Sequence {
+        &self.sequence
+    }
+
+    /// Set where the sampler's random choices come from. Each shaded point
+    /// gets its own [fork](Sequence::fork) of `sequence`.
+    pub fn set_sequence(&mut self, sequence: Sequence) {
+        self.sequence = sequence;
+    }
+
+    /// The point a fraction `s` along `u` and `t` along `v` from the corner.
+    pub fn point_at(&self, s: Float, t: Float) -> Point {
+        self.corner + self.u * s + self.v * t
+    }
+
+    /// The points on the light to sample when shading `point`, each with the
+    /// share of the light's contribution it carries. The shares add up to
+    /// 1.0. Random choices are keyed on `point`, so shading is repeatable.
+    pub fn samples_for(&self, point: &Point) -> Vec<(Point, Float)> {
+        // Widened so the `f32` feature seeds the same way for the same point.
+        #[allow(clippy::useless_conversion)]
+        let bits = |f: Float| f64::from(f).to_bits();
+        let seed =
+            bits(point.x()) ^ bits(point.y()).rotate_left(21) ^ bits(point.z()).rotate_left(42);
+        let positions: Vec<Point> = self
+            .sampler
+            .offsets_from(self.samples, &mut self.sequence.fork(seed))
+            .into_iter()
+            .map(|(s, t)| self.point_at(s, t))
+            .collect();
+
+        // Every sample stands for an equal area of the light, which covers a
+        // solid angle proportional to cos(theta) / distance^2 from `point`.
+        let normal = self.u.cross(self.v).normalize();
+        let weights: Vec<Float> = positions
+            .iter()
+            .map(|position| {
+                let to_light = *position - *point;
+                let distance_squared = to_light.dot(&to_light).max(EPSILON);
+                normal.dot(&to_light).abs() / (distance_squared * distance_squared.sqrt())
+            })
+            .collect();
+        let total: Float = weights.iter().sum();
+
+        let count = positions.len() as Float;
+        positions
+            .into_iter()
+            .zip(weights)
+            .map(|(position, weight)| {
+                // Edge on, the light covers no solid angle at all, so fall
+                // back to counting every sample equally.
+                let share = if total > 0.0 {
+                    weight / total
+                } else {
+                    1.0 / count
+                };
+                (position, share)
+            })
+            .collect()
+    }
+}
+
+/// A source of light in a world.
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Light {
+    Point(PointLight),
+    Area(AreaLight),
+}
+
+impl Light {
+    pub fn intensity(&self) -> Color {
+        match self {
+            Self::Point(light) => light.intensity(),
+            Self::Area(light) => light.intensity(),
+        }
+    }
+
+    /// The light's position, or for an area light, its middle.
+    pub fn position(&self) -> Point {
+        match self {
+            Self::Point(light) => light.position(),
+            Self::Area(light) => light.position(),
+        }
+    }
+}
+
+impl From<PointLight> for Light {
+    fn from(value: PointLight) -> Self {
+        Self::Point(value)
+    }
+}
+
+impl From<AreaLight> for Light {
+    fn from(value: AreaLight) -> Self {
+        Self::Area(value)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -32,8 +205,99 @@ mod test {
         let intensity = Color::new(1.0, 1.0, 1.0);
         let position = Point::new(0.0, 0.0, 0.0);
 
-        let light = PointLight::new(position.clone(), intensity.clone());
+        let light = PointLight::new(position, intensity);
         assert_eq!(light.position, position);
         assert_eq!(light.intensity, intensity);
     }
+
+    fn square_light() -> AreaLight {
+        AreaLight::new(
+            Point::new(-1.0, -1.0, 5.0),
+            Vector::new(2.0, 0.0, 0.0),
+            Vector::new(0.0, 2.0, 0.0),
+            Color::new(1.0, 1.0, 1.0),
+        )
+    }
+
+    #[test]
+    fn test_area_light() {
+        let light = square_light();
+        assert_eq!(light.position(), Point::new(0.0, 0.0, 5.0));
+        assert_eq!(light.point_at(1.0, 0.25), Point::new(1.0, -0.5, 5.0));
+        assert_eq!(Light::from(light).position(), Point::new(0.0, 0.0, 5.0));
+    }
+
+    #[test]
+    fn test_grid_area_light_samples() {
+        let mut light = square_light();
+        light.set_sampler(Sampler::Grid);
+        light.set_samples(2);
+        let samples = light.samples_for(&Point::origin());
+        let positions: Vec<Point> = samples.iter().map(|(p, _)| *p).collect();
+        assert_eq!(
+            positions,
+            vec![
+                Point::new(-0.5, -0.5, 5.0),
+                Point::new(0.5, -0.5, 5.0),
+                Point::new(-0.5, 0.5, 5.0),
+                Point::new(0.5, 0.5, 5.0),
+            ]
+        );
+        // Seen straight on, the samples are symmetric and share equally.
+        for (_, share) in samples {
+            assert_eq!(share, 0.25);
+        }
+    }
+
+    #[test]
+    fn test_jittered_area_light_samples_one_per_cell() {
+        let light = square_light();
+        let samples = light.samples_for(&Point::new(0.3, 0.2, 0.0));
+        assert_eq!(samples.len(), 16);
+        for (k, (p, _)) in samples.iter().enumerate() {
+            let (i, j) = (k % 4, k / 4);
+            assert_eq!(((p.x() + 1.0) * 2.0) as usize, i);
+            assert_eq!(((p.y() + 1.0) * 2.0) as usize, j);
+            assert_eq!(p.z(), 5.0);
+        }
+        assert_eq!(samples, light.samples_for(&Point::new(0.3, 0.2, 0.0)));
+    }
+
+    #[test]
+    fn test_area_light_samples_from_fixed_sequence() {
+        let mut light = square_light();
+        light.set_samples(2);
+        light.set_sequence(Sequence::fixed([0.5, 0.0]));
+        let positions: Vec<Point> = light
+            .samples_for(&Point::new(0.3, 0.2, 0.0))
+            .into_iter()
+            .map(|(p, _)| p)
+            .collect();
+        assert_eq!(
+            positions,
+            vec![
+                Point::new(-0.5, -1.0, 5.0),
+                Point::new(0.5, -1.0, 5.0),
+                Point::new(-0.5, 0.0, 5.0),
+                Point::new(0.5, 0.0, 5.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_area_light_weights_by_solid_angle() {
+        let mut light = square_light();
+        light.set_sampler(Sampler::Grid);
+        light.set_samples(2);
+        let samples = light.samples_for(&Point::new(3.0, 0.0, 4.0));
+        let total: Float = samples.iter().map(|(_, share)| share).sum();
+        assert!((total - 1.0).abs() < EPSILON);
+        // The near samples count for more, despite being seen more edge on.
+        assert!(samples[1].1 > samples[0].1);
+        assert_eq!(samples[1].1, samples[3].1);
+
+        // Seen exactly edge on, every sample counts equally.
+        let samples = light.samples_for(&Point::new(3.0, 0.0, 5.0));
+        assert!(samples.iter().all(|(_, share)| *share == 0.25));
+    }
 }
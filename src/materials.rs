@@ -1,41 +1,88 @@
+use crate::Float;
 use crate::{
     color::Color,
     lighting::PointLight,
+    patterns::Pattern,
+    shape::Shape,
     space::{Point, Vector},
 };
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Material {
     pub color: Color,
-    pub ambient: f64,
-    pub diffuse: f64,
-    pub specular: f64,
-    pub shininess: f64,
+    pub pattern: Option<Pattern>,
+    pub ambient: Float,
+    pub diffuse: Float,
+    pub specular: Float,
+    pub shininess: Float,
+    pub reflective: Float,
+    pub transparency: Float,
+    pub refractive_index: Float,
+    /// Whether the surface shows its color as it is, once, whatever lights
+    /// there are, as set by [`Material::skybox`].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub unlit: bool,
 }
 
 impl Material {
     pub fn new() -> Self {
         Self {
             color: Color::new(1.0, 1.0, 1.0),
+            pattern: None,
             ambient: 0.1,
             diffuse: 0.9,
             specular: 0.9,
             shininess: 200.0,
+            reflective: 0.0,
+            transparency: 0.0,
+            refractive_index: 1.0,
+            unlit: false,
+        }
+    }
+
+    /// A material that ignores lights entirely and just shows `pattern`, for
+    /// use on a large shape surrounding the scene as a backdrop.
+    pub fn skybox(pattern: impl Into<Pattern>) -> Self {
+        Self {
+            pattern: Some(pattern.into()),
+            ambient: 1.0,
+            diffuse: 0.0,
+            specular: 0.0,
+            unlit: true,
+            ..Self::new()
+        }
+    }
+
+    /// The color of the material at `position` on `object`, before lighting.
+    pub fn color_at(&self, object: &Shape, position: &Point) -> Color {
+        match &self.pattern {
+            Some(pattern) => pattern.pattern_at_shape(object, position),
+            None => self.color,
         }
     }
 
     pub fn lighting(
         &self,
+        object: &Shape,
         light: &PointLight,
         position: &Point,
         eyev: &Vector,
         normalv: &Vector,
+        in_shadow: bool,
     ) -> Color {
         let black = Color::new(0.0, 0.0, 0.0);
 
-        let effective_color = self.color * light.intensity();
+        let color = self.color_at(object, position);
+        if self.unlit {
+            return color;
+        }
+        let effective_color = color * light.intensity();
         let lightv = (&light.position() - position).normalize();
         let ambient = effective_color * self.ambient;
+        if in_shadow {
+            return ambient;
+        }
         let light_dot_normal = lightv.dot(normalv);
 
         let diffuse;
@@ -45,7 +92,7 @@ impl Material {
             specular = black;
         } else {
             diffuse = effective_color * self.diffuse * light_dot_normal;
-            let reflectv = (lightv * -1.0).reflect(normalv);
+            let reflectv = (-lightv).reflect(normalv);
             let reflect_dot_eye = reflectv.dot(eyev);
             if reflect_dot_eye <= 0.0 {
                 specular = black;
@@ -69,6 +116,8 @@ impl Default for Material {
 mod test {
     use crate::{
         lighting::PointLight,
+        patterns::StripePattern,
+        shape::Sphere,
         space::{Point, Vector},
     };
 
@@ -78,83 +127,145 @@ mod test {
     fn test_material() {
         let m: Material = Material::new();
         assert_eq!(m.color, Color::new(1.0, 1.0, 1.0));
+        assert_eq!(m.pattern, None);
         assert_eq!(m.ambient, 0.1);
         assert_eq!(m.diffuse, 0.9);
         assert_eq!(m.specular, 0.9);
         assert_eq!(m.shininess, 200.0);
+        assert_eq!(m.reflective, 0.0);
+        assert_eq!(m.transparency, 0.0);
+        assert_eq!(m.refractive_index, 1.0);
     }
 
     #[test]
     fn test_lighting_eye_between_light_and_surface() {
         let m = Material::new();
+        let object: Shape = Sphere::new().into();
         let position = Point::origin();
 
         let eyev = Vector::new(0.0, 0.0, -1.0);
         let normalv = Vector::new(0.0, 0.0, -1.0);
         let light = PointLight::new(Point::new(0.0, 0.0, -10.0), Color::new(1.0, 1.0, 1.0));
-        let result = m.lighting(&light, &position, &eyev, &normalv);
+        let result = m.lighting(&object, &light, &position, &eyev, &normalv, false);
         assert_eq!(result, Color::new(1.9, 1.9, 1.9));
     }
 
     #[test]
     fn test_eye_between_light_and_surface_at_45() {
         let m = Material::new();
+        let object: Shape = Sphere::new().into();
         let position = Point::origin();
 
-        let sqt = 2.0f64.sqrt() / 2.0;
+        let sqt = (2.0 as Float).sqrt() / 2.0;
         let eyev = Vector::new(0.0, sqt, -sqt);
         let normalv = Vector::new(0.0, 0.0, -1.0);
         let light = PointLight::new(Point::new(0.0, 0.0, -10.0), Color::new(1.0, 1.0, 1.0));
-        let result = m.lighting(&light, &position, &eyev, &normalv);
+        let result = m.lighting(&object, &light, &position, &eyev, &normalv, false);
         assert_eq!(result, Color::new(1.0, 1.0, 1.0));
     }
 
     #[test]
     fn test_eye_opp_surface_light_45() {
         let m = Material::new();
+        let object: Shape = Sphere::new().into();
         let position = Point::origin();
 
         let eyev = Vector::new(0.0, 0.0, -1.0);
         let normalv = Vector::new(0.0, 0.0, -1.0);
         let light = PointLight::new(Point::new(0.0, 10.0, -10.0), Color::new(1.0, 1.0, 1.0));
-        let result = m.lighting(&light, &position, &eyev, &normalv);
+        let result = m.lighting(&object, &light, &position, &eyev, &normalv, false);
         assert_eq!(result, Color::new(0.7364, 0.7364, 0.7364));
     }
 
     #[test]
     fn test_eye_in_reflection_path() {
         let m = Material::new();
+        let object: Shape = Sphere::new().into();
         let position = Point::origin();
 
-        let sqt = 2.0f64.sqrt() / 2.0;
+        let sqt = (2.0 as Float).sqrt() / 2.0;
         let eyev = Vector::new(0.0, -sqt, -sqt);
         let normalv = Vector::new(0.0, 0.0, -1.0);
         let light = PointLight::new(Point::new(0.0, 10.0, -10.0), Color::new(1.0, 1.0, 1.0));
-        let result = m.lighting(&light, &position, &eyev, &normalv);
+        let result = m.lighting(&object, &light, &position, &eyev, &normalv, false);
         assert_eq!(result, Color::new(1.6364, 1.6364, 1.6364));
     }
 
     #[test]
     fn test_light_behind_surface() {
         let m = Material::new();
+        let object: Shape = Sphere::new().into();
         let position = Point::origin();
 
         let eyev = Vector::new(0.0, 0.0, -1.0);
         let normalv = Vector::new(0.0, 0.0, -1.0);
         let light = PointLight::new(Point::new(0.0, 0.0, 10.0), Color::new(1.0, 1.0, 1.0));
-        let result = m.lighting(&light, &position, &eyev, &normalv);
+        let result = m.lighting(&object, &light, &position, &eyev, &normalv, false);
         assert_eq!(result, Color::new(0.1, 0.1, 0.1));
     }
 
-    // #[test]
-    // fn test_light_surface_in_shadow() {
-    //     let m = Material::new();
-    //     let position = Point::origin();
-
-    //     let eyev = Vector::new(0.0, 0.0, -1.0);
-    //     let normalv = Vector::new(0.0, 0.0, -1.0);
-    //     let light = PointLight::new(Point::new(0.0, 0.0, -10.0), Color::new(1.0, 1.0, 1.0));
-    //     let result = m.lighting(light, position, eyev, normalv, true);
-    //     assert_eq!(result, Color::new(0.1, 0.1, 0.1));
-    // }
+    #[test]
+    fn test_skybox_ignores_light() {
+        let m = Material::skybox(Color::new(0.2, 0.4, 0.6));
+        let object: Shape = Sphere::new().into();
+        let position = Point::origin();
+
+        let eyev = Vector::new(0.0, 0.0, -1.0);
+        let normalv = Vector::new(0.0, 0.0, -1.0);
+        let lit = PointLight::new(Point::new(0.0, 0.0, -10.0), Color::new(1.0, 1.0, 1.0));
+        let unlit = PointLight::new(Point::new(0.0, 0.0, 10.0), Color::new(1.0, 1.0, 1.0));
+        let tinted = PointLight::new(Point::new(0.0, 0.0, -10.0), Color::new(1.0, 0.0, 0.5));
+        for light in [lit, unlit, tinted] {
+            assert_eq!(
+                m.lighting(&object, &light, &position, &eyev, &normalv, false),
+                Color::new(0.2, 0.4, 0.6)
+            );
+        }
+    }
+
+    #[test]
+    fn test_lighting_with_pattern() {
+        let mut m = Material::new();
+        m.pattern =
+            Some(StripePattern::new(Color::new(1.0, 1.0, 1.0), Color::new(0.0, 0.0, 0.0)).into());
+        m.ambient = 1.0;
+        m.diffuse = 0.0;
+        m.specular = 0.0;
+        let object: Shape = Sphere::new().into();
+
+        let eyev = Vector::new(0.0, 0.0, -1.0);
+        let normalv = Vector::new(0.0, 0.0, -1.0);
+        let light = PointLight::new(Point::new(0.0, 0.0, -10.0), Color::new(1.0, 1.0, 1.0));
+        let c1 = m.lighting(
+            &object,
+            &light,
+            &Point::new(0.9, 0.0, 0.0),
+            &eyev,
+            &normalv,
+            false,
+        );
+        let c2 = m.lighting(
+            &object,
+            &light,
+            &Point::new(1.1, 0.0, 0.0),
+            &eyev,
+            &normalv,
+            false,
+        );
+        assert_eq!(c1, Color::new(1.0, 1.0, 1.0));
+        assert_eq!(c2, Color::new(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_light_surface_in_shadow() {
+        let m = Material::new();
+        let object: Shape = Sphere::new().into();
+        let position = Point::origin();
+
+        let eyev = Vector::new(0.0, 0.0, -1.0);
+        let normalv = Vector::new(0.0, 0.0, -1.0);
+        let light = PointLight::new(Point::new(0.0, 0.0, -10.0), Color::new(1.0, 1.0, 1.0));
+        let result = m.lighting(&object, &light, &position, &eyev, &normalv, true);
+        assert_eq!(result, Color::new(0.1, 0.1, 0.1));
+    }
 }
@@ -1,5 +1,10 @@
 use crate::approx_equal;
+use crate::error::{Error, MatrixError, Result};
+use crate::matrix2::Matrix2;
+use crate::matrix3::Matrix3;
+use crate::matrix4::Matrix4;
 use crate::space::{Point, Tuple, Vector};
+use crate::Float;
 use once_cell::sync::OnceCell;
 use std::{fmt::Debug, ops::Mul};
 
@@ -18,10 +23,11 @@ pub fn identity_matrix() -> &'static Matrix {
 }
 
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Matrix {
     rows: usize,
     cols: usize,
-    values: Vec<f64>,
+    values: Vec<Float>,
 }
 
 impl Matrix {
@@ -33,43 +39,110 @@ impl Matrix {
         }
     }
 
-    pub fn from_values(rows: usize, cols: usize, values: Vec<f64>) -> Self {
-        Self { rows, cols, values }
+    /// A matrix filled from `values`, row by row.
+    ///
+    /// Panics if there aren't exactly `rows * cols` values.
+    pub fn from_values(rows: usize, cols: usize, values: Vec<Float>) -> Self {
+        Self::try_from_values(rows, cols, values).unwrap_or_else(|e| panic!("{e}"))
     }
 
-    pub fn set(&mut self, row: usize, col: usize, value: f64) {
-        let index = self.index(row, col);
-        match self.values.get_mut(index) {
-            Some(val) => *val = value,
-            None => panic!("{row}, {col} is out of bounds for {self:?}"),
+    /// Like [`Matrix::from_values`], but fails with
+    /// [`Error::MatrixSizeMismatch`] for the wrong number of values.
+    pub fn try_from_values(rows: usize, cols: usize, values: Vec<Float>) -> Result<Self> {
+        if values.len() != rows * cols {
+            return Err(Error::MatrixSizeMismatch {
+                rows,
+                cols,
+                len: values.len(),
+            });
         }
+        Ok(Self { rows, cols, values })
     }
 
-    pub fn get(&self, row: usize, col: usize) -> f64 {
-        self.values.get(self.index(row, col)).copied().unwrap()
+    /// Panics if (`row`, `col`) is outside the matrix.
+    pub fn set(&mut self, row: usize, col: usize, value: Float) {
+        self.try_set(row, col, value)
+            .unwrap_or_else(|e| panic!("{e}"));
+    }
+
+    /// Like [`Matrix::set`], but fails with
+    /// [`Error::MatrixIndexOutOfBounds`] rather than panicking.
+    pub fn try_set(&mut self, row: usize, col: usize, value: Float) -> Result<()> {
+        let index = self.checked_index(row, col)?;
+        self.values[index] = value;
+        Ok(())
+    }
+
+    /// Panics if (`row`, `col`) is outside the matrix.
+    pub fn get(&self, row: usize, col: usize) -> Float {
+        self.try_get(row, col).unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Like [`Matrix::get`], but fails with
+    /// [`Error::MatrixIndexOutOfBounds`] rather than panicking.
+    pub fn try_get(&self, row: usize, col: usize) -> Result<Float> {
+        Ok(self.values[self.checked_index(row, col)?])
     }
 
     pub fn transpose(&self) -> Matrix {
-        let mut result = Matrix::new(self.cols, self.rows);
-        for row in 0..self.rows {
-            for col in 0..self.cols {
-                result.set(col, row, self.get(row, col));
-            }
-        }
-        result
+        Matrix::from_values(
+            self.cols,
+            self.rows,
+            self.cols().flatten().copied().collect(),
+        )
+    }
+
+    /// Each row in turn, as a slice.
+    pub fn rows(&self) -> impl Iterator<Item = &[Float]> + '_ {
+        (0..self.rows).map(move |row| &self.values[self.index(row, 0)..self.index(row + 1, 0)])
+    }
+
+    /// Each column in turn, as an iterator over its values from the top.
+    pub fn cols(&self) -> impl Iterator<Item = impl Iterator<Item = &Float> + '_> + '_ {
+        (0..self.cols).map(move |col| self.values.iter().skip(col).step_by(self.cols))
+    }
+
+    /// Every value, row by row.
+    pub fn iter(&self) -> std::slice::Iter<'_, Float> {
+        self.values.iter()
     }
 
     fn index(&self, row: usize, col: usize) -> usize {
         row * self.cols + col
     }
 
-    pub fn determinant(&self) -> f64 {
-        if self.rows == 2 && self.cols == 2 {
-            self.get(0, 0) * self.get(1, 1) - self.get(0, 1) * self.get(1, 0)
+    fn checked_index(&self, row: usize, col: usize) -> Result<usize> {
+        if row < self.rows && col < self.cols {
+            Ok(self.index(row, col))
         } else {
-            (0..self.cols)
+            Err(Error::MatrixIndexOutOfBounds {
+                row,
+                col,
+                rows: self.rows,
+                cols: self.cols,
+            })
+        }
+    }
+
+    /// The determinant. Matrices up to 4x4 use cofactor expansion, which is
+    /// exact for whole numbers, and larger ones the [`lu`](Self::lu)
+    /// decomposition, as the expansion takes exponential time.
+    ///
+    /// Panics if the matrix isn't square.
+    pub fn determinant(&self) -> Float {
+        assert!(
+            self.rows == self.cols,
+            "only square matrices have a determinant, not a {}x{} matrix",
+            self.rows,
+            self.cols
+        );
+        match self.rows {
+            2 => Matrix2::new(self.fixed()).determinant(),
+            3 => Matrix3::new(self.fixed()).determinant(),
+            4 => (0..self.cols)
                 .map(|col| self.get(0, col) * self.cofactor(0, col))
-                .sum()
+                .sum(),
+            _ => self.lu().unwrap().determinant(),
         }
     }
 
@@ -90,162 +163,229 @@ impl Matrix {
         result
     }
 
-    pub fn minor(&self, row: usize, col: usize) -> f64 {
-        self.submatrix(row, col).determinant()
+    pub fn minor(&self, row: usize, col: usize) -> Float {
+        // The fixed-size matrices find small minors without allocating.
+        match (self.rows, self.cols) {
+            (3, 3) => Matrix3::new(self.fixed()).minor(row, col),
+            (4, 4) => Matrix4::new(self.fixed()).submatrix(row, col).determinant(),
+            _ => self.submatrix(row, col).determinant(),
+        }
+    }
+
+    /// The values as an array, for a matrix known to be `N`x`N`.
+    fn fixed<const N: usize>(&self) -> [[Float; N]; N] {
+        std::array::from_fn(|row| std::array::from_fn(|col| self.get(row, col)))
     }
 
-    pub fn cofactor(&self, row: usize, col: usize) -> f64 {
+    pub fn cofactor(&self, row: usize, col: usize) -> Float {
         let modifier = if (row + col) % 2 == 1 { -1. } else { 1. };
         self.minor(row, col) * modifier
     }
 
+    /// Whether the matrix has an inverse, as for [`inverse`](Self::inverse).
     pub fn invertible(&self) -> bool {
-        self.determinant() != 0.0
-    }
+        self.lu().is_ok_and(|lu| !lu.is_singular())
+    }
+
+    /// The inverse of the matrix, failing with [`MatrixError::NotSquare`] if
+    /// it isn't square or [`MatrixError::Singular`] if it has no inverse.
+    ///
+    /// Uses the [`lu`](Self::lu) decomposition, which takes O(n³)
+    /// operations rather than recomputing a determinant for every cofactor.
+    pub fn inverse(&self) -> std::result::Result<Matrix, MatrixError> {
+        self.lu()?.inverse()
+    }
+
+    /// Factor the matrix into lower and upper triangular matrices, failing
+    /// with [`MatrixError::NotSquare`] if it isn't square. The decomposition
+    /// takes O(n³) operations, and can then give the determinant and
+    /// inverse or solve equations cheaply.
+    pub fn lu(&self) -> std::result::Result<LuDecomposition, MatrixError> {
+        if self.rows != self.cols {
+            return Err(MatrixError::NotSquare {
+                rows: self.rows,
+                cols: self.cols,
+            });
+        }
+        let n = self.rows;
+
+        // Pivots smaller than this are rounding noise in a singular matrix.
+        let largest = self.iter().fold(0.0 as Float, |m, v| m.max(v.abs()));
+        let tolerance = largest * n as Float * Float::EPSILON;
+
+        let mut lu = self.clone();
+        let mut rows: Vec<usize> = (0..n).collect();
+        let mut odd_swaps = false;
+        for col in 0..n {
+            // Swap the row with the largest value in this column into place,
+            // to keep the elimination numerically stable.
+            let pivot_row = (col..n)
+                .max_by(|&r1, &r2| lu.get(r1, col).abs().total_cmp(&lu.get(r2, col).abs()))
+                .unwrap();
+            if pivot_row != col {
+                lu.swap_rows(col, pivot_row);
+                rows.swap(col, pivot_row);
+                odd_swaps = !odd_swaps;
+            }
 
-    pub fn inverse(&self) -> Option<Matrix> {
-        if self.invertible() {
-            let mut result = Matrix::new(self.cols, self.rows);
-            let determinant = self.determinant();
-            for row in 0..self.rows {
-                for col in 0..self.cols {
-                    let c = self.cofactor(row, col);
-                    result.set(col, row, c / determinant)
+            // A zero pivot means the rest of the column is zero too.
+            let pivot = lu.get(col, col);
+            if pivot == 0.0 {
+                continue;
+            }
+            // Eliminate the column below the pivot, keeping the factors in
+            // the space that frees up.
+            for row in col + 1..n {
+                let factor = lu.get(row, col) / pivot;
+                lu.set(row, col, factor);
+                for c in col + 1..n {
+                    lu.set(row, c, lu.get(row, c) - factor * lu.get(col, c));
                 }
             }
-            Some(result)
-        } else {
-            None
         }
+
+        Ok(LuDecomposition {
+            lu,
+            rows,
+            odd_swaps,
+            tolerance,
+        })
     }
 
-    pub fn translation(x: f64, y: f64, z: f64) -> Self {
-        let mut result = identity_matrix().clone();
-        result.set(0, 3, x);
-        result.set(1, 3, y);
-        result.set(2, 3, z);
-        result
+    fn swap_rows(&mut self, r1: usize, r2: usize) {
+        if r1 != r2 {
+            for col in 0..self.cols {
+                let (i1, i2) = (self.index(r1, col), self.index(r2, col));
+                self.values.swap(i1, i2);
+            }
+        }
     }
+}
 
-    pub fn scaling(x: f64, y: f64, z: f64) -> Self {
-        let mut result = identity_matrix().clone();
-        result.set(0, 0, x);
-        result.set(1, 1, y);
-        result.set(2, 2, z);
-        result
+/// A square matrix factored by [`Matrix::lu`], with partial pivoting, into a
+/// lower triangular matrix L with ones on its diagonal and an upper
+/// triangular matrix U, whose product is the original matrix with its rows
+/// reordered.
+#[derive(Clone, Debug)]
+pub struct LuDecomposition {
+    /// L below the diagonal and U on and above it.
+    lu: Matrix,
+    /// The row of the original matrix that ended up in each row.
+    rows: Vec<usize>,
+    odd_swaps: bool,
+    tolerance: Float,
+}
+
+impl LuDecomposition {
+    pub fn determinant(&self) -> Float {
+        let product: Float = (0..self.lu.rows).map(|i| self.lu.get(i, i)).product();
+        if self.odd_swaps {
+            -product
+        } else {
+            product
+        }
     }
 
-    pub fn rotation_x(radians: f64) -> Self {
-        Self::from_values(
-            4,
-            4,
-            vec![
-                1.0,
-                0.0,
-                0.0,
-                0.0,
-                0.0,
-                radians.cos(),
-                -radians.sin(),
-                0.0,
-                0.0,
-                radians.sin(),
-                radians.cos(),
-                0.0,
-                0.0,
-                0.0,
-                0.0,
-                1.0,
-            ],
-        )
+    /// Whether the matrix has no inverse, because one of U's pivots is zero
+    /// or close enough to be rounding noise.
+    pub fn is_singular(&self) -> bool {
+        (0..self.lu.rows).any(|i| self.lu.get(i, i).abs() <= self.tolerance)
     }
 
-    pub fn rotation_y(radians: f64) -> Self {
-        Self::from_values(
-            4,
-            4,
-            vec![
-                radians.cos(),
-                0.0,
-                radians.sin(),
-                0.0,
-                0.0,
-                1.0,
-                0.0,
-                0.0,
-                -(radians.sin()),
-                0.0,
-                radians.cos(),
-                0.0,
-                0.0,
-                0.0,
-                0.0,
-                1.0,
-            ],
-        )
+    /// The `x` for which the original matrix times `x` is `b`, failing with
+    /// [`MatrixError::Singular`] if there isn't exactly one.
+    ///
+    /// Panics if `b` doesn't have a value for each row.
+    pub fn solve(&self, b: &[Float]) -> std::result::Result<Vec<Float>, MatrixError> {
+        let n = self.lu.rows;
+        assert_eq!(b.len(), n, "expected {n} values to solve for");
+        if self.is_singular() {
+            return Err(MatrixError::Singular);
+        }
+
+        // Solve L y = b by forward substitution, then U x = y by back
+        // substitution.
+        let mut x: Vec<Float> = self.rows.iter().map(|&row| b[row]).collect();
+        for row in 0..n {
+            for col in 0..row {
+                x[row] -= self.lu.get(row, col) * x[col];
+            }
+        }
+        for row in (0..n).rev() {
+            for col in row + 1..n {
+                x[row] -= self.lu.get(row, col) * x[col];
+            }
+            x[row] /= self.lu.get(row, row);
+        }
+        Ok(x)
+    }
+
+    /// The inverse of the original matrix, found a column at a time with
+    /// [`solve`](Self::solve).
+    pub fn inverse(&self) -> std::result::Result<Matrix, MatrixError> {
+        let n = self.lu.rows;
+        let mut result = Matrix::new(n, n);
+        let mut unit = vec![0.0; n];
+        for col in 0..n {
+            unit[col] = 1.0;
+            for (row, value) in self.solve(&unit)?.into_iter().enumerate() {
+                result.set(row, col, value);
+            }
+            unit[col] = 0.0;
+        }
+        Ok(result)
     }
+}
 
-    pub fn rotation_z(radians: f64) -> Self {
-        Self::from_values(
-            4,
-            4,
-            vec![
-                radians.cos(),
-                -radians.sin(),
-                0.0,
-                0.0,
-                radians.sin(),
-                radians.cos(),
-                0.0,
-                0.0,
-                0.0,
-                0.0,
-                1.0,
-                0.0,
-                0.0,
-                0.0,
-                0.0,
-                1.0,
-            ],
-        )
+/// A matrix with a row for each array, so it can be laid out as it reads.
+impl<const ROWS: usize, const COLS: usize> From<[[Float; COLS]; ROWS]> for Matrix {
+    fn from(value: [[Float; COLS]; ROWS]) -> Self {
+        Matrix::from_values(ROWS, COLS, value.concat())
     }
+}
 
-    pub fn shearing(xy: f64, xz: f64, yx: f64, yz: f64, zx: f64, zy: f64) -> Self {
-        Self::from_values(
-            4,
-            4,
-            vec![
-                1.0, xy, xz, 0.0, yx, 1.0, yz, 0.0, zx, zy, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0,
-            ],
-        )
+/// Builds a [`Matrix`](crate::matrix::Matrix) row by row:
+///
+/// ```
+/// use ray_tracer_challenge_2::matrix;
+///
+/// let m = matrix![
+///     [1.0, 2.0, 3.0],
+///     [4.0, 5.0, 6.0],
+/// ];
+/// assert_eq!(m.get(1, 0), 4.0);
+/// ```
+///
+/// Rows of different lengths don't compile.
+#[macro_export]
+macro_rules! matrix {
+    ($([$($value:expr),* $(,)?]),* $(,)?) => {
+        $crate::matrix::Matrix::from([$([$($value),*]),*])
+    };
+}
+
+impl<'a> IntoIterator for &'a Matrix {
+    type Item = &'a Float;
+    type IntoIter = std::slice::Iter<'a, Float>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
     }
 }
 
 impl PartialEq for Matrix {
     fn eq(&self, other: &Self) -> bool {
-        if self.rows == other.rows && self.cols == other.cols {
-            for row in 0..self.rows {
-                for col in 0..self.cols {
-                    if !approx_equal(self.get(row, col), other.get(row, col)) {
-                        return false;
-                    }
-                }
-            }
-            return true;
-        }
-        false
+        self.rows == other.rows
+            && self.cols == other.cols
+            && self.iter().zip(other).all(|(&a, &b)| approx_equal(a, b))
     }
 }
 
 impl Debug for Matrix {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         writeln!(f, "Matrix<{}, {}> [", self.rows, self.cols)?;
-        for row in 0..self.rows {
-            writeln!(
-                f,
-                "  {:?}",
-                &self.values[self.index(row, 0)..self.index(row, 0) + self.cols]
-            )?;
+        for row in self.rows() {
+            writeln!(f, "  {:?}", row)?;
         }
         writeln!(f, "]")?;
 
@@ -257,16 +397,13 @@ impl Mul for &Matrix {
     type Output = Matrix;
 
     fn mul(self, rhs: Self) -> Self::Output {
-        let mut values = vec![0.0; self.rows * rhs.cols];
-        for row in 0..self.rows {
-            for col in 0..rhs.cols {
-                let mut tally: f64 = 0.0;
-                for i in 0..self.rows {
-                    tally += self.get(row, i) * rhs.get(i, col);
-                }
-                *values.get_mut(self.index(row, col)).unwrap() = tally;
-            }
-        }
+        let values = self
+            .rows()
+            .flat_map(|row| {
+                rhs.cols()
+                    .map(move |col| row.iter().zip(col).map(|(a, b)| a * b).sum())
+            })
+            .collect();
 
         Matrix::from_values(self.rows, rhs.cols, values)
     }
@@ -291,12 +428,10 @@ impl Mul<&Self> for Matrix {
 impl Mul<&Tuple> for &Matrix {
     type Output = Tuple;
     fn mul(self, rhs: &Tuple) -> Self::Output {
-        let result_params: Vec<f64> = (0..4)
-            .map(|row| {
-                (0..self.cols)
-                    .map(|col| self.get(row, col) * rhs.get(col))
-                    .sum()
-            })
+        let result_params: Vec<Float> = self
+            .rows()
+            .take(4)
+            .map(|row| row.iter().enumerate().map(|(col, v)| v * rhs[col]).sum())
             .collect();
 
         Tuple::new(
@@ -359,21 +494,19 @@ impl Mul<Vector> for &Matrix {
 
 #[cfg(test)]
 mod test {
-    use std::f64::consts::PI;
-
     use super::*;
+    use crate::assert_approx_eq;
     use crate::space::*;
+    use crate::testlib::approx_equals_fail;
 
     #[test]
     fn test_matrix_construction_4x4() {
-        let m = Matrix::from_values(
-            4,
-            4,
-            vec![
-                1.0, 2.0, 3.0, 4.0, 5.5, 6.5, 7.5, 8.5, 9.0, 10.0, 11.0, 12.0, 13.5, 14.5, 15.5,
-                16.5,
-            ],
-        );
+        let m = matrix![
+            [1.0, 2.0, 3.0, 4.0],
+            [5.5, 6.5, 7.5, 8.5],
+            [9.0, 10.0, 11.0, 12.0],
+            [13.5, 14.5, 15.5, 16.5]
+        ];
 
         assert_eq!(m.get(0, 0), 1.);
         assert_eq!(m.get(0, 3), 4.);
@@ -386,7 +519,7 @@ mod test {
 
     #[test]
     fn test_matrix_construction_3x3() {
-        let m = Matrix::from_values(3, 3, vec![-3.0, 5.0, 0.0, 1.0, -2.0, -7.0, 0.0, 1.0, 1.0]);
+        let m = matrix![[-3.0, 5.0, 0.0], [1.0, -2.0, -7.0], [0.0, 1.0, 1.0]];
 
         assert_eq!(m.get(0, 0), -3.0);
         assert_eq!(m.get(1, 1), -2.0);
@@ -395,7 +528,7 @@ mod test {
 
     #[test]
     fn test_matrix_construction_2x2() {
-        let m = Matrix::from_values(2, 2, vec![-3.0, 5.0, 1.0, -2.0]);
+        let m = matrix![[-3.0, 5.0], [1.0, -2.0]];
         assert_eq!(m.get(0, 0), -3.0);
         assert_eq!(m.get(0, 1), 5.0);
         assert_eq!(m.get(1, 0), 1.0);
@@ -403,63 +536,100 @@ mod test {
     }
 
     #[test]
-    fn test_partial_eq() {
-        let m1 = Matrix::from_values(
-            4,
-            4,
-            vec![
-                1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 8.0, 7.0, 6.0, 5.0, 4.0, 3.0, 2.0,
-            ],
+    fn test_from_arrays() {
+        let m = Matrix::from([[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]);
+        assert_eq!(
+            m,
+            Matrix::from_values(2, 3, vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0])
         );
-        let m2 = Matrix::from_values(
-            4,
-            4,
-            vec![
-                1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 8.0, 7.0, 6.0, 5.0, 4.0, 3.0, 2.0,
-            ],
+        assert_eq!(
+            Matrix::from([
+                [1.0, 0.0, 0.0, 0.0],
+                [0.0, 1.0, 0.0, 0.0],
+                [0.0, 0.0, 1.0, 0.0],
+                [0.0, 0.0, 0.0, 1.0]
+            ]),
+            *identity_matrix()
         );
+    }
+
+    #[test]
+    fn test_partial_eq() {
+        let m1 = matrix![
+            [1.0, 2.0, 3.0, 4.0],
+            [5.0, 6.0, 7.0, 8.0],
+            [9.0, 8.0, 7.0, 6.0],
+            [5.0, 4.0, 3.0, 2.0]
+        ];
+        let m2 = matrix![
+            [1.0, 2.0, 3.0, 4.0],
+            [5.0, 6.0, 7.0, 8.0],
+            [9.0, 8.0, 7.0, 6.0],
+            [5.0, 4.0, 3.0, 2.0]
+        ];
 
         assert_eq!(m1, m2);
     }
 
     #[test]
     fn test_multiplication() {
-        let m1 = Matrix::from_values(
-            4,
-            4,
-            vec![
-                1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 8.0, 7.0, 6.0, 5.0, 4.0, 3.0, 2.0,
-            ],
-        );
-        let m2 = Matrix::from_values(
-            4,
-            4,
-            vec![
-                -2.0, 1.0, 2.0, 3.0, 3.0, 2.0, 1.0, -1.0, 4.0, 3.0, 6.0, 5.0, 1.0, 2.0, 7.0, 8.0,
-            ],
-        );
-
-        let result = Matrix::from_values(
-            4,
-            4,
-            vec![
-                20.0, 22.0, 50.0, 48.0, 44.0, 54.0, 114.0, 108.0, 40.0, 58.0, 110.0, 102.0, 16.0,
-                26.0, 46.0, 42.0,
-            ],
-        );
+        let m1 = matrix![
+            [1.0, 2.0, 3.0, 4.0],
+            [5.0, 6.0, 7.0, 8.0],
+            [9.0, 8.0, 7.0, 6.0],
+            [5.0, 4.0, 3.0, 2.0]
+        ];
+        let m2 = matrix![
+            [-2.0, 1.0, 2.0, 3.0],
+            [3.0, 2.0, 1.0, -1.0],
+            [4.0, 3.0, 6.0, 5.0],
+            [1.0, 2.0, 7.0, 8.0]
+        ];
+
+        let result = matrix![
+            [20.0, 22.0, 50.0, 48.0],
+            [44.0, 54.0, 114.0, 108.0],
+            [40.0, 58.0, 110.0, 102.0],
+            [16.0, 26.0, 46.0, 42.0]
+        ];
 
         assert_eq!(m1 * m2, result);
     }
 
+    #[test]
+    fn test_rows_cols_and_iter() {
+        let m = matrix![[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]];
+
+        let rows: Vec<&[Float]> = m.rows().collect();
+        assert_eq!(rows, [[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]);
+
+        let cols: Vec<Vec<Float>> = m.cols().map(|col| col.copied().collect()).collect();
+        assert_eq!(cols, [[1.0, 4.0], [2.0, 5.0], [3.0, 6.0]]);
+
+        assert_eq!(m.iter().sum::<Float>(), 21.0);
+        assert_eq!((&m).into_iter().count(), 6);
+        assert_eq!(Matrix::new(2, 0).rows().count(), 2);
+        assert_eq!(Matrix::new(2, 0).cols().count(), 0);
+        assert_eq!(Matrix::new(0, 2).rows().count(), 0);
+        let empty_cols: Vec<usize> = Matrix::new(0, 2).cols().map(Iterator::count).collect();
+        assert_eq!(empty_cols, [0, 0]);
+    }
+
+    #[test]
+    fn test_multiplication_of_different_sizes() {
+        let a = matrix![[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]];
+        let b = matrix![[1.0], [0.0], [-1.0]];
+        assert_eq!(&a * &b, matrix![[-2.0], [-2.0]]);
+    }
+
     #[test]
     fn test_tuple_multiplication() {
-        let m = Matrix::from_values(
-            4,
-            4,
-            vec![
-                1.0, 2.0, 3.0, 4.0, 2.0, 4.0, 4.0, 2.0, 8.0, 6.0, 4.0, 1.0, 0.0, 0.0, 0.0, 1.0,
-            ],
-        );
+        let m = matrix![
+            [1.0, 2.0, 3.0, 4.0],
+            [2.0, 4.0, 4.0, 2.0],
+            [8.0, 6.0, 4.0, 1.0],
+            [0.0, 0.0, 0.0, 1.0]
+        ];
 
         let t = Point::new(1.0, 2.0, 3.0);
 
@@ -470,13 +640,12 @@ mod test {
 
     #[test]
     fn test_multiply_by_identity() {
-        let m = Matrix::from_values(
-            4,
-            4,
-            vec![
-                0.0, 1.0, 2.0, 4.0, 1.0, 2.0, 4.0, 8.0, 2.0, 4.0, 8.0, 16.0, 4.0, 8.0, 16.0, 32.0,
-            ],
-        );
+        let m = matrix![
+            [0.0, 1.0, 2.0, 4.0],
+            [1.0, 2.0, 4.0, 8.0],
+            [2.0, 4.0, 8.0, 16.0],
+            [4.0, 8.0, 16.0, 32.0]
+        ];
 
         assert_eq!(m.clone() * identity_matrix(), m);
         assert_eq!(&m * identity_matrix(), m);
@@ -484,21 +653,19 @@ mod test {
 
     #[test]
     fn test_transpose() {
-        let m = Matrix::from_values(
-            4,
-            4,
-            vec![
-                0.0, 9.0, 3.0, 0.0, 9.0, 8.0, 0.0, 8.0, 1.0, 8.0, 5.0, 3.0, 0.0, 0.0, 5.0, 8.0,
-            ],
-        );
-
-        let result = Matrix::from_values(
-            4,
-            4,
-            vec![
-                0.0, 9.0, 1.0, 0.0, 9.0, 8.0, 8.0, 0.0, 3.0, 0.0, 5.0, 5.0, 0.0, 8.0, 3.0, 8.0,
-            ],
-        );
+        let m = matrix![
+            [0.0, 9.0, 3.0, 0.0],
+            [9.0, 8.0, 0.0, 8.0],
+            [1.0, 8.0, 5.0, 3.0],
+            [0.0, 0.0, 5.0, 8.0]
+        ];
+
+        let result = matrix![
+            [0.0, 9.0, 1.0, 0.0],
+            [9.0, 8.0, 8.0, 0.0],
+            [3.0, 0.0, 5.0, 5.0],
+            [0.0, 8.0, 3.0, 8.0]
+        ];
 
         assert_eq!(m.transpose(), result);
 
@@ -507,14 +674,14 @@ mod test {
 
     #[test]
     fn test_determinant_2x2() {
-        let m = Matrix::from_values(2, 2, vec![1., 5., -3., 2.]);
+        let m = matrix![[1., 5.], [-3., 2.]];
 
         assert_eq!(m.determinant(), 17.0);
     }
 
     #[test]
     fn test_determinant_3x3() {
-        let m = Matrix::from_values(3, 3, vec![1.0, 2.0, 6.0, -5.0, 8.0, -4.0, 2.0, 6.0, 4.0]);
+        let m = matrix![[1.0, 2.0, 6.0], [-5.0, 8.0, -4.0], [2.0, 6.0, 4.0]];
 
         assert_eq!(m.cofactor(0, 0), 56.0);
         assert_eq!(m.cofactor(0, 1), 12.0);
@@ -524,14 +691,12 @@ mod test {
 
     #[test]
     fn test_determinant_4x4() {
-        let m = Matrix::from_values(
-            4,
-            4,
-            vec![
-                -2.0, -8.0, 3.0, 5.0, -3.0, 1.0, 7.0, 3.0, 1.0, 2.0, -9.0, 6.0, -6.0, 7.0, 7.0,
-                -9.0,
-            ],
-        );
+        let m = matrix![
+            [-2.0, -8.0, 3.0, 5.0],
+            [-3.0, 1.0, 7.0, 3.0],
+            [1.0, 2.0, -9.0, 6.0],
+            [-6.0, 7.0, 7.0, -9.0]
+        ];
 
         assert_eq!(m.cofactor(0, 0), 690.0);
         assert_eq!(m.cofactor(0, 1), 447.0);
@@ -542,32 +707,31 @@ mod test {
 
     #[test]
     fn test_submatrix() {
-        let m1 = Matrix::from_values(3, 3, vec![1.0, 5.0, 0.0, -3.0, 2.0, 7.0, 0.0, 6.0, -3.0]);
-        let r1 = Matrix::from_values(2, 2, vec![-3., 2., 0., 6.]);
+        let m1 = matrix![[1.0, 5.0, 0.0], [-3.0, 2.0, 7.0], [0.0, 6.0, -3.0]];
+        let r1 = matrix![[-3., 2.], [0., 6.]];
 
         assert_eq!(m1.submatrix(0, 2), r1);
 
-        let m2 = Matrix::from_values(
-            4,
-            4,
-            vec![
-                -6.0, 1.0, 1.0, 6.0, -8.0, 5.0, 8.0, 6.0, -1.0, 0.0, 8.0, 2.0, -7.0, 1.0, -1.0, 1.0,
-            ],
-        );
-        let r2 = Matrix::from_values(3, 3, vec![-6.0, 1.0, 6.0, -8.0, 8.0, 6.0, -7.0, -1.0, 1.0]);
+        let m2 = matrix![
+            [-6.0, 1.0, 1.0, 6.0],
+            [-8.0, 5.0, 8.0, 6.0],
+            [-1.0, 0.0, 8.0, 2.0],
+            [-7.0, 1.0, -1.0, 1.0]
+        ];
+        let r2 = matrix![[-6.0, 1.0, 6.0], [-8.0, 8.0, 6.0], [-7.0, -1.0, 1.0]];
         assert_eq!(m2.submatrix(2, 1), r2);
     }
 
     #[test]
     fn test_minor() {
-        let m = Matrix::from_values(3, 3, vec![3.0, 5.0, 0.0, 2.0, -1.0, -7.0, 6.0, -1.0, 5.0]);
+        let m = matrix![[3.0, 5.0, 0.0], [2.0, -1.0, -7.0], [6.0, -1.0, 5.0]];
 
         assert_eq!(m.minor(1, 0), 25.);
     }
 
     #[test]
     fn test_cofactor() {
-        let m = Matrix::from_values(3, 3, vec![3.0, 5.0, 0.0, 2.0, -1.0, -7.0, 6.0, -1.0, 5.0]);
+        let m = matrix![[3.0, 5.0, 0.0], [2.0, -1.0, -7.0], [6.0, -1.0, 5.0]];
 
         assert_eq!(m.minor(0, 0), -12.);
         assert_eq!(m.cofactor(0, 0), -12.);
@@ -577,21 +741,19 @@ mod test {
 
     #[test]
     fn test_invertible() {
-        let invertible = Matrix::from_values(
-            4,
-            4,
-            vec![
-                6.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 6.0, 4.0, -9.0, 3.0, -7.0, 9.0, 1.0, 7.0, -6.0,
-            ],
-        );
-
-        let non_invertible = Matrix::from_values(
-            4,
-            4,
-            vec![
-                -4.0, 2.0, -2.0, -3.0, 9.0, 6.0, 2.0, 6.0, 0.0, -5.0, 1.0, -5.0, 0.0, 0.0, 0.0, 0.0,
-            ],
-        );
+        let invertible = matrix![
+            [6.0, 4.0, 4.0, 4.0],
+            [5.0, 5.0, 7.0, 6.0],
+            [4.0, -9.0, 3.0, -7.0],
+            [9.0, 1.0, 7.0, -6.0]
+        ];
+
+        let non_invertible = matrix![
+            [-4.0, 2.0, -2.0, -3.0],
+            [9.0, 6.0, 2.0, 6.0],
+            [0.0, -5.0, 1.0, -5.0],
+            [0.0, 0.0, 0.0, 0.0]
+        ];
 
         assert!(invertible.invertible());
         assert!(!non_invertible.invertible());
@@ -599,258 +761,178 @@ mod test {
 
     #[test]
     fn test_inversion() {
-        let a = Matrix::from_values(
-            4,
-            4,
-            vec![
-                -5.0, 2.0, 6.0, -8.0, 1.0, -5.0, 1.0, 8.0, 7.0, 7.0, -6.0, -7.0, 1.0, -3.0, 7.0,
-                4.0,
-            ],
-        );
+        let a = matrix![
+            [-5.0, 2.0, 6.0, -8.0],
+            [1.0, -5.0, 1.0, 8.0],
+            [7.0, 7.0, -6.0, -7.0],
+            [1.0, -3.0, 7.0, 4.0]
+        ];
         let b = a.inverse().unwrap();
 
-        let result = Matrix::from_values(
-            4,
-            4,
-            vec![
-                0.21805, 0.45113, 0.2406, -0.04511, -0.80827, -1.45677, -0.44361, 0.52068,
-                -0.07895, -0.22368, -0.05263, 0.19737, -0.52256, -0.81391, -0.30075, 0.30639,
-            ],
-        );
+        let result = matrix![
+            [0.21805, 0.45113, 0.2406, -0.04511],
+            [-0.80827, -1.45677, -0.44361, 0.52068],
+            [-0.07895, -0.22368, -0.05263, 0.19737],
+            [-0.52256, -0.81391, -0.30075, 0.30639]
+        ];
 
         assert_eq!(a.determinant(), 532.);
         assert_eq!(a.cofactor(2, 3), -160.);
 
-        assert_eq!(b.get(3, 2), -160. / 532.);
+        assert_approx_eq!(b.get(3, 2), -160. / 532.);
         assert_eq!(a.cofactor(3, 2), 105.);
-        assert_eq!(b.get(2, 3), 105. / 532.);
+        assert_approx_eq!(b.get(2, 3), 105. / 532.);
 
         assert_eq!(b, result);
     }
 
     #[test]
     fn test_inverse_multiplication() {
-        let a = Matrix::from_values(
-            4,
-            4,
-            vec![
-                3.0, -9.0, 7.0, 3.0, 3.0, -8.0, 2.0, -9.0, -4.0, 4.0, 4.0, 1.0, -6.0, 5.0, -1.0,
-                1.0,
-            ],
-        );
-        let b = Matrix::from_values(
-            4,
-            4,
-            vec![
-                8.0, 2.0, 2.0, 2.0, 3.0, -1.0, 7.0, 0.0, 7.0, 0.0, 5.0, 4.0, 6.0, -2.0, 0.0, 5.0,
-            ],
-        );
+        let a = matrix![
+            [3.0, -9.0, 7.0, 3.0],
+            [3.0, -8.0, 2.0, -9.0],
+            [-4.0, 4.0, 4.0, 1.0],
+            [-6.0, 5.0, -1.0, 1.0]
+        ];
+        let b = matrix![
+            [8.0, 2.0, 2.0, 2.0],
+            [3.0, -1.0, 7.0, 0.0],
+            [7.0, 0.0, 5.0, 4.0],
+            [6.0, -2.0, 0.0, 5.0]
+        ];
 
         let c = &a * &b;
         assert_eq!(c * b.inverse().unwrap(), a);
     }
 
     #[test]
-    fn test_translation() {
-        let transform = Matrix::translation(5., -3., 2.);
-        let p = Point::new(-3., 4., 5.);
-
-        assert_eq!(&transform * p, Point::new(2., 1., 7.));
-
-        let inv = transform.inverse().unwrap();
-        assert_eq!(inv * p, Point::new(-8., 7., 3.));
-
-        let v = Vector::new(-3., 4., 5.);
-        assert_eq!(transform * v, v);
-    }
-
-    #[test]
-    fn test_translation_chained() {
-        let p = Point::new(-3., 4., 5.);
-        assert_eq!(p.translate(5., -3., 2.), Point::new(2., 1., 7.));
-
-        let v = Vector::new(-3., 4., 5.);
-        assert_eq!(v.translate(5., -3., 2.), v);
-    }
-
-    #[test]
-    fn test_scaling() {
-        let transform = Matrix::scaling(2., 3., 4.);
-        let p = Point::new(-4., 6., 8.);
-        assert_eq!(&transform * p, Point::new(-8., 18., 32.));
-
-        let v = Vector::new(-4., 6., 8.);
-        assert_eq!(&transform * v, Vector::new(-8., 18., 32.));
+    fn test_out_of_bounds_access() {
+        let mut m = Matrix::new(2, 3);
+        assert!(m.try_set(1, 2, 5.0).is_ok());
+        assert_eq!(m.try_get(1, 2).unwrap(), 5.0);
 
-        let inv = transform.inverse().unwrap();
-        assert_eq!(inv * v, Vector::new(-2., 2., 2.));
-    }
-
-    #[test]
-    fn test_scaling_chained() {
-        let p = Point::new(-4., 6., 8.);
-        assert_eq!(p.scale(2., 3., 4.), Point::new(-8., 18., 32.));
-
-        let v = Vector::new(-4., 6., 8.);
-        assert_eq!(v.scale(2., 3., 4.), Vector::new(-8., 18., 32.));
-    }
-
-    #[test]
-    fn test_reflection() {
-        let transform = Matrix::scaling(-1., 1., 1.);
-        let p = Point::new(2., 3., 4.);
-
-        assert_eq!(transform * p, Point::new(-2., 3., 4.));
+        // Would be in range of the values if the column weren't checked.
+        assert!(matches!(
+            m.try_get(0, 3),
+            Err(Error::MatrixIndexOutOfBounds {
+                row: 0,
+                col: 3,
+                rows: 2,
+                cols: 3
+            })
+        ));
+        assert!(m.try_set(2, 0, 1.0).is_err());
     }
 
     #[test]
-    fn test_rotation_x() {
-        let p = Point::new(0., 1., 0.);
-        let half_quarter = Matrix::rotation_x(PI / 4.);
-        let full_quarter = Matrix::rotation_x(PI / 2.);
-        assert_eq!(
-            half_quarter * p,
-            Point::new(0.0, (2.0_f64).sqrt() / 2.0, (2.0_f64).sqrt() / 2.0)
-        );
-        assert_eq!(full_quarter * p, Point::new(0.0, 0.0, 1.0));
-
-        assert_eq!(
-            p.rotate_x(PI / 4.),
-            Point::new(0.0, (2.0_f64).sqrt() / 2.0, (2.0_f64).sqrt() / 2.0)
-        );
-        assert_eq!(p.rotate_x(PI / 2.), Point::new(0.0, 0.0, 1.0));
+    #[should_panic(expected = "0, 3 is out of bounds for a 2x3 matrix")]
+    fn test_set_out_of_bounds_panics() {
+        Matrix::new(2, 3).set(0, 3, 1.0);
     }
 
     #[test]
-    fn test_rotation_y() {
-        let p = Point::new(0., 0., 1.);
-        let half_quarter = Matrix::rotation_y(PI / 4.);
-        let full_quarter = Matrix::rotation_y(PI / 2.);
-        assert_eq!(
-            half_quarter * p,
-            Point::new((2.0_f64).sqrt() / 2.0, 0.0, (2.0_f64).sqrt() / 2.0)
-        );
-        assert_eq!(full_quarter * p, Point::new(1.0, 0.0, 0.0));
-
-        assert_eq!(
-            p.rotate_y(PI / 4.),
-            Point::new((2.0_f64).sqrt() / 2.0, 0.0, (2.0_f64).sqrt() / 2.0)
-        );
-        assert_eq!(p.rotate_y(PI / 2.), Point::new(1.0, 0.0, 0.0));
+    fn test_from_wrong_number_of_values() {
+        assert!(matches!(
+            Matrix::try_from_values(2, 2, vec![1.0, 2.0, 3.0]),
+            Err(Error::MatrixSizeMismatch {
+                rows: 2,
+                cols: 2,
+                len: 3
+            })
+        ));
+        assert!(Matrix::try_from_values(1, 3, vec![1.0, 2.0, 3.0]).is_ok());
     }
 
     #[test]
-    fn test_rotation_z() {
-        let p = Point::new(0., 1., 0.);
-        let half_quarter = Matrix::rotation_z(PI / 4.);
-        let full_quarter = Matrix::rotation_z(PI / 2.);
-        assert_eq!(
-            half_quarter * p,
-            Point::new(-(2.0_f64).sqrt() / 2.0, (2.0_f64).sqrt() / 2.0, 0.0)
-        );
-        assert_eq!(full_quarter * p, Point::new(-1.0, 0.0, 0.0));
+    fn test_inverse_of_singular_matrix() {
+        let m = matrix![
+            [-4.0, 2.0, -2.0, -3.0],
+            [9.0, 6.0, 2.0, 6.0],
+            [0.0, -5.0, 1.0, -5.0],
+            [0.0, 0.0, 0.0, 0.0]
+        ];
+        assert_eq!(m.inverse().unwrap_err(), MatrixError::Singular);
 
-        assert_eq!(
-            p.rotate_z(PI / 4.),
-            Point::new(-(2.0_f64).sqrt() / 2.0, (2.0_f64).sqrt() / 2.0, 0.0)
-        );
-        assert_eq!(p.rotate_z(PI / 2.), Point::new(-1.0, 0.0, 0.0));
+        // Rows that are only dependent after rounding are still singular.
+        let m = matrix![[1.0, 2.0, 3.0], [4.0, 5.0, 6.0], [7.0, 8.0, 9.0]];
+        assert_eq!(m.inverse().unwrap_err(), MatrixError::Singular);
     }
 
     #[test]
-    fn test_shearing_moves_x_to_y() {
-        let transform = Matrix::shearing(1.0, 0.0, 0.0, 0.0, 0.0, 0.0);
-        let p = Point::new(2.0, 3.0, 4.0);
-
-        assert_eq!(transform * p, Point::new(5.0, 3.0, 4.0));
-
+    fn test_inverse_of_non_square_matrix() {
+        let m = matrix![[1.0, 0.0, 0.0], [0.0, 1.0, 0.0]];
         assert_eq!(
-            p.shear(1.0, 0.0, 0.0, 0.0, 0.0, 0.0),
-            Point::new(5.0, 3.0, 4.0)
+            m.inverse().unwrap_err(),
+            MatrixError::NotSquare { rows: 2, cols: 3 }
         );
     }
 
     #[test]
-    fn test_shearing_moves_x_to_z() {
-        let transform = Matrix::shearing(0.0, 1.0, 0.0, 0.0, 0.0, 0.0);
-        let p = Point::new(2.0, 3.0, 4.0);
-
-        assert_eq!(transform * p, Point::new(6.0, 3.0, 4.0));
+    fn test_inverse_needs_pivoting() {
+        let m = matrix![[0.0, 1.0, 0.0], [0.0, 0.0, 2.0], [4.0, 0.0, 0.0]];
+        let result = matrix![[0.0, 0.0, 0.25], [1.0, 0.0, 0.0], [0.0, 0.5, 0.0]];
+        assert_eq!(m.inverse().unwrap(), result);
     }
 
     #[test]
-    fn test_shearing_moves_y_to_x() {
-        let transform = Matrix::shearing(0.0, 0.0, 1.0, 0.0, 0.0, 0.0);
-        let p = Point::new(2.0, 3.0, 4.0);
+    fn test_determinant_of_larger_matrix() {
+        // Upper triangular with a diagonal of 2, 3, -1, 5 and 0.5, once the
+        // second and fourth rows are swapped back.
+        let m = matrix![
+            [2.0, 1.0, 0.0, 4.0, 3.0],
+            [0.0, 0.0, 0.0, 5.0, 1.0],
+            [0.0, 0.0, -1.0, 2.0, 0.0],
+            [0.0, 3.0, 7.0, 1.0, 2.0],
+            [0.0, 0.0, 0.0, 0.0, 0.5]
+        ];
+        assert_approx_eq!(m.determinant(), 15.0);
 
-        assert_eq!(transform * p, Point::new(2.0, 5.0, 4.0));
-
-        assert_eq!(
-            p.shear(0.0, 0.0, 1.0, 0.0, 0.0, 0.0),
-            Point::new(2.0, 5.0, 4.0)
-        );
+        let singular =
+            Matrix::from_values(5, 5, (0..25).map(|i| (i % 5 + i / 5) as Float).collect());
+        assert_approx_eq!(singular.determinant(), 0.0);
+        assert!(!singular.invertible());
     }
 
     #[test]
-    fn test_shearing_moves_y_to_z() {
-        let transform = Matrix::shearing(0.0, 0.0, 0.0, 1.0, 0.0, 0.0);
-        let p = Point::new(2.0, 3.0, 4.0);
-
-        assert_eq!(transform * p, Point::new(2.0, 7.0, 4.0));
-        assert_eq!(
-            p.shear(0.0, 0.0, 0.0, 1.0, 0.0, 0.0),
-            Point::new(2.0, 7.0, 4.0)
-        );
+    fn test_lu_determinant_matches_cofactors() {
+        let m = matrix![
+            [-2.0, -8.0, 3.0, 5.0],
+            [-3.0, 1.0, 7.0, 3.0],
+            [1.0, 2.0, -9.0, 6.0],
+            [-6.0, 7.0, 7.0, -9.0]
+        ];
+        // Relative, as rounding scales with the determinant.
+        assert_approx_eq!(m.lu().unwrap().determinant() / -4071.0, 1.0);
     }
 
     #[test]
-    fn test_shearing_moves_z_to_x() {
-        let transform = Matrix::shearing(0.0, 0.0, 0.0, 0.0, 1.0, 0.0);
-        let p = Point::new(2.0, 3.0, 4.0);
-
-        assert_eq!(transform * p, Point::new(2.0, 3.0, 6.0));
+    fn test_lu_solve() {
+        let m = matrix![[0.0, 1.0, 0.0], [0.0, 0.0, 2.0], [4.0, 0.0, 1.0]];
+        let lu = m.lu().unwrap();
+        let x = lu.solve(&[3.0, 4.0, 9.0]).unwrap();
         assert_eq!(
-            p.shear(0.0, 0.0, 0.0, 0.0, 1.0, 0.0),
-            Point::new(2.0, 3.0, 6.0)
+            Tuple::new(x[0], x[1], x[2], 0.0),
+            Tuple::new(1.75, 3.0, 2.0, 0.0)
         );
-    }
-
-    #[test]
-    fn test_shearing_moves_z_to_y() {
-        let transform = Matrix::shearing(0.0, 0.0, 0.0, 0.0, 0.0, 1.0);
-        let p = Point::new(2.0, 3.0, 4.0);
-
-        assert_eq!(transform * p, Point::new(2.0, 3.0, 7.0));
         assert_eq!(
-            p.shear(0.0, 0.0, 0.0, 0.0, 0.0, 1.0),
-            Point::new(2.0, 3.0, 7.0)
+            Matrix::new(3, 3).lu().unwrap().solve(&[1.0, 2.0, 3.0]),
+            Err(MatrixError::Singular)
         );
     }
 
     #[test]
-    fn test_transformations_applied_in_sequence() {
-        let p = Point::new(1.0, 0.0, 1.0);
-        let a = Matrix::rotation_x(PI / 2.0);
-        let b = Matrix::scaling(5.0, 5.0, 5.0);
-        let c = Matrix::translation(10.0, 5.0, 7.0);
-
-        let p2 = &a * p;
-        assert_eq!(p2, Point::new(1.0, -1.0, 0.0));
-
-        let p3 = &b * p2;
-        assert_eq!(p3, Point::new(5.0, -5.0, 0.0));
-
-        let p4 = &c * p3;
-        assert_eq!(p4, Point::new(15.0, 0.0, 7.0));
-
-        let p5 = c * b * a * p;
-        assert_eq!(p5, Point::new(15.0, 0.0, 7.0));
-
-        assert_eq!(
-            p.rotate_x(PI / 2.0)
-                .scale(5.0, 5.0, 5.0)
-                .translate(10.0, 5.0, 7.0),
-            Point::new(15.0, 0.0, 7.0)
-        )
+    fn test_inverse_of_larger_matrix() {
+        let m = matrix![
+            [2.0, 1.0, 0.0, 0.0, 3.0],
+            [1.0, 4.0, 1.0, 0.0, 0.0],
+            [0.0, 1.0, 5.0, 2.0, 0.0],
+            [0.0, 0.0, 2.0, 6.0, 1.0],
+            [3.0, 0.0, 0.0, 1.0, 7.0]
+        ];
+        let mut identity = Matrix::new(5, 5);
+        for i in 0..5 {
+            identity.set(i, i, 1.0);
+        }
+        assert_eq!(&m * &m.inverse().unwrap(), identity);
     }
 }
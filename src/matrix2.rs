@@ -0,0 +1,147 @@
+use crate::approx_equal;
+use crate::error::MatrixError;
+use crate::Float;
+use std::{fmt::Debug, ops::Mul};
+
+/// A 2x2 matrix stored inline, which is what the minors of a
+/// [`Matrix3`](crate::matrix3::Matrix3) are made of.
+#[derive(Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Matrix2 {
+    values: [[Float; 2]; 2],
+}
+
+impl Matrix2 {
+    pub const IDENTITY: Matrix2 = Matrix2::new([[1.0, 0.0], [0.0, 1.0]]);
+
+    pub const fn new(values: [[Float; 2]; 2]) -> Self {
+        Self { values }
+    }
+
+    pub fn identity() -> Self {
+        Self::IDENTITY
+    }
+
+    pub fn set(&mut self, row: usize, col: usize, value: Float) {
+        self.values[row][col] = value;
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> Float {
+        self.values[row][col]
+    }
+
+    pub fn transpose(&self) -> Matrix2 {
+        let m = &self.values;
+        Matrix2::new([[m[0][0], m[1][0]], [m[0][1], m[1][1]]])
+    }
+
+    pub fn determinant(&self) -> Float {
+        let m = &self.values;
+        m[0][0] * m[1][1] - m[0][1] * m[1][0]
+    }
+
+    pub fn invertible(&self) -> bool {
+        self.determinant() != 0.0
+    }
+
+    /// The inverse of the matrix, failing with [`MatrixError::Singular`] if
+    /// it has none.
+    pub fn inverse(&self) -> Result<Matrix2, MatrixError> {
+        let determinant = self.determinant();
+        if determinant == 0.0 {
+            return Err(MatrixError::Singular);
+        }
+
+        let m = &self.values;
+        let inv = 1.0 / determinant;
+        Ok(Matrix2::new([
+            [m[1][1] * inv, -m[0][1] * inv],
+            [-m[1][0] * inv, m[0][0] * inv],
+        ]))
+    }
+}
+
+impl Default for Matrix2 {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl PartialEq for Matrix2 {
+    fn eq(&self, other: &Self) -> bool {
+        self.values
+            .iter()
+            .flatten()
+            .zip(other.values.iter().flatten())
+            .all(|(&a, &b)| approx_equal(a, b))
+    }
+}
+
+impl Debug for Matrix2 {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Matrix2 [")?;
+        for row in &self.values {
+            writeln!(f, "  {row:?}")?;
+        }
+        writeln!(f, "]")?;
+
+        Ok(())
+    }
+}
+
+impl From<[[Float; 2]; 2]> for Matrix2 {
+    fn from(value: [[Float; 2]; 2]) -> Self {
+        Matrix2::new(value)
+    }
+}
+
+impl Mul for &Matrix2 {
+    type Output = Matrix2;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        let mut values = [[0.0; 2]; 2];
+        for (row, result) in values.iter_mut().enumerate() {
+            for (col, value) in result.iter_mut().enumerate() {
+                *value = (0..2)
+                    .map(|i| self.values[row][i] * rhs.values[i][col])
+                    .sum();
+            }
+        }
+        Matrix2::new(values)
+    }
+}
+
+impl Mul for Matrix2 {
+    type Output = Matrix2;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        (&self).mul(&rhs)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_determinant() {
+        let m = Matrix2::new([[1.0, 5.0], [-3.0, 2.0]]);
+        assert_eq!(m.determinant(), 17.0);
+    }
+
+    #[test]
+    fn test_transpose() {
+        let m = Matrix2::new([[1.0, 2.0], [3.0, 4.0]]);
+        assert_eq!(m.transpose(), Matrix2::new([[1.0, 3.0], [2.0, 4.0]]));
+    }
+
+    #[test]
+    fn test_inverse() {
+        let m = Matrix2::new([[4.0, 7.0], [2.0, 6.0]]);
+        assert_eq!(m * m.inverse().unwrap(), Matrix2::identity());
+        assert_eq!(
+            Matrix2::new([[1.0, 2.0], [2.0, 4.0]]).inverse(),
+            Err(MatrixError::Singular)
+        );
+    }
+}
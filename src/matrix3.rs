@@ -0,0 +1,206 @@
+use crate::approx_equal;
+use crate::error::MatrixError;
+use crate::matrix2::Matrix2;
+use crate::space::Vector;
+use crate::Float;
+use std::{fmt::Debug, ops::Mul};
+
+/// A 3x3 matrix stored inline. It's what the minors of a
+/// [`Matrix4`](crate::matrix4::Matrix4) are made of, and is enough to
+/// transform normals, which translation doesn't affect.
+#[derive(Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Matrix3 {
+    values: [[Float; 3]; 3],
+}
+
+impl Matrix3 {
+    pub const IDENTITY: Matrix3 = Matrix3::new([[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]]);
+
+    pub const fn new(values: [[Float; 3]; 3]) -> Self {
+        Self { values }
+    }
+
+    pub fn identity() -> Self {
+        Self::IDENTITY
+    }
+
+    pub fn set(&mut self, row: usize, col: usize, value: Float) {
+        self.values[row][col] = value;
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> Float {
+        self.values[row][col]
+    }
+
+    pub fn transpose(&self) -> Matrix3 {
+        let m = &self.values;
+        Matrix3::new(std::array::from_fn(|row| {
+            std::array::from_fn(|col| m[col][row])
+        }))
+    }
+
+    /// The matrix without `row` and `col`.
+    pub fn submatrix(&self, row: usize, col: usize) -> Matrix2 {
+        let skip = |i: usize, skipped: usize| if i < skipped { i } else { i + 1 };
+        Matrix2::new(std::array::from_fn(|r| {
+            std::array::from_fn(|c| self.values[skip(r, row)][skip(c, col)])
+        }))
+    }
+
+    pub fn minor(&self, row: usize, col: usize) -> Float {
+        self.submatrix(row, col).determinant()
+    }
+
+    pub fn cofactor(&self, row: usize, col: usize) -> Float {
+        let minor = self.minor(row, col);
+        if (row + col) % 2 == 1 {
+            -minor
+        } else {
+            minor
+        }
+    }
+
+    pub fn determinant(&self) -> Float {
+        (0..3)
+            .map(|col| self.values[0][col] * self.cofactor(0, col))
+            .sum()
+    }
+
+    pub fn invertible(&self) -> bool {
+        self.determinant() != 0.0
+    }
+
+    /// The inverse of the matrix, failing with [`MatrixError::Singular`] if
+    /// it has none.
+    pub fn inverse(&self) -> Result<Matrix3, MatrixError> {
+        let determinant = self.determinant();
+        if determinant == 0.0 {
+            return Err(MatrixError::Singular);
+        }
+
+        // The transposed matrix of cofactors, over the determinant.
+        Ok(Matrix3::new(std::array::from_fn(|row| {
+            std::array::from_fn(|col| self.cofactor(col, row) / determinant)
+        })))
+    }
+}
+
+impl Default for Matrix3 {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl PartialEq for Matrix3 {
+    fn eq(&self, other: &Self) -> bool {
+        self.values
+            .iter()
+            .flatten()
+            .zip(other.values.iter().flatten())
+            .all(|(&a, &b)| approx_equal(a, b))
+    }
+}
+
+impl Debug for Matrix3 {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Matrix3 [")?;
+        for row in &self.values {
+            writeln!(f, "  {row:?}")?;
+        }
+        writeln!(f, "]")?;
+
+        Ok(())
+    }
+}
+
+impl From<[[Float; 3]; 3]> for Matrix3 {
+    fn from(value: [[Float; 3]; 3]) -> Self {
+        Matrix3::new(value)
+    }
+}
+
+impl Mul for &Matrix3 {
+    type Output = Matrix3;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        let mut values = [[0.0; 3]; 3];
+        for (row, result) in values.iter_mut().enumerate() {
+            for (col, value) in result.iter_mut().enumerate() {
+                *value = (0..3)
+                    .map(|i| self.values[row][i] * rhs.values[i][col])
+                    .sum();
+            }
+        }
+        Matrix3::new(values)
+    }
+}
+
+impl Mul for Matrix3 {
+    type Output = Matrix3;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        (&self).mul(&rhs)
+    }
+}
+
+impl Mul<Vector> for &Matrix3 {
+    type Output = Vector;
+    fn mul(self, rhs: Vector) -> Self::Output {
+        let row = |r: usize| (0..3).map(|col| self.values[r][col] * rhs[col]).sum();
+        Vector::new(row(0), row(1), row(2))
+    }
+}
+
+impl Mul<Vector> for Matrix3 {
+    type Output = Vector;
+    fn mul(self, rhs: Vector) -> Self::Output {
+        (&self).mul(rhs)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_submatrix() {
+        let m = Matrix3::new([[1.0, 5.0, 0.0], [-3.0, 2.0, 7.0], [0.0, 6.0, -3.0]]);
+        assert_eq!(m.submatrix(0, 2), Matrix2::new([[-3.0, 2.0], [0.0, 6.0]]));
+    }
+
+    #[test]
+    fn test_minor_and_cofactor() {
+        let m = Matrix3::new([[3.0, 5.0, 0.0], [2.0, -1.0, -7.0], [6.0, -1.0, 5.0]]);
+        assert_eq!(m.minor(0, 0), -12.0);
+        assert_eq!(m.cofactor(0, 0), -12.0);
+        assert_eq!(m.minor(1, 0), 25.0);
+        assert_eq!(m.cofactor(1, 0), -25.0);
+    }
+
+    #[test]
+    fn test_determinant() {
+        let m = Matrix3::new([[1.0, 2.0, 6.0], [-5.0, 8.0, -4.0], [2.0, 6.0, 4.0]]);
+        assert_eq!(m.cofactor(0, 0), 56.0);
+        assert_eq!(m.cofactor(0, 1), 12.0);
+        assert_eq!(m.cofactor(0, 2), -46.0);
+        assert_eq!(m.determinant(), -196.0);
+    }
+
+    #[test]
+    fn test_inverse() {
+        let m = Matrix3::new([[1.0, 2.0, 6.0], [-5.0, 8.0, -4.0], [2.0, 6.0, 4.0]]);
+        assert_eq!(m * m.inverse().unwrap(), Matrix3::identity());
+        assert_eq!(m.inverse().unwrap().get(2, 0), -46.0 / -196.0);
+
+        let singular = Matrix3::new([[1.0, 2.0, 3.0], [2.0, 4.0, 6.0], [0.0, 1.0, 1.0]]);
+        assert!(!singular.invertible());
+        assert_eq!(singular.inverse(), Err(MatrixError::Singular));
+    }
+
+    #[test]
+    fn test_vector_multiplication() {
+        let m = Matrix3::new([[0.0, -1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, 2.0]]);
+        assert_eq!(m * Vector::new(1.0, 2.0, 3.0), Vector::new(-2.0, 1.0, 6.0));
+    }
+}
@@ -0,0 +1,854 @@
+use crate::approx_equal;
+use crate::error::MatrixError;
+use crate::matrix3::Matrix3;
+use crate::space::{Point, Tuple, Vector};
+use crate::Float;
+use std::{fmt::Debug, ops::Mul};
+
+/// A 4x4 matrix stored inline, for transformations.
+///
+/// Unlike [`Matrix`](crate::matrix::Matrix) it never allocates, and its
+/// determinant and inverse are computed in closed form rather than by
+/// recursive cofactor expansion, which makes it much cheaper to use per ray.
+#[derive(Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Matrix4 {
+    values: [[Float; 4]; 4],
+}
+
+impl Matrix4 {
+    pub const IDENTITY: Matrix4 = Matrix4::new([
+        [1.0, 0.0, 0.0, 0.0],
+        [0.0, 1.0, 0.0, 0.0],
+        [0.0, 0.0, 1.0, 0.0],
+        [0.0, 0.0, 0.0, 1.0],
+    ]);
+
+    pub const fn new(values: [[Float; 4]; 4]) -> Self {
+        Self { values }
+    }
+
+    pub fn identity() -> Self {
+        Self::IDENTITY
+    }
+
+    pub fn set(&mut self, row: usize, col: usize, value: Float) {
+        self.values[row][col] = value;
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> Float {
+        self.values[row][col]
+    }
+
+    pub fn transpose(&self) -> Matrix4 {
+        let m = &self.values;
+        let mut result = [[0.0; 4]; 4];
+        for (row, values) in result.iter_mut().enumerate() {
+            for (col, value) in values.iter_mut().enumerate() {
+                *value = m[col][row];
+            }
+        }
+        Matrix4::new(result)
+    }
+
+    /// The matrix without `row` and `col`. Leaving out the last row and
+    /// column gives the part of a transformation that isn't translation.
+    pub fn submatrix(&self, row: usize, col: usize) -> Matrix3 {
+        let skip = |i: usize, skipped: usize| if i < skipped { i } else { i + 1 };
+        Matrix3::new(std::array::from_fn(|r| {
+            std::array::from_fn(|c| self.values[skip(r, row)][skip(c, col)])
+        }))
+    }
+
+    /// The matrix that transforms normals the way this one transforms
+    /// points: the inverse transpose of its [`submatrix`](Self::submatrix)
+    /// without translation. Fails with [`MatrixError::Singular`] if the
+    /// transformation flattens space.
+    pub fn normal_matrix(&self) -> Result<Matrix3, MatrixError> {
+        Ok(self.submatrix(3, 3).inverse()?.transpose())
+    }
+
+    /// The determinants of the 2x2 submatrices in the top two rows (`s`) and
+    /// bottom two rows (`c`), which between them give the determinant and
+    /// every cofactor of the 4x4 matrix.
+    fn sub_determinants(&self) -> ([Float; 6], [Float; 6]) {
+        let m = &self.values;
+        let s = [
+            m[0][0] * m[1][1] - m[1][0] * m[0][1],
+            m[0][0] * m[1][2] - m[1][0] * m[0][2],
+            m[0][0] * m[1][3] - m[1][0] * m[0][3],
+            m[0][1] * m[1][2] - m[1][1] * m[0][2],
+            m[0][1] * m[1][3] - m[1][1] * m[0][3],
+            m[0][2] * m[1][3] - m[1][2] * m[0][3],
+        ];
+        let c = [
+            m[2][0] * m[3][1] - m[3][0] * m[2][1],
+            m[2][0] * m[3][2] - m[3][0] * m[2][2],
+            m[2][0] * m[3][3] - m[3][0] * m[2][3],
+            m[2][1] * m[3][2] - m[3][1] * m[2][2],
+            m[2][1] * m[3][3] - m[3][1] * m[2][3],
+            m[2][2] * m[3][3] - m[3][2] * m[2][3],
+        ];
+        (s, c)
+    }
+
+    pub fn determinant(&self) -> Float {
+        let (s, c) = self.sub_determinants();
+        s[0] * c[5] - s[1] * c[4] + s[2] * c[3] + s[3] * c[2] - s[4] * c[1] + s[5] * c[0]
+    }
+
+    /// Whether the matrix has an inverse. As with
+    /// [`Matrix::lu`](crate::matrix::Matrix::lu), it counts as singular if
+    /// eliminating it leaves a pivot within rounding error of zero, relative
+    /// to its largest value, so a transformation that all but flattens space
+    /// is rejected even though its determinant isn't quite zero.
+    pub fn invertible(&self) -> bool {
+        let mut m = self.values;
+        let largest = m.iter().flatten().fold(0.0 as Float, |l, v| l.max(v.abs()));
+        let tolerance = largest * 4.0 * Float::EPSILON;
+        for col in 0..4 {
+            let pivot_row = (col..4)
+                .max_by(|&r1, &r2| m[r1][col].abs().total_cmp(&m[r2][col].abs()))
+                .unwrap();
+            m.swap(col, pivot_row);
+            let pivot = m[col][col];
+            if pivot.abs() <= tolerance {
+                return false;
+            }
+            let (above, below) = m.split_at_mut(col + 1);
+            for row in below {
+                let factor = row[col] / pivot;
+                for (value, p) in row[col + 1..].iter_mut().zip(&above[col][col + 1..]) {
+                    *value -= factor * p;
+                }
+            }
+        }
+        true
+    }
+
+    /// The inverse of the matrix, failing with [`MatrixError::Singular`] if
+    /// it has none. See [`Matrix4::invertible`].
+    pub fn inverse(&self) -> Result<Matrix4, MatrixError> {
+        if !self.invertible() {
+            return Err(MatrixError::Singular);
+        }
+        let (s, c) = self.sub_determinants();
+        let determinant =
+            s[0] * c[5] - s[1] * c[4] + s[2] * c[3] + s[3] * c[2] - s[4] * c[1] + s[5] * c[0];
+
+        let m = &self.values;
+        let inv = 1.0 / determinant;
+        Ok(Matrix4::new([
+            [
+                (m[1][1] * c[5] - m[1][2] * c[4] + m[1][3] * c[3]) * inv,
+                (-m[0][1] * c[5] + m[0][2] * c[4] - m[0][3] * c[3]) * inv,
+                (m[3][1] * s[5] - m[3][2] * s[4] + m[3][3] * s[3]) * inv,
+                (-m[2][1] * s[5] + m[2][2] * s[4] - m[2][3] * s[3]) * inv,
+            ],
+            [
+                (-m[1][0] * c[5] + m[1][2] * c[2] - m[1][3] * c[1]) * inv,
+                (m[0][0] * c[5] - m[0][2] * c[2] + m[0][3] * c[1]) * inv,
+                (-m[3][0] * s[5] + m[3][2] * s[2] - m[3][3] * s[1]) * inv,
+                (m[2][0] * s[5] - m[2][2] * s[2] + m[2][3] * s[1]) * inv,
+            ],
+            [
+                (m[1][0] * c[4] - m[1][1] * c[2] + m[1][3] * c[0]) * inv,
+                (-m[0][0] * c[4] + m[0][1] * c[2] - m[0][3] * c[0]) * inv,
+                (m[3][0] * s[4] - m[3][1] * s[2] + m[3][3] * s[0]) * inv,
+                (-m[2][0] * s[4] + m[2][1] * s[2] - m[2][3] * s[0]) * inv,
+            ],
+            [
+                (-m[1][0] * c[3] + m[1][1] * c[1] - m[1][2] * c[0]) * inv,
+                (m[0][0] * c[3] - m[0][1] * c[1] + m[0][2] * c[0]) * inv,
+                (-m[3][0] * s[3] + m[3][1] * s[1] - m[3][2] * s[0]) * inv,
+                (m[2][0] * s[3] - m[2][1] * s[1] + m[2][2] * s[0]) * inv,
+            ],
+        ]))
+    }
+
+    pub fn translation(x: Float, y: Float, z: Float) -> Self {
+        Self::new([
+            [1.0, 0.0, 0.0, x],
+            [0.0, 1.0, 0.0, y],
+            [0.0, 0.0, 1.0, z],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn scaling(x: Float, y: Float, z: Float) -> Self {
+        Self::new([
+            [x, 0.0, 0.0, 0.0],
+            [0.0, y, 0.0, 0.0],
+            [0.0, 0.0, z, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn rotation_x(radians: Float) -> Self {
+        let (sin, cos) = radians.sin_cos();
+        Self::new([
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, cos, -sin, 0.0],
+            [0.0, sin, cos, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn rotation_y(radians: Float) -> Self {
+        let (sin, cos) = radians.sin_cos();
+        Self::new([
+            [cos, 0.0, sin, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [-sin, 0.0, cos, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn rotation_z(radians: Float) -> Self {
+        let (sin, cos) = radians.sin_cos();
+        Self::new([
+            [cos, -sin, 0.0, 0.0],
+            [sin, cos, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    /// A transformation that orients the world relative to an eye at `from`,
+    /// looking towards `to`, with `up` being roughly upwards.
+    pub fn view_transform(from: Point, to: Point, up: Vector) -> Self {
+        let forward = (to - from).normalize();
+        let left = forward.cross(up.normalize());
+        let true_up = left.cross(forward);
+        let orientation = Self::new([
+            [left.x(), left.y(), left.z(), 0.0],
+            [true_up.x(), true_up.y(), true_up.z(), 0.0],
+            [-forward.x(), -forward.y(), -forward.z(), 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+        orientation * Self::translation(-from.x(), -from.y(), -from.z())
+    }
+
+    /// Interpolate element by element between this matrix, at `t` = 0.0, and
+    /// `other`, at `t` = 1.0. This is exact for translation and scaling but
+    /// only approximate for rotation.
+    pub fn lerp(&self, other: &Matrix4, t: Float) -> Matrix4 {
+        let mut m = *self;
+        for (a, b) in m
+            .values
+            .iter_mut()
+            .flatten()
+            .zip(other.values.iter().flatten())
+        {
+            *a += (b - *a) * t;
+        }
+        m
+    }
+
+    pub fn shearing(xy: Float, xz: Float, yx: Float, yz: Float, zx: Float, zy: Float) -> Self {
+        Self::new([
+            [1.0, xy, xz, 0.0],
+            [yx, 1.0, yz, 0.0],
+            [zx, zy, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+}
+
+impl Default for Matrix4 {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl PartialEq for Matrix4 {
+    fn eq(&self, other: &Self) -> bool {
+        self.values
+            .iter()
+            .flatten()
+            .zip(other.values.iter().flatten())
+            .all(|(&a, &b)| approx_equal(a, b))
+    }
+}
+
+impl Debug for Matrix4 {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Matrix4 [")?;
+        for row in &self.values {
+            writeln!(f, "  {row:?}")?;
+        }
+        writeln!(f, "]")?;
+
+        Ok(())
+    }
+}
+
+impl Mul for &Matrix4 {
+    type Output = Matrix4;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        let mut values = [[0.0; 4]; 4];
+        for (row, result) in values.iter_mut().enumerate() {
+            for (col, value) in result.iter_mut().enumerate() {
+                *value = (0..4)
+                    .map(|i| self.values[row][i] * rhs.values[i][col])
+                    .sum();
+            }
+        }
+        Matrix4::new(values)
+    }
+}
+
+impl From<[[Float; 4]; 4]> for Matrix4 {
+    fn from(value: [[Float; 4]; 4]) -> Self {
+        Matrix4::new(value)
+    }
+}
+
+impl Mul for Matrix4 {
+    type Output = Matrix4;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        (&self).mul(&rhs)
+    }
+}
+
+impl Mul<&Self> for Matrix4 {
+    type Output = Matrix4;
+
+    fn mul(self, rhs: &Self) -> Self::Output {
+        (&self).mul(rhs)
+    }
+}
+
+impl Mul<&Tuple> for &Matrix4 {
+    type Output = Tuple;
+    fn mul(self, rhs: &Tuple) -> Self::Output {
+        let row = |r: usize| (0..4).map(|col| self.values[r][col] * rhs.get(col)).sum();
+        Tuple::new(row(0), row(1), row(2), row(3))
+    }
+}
+
+impl Mul<&Tuple> for Matrix4 {
+    type Output = Tuple;
+    fn mul(self, rhs: &Tuple) -> Self::Output {
+        (&self).mul(rhs)
+    }
+}
+
+impl Mul<Tuple> for Matrix4 {
+    type Output = Tuple;
+    fn mul(self, rhs: Tuple) -> Self::Output {
+        (&self).mul(&rhs)
+    }
+}
+
+impl Mul<Tuple> for &Matrix4 {
+    type Output = Tuple;
+    fn mul(self, rhs: Tuple) -> Self::Output {
+        self.mul(&rhs)
+    }
+}
+
+impl Mul<Point> for Matrix4 {
+    type Output = Point;
+    fn mul(self, rhs: Point) -> Self::Output {
+        self.mul(*rhs).into()
+    }
+}
+
+impl Mul<Point> for &Matrix4 {
+    type Output = Point;
+    fn mul(self, rhs: Point) -> Self::Output {
+        self.mul(*rhs).into()
+    }
+}
+
+impl Mul<Vector> for Matrix4 {
+    type Output = Vector;
+    fn mul(self, rhs: Vector) -> Self::Output {
+        self.mul(*rhs).into()
+    }
+}
+
+impl Mul<Vector> for &Matrix4 {
+    type Output = Vector;
+    fn mul(self, rhs: Vector) -> Self::Output {
+        self.mul(*rhs).into()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::consts::{FRAC_1_SQRT_2, PI};
+
+    use super::*;
+    use crate::assert_approx_eq;
+    use crate::matrix::Matrix;
+    use crate::testlib::approx_equals_fail;
+
+    fn to_dynamic(m: &Matrix4) -> Matrix {
+        Matrix::from_values(4, 4, m.values.iter().flatten().copied().collect())
+    }
+
+    #[test]
+    fn test_multiplication() {
+        let a = Matrix4::new([
+            [1.0, 2.0, 3.0, 4.0],
+            [5.0, 6.0, 7.0, 8.0],
+            [9.0, 8.0, 7.0, 6.0],
+            [5.0, 4.0, 3.0, 2.0],
+        ]);
+        let b = Matrix4::new([
+            [-2.0, 1.0, 2.0, 3.0],
+            [3.0, 2.0, 1.0, -1.0],
+            [4.0, 3.0, 6.0, 5.0],
+            [1.0, 2.0, 7.0, 8.0],
+        ]);
+        let result = Matrix4::new([
+            [20.0, 22.0, 50.0, 48.0],
+            [44.0, 54.0, 114.0, 108.0],
+            [40.0, 58.0, 110.0, 102.0],
+            [16.0, 26.0, 46.0, 42.0],
+        ]);
+        assert_eq!(a * b, result);
+        assert_eq!(a * Matrix4::identity(), a);
+    }
+
+    #[test]
+    fn test_tuple_multiplication() {
+        let m = Matrix4::new([
+            [1.0, 2.0, 3.0, 4.0],
+            [2.0, 4.0, 4.0, 2.0],
+            [8.0, 6.0, 4.0, 1.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+        let t = Tuple::new(1.0, 2.0, 3.0, 1.0);
+        assert_eq!(m * t, Tuple::new(18.0, 24.0, 33.0, 1.0));
+    }
+
+    #[test]
+    fn test_transpose() {
+        let m = Matrix4::new([
+            [0.0, 9.0, 3.0, 0.0],
+            [9.0, 8.0, 0.0, 8.0],
+            [1.0, 8.0, 5.0, 3.0],
+            [0.0, 0.0, 5.0, 8.0],
+        ]);
+        let result = Matrix4::new([
+            [0.0, 9.0, 1.0, 0.0],
+            [9.0, 8.0, 8.0, 0.0],
+            [3.0, 0.0, 5.0, 5.0],
+            [0.0, 8.0, 3.0, 8.0],
+        ]);
+        assert_eq!(m.transpose(), result);
+        assert_eq!(Matrix4::identity().transpose(), Matrix4::identity());
+    }
+
+    #[test]
+    fn test_determinant() {
+        let m = Matrix4::new([
+            [-2.0, -8.0, 3.0, 5.0],
+            [-3.0, 1.0, 7.0, 3.0],
+            [1.0, 2.0, -9.0, 6.0],
+            [-6.0, 7.0, 7.0, -9.0],
+        ]);
+        assert_eq!(m.determinant(), -4071.0);
+    }
+
+    #[test]
+    fn test_invertible() {
+        let invertible = Matrix4::new([
+            [6.0, 4.0, 4.0, 4.0],
+            [5.0, 5.0, 7.0, 6.0],
+            [4.0, -9.0, 3.0, -7.0],
+            [9.0, 1.0, 7.0, -6.0],
+        ]);
+        assert!(invertible.invertible());
+
+        let non_invertible = Matrix4::new([
+            [-4.0, 2.0, -2.0, -3.0],
+            [9.0, 6.0, 2.0, 6.0],
+            [0.0, -5.0, 1.0, -5.0],
+            [0.0, 0.0, 0.0, 0.0],
+        ]);
+        assert!(!non_invertible.invertible());
+        assert_eq!(non_invertible.inverse(), Err(MatrixError::Singular));
+    }
+
+    #[test]
+    fn test_nearly_singular() {
+        // All but flattened, though the determinant isn't quite zero.
+        let flattened = Matrix4::translation(1.0, 2.0, 3.0) * Matrix4::scaling(1.0, 1e-20, 1.0);
+        assert_ne!(flattened.determinant(), 0.0);
+        assert!(!flattened.invertible());
+        assert_eq!(flattened.inverse(), Err(MatrixError::Singular));
+
+        // A scale that's small in every direction is fine.
+        let tiny = Matrix4::scaling(0.01, 0.01, 0.01);
+        assert!(tiny.invertible());
+        assert_eq!(
+            tiny.inverse().unwrap(),
+            Matrix4::scaling(100.0, 100.0, 100.0)
+        );
+    }
+
+    #[test]
+    fn test_inversion() {
+        let a = Matrix4::new([
+            [-5.0, 2.0, 6.0, -8.0],
+            [1.0, -5.0, 1.0, 8.0],
+            [7.0, 7.0, -6.0, -7.0],
+            [1.0, -3.0, 7.0, 4.0],
+        ]);
+        let result = Matrix4::new([
+            [0.21805, 0.45113, 0.24060, -0.04511],
+            [-0.80827, -1.45677, -0.44361, 0.52068],
+            [-0.07895, -0.22368, -0.05263, 0.19737],
+            [-0.52256, -0.81391, -0.30075, 0.30639],
+        ]);
+        assert_eq!(a.inverse().unwrap(), result);
+    }
+
+    #[test]
+    fn test_inverse_matches_cofactor_expansion() {
+        let m = Matrix4::rotation_x(0.3)
+            * Matrix4::shearing(0.2, 0.0, 0.5, 0.1, 0.0, 0.7)
+            * Matrix4::scaling(2.0, 0.5, 3.0)
+            * Matrix4::translation(1.0, -2.0, 3.0);
+        let expected = to_dynamic(&m).inverse().unwrap();
+        assert_eq!(to_dynamic(&m.inverse().unwrap()), expected);
+        assert_approx_eq!(m.determinant(), to_dynamic(&m).determinant());
+    }
+
+    #[test]
+    fn test_submatrix() {
+        let m = Matrix4::new([
+            [-6.0, 1.0, 1.0, 6.0],
+            [-8.0, 5.0, 8.0, 6.0],
+            [-1.0, 0.0, 8.0, 2.0],
+            [-7.0, 1.0, -1.0, 1.0],
+        ]);
+        let result = Matrix3::new([[-6.0, 1.0, 6.0], [-8.0, 8.0, 6.0], [-7.0, -1.0, 1.0]]);
+        assert_eq!(m.submatrix(2, 1), result);
+    }
+
+    #[test]
+    fn test_normal_matrix() {
+        let m = Matrix4::translation(5.0, -3.0, 2.0)
+            * Matrix4::rotation_z(PI / 5.0)
+            * Matrix4::scaling(1.0, 0.5, 1.0);
+        let n = Vector::new(0.0, FRAC_1_SQRT_2, -FRAC_1_SQRT_2);
+        let expected = m.inverse().unwrap().transpose() * n;
+        assert_eq!(m.normal_matrix().unwrap() * n, expected);
+
+        assert_eq!(
+            Matrix4::scaling(1.0, 0.0, 1.0).normal_matrix(),
+            Err(MatrixError::Singular)
+        );
+    }
+
+    #[test]
+    fn test_inverse_multiplication() {
+        let a = Matrix4::new([
+            [3.0, -9.0, 7.0, 3.0],
+            [3.0, -8.0, 2.0, -9.0],
+            [-4.0, 4.0, 4.0, 1.0],
+            [-6.0, 5.0, -1.0, 1.0],
+        ]);
+        let b = Matrix4::new([
+            [8.0, 2.0, 2.0, 2.0],
+            [3.0, -1.0, 7.0, 0.0],
+            [7.0, 0.0, 5.0, 4.0],
+            [6.0, -2.0, 0.0, 5.0],
+        ]);
+        let c = a * b;
+        assert_eq!(c * b.inverse().unwrap(), a);
+    }
+
+    #[test]
+    fn test_translation() {
+        let transform = Matrix4::translation(5., -3., 2.);
+        let p = Point::new(-3., 4., 5.);
+
+        assert_eq!(transform * p, Point::new(2., 1., 7.));
+
+        let inv = transform.inverse().unwrap();
+        assert_eq!(inv * p, Point::new(-8., 7., 3.));
+
+        let v = Vector::new(-3., 4., 5.);
+        assert_eq!(transform * v, v);
+    }
+
+    #[test]
+    fn test_translation_chained() {
+        let p = Point::new(-3., 4., 5.);
+        assert_eq!(p.translate(5., -3., 2.), Point::new(2., 1., 7.));
+
+        let v = Vector::new(-3., 4., 5.);
+        assert_eq!(v.translate(5., -3., 2.), v);
+    }
+
+    #[test]
+    fn test_scaling() {
+        let transform = Matrix4::scaling(2., 3., 4.);
+        let p = Point::new(-4., 6., 8.);
+        assert_eq!(transform * p, Point::new(-8., 18., 32.));
+
+        let v = Vector::new(-4., 6., 8.);
+        assert_eq!(transform * v, Vector::new(-8., 18., 32.));
+
+        let inv = transform.inverse().unwrap();
+        assert_eq!(inv * v, Vector::new(-2., 2., 2.));
+    }
+
+    #[test]
+    fn test_scaling_chained() {
+        let p = Point::new(-4., 6., 8.);
+        assert_eq!(p.scale(2., 3., 4.), Point::new(-8., 18., 32.));
+
+        let v = Vector::new(-4., 6., 8.);
+        assert_eq!(v.scale(2., 3., 4.), Vector::new(-8., 18., 32.));
+    }
+
+    #[test]
+    fn test_reflection() {
+        let transform = Matrix4::scaling(-1., 1., 1.);
+        let p = Point::new(2., 3., 4.);
+
+        assert_eq!(transform * p, Point::new(-2., 3., 4.));
+    }
+
+    #[test]
+    fn test_rotation_x() {
+        let p = Point::new(0., 1., 0.);
+        let half_quarter = Matrix4::rotation_x(PI / 4.);
+        let full_quarter = Matrix4::rotation_x(PI / 2.);
+        assert_eq!(
+            half_quarter * p,
+            Point::new(
+                0.0,
+                (2.0 as Float).sqrt() / 2.0,
+                (2.0 as Float).sqrt() / 2.0
+            )
+        );
+        assert_eq!(full_quarter * p, Point::new(0.0, 0.0, 1.0));
+
+        assert_eq!(
+            p.rotate_x(PI / 4.),
+            Point::new(
+                0.0,
+                (2.0 as Float).sqrt() / 2.0,
+                (2.0 as Float).sqrt() / 2.0
+            )
+        );
+        assert_eq!(p.rotate_x(PI / 2.), Point::new(0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn test_rotation_y() {
+        let p = Point::new(0., 0., 1.);
+        let half_quarter = Matrix4::rotation_y(PI / 4.);
+        let full_quarter = Matrix4::rotation_y(PI / 2.);
+        assert_eq!(
+            half_quarter * p,
+            Point::new(
+                (2.0 as Float).sqrt() / 2.0,
+                0.0,
+                (2.0 as Float).sqrt() / 2.0
+            )
+        );
+        assert_eq!(full_quarter * p, Point::new(1.0, 0.0, 0.0));
+
+        assert_eq!(
+            p.rotate_y(PI / 4.),
+            Point::new(
+                (2.0 as Float).sqrt() / 2.0,
+                0.0,
+                (2.0 as Float).sqrt() / 2.0
+            )
+        );
+        assert_eq!(p.rotate_y(PI / 2.), Point::new(1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_rotation_z() {
+        let p = Point::new(0., 1., 0.);
+        let half_quarter = Matrix4::rotation_z(PI / 4.);
+        let full_quarter = Matrix4::rotation_z(PI / 2.);
+        assert_eq!(
+            half_quarter * p,
+            Point::new(
+                -(2.0 as Float).sqrt() / 2.0,
+                (2.0 as Float).sqrt() / 2.0,
+                0.0
+            )
+        );
+        assert_eq!(full_quarter * p, Point::new(-1.0, 0.0, 0.0));
+
+        assert_eq!(
+            p.rotate_z(PI / 4.),
+            Point::new(
+                -(2.0 as Float).sqrt() / 2.0,
+                (2.0 as Float).sqrt() / 2.0,
+                0.0
+            )
+        );
+        assert_eq!(p.rotate_z(PI / 2.), Point::new(-1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_shearing_moves_x_to_y() {
+        let transform = Matrix4::shearing(1.0, 0.0, 0.0, 0.0, 0.0, 0.0);
+        let p = Point::new(2.0, 3.0, 4.0);
+
+        assert_eq!(transform * p, Point::new(5.0, 3.0, 4.0));
+
+        assert_eq!(
+            p.shear(1.0, 0.0, 0.0, 0.0, 0.0, 0.0),
+            Point::new(5.0, 3.0, 4.0)
+        );
+    }
+
+    #[test]
+    fn test_shearing_moves_x_to_z() {
+        let transform = Matrix4::shearing(0.0, 1.0, 0.0, 0.0, 0.0, 0.0);
+        let p = Point::new(2.0, 3.0, 4.0);
+
+        assert_eq!(transform * p, Point::new(6.0, 3.0, 4.0));
+    }
+
+    #[test]
+    fn test_shearing_moves_y_to_x() {
+        let transform = Matrix4::shearing(0.0, 0.0, 1.0, 0.0, 0.0, 0.0);
+        let p = Point::new(2.0, 3.0, 4.0);
+
+        assert_eq!(transform * p, Point::new(2.0, 5.0, 4.0));
+
+        assert_eq!(
+            p.shear(0.0, 0.0, 1.0, 0.0, 0.0, 0.0),
+            Point::new(2.0, 5.0, 4.0)
+        );
+    }
+
+    #[test]
+    fn test_shearing_moves_y_to_z() {
+        let transform = Matrix4::shearing(0.0, 0.0, 0.0, 1.0, 0.0, 0.0);
+        let p = Point::new(2.0, 3.0, 4.0);
+
+        assert_eq!(transform * p, Point::new(2.0, 7.0, 4.0));
+        assert_eq!(
+            p.shear(0.0, 0.0, 0.0, 1.0, 0.0, 0.0),
+            Point::new(2.0, 7.0, 4.0)
+        );
+    }
+
+    #[test]
+    fn test_shearing_moves_z_to_x() {
+        let transform = Matrix4::shearing(0.0, 0.0, 0.0, 0.0, 1.0, 0.0);
+        let p = Point::new(2.0, 3.0, 4.0);
+
+        assert_eq!(transform * p, Point::new(2.0, 3.0, 6.0));
+        assert_eq!(
+            p.shear(0.0, 0.0, 0.0, 0.0, 1.0, 0.0),
+            Point::new(2.0, 3.0, 6.0)
+        );
+    }
+
+    #[test]
+    fn test_shearing_moves_z_to_y() {
+        let transform = Matrix4::shearing(0.0, 0.0, 0.0, 0.0, 0.0, 1.0);
+        let p = Point::new(2.0, 3.0, 4.0);
+
+        assert_eq!(transform * p, Point::new(2.0, 3.0, 7.0));
+        assert_eq!(
+            p.shear(0.0, 0.0, 0.0, 0.0, 0.0, 1.0),
+            Point::new(2.0, 3.0, 7.0)
+        );
+    }
+
+    #[test]
+    fn test_transformations_applied_in_sequence() {
+        let p = Point::new(1.0, 0.0, 1.0);
+        let a = Matrix4::rotation_x(PI / 2.0);
+        let b = Matrix4::scaling(5.0, 5.0, 5.0);
+        let c = Matrix4::translation(10.0, 5.0, 7.0);
+
+        let p2 = a * p;
+        assert_eq!(p2, Point::new(1.0, -1.0, 0.0));
+
+        let p3 = b * p2;
+        assert_eq!(p3, Point::new(5.0, -5.0, 0.0));
+
+        let p4 = c * p3;
+        assert_eq!(p4, Point::new(15.0, 0.0, 7.0));
+
+        let p5 = c * b * a * p;
+        assert_eq!(p5, Point::new(15.0, 0.0, 7.0));
+
+        assert_eq!(
+            p.rotate_x(PI / 2.0)
+                .scale(5.0, 5.0, 5.0)
+                .translate(10.0, 5.0, 7.0),
+            Point::new(15.0, 0.0, 7.0)
+        )
+    }
+
+    #[test]
+    fn test_lerp() {
+        let a = Matrix4::translation(0.0, 2.0, 0.0);
+        let b = Matrix4::translation(4.0, 0.0, 0.0) * Matrix4::scaling(3.0, 3.0, 3.0);
+        assert_eq!(a.lerp(&b, 0.0), a);
+        assert_eq!(a.lerp(&b, 1.0), b);
+        assert_eq!(
+            a.lerp(&b, 0.5),
+            Matrix4::translation(2.0, 1.0, 0.0) * Matrix4::scaling(2.0, 2.0, 2.0)
+        );
+    }
+
+    #[test]
+    fn test_view_transform_default_orientation() {
+        let t = Matrix4::view_transform(
+            Point::origin(),
+            Point::new(0.0, 0.0, -1.0),
+            Vector::new(0.0, 1.0, 0.0),
+        );
+        assert_eq!(t, Matrix4::identity());
+    }
+
+    #[test]
+    fn test_view_transform_positive_z() {
+        let t = Matrix4::view_transform(
+            Point::origin(),
+            Point::new(0.0, 0.0, 1.0),
+            Vector::new(0.0, 1.0, 0.0),
+        );
+        assert_eq!(t, Matrix4::scaling(-1.0, 1.0, -1.0));
+    }
+
+    #[test]
+    fn test_view_transform_moves_world() {
+        let t = Matrix4::view_transform(
+            Point::new(0.0, 0.0, 8.0),
+            Point::origin(),
+            Vector::new(0.0, 1.0, 0.0),
+        );
+        assert_eq!(t, Matrix4::translation(0.0, 0.0, -8.0));
+    }
+
+    #[test]
+    fn test_view_transform_arbitrary() {
+        let t = Matrix4::view_transform(
+            Point::new(1.0, 3.0, 2.0),
+            Point::new(4.0, -2.0, 8.0),
+            Vector::new(1.0, 1.0, 0.0),
+        );
+        let result = Matrix4::new([
+            [-0.50709, 0.50709, 0.67612, -2.36643],
+            [0.76772, 0.60609, 0.12122, -2.82843],
+            [-0.35857, 0.59761, -0.71714, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+        assert_eq!(t, result);
+    }
+}
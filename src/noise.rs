@@ -0,0 +1,128 @@
+use crate::Float;
+
+/// Ken Perlin's reference permutation table.
+const PERMUTATION: [u8; 256] = [
+    151, 160, 137, 91, 90, 15, 131, 13, 201, 95, 96, 53, 194, 233, 7, 225, 140, 36, 103, 30, 69,
+    142, 8, 99, 37, 240, 21, 10, 23, 190, 6, 148, 247, 120, 234, 75, 0, 26, 197, 62, 94, 252, 219,
+    203, 117, 35, 11, 32, 57, 177, 33, 88, 237, 149, 56, 87, 174, 20, 125, 136, 171, 168, 68, 175,
+    74, 165, 71, 134, 139, 48, 27, 166, 77, 146, 158, 231, 83, 111, 229, 122, 60, 211, 133, 230,
+    220, 105, 92, 41, 55, 46, 245, 40, 244, 102, 143, 54, 65, 25, 63, 161, 1, 216, 80, 73, 209, 76,
+    132, 187, 208, 89, 18, 169, 200, 196, 135, 130, 116, 188, 159, 86, 164, 100, 109, 198, 173,
+    186, 3, 64, 52, 217, 226, 250, 124, 123, 5, 202, 38, 147, 118, 126, 255, 82, 85, 212, 207, 206,
+    59, 227, 47, 16, 58, 17, 182, 189, 28, 42, 223, 183, 170, 213, 119, 248, 152, 2, 44, 154, 163,
+    70, 221, 153, 101, 155, 167, 43, 172, 9, 129, 22, 39, 253, 19, 98, 108, 110, 79, 113, 224, 232,
+    178, 185, 112, 104, 218, 246, 97, 228, 251, 34, 242, 193, 238, 210, 144, 12, 191, 179, 162,
+    241, 81, 51, 145, 235, 249, 14, 239, 107, 49, 192, 214, 31, 181, 199, 106, 157, 184, 84, 204,
+    176, 115, 121, 50, 45, 127, 4, 150, 254, 138, 236, 205, 93, 222, 114, 67, 29, 24, 72, 243, 141,
+    128, 195, 78, 66, 215, 61, 156, 180,
+];
+
+fn p(i: usize) -> usize {
+    PERMUTATION[i & 255] as usize
+}
+
+fn fade(t: Float) -> Float {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(t: Float, a: Float, b: Float) -> Float {
+    a + t * (b - a)
+}
+
+fn grad(hash: usize, x: Float, y: Float, z: Float) -> Float {
+    // Pick one of 12 gradient directions from the low 4 bits of the hash.
+    let h = hash & 15;
+    let u = if h < 8 { x } else { y };
+    let v = if h < 4 {
+        y
+    } else if h == 12 || h == 14 {
+        x
+    } else {
+        z
+    };
+    (if h & 1 == 0 { u } else { -u }) + (if h & 2 == 0 { v } else { -v })
+}
+
+/// Perlin's improved gradient noise, in roughly the range -1.0 to 1.0.
+///
+/// The noise is zero at every integer lattice point and varies smoothly in
+/// between, repeating every 256 units along each axis.
+pub fn perlin(x: Float, y: Float, z: Float) -> Float {
+    let (xf, yf, zf) = (x.floor(), y.floor(), z.floor());
+    let xi = xf.rem_euclid(256.0) as usize;
+    let yi = yf.rem_euclid(256.0) as usize;
+    let zi = zf.rem_euclid(256.0) as usize;
+    let (x, y, z) = (x - xf, y - yf, z - zf);
+    let (u, v, w) = (fade(x), fade(y), fade(z));
+
+    let a = p(xi) + yi;
+    let aa = p(a) + zi;
+    let ab = p(a + 1) + zi;
+    let b = p(xi + 1) + yi;
+    let ba = p(b) + zi;
+    let bb = p(b + 1) + zi;
+
+    lerp(
+        w,
+        lerp(
+            v,
+            lerp(u, grad(p(aa), x, y, z), grad(p(ba), x - 1.0, y, z)),
+            lerp(
+                u,
+                grad(p(ab), x, y - 1.0, z),
+                grad(p(bb), x - 1.0, y - 1.0, z),
+            ),
+        ),
+        lerp(
+            v,
+            lerp(
+                u,
+                grad(p(aa + 1), x, y, z - 1.0),
+                grad(p(ba + 1), x - 1.0, y, z - 1.0),
+            ),
+            lerp(
+                u,
+                grad(p(ab + 1), x, y - 1.0, z - 1.0),
+                grad(p(bb + 1), x - 1.0, y - 1.0, z - 1.0),
+            ),
+        ),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::assert_approx_eq;
+    use crate::testlib::approx_equals_fail;
+
+    #[test]
+    fn test_perlin_zero_on_lattice() {
+        for (x, y, z) in [(0.0, 0.0, 0.0), (1.0, 2.0, 3.0), (-4.0, 7.0, -1.0)] {
+            assert_eq!(perlin(x, y, z), 0.0);
+        }
+    }
+
+    #[test]
+    fn test_perlin_is_deterministic() {
+        assert_eq!(perlin(0.3, 1.7, -2.2), perlin(0.3, 1.7, -2.2));
+    }
+
+    #[test]
+    fn test_perlin_in_range_and_varies() {
+        let mut min = Float::MAX;
+        let mut max = Float::MIN;
+        for i in 0..1000 {
+            let t = i as Float * 0.037;
+            let n = perlin(t, t * 0.5 + 0.1, -t * 0.3 + 0.2);
+            min = min.min(n);
+            max = max.max(n);
+        }
+        assert!(min >= -1.0 && max <= 1.0);
+        assert!(min < -0.1 && max > 0.1);
+    }
+
+    #[test]
+    fn test_perlin_repeats_every_256() {
+        assert_approx_eq!(perlin(0.3, 0.6, 0.9), perlin(256.3, 0.6, -255.1));
+    }
+}
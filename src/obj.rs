@@ -0,0 +1,343 @@
+//! Import meshes from Wavefront OBJ files.
+
+use std::fs::File;
+use std::io::{prelude::*, Error, ErrorKind, Result};
+use std::path::Path;
+
+use crate::shape::{Group, Shape, Triangle};
+use crate::space::{Point, Vector};
+use crate::Float;
+
+/// The vertices and faces read from an OBJ file. Faces before any `g` or `o`
+/// statement go in the default group, and those after one in a group of
+/// that name, so parts of a model can be told apart after import.
+///
+/// Only vertices (`v`), vertex normals (`vn`), faces (`f`) and groups and
+/// objects (`g` and `o`) are read. Every other statement is skipped and
+/// counted.
+#[derive(Clone, Debug, Default)]
+pub struct ObjParser {
+    vertices: Vec<Point>,
+    normals: Vec<Vector>,
+    default_group: Group,
+    /// In the order they first appear.
+    groups: Vec<(String, Group)>,
+    ignored: usize,
+}
+
+impl ObjParser {
+    /// Parse the text of an OBJ file. Faces with more than three vertices
+    /// are split into a fan of triangles around the first, and faces with a
+    /// normal at every vertex are smooth.
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut parser = Self::default();
+        // Into the default group, or the named one at this index.
+        let mut current: Option<usize> = None;
+        for (number, line) in text.lines().enumerate() {
+            let invalid_line = || invalid(format!("invalid OBJ on line {}: {line:?}", number + 1));
+            match line.split_whitespace().collect::<Vec<_>>().as_slice() {
+                ["v", x, y, z, ..] => {
+                    let [x, y, z] = [x, y, z].map(|value| value.parse::<Float>());
+                    parser.vertices.push(Point::new(
+                        x.map_err(|_| invalid_line())?,
+                        y.map_err(|_| invalid_line())?,
+                        z.map_err(|_| invalid_line())?,
+                    ));
+                }
+                ["vn", x, y, z, ..] => {
+                    let [x, y, z] = [x, y, z].map(|value| value.parse::<Float>());
+                    parser.normals.push(Vector::new(
+                        x.map_err(|_| invalid_line())?,
+                        y.map_err(|_| invalid_line())?,
+                        z.map_err(|_| invalid_line())?,
+                    ));
+                }
+                ["f", indexes @ ..] if indexes.len() >= 3 => {
+                    let (points, normals): (Vec<_>, Vec<_>) = indexes
+                        .iter()
+                        .map(|index| parser.corner(index).ok_or_else(invalid_line))
+                        .collect::<Result<Vec<_>>>()?
+                        .into_iter()
+                        .unzip();
+                    let normals: Option<Vec<Vector>> = normals.into_iter().collect();
+                    let group = match current {
+                        Some(index) => &mut parser.groups[index].1,
+                        None => &mut parser.default_group,
+                    };
+                    for i in 1..points.len() - 1 {
+                        group.add_child(match &normals {
+                            Some(n) => Triangle::with_normals(
+                                points[0],
+                                points[i],
+                                points[i + 1],
+                                n[0],
+                                n[i],
+                                n[i + 1],
+                            ),
+                            None => Triangle::new(points[0], points[i], points[i + 1]),
+                        });
+                    }
+                }
+                ["g" | "o", name @ ..] if !name.is_empty() => {
+                    let name = name.join(" ");
+                    current = Some(match parser.groups.iter().position(|(n, _)| *n == name) {
+                        Some(index) => index,
+                        None => {
+                            parser.groups.push((name, Group::new()));
+                            parser.groups.len() - 1
+                        }
+                    });
+                }
+                ["v" | "vn" | "f" | "g" | "o", ..] => return Err(invalid_line()),
+                _ => parser.ignored += 1,
+            }
+        }
+        Ok(parser)
+    }
+
+    /// Read and parse an OBJ file. See [`ObjParser::parse`].
+    pub fn read(source: &mut impl Read) -> Result<Self> {
+        let mut text = String::new();
+        source
+            .read_to_string(&mut text)
+            .map_err(|_| invalid("OBJ file isn't UTF-8"))?;
+        Self::parse(&text)
+    }
+
+    /// Load and parse the OBJ file at `path`. See [`ObjParser::parse`].
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, fields(path = %path.as_ref().display()))
+    )]
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        Self::read(&mut File::open(path)?)
+    }
+
+    /// The vertex, and normal if it has one, an `f` statement refers to as
+    /// `index`: a vertex index, then optionally texture and normal indexes
+    /// after slashes, as in `1`, `1/2`, `1/2/3` or `1//3`. Texture indexes
+    /// are skipped.
+    fn corner(&self, index: &str) -> Option<(Point, Option<Vector>)> {
+        let mut indexes = index.split('/');
+        let point = *lookup(&self.vertices, indexes.next()?)?;
+        let normal = match indexes.nth(1) {
+            None | Some("") => None,
+            Some(index) => Some(*lookup(&self.normals, index)?),
+        };
+        Some((point, normal))
+    }
+
+    pub fn vertices(&self) -> &[Point] {
+        &self.vertices
+    }
+
+    pub fn normals(&self) -> &[Vector] {
+        &self.normals
+    }
+
+    /// How many lines were skipped, including blank lines and comments.
+    pub fn ignored(&self) -> usize {
+        self.ignored
+    }
+
+    /// The triangles from faces outside any named group.
+    pub fn default_group(&self) -> &Group {
+        &self.default_group
+    }
+
+    /// The group of faces following `g name` or `o name` statements.
+    pub fn group(&self, name: &str) -> Option<&Group> {
+        self.groups
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, group)| group)
+    }
+
+    /// Like [`ObjParser::group`], to transform the group before it's
+    /// gathered up by [`ObjParser::into_group`].
+    pub fn group_mut(&mut self, name: &str) -> Option<&mut Group> {
+        self.groups
+            .iter_mut()
+            .find(|(n, _)| n == name)
+            .map(|(_, group)| group)
+    }
+
+    /// The whole model as one group: the default group's triangles, then
+    /// each named group as a child of that name, to be found later with
+    /// [`Shape::find`].
+    pub fn into_group(self) -> Group {
+        let mut model = self.default_group;
+        for (name, group) in self.groups {
+            let mut child: Shape = group.into();
+            child.set_name(name);
+            model.add_child(child);
+        }
+        model
+    }
+}
+
+/// Read a model from an OBJ file, as a single group. See
+/// [`ObjParser::into_group`].
+pub fn read(source: &mut impl Read) -> Result<Group> {
+    ObjParser::read(source).map(ObjParser::into_group)
+}
+
+/// Load a model from the OBJ file at `path`. See [`read`].
+pub fn load(path: impl AsRef<Path>) -> Result<Group> {
+    ObjParser::load(path).map(ObjParser::into_group)
+}
+
+/// The item `index` refers to, counting from 1, or back from the last item
+/// read if negative.
+fn lookup<'a, T>(items: &'a [T], index: &str) -> Option<&'a T> {
+    let index: isize = index.parse().ok()?;
+    let position = if index < 0 {
+        items.len().checked_sub(index.unsigned_abs())?
+    } else {
+        usize::try_from(index).ok()?.checked_sub(1)?
+    };
+    items.get(position)
+}
+
+fn invalid(message: impl Into<String>) -> Error {
+    Error::new(ErrorKind::InvalidData, message.into())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::matrix4::Matrix4;
+
+    const WHEELS: &str = "# A body and two wheels
+v -1 1 0
+v -1 0 0
+v 1 0 0
+v 1 1 0
+
+o body
+f 1 2 3 4
+g wheel
+f 1 2 3
+g hub cap
+f 1/1 3/3 4/4
+g wheel
+f -4 -3 -1
+";
+
+    fn points(group: &Group) -> Vec<(Point, Point, Point)> {
+        group
+            .children()
+            .iter()
+            .map(|child| match child {
+                Shape::Triangle(triangle) => triangle.points(),
+                _ => unreachable!(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_ignores_unknown_lines() {
+        let parser = ObjParser::parse("There was a young lady named Bright\nvt 0 0\n").unwrap();
+        assert_eq!(parser.ignored(), 2);
+        assert!(parser.vertices().is_empty());
+    }
+
+    #[test]
+    fn test_faces_in_default_group() {
+        let parser = ObjParser::parse("v -1 1 0\nv -1 0 0\nv 1 0 0\nv 1 1 0\nf 1 2 3 4\n").unwrap();
+        assert_eq!(parser.vertices().len(), 4);
+        let (p1, p2, p3, p4) = (
+            Point::new(-1.0, 1.0, 0.0),
+            Point::new(-1.0, 0.0, 0.0),
+            Point::new(1.0, 0.0, 0.0),
+            Point::new(1.0, 1.0, 0.0),
+        );
+        assert_eq!(points(parser.default_group()), [(p1, p2, p3), (p1, p3, p4)]);
+    }
+
+    #[test]
+    fn test_faces_with_normals() {
+        let parser = ObjParser::parse(
+            "v 0 1 0\nv -1 0 0\nv 1 0 0\nvn 0 1 0\nvn -1 0 0\nvn 1 0 0\nf 1//1 2//2 3//3\nf 1 2 3//3\n",
+        )
+        .unwrap();
+        assert_eq!(parser.normals().len(), 3);
+        let triangles: Vec<_> = parser
+            .default_group()
+            .children()
+            .iter()
+            .map(|child| match child {
+                Shape::Triangle(triangle) => triangle.normals(),
+                _ => unreachable!(),
+            })
+            .collect();
+        let (n1, n2, n3) = (
+            Vector::new(0.0, 1.0, 0.0),
+            Vector::new(-1.0, 0.0, 0.0),
+            Vector::new(1.0, 0.0, 0.0),
+        );
+        // Only faces with a normal at every vertex are smooth.
+        assert_eq!(triangles, [Some((n1, n2, n3)), None]);
+    }
+
+    #[test]
+    fn test_named_groups() {
+        let parser = ObjParser::parse(WHEELS).unwrap();
+        assert_eq!(parser.ignored(), 2);
+        assert!(parser.default_group().children().is_empty());
+        assert_eq!(parser.group("body").unwrap().children().len(), 2);
+        assert_eq!(parser.group("hub cap").unwrap().children().len(), 1);
+        assert!(parser.group("missing").is_none());
+
+        // Named again, the wheel's group is added to.
+        let p = |i: usize| parser.vertices()[i - 1];
+        assert_eq!(
+            points(parser.group("wheel").unwrap()),
+            [(p(1), p(2), p(3)), (p(1), p(2), p(4))]
+        );
+    }
+
+    #[test]
+    fn test_into_group() {
+        let mut parser = ObjParser::parse(WHEELS).unwrap();
+        parser
+            .group_mut("wheel")
+            .unwrap()
+            .set_transform(Matrix4::translation(0.0, 0.0, 5.0));
+        let model: Shape = parser.into_group().into();
+
+        let Shape::Group(group) = &model else {
+            unreachable!()
+        };
+        let names: Vec<_> = group.children().iter().map(Shape::name).collect();
+        assert_eq!(names, [Some("body"), Some("wheel"), Some("hub cap")]);
+        assert_eq!(
+            model.find("wheel").unwrap().transformation(),
+            &Matrix4::translation(0.0, 0.0, 5.0)
+        );
+    }
+
+    #[test]
+    fn test_read_invalid() {
+        let error = |obj: &str| read(&mut obj.as_bytes()).unwrap_err().to_string();
+        assert_eq!(error("v 1 0\n"), "invalid OBJ on line 1: \"v 1 0\"");
+        assert_eq!(
+            error("v 1 0 0\nv 0 1 0\nf 1 2 3\n"),
+            "invalid OBJ on line 3: \"f 1 2 3\""
+        );
+        assert_eq!(
+            error("v 1 0 0\nf 1 1 -2\n"),
+            "invalid OBJ on line 2: \"f 1 1 -2\""
+        );
+        assert_eq!(error("g\n"), "invalid OBJ on line 1: \"g\"");
+        assert_eq!(
+            error("v 1 0 0\nv 0 1 0\nv 0 0 1\nvn 0 0 1\nf 1//1 2//1 3//2\n"),
+            "invalid OBJ on line 5: \"f 1//1 2//1 3//2\""
+        );
+        assert_eq!(error("vn 1 x 0\n"), "invalid OBJ on line 1: \"vn 1 x 0\"");
+        assert_eq!(
+            read(&mut &[0xff, 0xfe][..]).unwrap_err().kind(),
+            ErrorKind::InvalidData
+        );
+    }
+}
@@ -0,0 +1,207 @@
+use crate::bounds::BoundingBox;
+use crate::ray::Ray;
+use crate::space::Vector;
+use crate::Float;
+
+/// How deep an [`Octree`] may nest, unless set otherwise.
+pub const DEFAULT_MAX_DEPTH: usize = 8;
+
+/// How many objects an [`Octree`] node may hold before it's split, unless
+/// set otherwise.
+pub const DEFAULT_LEAF_SIZE: usize = 8;
+
+/// A spatial index over a list of objects, found by their positions in the
+/// list, which narrows down the objects a ray might hit.
+///
+/// The space around the objects is cut into eight octants, and each octant
+/// again while it holds more than a few objects. An object that straddles
+/// octants stays with the node above them. Rays then only test the objects
+/// in the octants they pass through. Unlike the bounding boxes of a
+/// [`Group`](crate::shape::Group), the octants never overlap, which suits
+/// scenes of many evenly spread objects.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Octree {
+    bounds: BoundingBox,
+    root: Node,
+    /// Objects without finite bounds, which every ray has to test.
+    unbounded: Vec<usize>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Node {
+    /// In a leaf, all the node's objects. Otherwise, those straddling more
+    /// than one child.
+    objects: Vec<usize>,
+    /// Indexed as for [`BoundingBox::octants`].
+    children: Option<Box<[Node; 8]>>,
+}
+
+impl Octree {
+    /// Index objects with the given bounds, splitting nodes with more than
+    /// `leaf_size` objects until they're `max_depth` levels deep.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip(bounds))
+    )]
+    pub fn new(
+        bounds: impl IntoIterator<Item = BoundingBox>,
+        max_depth: usize,
+        leaf_size: usize,
+    ) -> Self {
+        let mut total = BoundingBox::empty();
+        let mut entries = vec![];
+        let mut unbounded = vec![];
+        for (index, object_bounds) in bounds.into_iter().enumerate() {
+            if object_bounds.is_finite() {
+                total.add_box(&object_bounds);
+                entries.push((index, object_bounds));
+            } else {
+                unbounded.push(index);
+            }
+        }
+
+        // Make the root a cube, so objects spread across a plane don't all
+        // straddle its middle.
+        let size = (0..3)
+            .map(|axis| total.max.get(axis) - total.min.get(axis))
+            .fold(0.0, Float::max);
+        let bounds = BoundingBox::new(total.min, total.min + Vector::new(size, size, size));
+
+        Self {
+            root: Node::new(entries, &bounds, max_depth, leaf_size),
+            bounds,
+            unbounded,
+        }
+    }
+
+    /// Call `visit` with each object that `ray` might hit. Each object is
+    /// visited at most once, in no particular order.
+    pub fn visit(&self, ray: &Ray, mut visit: impl FnMut(usize)) {
+        for &index in &self.unbounded {
+            visit(index);
+        }
+        self.root.visit(&self.bounds, ray, &mut visit);
+    }
+}
+
+impl Node {
+    fn new(
+        entries: Vec<(usize, BoundingBox)>,
+        bounds: &BoundingBox,
+        depth: usize,
+        leaf_size: usize,
+    ) -> Self {
+        let leaf = |entries: Vec<(usize, BoundingBox)>| Self {
+            objects: entries.into_iter().map(|(index, _)| index).collect(),
+            children: None,
+        };
+        if entries.len() <= leaf_size || depth == 0 {
+            return leaf(entries);
+        }
+
+        let octants = bounds.octants();
+        let mut inside: [Vec<(usize, BoundingBox)>; 8] = Default::default();
+        let mut straddling = vec![];
+        for entry in entries {
+            match octants
+                .iter()
+                .position(|octant| octant.contains_box(&entry.1))
+            {
+                Some(octant) => inside[octant].push(entry),
+                None => straddling.push(entry),
+            }
+        }
+        // Splitting is no use if nothing fits in an octant.
+        if inside.iter().all(Vec::is_empty) {
+            return leaf(straddling);
+        }
+
+        Self {
+            objects: straddling.into_iter().map(|(index, _)| index).collect(),
+            children: Some(Box::new(std::array::from_fn(|octant| {
+                let entries = std::mem::take(&mut inside[octant]);
+                Node::new(entries, &octants[octant], depth - 1, leaf_size)
+            }))),
+        }
+    }
+
+    fn visit(&self, bounds: &BoundingBox, ray: &Ray, visit: &mut dyn FnMut(usize)) {
+        if !bounds.intersects(ray) {
+            return;
+        }
+        for &index in &self.objects {
+            visit(index);
+        }
+        if let Some(children) = &self.children {
+            for (child, octant) in children.iter().zip(bounds.octants()) {
+                child.visit(&octant, ray, visit);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::space::Point;
+
+    fn unit_box(x: Float, y: Float, z: Float) -> BoundingBox {
+        BoundingBox::new(
+            Point::new(x - 0.5, y - 0.5, z - 0.5),
+            Point::new(x + 0.5, y + 0.5, z + 0.5),
+        )
+    }
+
+    fn visited(octree: &Octree, ray: &Ray) -> Vec<usize> {
+        let mut indexes = vec![];
+        octree.visit(ray, |index| indexes.push(index));
+        indexes.sort();
+        indexes
+    }
+
+    #[test]
+    fn test_ray_visits_only_nearby_objects() {
+        // A 10x10 grid of boxes, two apart.
+        let boxes: Vec<BoundingBox> = (0..100)
+            .map(|i| unit_box((i % 10) as Float * 2.0, (i / 10) as Float * 2.0, 0.0))
+            .collect();
+        let octree = Octree::new(boxes.iter().copied(), DEFAULT_MAX_DEPTH, 2);
+
+        // Along the row at y = 4.
+        let ray = Ray::new(Point::new(-5.0, 4.0, 0.0), Vector::new(1.0, 0.0, 0.0));
+        let indexes = visited(&octree, &ray);
+        assert!((20..30).all(|i| indexes.contains(&i)));
+        assert!(indexes.len() < 50);
+
+        let ray = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(-1.0, 0.0, 0.0));
+        assert_eq!(visited(&octree, &ray), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn test_straddling_and_unbounded_objects() {
+        let boxes = [
+            unit_box(0.0, 0.0, 0.0),
+            unit_box(-4.0, -4.0, -4.0),
+            unit_box(4.0, 4.0, 4.0),
+            BoundingBox::infinite(),
+        ];
+        let octree = Octree::new(boxes, DEFAULT_MAX_DEPTH, 1);
+
+        // The box in the middle is kept at the root, so any ray that
+        // reaches the octree visits it, and the unbounded object is always
+        // visited.
+        let ray = Ray::new(Point::new(-4.0, -4.0, -10.0), Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(visited(&octree, &ray), [0, 1, 3]);
+        let ray = Ray::new(Point::new(4.0, 4.0, -10.0), Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(visited(&octree, &ray), [0, 2, 3]);
+        let ray = Ray::new(Point::new(10.0, 10.0, -10.0), Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(visited(&octree, &ray), [3]);
+    }
+
+    #[test]
+    fn test_identical_objects_stop_splitting() {
+        let octree = Octree::new(vec![unit_box(1.0, 1.0, 1.0); 20], DEFAULT_MAX_DEPTH, 1);
+        let ray = Ray::new(Point::new(1.0, 1.0, -10.0), Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(visited(&octree, &ray), (0..20).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,168 @@
+use crate::camera::Camera;
+use crate::canvas::Canvas;
+use crate::color::Color;
+use crate::ray::Computations;
+use crate::world::World;
+use crate::Float;
+
+/// Which object is visible at each pixel of a render, for masking and
+/// compositing objects separately. Objects are identified by their index in
+/// the world, in the order they were added.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ObjectIds {
+    pub width: usize,
+    pub height: usize,
+    ids: Vec<Option<usize>>,
+}
+
+impl ObjectIds {
+    /// The object visible at (`x`, `y`), or `None` where only the background
+    /// shows.
+    pub fn id_at(&self, x: usize, y: usize) -> Option<usize> {
+        self.ids[y * self.width + x]
+    }
+
+    /// A black and white matte, white where the object `id` is visible.
+    pub fn mask(&self, id: usize) -> Canvas {
+        let white = Color::new(1.0, 1.0, 1.0);
+        let black = Color::new(0.0, 0.0, 0.0);
+        let pixels = self
+            .ids
+            .iter()
+            .map(|&pixel| if pixel == Some(id) { white } else { black })
+            .collect();
+        Canvas::from_pixels(self.width, self.height, pixels)
+    }
+
+    /// An image giving each object its own flat color, with the background
+    /// black, for picking objects out in an image editor.
+    pub fn to_canvas(&self) -> Canvas {
+        let pixels = self
+            .ids
+            .iter()
+            .map(|pixel| pixel.map_or(Color::new(0.0, 0.0, 0.0), id_color))
+            .collect();
+        Canvas::from_pixels(self.width, self.height, pixels)
+    }
+}
+
+// Written for f64, so more precise than f32 can hold.
+#[cfg_attr(feature = "f32", allow(clippy::excessive_precision))]
+const GOLDEN_RATIO_CONJUGATE: Float = 0.618_033_988_749_895;
+
+/// A bright color for `id`, with the hue stepped around the color wheel by
+/// the golden angle so neighbouring IDs look very different.
+fn id_color(id: usize) -> Color {
+    let hue = (id as Float * GOLDEN_RATIO_CONJUGATE).fract() * 6.0;
+    let x = 1.0 - (hue % 2.0 - 1.0).abs();
+    let (r, g, b) = match hue as usize {
+        0 => (1.0, x, 0.0),
+        1 => (x, 1.0, 0.0),
+        2 => (0.0, 1.0, x),
+        3 => (0.0, x, 1.0),
+        4 => (x, 0.0, 1.0),
+        _ => (1.0, 0.0, x),
+    };
+    Color::new(r, g, b)
+}
+
+impl Camera {
+    /// Find the object visible through the center of each pixel. Unlike a
+    /// color render, pixels aren't sampled more than once, as IDs can't be
+    /// averaged.
+    pub fn render_object_ids(&self, world: &World) -> ObjectIds {
+        let mut ids = Vec::with_capacity(self.hsize() * self.vsize());
+        for y in 0..self.vsize() {
+            for x in 0..self.hsize() {
+                ids.push(world.object_at(&self.ray_for_pixel(x, y)));
+            }
+        }
+        ObjectIds {
+            width: self.hsize(),
+            height: self.vsize(),
+            ids,
+        }
+    }
+
+    /// The surface normal seen through the center of each pixel, facing the
+    /// camera, with its x, y and z stored as red, green and blue. Components
+    /// range from -1.0 to 1.0, so this is a guide for filtering such as
+    /// [`Canvas::denoise_guided`] rather than an image to view. The
+    /// background is black.
+    pub fn render_normals(&self, world: &World) -> Canvas {
+        self.render_hits(world, |comps| {
+            let n = comps.normalv;
+            Color::new(n.x(), n.y(), n.z())
+        })
+    }
+
+    /// The distance along the ray to the surface seen through the center of
+    /// each pixel, stored in every channel. The background is black.
+    pub fn render_depth(&self, world: &World) -> Canvas {
+        self.render_hits(world, |comps| Color::new(comps.t, comps.t, comps.t))
+    }
+
+    fn render_hits(&self, world: &World, value: impl Fn(&Computations) -> Color) -> Canvas {
+        let mut image = Canvas::new(self.hsize(), self.vsize());
+        for (x, y, pixel) in image.enumerate_pixels_mut() {
+            let ray = self.ray_for_pixel(x, y);
+            let xs = world.intersect(&ray);
+            if let Some(hit) = xs.hit() {
+                *pixel = value(&hit.prepare_computations(&ray, &xs));
+            }
+        }
+        image
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::fixtures::{default_camera, default_world};
+    use crate::matrix4::Matrix4;
+    use crate::shape::Sphere;
+
+    #[test]
+    fn test_render_object_ids() {
+        let mut w = default_world();
+        w.add_object(Sphere::with_transform(
+            Matrix4::translation(0.0, 0.0, -3.0) * Matrix4::scaling(0.2, 0.2, 0.2),
+        ));
+        let ids = default_camera().render_object_ids(&w);
+        assert_eq!((ids.width, ids.height), (11, 11));
+        assert_eq!(ids.id_at(5, 5), Some(2));
+        assert_eq!(ids.id_at(5, 4), Some(0));
+        assert_eq!(ids.id_at(0, 0), None);
+    }
+
+    #[test]
+    fn test_object_mask() {
+        let ids = default_camera().render_object_ids(&default_world());
+        let mask = ids.mask(0);
+        assert_eq!(mask.pixel_at(5, 5), Color::new(1.0, 1.0, 1.0));
+        assert_eq!(mask.pixel_at(0, 0), Color::new(0.0, 0.0, 0.0));
+        assert_eq!(ids.mask(1).pixel_at(5, 5), Color::new(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_render_normals_and_depth() {
+        let w = default_world();
+        let normals = default_camera().render_normals(&w);
+        assert_eq!(normals.pixel_at(5, 5), Color::new(0.0, 0.0, -1.0));
+        assert_eq!(normals.pixel_at(0, 0), Color::new(0.0, 0.0, 0.0));
+
+        let depth = default_camera().render_depth(&w);
+        assert_eq!(depth.pixel_at(5, 5), Color::new(4.0, 4.0, 4.0));
+        assert_eq!(depth.pixel_at(0, 0), Color::new(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_object_id_colors() {
+        let ids = default_camera().render_object_ids(&default_world());
+        let image = ids.to_canvas();
+        assert_eq!(image.pixel_at(5, 5), id_color(0));
+        assert_eq!(image.pixel_at(0, 0), Color::new(0.0, 0.0, 0.0));
+        assert_ne!(id_color(0), id_color(1));
+        assert_ne!(id_color(1), id_color(2));
+    }
+}
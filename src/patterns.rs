@@ -0,0 +1,794 @@
+use crate::color::Color;
+use crate::error::{Error, MatrixError};
+use crate::matrix4::Matrix4;
+use crate::noise::perlin;
+use crate::shape::Shape;
+use crate::space::{Point, Vector};
+use crate::uv::{CubeFace, UvMapping, UvPattern};
+use crate::Float;
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Pattern {
+    Solid(Color),
+    Stripe(StripePattern),
+    Gradient(GradientPattern),
+    Blend(BlendPattern),
+    Perturb(PerturbPattern),
+    TextureMap(TextureMapPattern),
+    CubeMap(Box<CubeMapPattern>),
+}
+
+impl Pattern {
+    /// The color of the pattern at a point in pattern space.
+    pub fn pattern_at(&self, point: &Point) -> Color {
+        match self {
+            Self::Solid(color) => *color,
+            Self::Stripe(stripe) => stripe.pattern_at(point),
+            Self::Gradient(gradient) => gradient.pattern_at(point),
+            Self::Blend(blend) => blend.pattern_at(point),
+            Self::Perturb(perturb) => perturb.pattern_at(point),
+            Self::TextureMap(texture_map) => texture_map.pattern_at(point),
+            Self::CubeMap(cube_map) => cube_map.pattern_at(point),
+        }
+    }
+
+    /// The color of this pattern when nested inside another pattern, where
+    /// `point` is in the parent pattern's space.
+    fn nested_at(&self, point: &Point) -> Color {
+        match self {
+            Self::Solid(color) => *color,
+            _ => self.pattern_at(&(self.inverse_transformation() * *point)),
+        }
+    }
+
+    /// The color of the pattern at a world-space point on `shape`, taking both
+    /// the shape's and the pattern's transformations into account.
+    pub fn pattern_at_shape(&self, shape: &Shape, world_point: &Point) -> Color {
+        let object_point = shape.world_to_object(world_point);
+        let pattern_point = self.inverse_transformation() * object_point;
+        self.pattern_at(&pattern_point)
+    }
+
+    fn inverse_transformation(&self) -> &Matrix4 {
+        match self {
+            Self::Solid(_) => &Matrix4::IDENTITY,
+            _ => &self.placement().inverse,
+        }
+    }
+
+    pub fn transformation(&self) -> &Matrix4 {
+        match self {
+            Self::Solid(_) => &Matrix4::IDENTITY,
+            _ => &self.placement().transformation,
+        }
+    }
+
+    /// Panics for a solid color, which has no transformation.
+    fn placement(&self) -> &PatternPlacement {
+        match self {
+            Self::Solid(_) => panic!("solid colors have no transformation"),
+            Self::Stripe(stripe) => &stripe.transformation,
+            Self::Gradient(gradient) => &gradient.transformation,
+            Self::Blend(blend) => &blend.transformation,
+            Self::Perturb(perturb) => &perturb.transformation,
+            Self::TextureMap(texture_map) => &texture_map.transformation,
+            Self::CubeMap(cube_map) => &cube_map.transformation,
+        }
+    }
+}
+
+/// A pattern's transformation, with its inverse worked out when it's set
+/// rather than for every color looked up. Serialized as the transformation
+/// alone.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "Matrix4", try_from = "Matrix4")
+)]
+struct PatternPlacement {
+    transformation: Matrix4,
+    inverse: Matrix4,
+}
+
+impl PatternPlacement {
+    /// Panics if `transformation` isn't invertible.
+    fn new(transformation: Matrix4) -> Self {
+        Self::try_from(transformation).expect("pattern transformation must be invertible")
+    }
+}
+
+impl TryFrom<Matrix4> for PatternPlacement {
+    type Error = MatrixError;
+
+    fn try_from(transformation: Matrix4) -> Result<Self, Self::Error> {
+        Ok(Self {
+            transformation,
+            inverse: transformation.inverse()?,
+        })
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<PatternPlacement> for Matrix4 {
+    fn from(value: PatternPlacement) -> Self {
+        value.transformation
+    }
+}
+
+impl From<Color> for Pattern {
+    fn from(value: Color) -> Self {
+        Self::Solid(value)
+    }
+}
+
+impl From<StripePattern> for Pattern {
+    fn from(value: StripePattern) -> Self {
+        Self::Stripe(value)
+    }
+}
+
+impl From<GradientPattern> for Pattern {
+    fn from(value: GradientPattern) -> Self {
+        Self::Gradient(value)
+    }
+}
+
+impl From<BlendPattern> for Pattern {
+    fn from(value: BlendPattern) -> Self {
+        Self::Blend(value)
+    }
+}
+
+impl From<PerturbPattern> for Pattern {
+    fn from(value: PerturbPattern) -> Self {
+        Self::Perturb(value)
+    }
+}
+
+impl From<TextureMapPattern> for Pattern {
+    fn from(value: TextureMapPattern) -> Self {
+        Self::TextureMap(value)
+    }
+}
+
+impl From<CubeMapPattern> for Pattern {
+    fn from(value: CubeMapPattern) -> Self {
+        Self::CubeMap(Box::new(value))
+    }
+}
+
+/// Alternating stripes of two patterns along the x axis.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StripePattern {
+    a: Box<Pattern>,
+    b: Box<Pattern>,
+    transformation: PatternPlacement,
+}
+
+impl StripePattern {
+    pub fn new(a: impl Into<Pattern>, b: impl Into<Pattern>) -> Self {
+        Self::with_transform(a, b, Matrix4::identity())
+    }
+
+    pub fn with_transform(
+        a: impl Into<Pattern>,
+        b: impl Into<Pattern>,
+        transformation: Matrix4,
+    ) -> Self {
+        Self {
+            a: Box::new(a.into()),
+            b: Box::new(b.into()),
+            transformation: PatternPlacement::new(transformation),
+        }
+    }
+
+    pub fn transformation(&self) -> &Matrix4 {
+        &self.transformation.transformation
+    }
+
+    /// Panics if `transformation` isn't invertible.
+    pub fn set_transform(&mut self, transformation: Matrix4) {
+        self.transformation = PatternPlacement::new(transformation);
+    }
+
+    /// Like [`StripePattern::set_transform`], but failing with
+    /// [`MatrixError::Singular`] rather than panicking, leaving the pattern
+    /// as it was.
+    pub fn try_set_transform(&mut self, transformation: Matrix4) -> Result<(), Error> {
+        self.transformation = transformation.try_into()?;
+        Ok(())
+    }
+
+    pub fn pattern_at(&self, point: &Point) -> Color {
+        if point.x().floor().rem_euclid(2.0) == 0.0 {
+            self.a.nested_at(point)
+        } else {
+            self.b.nested_at(point)
+        }
+    }
+}
+
+/// A linear blend from one pattern to another, repeating every unit along the
+/// x axis.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GradientPattern {
+    a: Box<Pattern>,
+    b: Box<Pattern>,
+    transformation: PatternPlacement,
+}
+
+impl GradientPattern {
+    pub fn new(a: impl Into<Pattern>, b: impl Into<Pattern>) -> Self {
+        Self::with_transform(a, b, Matrix4::identity())
+    }
+
+    pub fn with_transform(
+        a: impl Into<Pattern>,
+        b: impl Into<Pattern>,
+        transformation: Matrix4,
+    ) -> Self {
+        Self {
+            a: Box::new(a.into()),
+            b: Box::new(b.into()),
+            transformation: PatternPlacement::new(transformation),
+        }
+    }
+
+    pub fn transformation(&self) -> &Matrix4 {
+        &self.transformation.transformation
+    }
+
+    /// Panics if `transformation` isn't invertible.
+    pub fn set_transform(&mut self, transformation: Matrix4) {
+        self.transformation = PatternPlacement::new(transformation);
+    }
+
+    /// Like [`GradientPattern::set_transform`], but failing with
+    /// [`MatrixError::Singular`] rather than panicking, leaving the pattern
+    /// as it was.
+    pub fn try_set_transform(&mut self, transformation: Matrix4) -> Result<(), Error> {
+        self.transformation = transformation.try_into()?;
+        Ok(())
+    }
+
+    pub fn pattern_at(&self, point: &Point) -> Color {
+        let a = self.a.nested_at(point);
+        let b = self.b.nested_at(point);
+        let fraction = point.x() - point.x().floor();
+        a.lerp(b, fraction)
+    }
+}
+
+/// The average of two patterns, for overlaying one on another.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BlendPattern {
+    a: Box<Pattern>,
+    b: Box<Pattern>,
+    transformation: PatternPlacement,
+}
+
+impl BlendPattern {
+    pub fn new(a: impl Into<Pattern>, b: impl Into<Pattern>) -> Self {
+        Self::with_transform(a, b, Matrix4::identity())
+    }
+
+    pub fn with_transform(
+        a: impl Into<Pattern>,
+        b: impl Into<Pattern>,
+        transformation: Matrix4,
+    ) -> Self {
+        Self {
+            a: Box::new(a.into()),
+            b: Box::new(b.into()),
+            transformation: PatternPlacement::new(transformation),
+        }
+    }
+
+    pub fn transformation(&self) -> &Matrix4 {
+        &self.transformation.transformation
+    }
+
+    /// Panics if `transformation` isn't invertible.
+    pub fn set_transform(&mut self, transformation: Matrix4) {
+        self.transformation = PatternPlacement::new(transformation);
+    }
+
+    /// Like [`BlendPattern::set_transform`], but failing with
+    /// [`MatrixError::Singular`] rather than panicking, leaving the pattern
+    /// as it was.
+    pub fn try_set_transform(&mut self, transformation: Matrix4) -> Result<(), Error> {
+        self.transformation = transformation.try_into()?;
+        Ok(())
+    }
+
+    pub fn pattern_at(&self, point: &Point) -> Color {
+        (self.a.nested_at(point) + self.b.nested_at(point)) * 0.5
+    }
+}
+
+/// Jitters points with Perlin noise before looking them up in the wrapped
+/// pattern, for a marbled look.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PerturbPattern {
+    pattern: Box<Pattern>,
+    scale: Float,
+    transformation: PatternPlacement,
+}
+
+impl PerturbPattern {
+    /// Wrap `pattern`, moving each point by up to `scale` units along each axis.
+    pub fn new(pattern: impl Into<Pattern>, scale: Float) -> Self {
+        Self::with_transform(pattern, scale, Matrix4::identity())
+    }
+
+    pub fn with_transform(
+        pattern: impl Into<Pattern>,
+        scale: Float,
+        transformation: Matrix4,
+    ) -> Self {
+        Self {
+            pattern: Box::new(pattern.into()),
+            scale,
+            transformation: PatternPlacement::new(transformation),
+        }
+    }
+
+    pub fn transformation(&self) -> &Matrix4 {
+        &self.transformation.transformation
+    }
+
+    /// Panics if `transformation` isn't invertible.
+    pub fn set_transform(&mut self, transformation: Matrix4) {
+        self.transformation = PatternPlacement::new(transformation);
+    }
+
+    /// Like [`PerturbPattern::set_transform`], but failing with
+    /// [`MatrixError::Singular`] rather than panicking, leaving the pattern
+    /// as it was.
+    pub fn try_set_transform(&mut self, transformation: Matrix4) -> Result<(), Error> {
+        self.transformation = transformation.try_into()?;
+        Ok(())
+    }
+
+    pub fn pattern_at(&self, point: &Point) -> Color {
+        self.pattern
+            .nested_at(&(*point + Self::jitter(point) * self.scale))
+    }
+
+    /// How far to move `point`, up to about a unit along each axis.
+    fn jitter(point: &Point) -> Vector {
+        let (x, y, z) = (point.x(), point.y(), point.z());
+        // Look each axis up in a distant, unrelated part of the noise, offset
+        // along every axis by non-whole amounts, so the three don't move
+        // together.
+        Vector::new(
+            perlin(x, y, z),
+            perlin(x + 31.7, y + 58.3, z + 11.9),
+            perlin(x + 73.1, y + 17.5, z + 93.7),
+        )
+    }
+}
+
+/// Wraps a two-dimensional (u, v) pattern around a surface.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TextureMapPattern {
+    uv_pattern: UvPattern,
+    mapping: UvMapping,
+    transformation: PatternPlacement,
+}
+
+impl TextureMapPattern {
+    pub fn new(uv_pattern: impl Into<UvPattern>, mapping: UvMapping) -> Self {
+        Self::with_transform(uv_pattern, mapping, Matrix4::identity())
+    }
+
+    pub fn with_transform(
+        uv_pattern: impl Into<UvPattern>,
+        mapping: UvMapping,
+        transformation: Matrix4,
+    ) -> Self {
+        Self {
+            uv_pattern: uv_pattern.into(),
+            mapping,
+            transformation: PatternPlacement::new(transformation),
+        }
+    }
+
+    pub fn transformation(&self) -> &Matrix4 {
+        &self.transformation.transformation
+    }
+
+    /// Panics if `transformation` isn't invertible.
+    pub fn set_transform(&mut self, transformation: Matrix4) {
+        self.transformation = PatternPlacement::new(transformation);
+    }
+
+    /// Like [`TextureMapPattern::set_transform`], but failing with
+    /// [`MatrixError::Singular`] rather than panicking, leaving the pattern
+    /// as it was.
+    pub fn try_set_transform(&mut self, transformation: Matrix4) -> Result<(), Error> {
+        self.transformation = transformation.try_into()?;
+        Ok(())
+    }
+
+    pub fn pattern_at(&self, point: &Point) -> Color {
+        let (u, v) = self.mapping.map(point);
+        self.uv_pattern.uv_pattern_at(u, v)
+    }
+}
+
+/// A separate (u, v) pattern on each face of a cube centred on the origin.
+///
+/// Faces are chosen by direction from the origin, so this also works as an
+/// environment map on a large sphere: see [`Material::skybox`].
+///
+/// [`Material::skybox`]: crate::materials::Material::skybox
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CubeMapPattern {
+    left: UvPattern,
+    front: UvPattern,
+    right: UvPattern,
+    back: UvPattern,
+    up: UvPattern,
+    down: UvPattern,
+    transformation: PatternPlacement,
+}
+
+impl CubeMapPattern {
+    pub fn new(
+        left: impl Into<UvPattern>,
+        front: impl Into<UvPattern>,
+        right: impl Into<UvPattern>,
+        back: impl Into<UvPattern>,
+        up: impl Into<UvPattern>,
+        down: impl Into<UvPattern>,
+    ) -> Self {
+        Self {
+            left: left.into(),
+            front: front.into(),
+            right: right.into(),
+            back: back.into(),
+            up: up.into(),
+            down: down.into(),
+            transformation: PatternPlacement::new(Matrix4::identity()),
+        }
+    }
+
+    pub fn transformation(&self) -> &Matrix4 {
+        &self.transformation.transformation
+    }
+
+    /// Panics if `transformation` isn't invertible.
+    pub fn set_transform(&mut self, transformation: Matrix4) {
+        self.transformation = PatternPlacement::new(transformation);
+    }
+
+    /// Like [`CubeMapPattern::set_transform`], but failing with
+    /// [`MatrixError::Singular`] rather than panicking, leaving the pattern
+    /// as it was.
+    pub fn try_set_transform(&mut self, transformation: Matrix4) -> Result<(), Error> {
+        self.transformation = transformation.try_into()?;
+        Ok(())
+    }
+
+    pub fn pattern_at(&self, point: &Point) -> Color {
+        let face = CubeFace::from_point(point);
+        let (u, v) = face.map(point);
+        let pattern = match face {
+            CubeFace::Left => &self.left,
+            CubeFace::Front => &self.front,
+            CubeFace::Right => &self.right,
+            CubeFace::Back => &self.back,
+            CubeFace::Up => &self.up,
+            CubeFace::Down => &self.down,
+        };
+        pattern.uv_pattern_at(u, v)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::consts::PI;
+
+    use crate::shape::Sphere;
+    use crate::uv::{UvAlignCheck, UvCheckers};
+
+    use super::*;
+
+    fn white() -> Color {
+        Color::new(1.0, 1.0, 1.0)
+    }
+
+    fn black() -> Color {
+        Color::new(0.0, 0.0, 0.0)
+    }
+
+    #[test]
+    fn test_stripe_pattern() {
+        let pattern = StripePattern::new(white(), black());
+        assert_eq!(*pattern.a, Pattern::Solid(white()));
+        assert_eq!(*pattern.b, Pattern::Solid(black()));
+        assert_eq!(*pattern.transformation(), Matrix4::identity());
+    }
+
+    #[test]
+    fn test_stripe_constant_in_y() {
+        let pattern = StripePattern::new(white(), black());
+        assert_eq!(pattern.pattern_at(&Point::new(0.0, 0.0, 0.0)), white());
+        assert_eq!(pattern.pattern_at(&Point::new(0.0, 1.0, 0.0)), white());
+        assert_eq!(pattern.pattern_at(&Point::new(0.0, 2.0, 0.0)), white());
+    }
+
+    #[test]
+    fn test_stripe_constant_in_z() {
+        let pattern = StripePattern::new(white(), black());
+        assert_eq!(pattern.pattern_at(&Point::new(0.0, 0.0, 0.0)), white());
+        assert_eq!(pattern.pattern_at(&Point::new(0.0, 0.0, 1.0)), white());
+        assert_eq!(pattern.pattern_at(&Point::new(0.0, 0.0, 2.0)), white());
+    }
+
+    #[test]
+    fn test_stripe_alternates_in_x() {
+        let pattern = StripePattern::new(white(), black());
+        assert_eq!(pattern.pattern_at(&Point::new(0.0, 0.0, 0.0)), white());
+        assert_eq!(pattern.pattern_at(&Point::new(0.9, 0.0, 0.0)), white());
+        assert_eq!(pattern.pattern_at(&Point::new(1.0, 0.0, 0.0)), black());
+        assert_eq!(pattern.pattern_at(&Point::new(-0.1, 0.0, 0.0)), black());
+        assert_eq!(pattern.pattern_at(&Point::new(-1.0, 0.0, 0.0)), black());
+        assert_eq!(pattern.pattern_at(&Point::new(-1.1, 0.0, 0.0)), white());
+    }
+
+    #[test]
+    fn test_stripes_with_object_transformation() {
+        let object: Shape = Sphere::with_transform(Matrix4::scaling(2.0, 2.0, 2.0)).into();
+        let pattern: Pattern = StripePattern::new(white(), black()).into();
+        let c = pattern.pattern_at_shape(&object, &Point::new(1.5, 0.0, 0.0));
+        assert_eq!(c, white());
+    }
+
+    #[test]
+    fn test_stripes_with_pattern_transformation() {
+        let object: Shape = Sphere::new().into();
+        let pattern: Pattern =
+            StripePattern::with_transform(white(), black(), Matrix4::scaling(2.0, 2.0, 2.0)).into();
+        let c = pattern.pattern_at_shape(&object, &Point::new(1.5, 0.0, 0.0));
+        assert_eq!(c, white());
+    }
+
+    #[test]
+    fn test_stripes_with_object_and_pattern_transformation() {
+        let object: Shape = Sphere::with_transform(Matrix4::scaling(2.0, 2.0, 2.0)).into();
+        let mut pattern = StripePattern::new(white(), black());
+        pattern.set_transform(Matrix4::translation(0.5, 0.0, 0.0));
+        let pattern: Pattern = pattern.into();
+        let c = pattern.pattern_at_shape(&object, &Point::new(2.5, 0.0, 0.0));
+        assert_eq!(c, white());
+    }
+
+    #[test]
+    fn test_try_set_singular_transform() {
+        let mut pattern = StripePattern::new(white(), black());
+        pattern.set_transform(Matrix4::scaling(2.0, 2.0, 2.0));
+        assert!(matches!(
+            pattern.try_set_transform(Matrix4::scaling(0.0, 1.0, 1.0)),
+            Err(Error::Matrix(MatrixError::Singular))
+        ));
+        assert_eq!(*pattern.transformation(), Matrix4::scaling(2.0, 2.0, 2.0));
+    }
+
+    #[test]
+    #[should_panic(expected = "pattern transformation must be invertible")]
+    fn test_set_singular_transform() {
+        GradientPattern::new(white(), black()).set_transform(Matrix4::scaling(1.0, 0.0, 1.0));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_pattern_json_round_trip() {
+        let pattern: Pattern =
+            StripePattern::with_transform(white(), black(), Matrix4::scaling(2.0, 2.0, 2.0)).into();
+        let json = serde_json::to_string(&pattern).unwrap();
+        assert_eq!(serde_json::from_str::<Pattern>(&json).unwrap(), pattern);
+        let singular = json.replace("2.0", "0.0");
+        assert!(serde_json::from_str::<Pattern>(&singular).is_err());
+    }
+
+    #[test]
+    fn test_gradient_interpolates() {
+        let pattern = GradientPattern::new(white(), black());
+        assert_eq!(pattern.pattern_at(&Point::new(0.0, 0.0, 0.0)), white());
+        assert_eq!(
+            pattern.pattern_at(&Point::new(0.25, 0.0, 0.0)),
+            Color::new(0.75, 0.75, 0.75)
+        );
+        assert_eq!(
+            pattern.pattern_at(&Point::new(0.5, 0.0, 0.0)),
+            Color::new(0.5, 0.5, 0.5)
+        );
+        assert_eq!(
+            pattern.pattern_at(&Point::new(0.75, 0.0, 0.0)),
+            Color::new(0.25, 0.25, 0.25)
+        );
+    }
+
+    #[test]
+    fn test_blend_averages_patterns() {
+        let red = Color::new(1.0, 0.0, 0.0);
+        let blue = Color::new(0.0, 0.0, 1.0);
+        let pattern = BlendPattern::new(
+            StripePattern::new(red, white()),
+            StripePattern::with_transform(blue, black(), Matrix4::rotation_y(PI / 2.0)),
+        );
+
+        assert_eq!(
+            pattern.pattern_at(&Point::new(0.5, 0.0, -0.5)),
+            Color::new(0.5, 0.0, 0.5)
+        );
+        assert_eq!(
+            pattern.pattern_at(&Point::new(1.5, 0.0, -0.5)),
+            Color::new(0.5, 0.5, 1.0)
+        );
+        assert_eq!(
+            pattern.pattern_at(&Point::new(0.5, 0.0, 0.5)),
+            Color::new(0.5, 0.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn test_nested_patterns_use_their_own_transformation() {
+        let inner =
+            StripePattern::with_transform(white(), black(), Matrix4::scaling(0.5, 1.0, 1.0));
+        let pattern = StripePattern::new(inner, black());
+
+        assert_eq!(pattern.pattern_at(&Point::new(0.25, 0.0, 0.0)), white());
+        assert_eq!(pattern.pattern_at(&Point::new(0.75, 0.0, 0.0)), black());
+        assert_eq!(pattern.pattern_at(&Point::new(1.25, 0.0, 0.0)), black());
+    }
+
+    #[test]
+    fn test_stripes_over_gradient() {
+        let pattern = StripePattern::new(GradientPattern::new(white(), black()), black());
+        assert_eq!(
+            pattern.pattern_at(&Point::new(0.5, 0.0, 0.0)),
+            Color::new(0.5, 0.5, 0.5)
+        );
+        assert_eq!(pattern.pattern_at(&Point::new(1.5, 0.0, 0.0)), black());
+    }
+
+    #[test]
+    fn test_perturb_with_zero_scale_matches_wrapped_pattern() {
+        let stripes = StripePattern::new(white(), black());
+        let pattern = PerturbPattern::new(stripes.clone(), 0.0);
+        for x in [0.1, 0.9, 1.2, -0.4] {
+            let p = Point::new(x, 0.3, 0.7);
+            assert_eq!(pattern.pattern_at(&p), stripes.pattern_at(&p));
+        }
+    }
+
+    #[test]
+    fn test_perturb_jitters_points() {
+        let stripes = StripePattern::new(white(), black());
+        let pattern = PerturbPattern::new(stripes.clone(), 1.0);
+        let differs = (0..100).any(|i| {
+            let p = Point::new(i as Float * 0.13, i as Float * 0.07, 0.5);
+            pattern.pattern_at(&p) != stripes.pattern_at(&p)
+        });
+        assert!(differs);
+    }
+
+    #[test]
+    fn test_perturb_jitter_axes_are_unrelated() {
+        // No axis's jitter is another's a few whole cells of noise away.
+        let points: Vec<Point> = (0..20)
+            .map(|i| {
+                let i = i as Float;
+                Point::new(i * 0.37, i * 0.19, i * 0.11)
+            })
+            .collect();
+        let jitter = |p: &Point| {
+            let jitter = PerturbPattern::jitter(p);
+            [jitter.x(), jitter.y(), jitter.z()]
+        };
+        for shift in -3..=3 {
+            let shift = shift as Float;
+            for offset in [
+                Vector::new(shift, 0.0, 0.0),
+                Vector::new(0.0, shift, 0.0),
+                Vector::new(0.0, 0.0, shift),
+            ] {
+                for (a, b) in [(0, 1), (0, 2), (1, 2), (1, 0), (2, 0), (2, 1)] {
+                    let copied = points
+                        .iter()
+                        .all(|p| jitter(p)[b] == jitter(&(*p + offset))[a]);
+                    assert!(!copied, "axis {b} copies axis {a} moved by {offset:?}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_texture_map_spherical_checkers() {
+        let checkers = UvCheckers::new(16.0, 8.0, black(), white());
+        let pattern = TextureMapPattern::new(checkers, UvMapping::Spherical);
+
+        let cases = [
+            (Point::new(0.4315, 0.4670, 0.7719), white()),
+            (Point::new(-0.9654, 0.2552, -0.0534), black()),
+            (Point::new(0.1039, 0.7090, 0.6975), white()),
+            (Point::new(-0.4986, -0.7856, -0.3663), black()),
+            (Point::new(-0.0317, -0.9395, 0.3411), black()),
+            (Point::new(0.4809, -0.7721, 0.4154), black()),
+            (Point::new(0.0285, -0.9612, -0.2745), black()),
+            (Point::new(-0.5734, -0.2162, -0.7903), white()),
+            (Point::new(0.7688, -0.1470, 0.6223), black()),
+            (Point::new(-0.7652, 0.2175, 0.6060), black()),
+        ];
+        for (point, color) in cases {
+            assert_eq!(pattern.pattern_at(&point), color, "at {:?}", point);
+        }
+    }
+
+    #[test]
+    fn test_cube_map() {
+        let red = Color::new(1.0, 0.0, 0.0);
+        let yellow = Color::new(1.0, 1.0, 0.0);
+        let brown = Color::new(1.0, 0.5, 0.0);
+        let green = Color::new(0.0, 1.0, 0.0);
+        let cyan = Color::new(0.0, 1.0, 1.0);
+        let blue = Color::new(0.0, 0.0, 1.0);
+        let purple = Color::new(1.0, 0.0, 1.0);
+        let white = white();
+
+        let left = UvAlignCheck::new(yellow, cyan, red, blue, brown);
+        let front = UvAlignCheck::new(cyan, red, yellow, brown, green);
+        let right = UvAlignCheck::new(red, yellow, purple, green, white);
+        let back = UvAlignCheck::new(green, purple, cyan, white, blue);
+        let up = UvAlignCheck::new(brown, cyan, purple, red, yellow);
+        let down = UvAlignCheck::new(purple, brown, green, blue, white);
+        let pattern = CubeMapPattern::new(left, front, right, back, up, down);
+
+        let cases = [
+            (Point::new(-1.0, 0.0, 0.0), yellow),
+            (Point::new(-1.0, 0.9, -0.9), cyan),
+            (Point::new(-1.0, 0.9, 0.9), red),
+            (Point::new(-1.0, -0.9, -0.9), blue),
+            (Point::new(-1.0, -0.9, 0.9), brown),
+            (Point::new(0.0, 0.0, 1.0), cyan),
+            (Point::new(-0.9, 0.9, 1.0), red),
+            (Point::new(0.9, 0.9, 1.0), yellow),
+            (Point::new(-0.9, -0.9, 1.0), brown),
+            (Point::new(0.9, -0.9, 1.0), green),
+            (Point::new(1.0, 0.0, 0.0), red),
+            (Point::new(1.0, 0.9, 0.9), yellow),
+            (Point::new(1.0, 0.9, -0.9), purple),
+            (Point::new(1.0, -0.9, 0.9), green),
+            (Point::new(1.0, -0.9, -0.9), white),
+            (Point::new(0.0, 0.0, -1.0), green),
+            (Point::new(0.9, 0.9, -1.0), purple),
+            (Point::new(-0.9, 0.9, -1.0), cyan),
+            (Point::new(0.9, -0.9, -1.0), white),
+            (Point::new(-0.9, -0.9, -1.0), blue),
+            (Point::new(0.0, 1.0, 0.0), brown),
+            (Point::new(-0.9, 1.0, -0.9), cyan),
+            (Point::new(0.9, 1.0, -0.9), purple),
+            (Point::new(-0.9, 1.0, 0.9), red),
+            (Point::new(0.9, 1.0, 0.9), yellow),
+            (Point::new(0.0, -1.0, 0.0), purple),
+            (Point::new(-0.9, -1.0, 0.9), brown),
+            (Point::new(0.9, -1.0, 0.9), green),
+            (Point::new(-0.9, -1.0, -0.9), blue),
+            (Point::new(0.9, -1.0, -0.9), white),
+        ];
+        for (point, color) in cases {
+            assert_eq!(pattern.pattern_at(&point), color, "at {:?}", point);
+        }
+    }
+}
@@ -0,0 +1,57 @@
+use crate::canvas::Canvas;
+use std::io::{prelude::*, Result};
+
+impl Canvas {
+    /// Write the canvas as a portable float map (.pfm): each component as a
+    /// 32-bit float, neither clamped nor encoded, for passing renders to
+    /// compositing tools without losing anything.
+    pub fn write_pfm(&self, sink: &mut impl Write) -> Result<()> {
+        writeln!(sink, "PF")?;
+        writeln!(sink, "{} {}", self.width, self.height)?;
+        // A negative scale marks the data as little-endian.
+        writeln!(sink, "-1.0")?;
+
+        // Rows run from the bottom of the image to the top.
+        let mut bytes = Vec::with_capacity(self.width * 12);
+        for row in (0..self.height).rev() {
+            bytes.clear();
+            for col in 0..self.width {
+                let pixel = self.pixel_at(col, row);
+                for f in [pixel.red(), pixel.green(), pixel.blue()] {
+                    // Always f32, whichever type `Float` is.
+                    #[allow(clippy::unnecessary_cast)]
+                    bytes.extend_from_slice(&(f as f32).to_le_bytes());
+                }
+            }
+            sink.write_all(&bytes)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::color::Color;
+
+    #[test]
+    fn test_pfm() {
+        let mut c = Canvas::new(2, 2);
+        c.write_pixel(0, 0, Color::new(1.5, -0.25, 0.0));
+        c.write_pixel(1, 1, Color::new(0.5, 2.0, 100.0));
+        let mut bytes = Vec::new();
+        c.write_pfm(&mut bytes).unwrap();
+
+        let header = b"PF\n2 2\n-1.0\n";
+        assert_eq!(&bytes[..header.len()], header);
+        let floats: Vec<f32> = bytes[header.len()..]
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes(b.try_into().unwrap()))
+            .collect();
+        // The bottom row comes first.
+        assert_eq!(
+            floats,
+            [0.0, 0.0, 0.0, 0.5, 2.0, 100.0, 1.5, -0.25, 0.0, 0.0, 0.0, 0.0]
+        );
+    }
+}
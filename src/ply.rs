@@ -0,0 +1,390 @@
+//! Import meshes from PLY files, the format many scanned models such as the
+//! Stanford bunny come in.
+
+use std::fs::File;
+use std::io::{prelude::*, BufReader, Error, ErrorKind, Result};
+use std::path::Path;
+
+use crate::shape::{Group, Triangle};
+use crate::space::{Point, Vector};
+use crate::Float;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Format {
+    Ascii,
+    BinaryLittleEndian,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Type {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+#[derive(Debug)]
+enum Property {
+    Scalar {
+        name: String,
+        kind: Type,
+    },
+    List {
+        name: String,
+        count: Type,
+        item: Type,
+    },
+}
+
+#[derive(Debug)]
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+/// Read a mesh from an ASCII or binary little-endian PLY file, as a group
+/// with a triangle for each face. Faces with more than three vertices are
+/// split into a fan of triangles. If the vertices have normals, the
+/// triangles are smooth.
+///
+/// Elements other than vertices and faces are skipped. Build a kd-tree for
+/// the group with [`Group::build_kd_tree`] before rendering a large mesh.
+pub fn read(source: &mut impl BufRead) -> Result<Group> {
+    let (format, elements) = read_header(source)?;
+
+    let mut points = vec![];
+    let mut normals = vec![];
+    let mut faces = vec![];
+    let mut line = String::new();
+    for element in &elements {
+        let position = |name: &str| element.properties.iter().position(|p| p.name() == name);
+        let xyz = [position("x"), position("y"), position("z")];
+        let normal = [position("nx"), position("ny"), position("nz")];
+        let indices = position("vertex_indices").or(position("vertex_index"));
+
+        for _ in 0..element.count {
+            let values = read_values(format, source, element, &mut line)?;
+            match element.name.as_str() {
+                "vertex" => {
+                    let [x, y, z] = xyz.map(|i| i.map_or(0.0, |i| values[i][0]));
+                    points.push(Point::new(x as Float, y as Float, z as Float));
+                    if let [Some(x), Some(y), Some(z)] = normal {
+                        let (x, y, z) = (values[x][0], values[y][0], values[z][0]);
+                        normals.push(Vector::new(x as Float, y as Float, z as Float));
+                    }
+                }
+                "face" => {
+                    let indices =
+                        indices.ok_or_else(|| invalid("PLY faces have no vertex indices"))?;
+                    faces.push(values[indices].clone());
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let smooth = !normals.is_empty() && normals.len() == points.len();
+    let vertex = |index: f64| {
+        let valid = index >= 0.0 && index.fract() == 0.0 && (index as usize) < points.len();
+        if valid {
+            Ok(index as usize)
+        } else {
+            Err(invalid(format!(
+                "PLY face refers to missing vertex {index}"
+            )))
+        }
+    };
+    let mut group = Group::new();
+    for face in faces {
+        let face = face.into_iter().map(vertex).collect::<Result<Vec<_>>>()?;
+        for i in 1..face.len().saturating_sub(1) {
+            let [a, b, c] = [face[0], face[i], face[i + 1]];
+            group.add_child(if smooth {
+                Triangle::with_normals(
+                    points[a], points[b], points[c], normals[a], normals[b], normals[c],
+                )
+            } else {
+                Triangle::new(points[a], points[b], points[c])
+            });
+        }
+    }
+    Ok(group)
+}
+
+/// Load a mesh from the PLY file at `path`. See [`read`].
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(skip_all, fields(path = %path.as_ref().display()))
+)]
+pub fn load(path: impl AsRef<Path>) -> Result<Group> {
+    read(&mut BufReader::new(File::open(path)?))
+}
+
+fn read_header(source: &mut impl BufRead) -> Result<(Format, Vec<Element>)> {
+    let mut lines = source.lines();
+    let mut next_line = || {
+        lines
+            .next()
+            .unwrap_or_else(|| Err(invalid("PLY header is incomplete")))
+    };
+    if next_line()?.trim_end() != "ply" {
+        return Err(invalid("not a PLY file"));
+    }
+
+    let mut format = None;
+    let mut elements: Vec<Element> = vec![];
+    loop {
+        let line = next_line()?;
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words.as_slice() {
+            ["format", "ascii", _] => format = Some(Format::Ascii),
+            ["format", "binary_little_endian", _] => format = Some(Format::BinaryLittleEndian),
+            ["format", other, _] => {
+                return Err(invalid(format!("PLY format {other} isn't supported")))
+            }
+            ["comment", ..] | ["obj_info", ..] | [] => {}
+            ["element", name, count] => elements.push(Element {
+                name: name.to_string(),
+                count: count
+                    .parse()
+                    .map_err(|_| invalid("PLY element count isn't a number"))?,
+                properties: vec![],
+            }),
+            ["property", "list", count, item, name] => {
+                let property = Property::List {
+                    name: name.to_string(),
+                    count: Type::parse(count)?,
+                    item: Type::parse(item)?,
+                };
+                last_element(&mut elements)?.properties.push(property);
+            }
+            ["property", kind, name] => {
+                let property = Property::Scalar {
+                    name: name.to_string(),
+                    kind: Type::parse(kind)?,
+                };
+                last_element(&mut elements)?.properties.push(property);
+            }
+            ["end_header"] => break,
+            _ => return Err(invalid(format!("unexpected PLY header line {line:?}"))),
+        }
+    }
+
+    let format = format.ok_or_else(|| invalid("PLY header has no format"))?;
+    Ok((format, elements))
+}
+
+fn last_element(elements: &mut [Element]) -> Result<&mut Element> {
+    elements
+        .last_mut()
+        .ok_or_else(|| invalid("PLY property comes before any element"))
+}
+
+/// Read one of `element`'s entries, with the values of each property in
+/// turn. Scalar properties have a single value.
+///
+/// Values are read as f64 whatever [`Float`] is, so large vertex indices
+/// survive.
+fn read_values(
+    format: Format,
+    source: &mut impl BufRead,
+    element: &Element,
+    line: &mut String,
+) -> Result<Vec<Vec<f64>>> {
+    match format {
+        Format::Ascii => {
+            line.clear();
+            while line.trim().is_empty() {
+                line.clear();
+                if source.read_line(line)? == 0 {
+                    return Err(invalid(format!(
+                        "PLY file ends within its {}s",
+                        element.name
+                    )));
+                }
+            }
+            let mut words = line.split_whitespace();
+            read_properties(element, |_| {
+                words
+                    .next()
+                    .ok_or_else(|| invalid(format!("PLY {} has too few values", element.name)))?
+                    .parse()
+                    .map_err(|_| {
+                        invalid(format!(
+                            "PLY {} has a value that isn't a number",
+                            element.name
+                        ))
+                    })
+            })
+        }
+        Format::BinaryLittleEndian => read_properties(element, |kind| kind.read_le(source)),
+    }
+}
+
+fn read_properties(
+    element: &Element,
+    mut next: impl FnMut(Type) -> Result<f64>,
+) -> Result<Vec<Vec<f64>>> {
+    element
+        .properties
+        .iter()
+        .map(|property| match property {
+            Property::Scalar { kind, .. } => Ok(vec![next(*kind)?]),
+            Property::List { count, item, .. } => {
+                let count = next(*count)?;
+                if count < 0.0 || count.fract() != 0.0 {
+                    return Err(invalid(format!("PLY list length {count} is invalid")));
+                }
+                (0..count as usize).map(|_| next(*item)).collect()
+            }
+        })
+        .collect()
+}
+
+impl Type {
+    fn parse(name: &str) -> Result<Self> {
+        Ok(match name {
+            "char" | "int8" => Self::I8,
+            "uchar" | "uint8" => Self::U8,
+            "short" | "int16" => Self::I16,
+            "ushort" | "uint16" => Self::U16,
+            "int" | "int32" => Self::I32,
+            "uint" | "uint32" => Self::U32,
+            "float" | "float32" => Self::F32,
+            "double" | "float64" => Self::F64,
+            _ => return Err(invalid(format!("unknown PLY property type {name}"))),
+        })
+    }
+
+    fn read_le(self, source: &mut impl Read) -> Result<f64> {
+        fn bytes<const N: usize>(source: &mut impl Read) -> Result<[u8; N]> {
+            let mut bytes = [0; N];
+            source.read_exact(&mut bytes)?;
+            Ok(bytes)
+        }
+
+        Ok(match self {
+            Self::I8 => i8::from_le_bytes(bytes(source)?).into(),
+            Self::U8 => u8::from_le_bytes(bytes(source)?).into(),
+            Self::I16 => i16::from_le_bytes(bytes(source)?).into(),
+            Self::U16 => u16::from_le_bytes(bytes(source)?).into(),
+            Self::I32 => i32::from_le_bytes(bytes(source)?).into(),
+            Self::U32 => u32::from_le_bytes(bytes(source)?).into(),
+            Self::F32 => f32::from_le_bytes(bytes(source)?).into(),
+            Self::F64 => f64::from_le_bytes(bytes(source)?),
+        })
+    }
+}
+
+impl Property {
+    fn name(&self) -> &str {
+        match self {
+            Self::Scalar { name, .. } | Self::List { name, .. } => name,
+        }
+    }
+}
+
+fn invalid(message: impl Into<String>) -> Error {
+    Error::new(ErrorKind::InvalidData, message.into())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::shape::Shape;
+
+    const SQUARE: &str = "ply
+format ascii 1.0
+comment A unit square as one quad, with an edge element to skip.
+element vertex 4
+property float x
+property float y
+property float z
+element face 1
+property list uchar int vertex_indices
+element edge 1
+property int vertex1
+property int vertex2
+end_header
+0 0 0
+1 0 0
+1 1 0
+0 1 0
+4 0 1 2 3
+0 2
+";
+
+    fn triangles(group: &Group) -> Vec<&Triangle> {
+        group
+            .children()
+            .iter()
+            .map(|child| match child {
+                Shape::Triangle(triangle) => triangle,
+                _ => unreachable!(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_read_ascii() {
+        let group = read(&mut SQUARE.as_bytes()).unwrap();
+        let triangles = triangles(&group);
+        assert_eq!(triangles.len(), 2);
+        assert_eq!(
+            triangles[1].points(),
+            (
+                Point::new(0.0, 0.0, 0.0),
+                Point::new(1.0, 1.0, 0.0),
+                Point::new(0.0, 1.0, 0.0)
+            )
+        );
+        assert!(!triangles[0].smooth());
+    }
+
+    #[test]
+    fn test_read_binary_with_normals() {
+        let mut ply = b"ply\nformat binary_little_endian 1.0\nelement vertex 3\n\
+            property float x\nproperty float y\nproperty float z\n\
+            property float nx\nproperty float ny\nproperty float nz\n\
+            element face 1\nproperty list uchar uint vertex_index\nend_header\n"
+            .to_vec();
+        for [x, y, z] in [[0.0, 1.0, 0.0], [-1.0, 0.0, 0.0], [1.0, 0.0, 0.0]] {
+            for value in [x, y, z, x, y, z] {
+                ply.extend_from_slice(&(value as f32).to_le_bytes());
+            }
+        }
+        ply.push(3);
+        for index in [0u32, 1, 2] {
+            ply.extend_from_slice(&index.to_le_bytes());
+        }
+
+        let group = read(&mut ply.as_slice()).unwrap();
+        let triangle = triangles(&group)[0];
+        assert_eq!(triangle.points().1, Point::new(-1.0, 0.0, 0.0));
+        assert!(triangle.smooth());
+        assert_eq!(triangle.normals().unwrap().2, Vector::new(1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_read_invalid() {
+        let error = |ply: &str| read(&mut ply.as_bytes()).unwrap_err().to_string();
+        assert_eq!(error("solid cube\n"), "not a PLY file");
+        assert_eq!(
+            error("ply\nformat binary_big_endian 1.0\nend_header\n"),
+            "PLY format binary_big_endian isn't supported"
+        );
+        assert_eq!(
+            error(&SQUARE.replace("4 0 1 2 3", "3 0 1 7")),
+            "PLY face refers to missing vertex 7"
+        );
+        assert_eq!(
+            error(&SQUARE.replace("\n0 2\n", "\n")),
+            "PLY file ends within its edges"
+        );
+    }
+}
@@ -0,0 +1,243 @@
+use crate::canvas::Canvas;
+use crate::tonemap::OutputOptions;
+use std::io::{prelude::*, Result};
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+/// The largest block deflate can store without compression.
+const MAX_STORED_BLOCK: usize = 0xffff;
+
+const CRC_TABLE: [u32; 256] = crc_table();
+
+const fn crc_table() -> [u32; 256] {
+    let mut table = [0; 256];
+    let mut n = 0;
+    while n < 256 {
+        let mut c = n as u32;
+        let mut k = 0;
+        while k < 8 {
+            c = if c & 1 == 1 {
+                0xedb88320 ^ (c >> 1)
+            } else {
+                c >> 1
+            };
+            k += 1;
+        }
+        table[n] = c;
+        n += 1;
+    }
+    table
+}
+
+/// The CRC-32 that PNG uses to check each chunk.
+fn crc32(data: &[u8]) -> u32 {
+    let crc = data.iter().fold(0xffffffff_u32, |c, &b| {
+        CRC_TABLE[((c ^ b as u32) & 0xff) as usize] ^ (c >> 8)
+    });
+    crc ^ 0xffffffff
+}
+
+/// The Adler-32 checksum that ends a zlib stream.
+fn adler32(data: &[u8]) -> u32 {
+    const MOD_ADLER: u32 = 65521;
+    let (a, b) = data.iter().fold((1_u32, 0_u32), |(a, b), &byte| {
+        let a = (a + byte as u32) % MOD_ADLER;
+        (a, (b + a) % MOD_ADLER)
+    });
+    (b << 16) | a
+}
+
+/// Wrap `data` in a zlib stream of uncompressed deflate blocks. This keeps
+/// the writer dependency-free, at the cost of files about the same size as
+/// the raw pixels.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut out = vec![0x78, 0x01];
+    let mut blocks = data.chunks(MAX_STORED_BLOCK).peekable();
+    if blocks.peek().is_none() {
+        out.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(block) = blocks.next() {
+        let last = blocks.peek().is_none();
+        let len = block.len() as u16;
+        out.push(last as u8);
+        out.extend_from_slice(&len.to_le_bytes());
+        out.extend_from_slice(&(!len).to_le_bytes());
+        out.extend_from_slice(block);
+    }
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+fn write_chunk(sink: &mut impl Write, kind: &[u8; 4], data: &[u8]) -> Result<()> {
+    sink.write_all(&(data.len() as u32).to_be_bytes())?;
+    sink.write_all(kind)?;
+    sink.write_all(data)?;
+
+    let mut checked = kind.to_vec();
+    checked.extend_from_slice(data);
+    sink.write_all(&crc32(&checked).to_be_bytes())
+}
+
+/// Settings controlling how colors are converted when writing a PNG.
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PngOptions {
+    /// Encode with the sRGB transfer curve, which PNG viewers assume, rather
+    /// than writing values linearly.
+    pub srgb: bool,
+    /// Apply ordered dithering when rounding colors, so smooth gradients
+    /// don't show visible bands.
+    pub dither: bool,
+    /// Exposure and tone mapping, applied before the transfer curve.
+    pub output: OutputOptions,
+}
+
+impl Canvas {
+    /// Write the canvas as an 8-bit RGB PNG image, clamping colors to the
+    /// range 0.0 to 1.0 as for PPM.
+    pub fn write_png(&self, sink: &mut impl Write) -> Result<()> {
+        self.write_png_with_options(sink, &PngOptions::default())
+    }
+
+    pub fn write_png_with_options(
+        &self,
+        sink: &mut impl Write,
+        options: &PngOptions,
+    ) -> Result<()> {
+        sink.write_all(&SIGNATURE)?;
+
+        let mut header = vec![];
+        header.extend_from_slice(&(self.width as u32).to_be_bytes());
+        header.extend_from_slice(&(self.height as u32).to_be_bytes());
+        // 8 bits per channel, truecolor, default compression, filtering and
+        // no interlacing.
+        header.extend_from_slice(&[8, 2, 0, 0, 0]);
+        write_chunk(sink, b"IHDR", &header)?;
+
+        let canvas = self.for_output(&options.output);
+        // Each scanline starts with its filter type; we don't filter.
+        let mut raw = Vec::with_capacity((self.width * 3 + 1) * self.height);
+        for row in 0..self.height {
+            raw.push(0);
+            for col in 0..self.width {
+                let pixel = canvas.pixel_at(col, row);
+                let pixel = if options.srgb { pixel.to_srgb() } else { pixel };
+                let (r, g, b) = if options.dither {
+                    pixel.to_u8_dithered(col, row)
+                } else {
+                    pixel.to_u8()
+                };
+                raw.extend_from_slice(&[r, g, b]);
+            }
+        }
+        write_chunk(sink, b"IDAT", &zlib_stored(&raw))?;
+
+        write_chunk(sink, b"IEND", &[])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::color::Color;
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(crc32(b"123456789"), 0xcbf43926);
+        assert_eq!(crc32(b"IEND"), 0xae426082);
+    }
+
+    #[test]
+    fn test_adler32() {
+        assert_eq!(adler32(b"Wikipedia"), 0x11e60398);
+        assert_eq!(adler32(b""), 1);
+    }
+
+    #[test]
+    fn test_zlib_stored_splits_large_data() {
+        let data = vec![7; MAX_STORED_BLOCK + 10];
+        let z = zlib_stored(&data);
+        assert_eq!(&z[..2], &[0x78, 0x01]);
+        // A full block that isn't last...
+        assert_eq!(&z[2..7], &[0, 0xff, 0xff, 0, 0]);
+        // ...then the final short block.
+        let second = 7 + MAX_STORED_BLOCK;
+        assert_eq!(&z[second..second + 5], &[1, 10, 0, 0xf5, 0xff]);
+        assert_eq!(z.len(), 2 + 5 + MAX_STORED_BLOCK + 5 + 10 + 4);
+    }
+
+    #[test]
+    fn test_png_structure() {
+        let mut c = Canvas::new(2, 1);
+        c.write_pixel(0, 0, Color::new(1.0, 0.0, 0.2));
+        c.write_pixel(1, 0, Color::new(1.5, 0.5, -0.5));
+        let mut bytes = Vec::new();
+        c.write_png(&mut bytes).unwrap();
+
+        assert_eq!(&bytes[..8], &SIGNATURE);
+
+        // IHDR
+        assert_eq!(&bytes[8..16], &[0, 0, 0, 13, b'I', b'H', b'D', b'R']);
+        assert_eq!(&bytes[16..29], &[0, 0, 0, 2, 0, 0, 0, 1, 8, 2, 0, 0, 0]);
+        assert_eq!(&bytes[29..33], &crc32(&bytes[12..29]).to_be_bytes());
+
+        // IDAT: one filter byte and two pixels, stored uncompressed
+        let raw = [0, 255, 0, 51, 255, 128, 0];
+        assert_eq!(&bytes[33..41], &[0, 0, 0, 18, b'I', b'D', b'A', b'T']);
+        assert_eq!(&bytes[41..48], &[0x78, 0x01, 1, 7, 0, 0xf8, 0xff]);
+        assert_eq!(&bytes[48..55], &raw);
+        assert_eq!(&bytes[55..59], &adler32(&raw).to_be_bytes());
+
+        // IEND
+        assert_eq!(
+            &bytes[63..],
+            &[0, 0, 0, 0, b'I', b'E', b'N', b'D', 0xae, 0x42, 0x60, 0x82]
+        );
+    }
+
+    #[test]
+    fn test_png_srgb() {
+        let mut c = Canvas::new(2, 1);
+        c.write_pixel(0, 0, Color::new(0.5, 0.002, 1.0));
+        c.write_pixel(1, 0, Color::new(0.18, -0.5, 1.5));
+        let mut bytes = Vec::new();
+        c.write_png_with_options(
+            &mut bytes,
+            &PngOptions {
+                srgb: true,
+                ..PngOptions::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(&bytes[48..55], &[0, 188, 7, 255, 118, 0, 255]);
+    }
+
+    #[test]
+    fn test_png_dither() {
+        let mut c = Canvas::new(2, 1);
+        c.fill(Color::new(0.5, 0.0, 1.0));
+        let mut bytes = Vec::new();
+        let options = PngOptions {
+            dither: true,
+            ..PngOptions::default()
+        };
+        c.write_png_with_options(&mut bytes, &options).unwrap();
+        assert_eq!(&bytes[48..55], &[0, 127, 0, 255, 128, 0, 255]);
+    }
+
+    #[test]
+    fn test_png_exposure() {
+        let mut c = Canvas::new(1, 1);
+        c.write_pixel(0, 0, Color::new(2.0, 1.0, 0.0));
+        let mut bytes = Vec::new();
+        let options = PngOptions {
+            output: OutputOptions {
+                exposure: -1.0,
+                ..OutputOptions::default()
+            },
+            ..PngOptions::default()
+        };
+        c.write_png_with_options(&mut bytes, &options).unwrap();
+        assert_eq!(&bytes[48..52], &[0, 255, 128, 0]);
+    }
+}
@@ -1,43 +1,142 @@
 use crate::canvas::Canvas;
+use crate::color::{dither_offset, Color};
+use crate::tonemap::OutputOptions;
+use crate::Float;
 use std::fmt::Write as FormatWrite;
 use std::io::{prelude::*, Result};
 
-fn clamp_int(f: f64) -> u16 {
-    match (f * 255_f64).round() {
+/// Settings controlling how colors are converted when writing a PPM.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PpmOptions {
+    /// The display gamma to encode for. Each component is raised to the power
+    /// of `1.0 / gamma` before quantizing, so 1.0 writes values linearly and
+    /// 2.2 suits most monitors.
+    pub gamma: Float,
+    /// Encode with the standard sRGB transfer curve instead of `gamma`,
+    /// which is what most viewers expect.
+    pub srgb: bool,
+    /// Apply ordered dithering when rounding colors, so smooth gradients
+    /// don't show visible bands.
+    pub dither: bool,
+    /// Exposure and tone mapping, applied before the transfer curve.
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub output: OutputOptions,
+    /// The value written for full intensity: 255 for 8-bit images, or up to
+    /// 65535 to keep more precision for further grading. Must not be 0.
+    pub max_value: u16,
+    /// Write the compact binary form of the format, P6, rather than text.
+    /// Components take two bytes each when `max_value` is over 255.
+    pub binary: bool,
+}
+
+impl Default for PpmOptions {
+    fn default() -> Self {
+        Self {
+            gamma: 1.0,
+            srgb: false,
+            dither: false,
+            output: OutputOptions::default(),
+            max_value: 255,
+            binary: false,
+        }
+    }
+}
+
+/// Convert linear light to the values written, applying the transfer curve.
+fn encode(pixel: Color, options: &PpmOptions) -> Color {
+    if options.srgb {
+        pixel.to_srgb()
+    } else if options.gamma == 1.0 {
+        pixel
+    } else {
+        let encode = |f: Float| f.max(0.0).powf(1.0 / options.gamma);
+        Color::new(
+            encode(pixel.red()),
+            encode(pixel.green()),
+            encode(pixel.blue()),
+        )
+    }
+}
+
+/// Quantize `f` to the range 0 to `max`, first adding `dither`, between -0.5
+/// and 0.5.
+fn clamp_int(f: Float, dither: Float, max: u16) -> u16 {
+    match (f * max as Float + dither).round() {
         v if v < 0. => 0,
-        v if v > 255. => 255,
+        v if v > max as Float => max,
         v => v as u16,
     }
 }
 
+/// Write samples as text, wrapping lines at 70 characters.
+fn write_text_row(sink: &mut impl Write, samples: &[u16]) -> Result<()> {
+    let mut line = String::new();
+    for sample in samples {
+        let token = sample.to_string();
+        if line.len() + token.len() + 2 > 70 {
+            writeln!(sink, "{}", line)?;
+            line = String::new();
+        }
+        if line.is_empty() {
+            write!(line, "{}", token).unwrap();
+        } else {
+            write!(line, " {}", token).unwrap();
+        }
+    }
+    if !line.is_empty() {
+        writeln!(sink, "{}", line)?;
+    }
+    Ok(())
+}
+
+/// Write samples as bytes: one each if they fit, or two, most significant
+/// first.
+fn write_binary_row(sink: &mut impl Write, samples: &[u16], max_value: u16) -> Result<()> {
+    let bytes: Vec<u8> = if max_value <= 255 {
+        samples.iter().map(|&sample| sample as u8).collect()
+    } else {
+        samples
+            .iter()
+            .flat_map(|sample| sample.to_be_bytes())
+            .collect()
+    };
+    sink.write_all(&bytes)
+}
+
 impl Canvas {
     pub fn write_ppm(&self, sink: &mut impl Write) -> Result<()> {
-        writeln!(sink, "P3")?;
+        self.write_ppm_with_options(sink, &PpmOptions::default())
+    }
+
+    pub fn write_ppm_with_options(
+        &self,
+        sink: &mut impl Write,
+        options: &PpmOptions,
+    ) -> Result<()> {
+        assert!(options.max_value > 0, "PPM max value must not be 0");
+        writeln!(sink, "{}", if options.binary { "P6" } else { "P3" })?;
         writeln!(sink, "{} {}", self.width, self.height)?;
-        writeln!(sink, "255")?;
+        writeln!(sink, "{}", options.max_value)?;
 
+        let canvas = self.for_output(&options.output);
         for row in 0..self.height {
-            let mut tokens = vec![];
+            let mut samples = vec![];
             for col in 0..self.width {
-                let pixel = self.pixel_at(col, row);
-                tokens.push(clamp_int(pixel.red()).to_string());
-                tokens.push(clamp_int(pixel.green()).to_string());
-                tokens.push(clamp_int(pixel.blue()).to_string());
-            }
-            let mut line = String::new();
-            for token in tokens {
-                if line.len() + token.len() + 2 > 70 {
-                    writeln!(sink, "{}", line)?;
-                    line = String::new();
-                }
-                if line.is_empty() {
-                    write!(line, "{}", token).unwrap();
+                let pixel = encode(canvas.pixel_at(col, row), options);
+                let dither = if options.dither {
+                    dither_offset(col, row)
                 } else {
-                    write!(line, " {}", token).unwrap();
+                    0.0
+                };
+                for f in [pixel.red(), pixel.green(), pixel.blue()] {
+                    samples.push(clamp_int(f, dither, options.max_value));
                 }
             }
-            if !line.is_empty() {
-                writeln!(sink, "{}", line)?;
+            if options.binary {
+                write_binary_row(sink, &samples, options.max_value)?;
+            } else {
+                write_text_row(sink, &samples)?;
             }
         }
 
@@ -49,6 +148,8 @@ impl Canvas {
 mod test {
     use std::str::from_utf8;
 
+    use super::PpmOptions;
+    use crate::tonemap::{OutputOptions, ToneMap};
     use crate::{canvas::Canvas, color::Color};
 
     #[test]
@@ -124,7 +225,141 @@ mod test {
         let canvas = Canvas::new(5, 3);
         let mut bytes = Vec::new();
         canvas.write_ppm(&mut bytes).unwrap();
-        let last_char = from_utf8(&bytes).unwrap().chars().rev().next();
+        let last_char = from_utf8(&bytes).unwrap().chars().next_back();
         assert_eq!(last_char, Some('\n'));
     }
+
+    #[test]
+    fn test_ppm_gamma() {
+        let mut c = Canvas::new(3, 1);
+        c.write_pixel(0, 0, Color::new(0.5, 0.25, 1.0));
+        c.write_pixel(1, 0, Color::new(0.0, -0.5, 1.5));
+        c.write_pixel(2, 0, Color::new(0.18, 0.18, 0.18));
+
+        let mut bytes = Vec::new();
+        c.write_ppm_with_options(
+            &mut bytes,
+            &PpmOptions {
+                gamma: 2.2,
+                ..PpmOptions::default()
+            },
+        )
+        .unwrap();
+        let lines: Vec<_> = from_utf8(&bytes).unwrap().lines().collect();
+        assert_eq!(lines[3], "186 136 255 0 0 255 117 117 117");
+    }
+
+    #[test]
+    fn test_ppm_srgb() {
+        let mut c = Canvas::new(2, 1);
+        c.write_pixel(0, 0, Color::new(0.5, 0.002, 1.0));
+        c.write_pixel(1, 0, Color::new(0.18, -0.5, 1.5));
+
+        let mut bytes = Vec::new();
+        let options = PpmOptions {
+            gamma: 2.2,
+            srgb: true,
+            ..PpmOptions::default()
+        };
+        c.write_ppm_with_options(&mut bytes, &options).unwrap();
+        let lines: Vec<_> = from_utf8(&bytes).unwrap().lines().collect();
+        assert_eq!(lines[3], "188 7 255 118 0 255");
+    }
+
+    #[test]
+    fn test_ppm_dither() {
+        let mut c = Canvas::new(2, 1);
+        c.fill(Color::new(0.5, 0.0, 1.0));
+
+        let mut bytes = Vec::new();
+        let options = PpmOptions {
+            dither: true,
+            ..PpmOptions::default()
+        };
+        c.write_ppm_with_options(&mut bytes, &options).unwrap();
+        let lines: Vec<_> = from_utf8(&bytes).unwrap().lines().collect();
+        assert_eq!(lines[3], "127 0 255 128 0 255");
+    }
+
+    #[test]
+    fn test_ppm_16_bit() {
+        let mut c = Canvas::new(2, 1);
+        c.write_pixel(0, 0, Color::new(0.5, 0.0, 1.0));
+        c.write_pixel(1, 0, Color::new(0.001, 1.5, -0.5));
+
+        let mut bytes = Vec::new();
+        let options = PpmOptions {
+            max_value: 65535,
+            ..PpmOptions::default()
+        };
+        c.write_ppm_with_options(&mut bytes, &options).unwrap();
+        let lines: Vec<_> = from_utf8(&bytes).unwrap().lines().collect();
+        assert_eq!(lines[2], "65535");
+        assert_eq!(lines[3], "32768 0 65535 66 65535 0");
+    }
+
+    #[test]
+    fn test_ppm_binary() {
+        let mut c = Canvas::new(2, 1);
+        c.write_pixel(0, 0, Color::new(0.5, 0.0, 1.0));
+        c.write_pixel(1, 0, Color::new(0.2, 1.5, -0.5));
+
+        let mut bytes = Vec::new();
+        let options = PpmOptions {
+            binary: true,
+            ..PpmOptions::default()
+        };
+        c.write_ppm_with_options(&mut bytes, &options).unwrap();
+        assert_eq!(&bytes[..11], b"P6\n2 1\n255\n");
+        assert_eq!(&bytes[11..], &[128, 0, 255, 51, 255, 0]);
+
+        let mut bytes = Vec::new();
+        let options = PpmOptions {
+            binary: true,
+            max_value: 65535,
+            ..PpmOptions::default()
+        };
+        c.write_ppm_with_options(&mut bytes, &options).unwrap();
+        assert_eq!(&bytes[..13], b"P6\n2 1\n65535\n");
+        assert_eq!(
+            &bytes[13..],
+            &[0x80, 0x00, 0, 0, 0xff, 0xff, 0x33, 0x33, 0xff, 0xff, 0, 0]
+        );
+    }
+
+    #[test]
+    fn test_ppm_exposure_and_tone_mapping() {
+        let mut c = Canvas::new(2, 1);
+        c.write_pixel(0, 0, Color::new(0.25, 0.5, 1.0));
+        c.write_pixel(1, 0, Color::new(3.0, 1.0, 0.0));
+
+        let mut bytes = Vec::new();
+        let options = PpmOptions {
+            output: OutputOptions {
+                exposure: 1.0,
+                tone_map: ToneMap::Reinhard,
+            },
+            ..PpmOptions::default()
+        };
+        c.write_ppm_with_options(&mut bytes, &options).unwrap();
+        let lines: Vec<_> = from_utf8(&bytes).unwrap().lines().collect();
+        assert_eq!(lines[3], "85 128 170 219 170 0");
+    }
+
+    #[test]
+    fn test_ppm_default_gamma_is_linear() {
+        let mut c = Canvas::new(1, 1);
+        c.write_pixel(0, 0, Color::new(0.5, 0.25, 1.0));
+
+        let mut linear = Vec::new();
+        c.write_ppm(&mut linear).unwrap();
+        let mut gamma = Vec::new();
+        c.write_ppm_with_options(&mut gamma, &PpmOptions::default())
+            .unwrap();
+        assert_eq!(linear, gamma);
+        assert_eq!(
+            from_utf8(&linear).unwrap().lines().nth(3),
+            Some("128 64 255")
+        );
+    }
 }
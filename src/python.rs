@@ -0,0 +1,496 @@
+//! Python bindings, so scenes can be built and rendered from Python scripts
+//! and notebooks. Colors, points and vectors are passed as `(x, y, z)`
+//! tuples.
+//!
+//! ```python
+//! from math import pi
+//! from ray_tracer_challenge_2 import Camera, Material, Matrix, PointLight, Shape, World, render
+//!
+//! world = World()
+//! world.set_light(PointLight((-10, 10, -10), (1, 1, 1)))
+//! world.add(Shape.sphere(material=Material(color=(1, 0.2, 1))))
+//! camera = Camera(100, 50, pi / 3, Matrix.view_transform((0, 1.5, -5), (0, 1, 0), (0, 1, 0)))
+//! render(camera, world).save("sphere.png")
+//! ```
+
+// The code pyo3 generates for methods returning `PyResult` trips this lint.
+#![allow(clippy::useless_conversion)]
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+
+use crate::camera::{Camera, RenderOptions};
+use crate::canvas::Canvas;
+use crate::color::Color;
+use crate::lighting::PointLight;
+use crate::materials::Material;
+use crate::matrix4::Matrix4;
+use crate::shape::{Group, Quad, Shape, Sphere, Triangle};
+use crate::space::{Point, Vector};
+use crate::world::{Background, World};
+use crate::Float;
+
+type Triple = (Float, Float, Float);
+
+fn color((r, g, b): Triple) -> Color {
+    Color::new(r, g, b)
+}
+
+fn point((x, y, z): Triple) -> Point {
+    Point::new(x, y, z)
+}
+
+fn vector((x, y, z): Triple) -> Vector {
+    Vector::new(x, y, z)
+}
+
+/// A 4x4 transformation matrix. Combine matrices with `@`, applying the
+/// right-hand one first.
+#[pyclass(name = "Matrix", module = "ray_tracer_challenge_2", frozen)]
+#[derive(Clone)]
+struct PyMatrix(Matrix4);
+
+#[pymethods]
+impl PyMatrix {
+    #[staticmethod]
+    fn identity() -> Self {
+        Self(Matrix4::identity())
+    }
+
+    #[staticmethod]
+    fn translation(x: Float, y: Float, z: Float) -> Self {
+        Self(Matrix4::translation(x, y, z))
+    }
+
+    #[staticmethod]
+    fn scaling(x: Float, y: Float, z: Float) -> Self {
+        Self(Matrix4::scaling(x, y, z))
+    }
+
+    #[staticmethod]
+    fn rotation_x(radians: Float) -> Self {
+        Self(Matrix4::rotation_x(radians))
+    }
+
+    #[staticmethod]
+    fn rotation_y(radians: Float) -> Self {
+        Self(Matrix4::rotation_y(radians))
+    }
+
+    #[staticmethod]
+    fn rotation_z(radians: Float) -> Self {
+        Self(Matrix4::rotation_z(radians))
+    }
+
+    #[staticmethod]
+    fn view_transform(from: Triple, to: Triple, up: Triple) -> Self {
+        Self(Matrix4::view_transform(point(from), point(to), vector(up)))
+    }
+
+    fn __matmul__(&self, other: &Self) -> Self {
+        Self(self.0 * other.0)
+    }
+
+    fn __repr__(&self) -> String {
+        format!("{:?}", self.0)
+    }
+}
+
+/// The transformation to give a shape, which must be invertible.
+fn shape_transform(transform: Option<&PyMatrix>) -> PyResult<Matrix4> {
+    let transform = transform.map_or_else(Matrix4::identity, |m| m.0);
+    match transform.inverse() {
+        Ok(_) => Ok(transform),
+        Err(_) => Err(PyValueError::new_err(
+            "shape transformation must be invertible",
+        )),
+    }
+}
+
+/// A surface material. Any property left out takes the default from
+/// `Material::new`.
+#[pyclass(name = "Material", module = "ray_tracer_challenge_2", get_all, set_all)]
+#[derive(Clone)]
+struct PyMaterial {
+    color: Triple,
+    ambient: Float,
+    diffuse: Float,
+    specular: Float,
+    shininess: Float,
+    reflective: Float,
+    transparency: Float,
+    refractive_index: Float,
+}
+
+#[pymethods]
+impl PyMaterial {
+    #[new]
+    #[pyo3(signature = (
+        color=None,
+        ambient=None,
+        diffuse=None,
+        specular=None,
+        shininess=None,
+        reflective=None,
+        transparency=None,
+        refractive_index=None,
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        color: Option<Triple>,
+        ambient: Option<Float>,
+        diffuse: Option<Float>,
+        specular: Option<Float>,
+        shininess: Option<Float>,
+        reflective: Option<Float>,
+        transparency: Option<Float>,
+        refractive_index: Option<Float>,
+    ) -> Self {
+        let defaults = Material::new();
+        let c = defaults.color;
+        Self {
+            color: color.unwrap_or((c.red(), c.green(), c.blue())),
+            ambient: ambient.unwrap_or(defaults.ambient),
+            diffuse: diffuse.unwrap_or(defaults.diffuse),
+            specular: specular.unwrap_or(defaults.specular),
+            shininess: shininess.unwrap_or(defaults.shininess),
+            reflective: reflective.unwrap_or(defaults.reflective),
+            transparency: transparency.unwrap_or(defaults.transparency),
+            refractive_index: refractive_index.unwrap_or(defaults.refractive_index),
+        }
+    }
+}
+
+impl From<&PyMaterial> for Material {
+    fn from(value: &PyMaterial) -> Self {
+        Self {
+            color: color(value.color),
+            ambient: value.ambient,
+            diffuse: value.diffuse,
+            specular: value.specular,
+            shininess: value.shininess,
+            reflective: value.reflective,
+            transparency: value.transparency,
+            refractive_index: value.refractive_index,
+            ..Material::new()
+        }
+    }
+}
+
+/// A shape to add to a world, made with one of the static constructors.
+/// Adding a shape to a world or group copies it.
+#[pyclass(name = "Shape", module = "ray_tracer_challenge_2")]
+#[derive(Clone)]
+struct PyShape(Shape);
+
+impl PyShape {
+    fn new(shape: impl Into<Shape>, material: Option<&PyMaterial>, name: Option<String>) -> Self {
+        let mut shape = shape.into();
+        if let Some(material) = material {
+            *shape.material_mut() = material.into();
+        }
+        if let Some(name) = name {
+            shape.set_name(name);
+        }
+        Self(shape)
+    }
+}
+
+#[pymethods]
+impl PyShape {
+    /// A unit sphere at the origin, before `transform` is applied.
+    #[staticmethod]
+    #[pyo3(signature = (transform=None, material=None, name=None))]
+    fn sphere(
+        transform: Option<&PyMatrix>,
+        material: Option<&PyMaterial>,
+        name: Option<String>,
+    ) -> PyResult<Self> {
+        let sphere = Sphere::with_transform(shape_transform(transform)?);
+        Ok(Self::new(sphere, material, name))
+    }
+
+    #[staticmethod]
+    #[pyo3(signature = (p1, p2, p3, transform=None, material=None, name=None))]
+    fn triangle(
+        p1: Triple,
+        p2: Triple,
+        p3: Triple,
+        transform: Option<&PyMatrix>,
+        material: Option<&PyMaterial>,
+        name: Option<String>,
+    ) -> PyResult<Self> {
+        let mut triangle = Triangle::new(point(p1), point(p2), point(p3));
+        triangle.set_transform(shape_transform(transform)?);
+        Ok(Self::new(triangle, material, name))
+    }
+
+    /// A parallelogram with one corner at `corner` and sides along the edge
+    /// vectors `u` and `v`, which mustn't be parallel.
+    #[staticmethod]
+    #[pyo3(signature = (corner, u, v, transform=None, material=None, name=None))]
+    fn quad(
+        corner: Triple,
+        u: Triple,
+        v: Triple,
+        transform: Option<&PyMatrix>,
+        material: Option<&PyMaterial>,
+        name: Option<String>,
+    ) -> PyResult<Self> {
+        let mut quad = Quad::try_new(point(corner), vector(u), vector(v))
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+        quad.set_transform(shape_transform(transform)?);
+        Ok(Self::new(quad, material, name))
+    }
+
+    /// A group of shapes transformed together.
+    #[staticmethod]
+    #[pyo3(signature = (children, transform=None, name=None))]
+    fn group(
+        children: Vec<PyRef<'_, PyShape>>,
+        transform: Option<&PyMatrix>,
+        name: Option<String>,
+    ) -> PyResult<Self> {
+        let mut group = Group::with_transform(shape_transform(transform)?);
+        for child in children {
+            group.add_child(child.0.clone());
+        }
+        Ok(Self::new(group, None, name))
+    }
+
+    #[getter]
+    fn name(&self) -> Option<&str> {
+        self.0.name()
+    }
+
+    /// Which lights shine on the shape, one bit per light id.
+    #[getter]
+    fn light_mask(&self) -> u64 {
+        self.0.light_mask()
+    }
+
+    #[setter]
+    fn set_light_mask(&mut self, mask: u64) {
+        self.0.set_light_mask(mask);
+    }
+
+    fn __repr__(&self) -> String {
+        format!("{:?}", self.0)
+    }
+}
+
+#[pyclass(name = "PointLight", module = "ray_tracer_challenge_2", frozen)]
+#[derive(Clone)]
+struct PyPointLight(PointLight);
+
+#[pymethods]
+impl PyPointLight {
+    #[new]
+    fn new(position: Triple, intensity: Triple) -> Self {
+        Self(PointLight::new(point(position), color(intensity)))
+    }
+}
+
+#[pyclass(name = "World", module = "ray_tracer_challenge_2")]
+struct PyWorld(World);
+
+#[pymethods]
+impl PyWorld {
+    #[new]
+    fn new() -> Self {
+        Self(World::new())
+    }
+
+    fn set_light(&mut self, light: &PyPointLight) {
+        self.0.set_light(light.0.clone());
+    }
+
+    /// Add another light, returning its id for shapes' `light_mask`.
+    fn add_light(&mut self, light: &PyPointLight) -> usize {
+        self.0.add_light(light.0.clone())
+    }
+
+    fn set_background(&mut self, background: Triple) {
+        self.0.set_background(color(background));
+    }
+
+    /// Blend the background from `bottom`, looking down, to `top`, looking
+    /// up.
+    fn set_background_gradient(&mut self, bottom: Triple, top: Triple) {
+        self.0.set_background(Background::Gradient {
+            bottom: color(bottom),
+            top: color(top),
+        });
+    }
+
+    fn add(&mut self, shape: &PyShape) {
+        self.0.add_object(shape.0.clone());
+    }
+
+    /// A copy of the first shape named `name`, including those in groups.
+    fn find(&self, name: &str) -> Option<PyShape> {
+        self.0.find_object(name).cloned().map(PyShape)
+    }
+}
+
+#[pyclass(name = "Camera", module = "ray_tracer_challenge_2")]
+struct PyCamera(Camera);
+
+#[pymethods]
+impl PyCamera {
+    #[new]
+    #[pyo3(signature = (hsize, vsize, field_of_view, transform=None))]
+    fn new(hsize: usize, vsize: usize, field_of_view: Float, transform: Option<&PyMatrix>) -> Self {
+        let mut camera = Camera::new(hsize, vsize, field_of_view);
+        if let Some(transform) = transform {
+            camera.set_transform(transform.0);
+        }
+        Self(camera)
+    }
+
+    #[getter]
+    fn hsize(&self) -> usize {
+        self.0.hsize()
+    }
+
+    #[getter]
+    fn vsize(&self) -> usize {
+        self.0.vsize()
+    }
+
+    /// Trace `samples` rays through each pixel and average them.
+    #[getter]
+    fn samples(&self) -> usize {
+        self.0.samples()
+    }
+
+    #[setter]
+    fn set_samples(&mut self, samples: usize) {
+        self.0.set_samples(samples);
+    }
+
+    #[pyo3(signature = (world, threads=None))]
+    fn render(&self, py: Python<'_>, world: &PyWorld, threads: Option<usize>) -> PyCanvas {
+        render(py, self, world, threads)
+    }
+}
+
+#[pyclass(name = "Canvas", module = "ray_tracer_challenge_2")]
+struct PyCanvas(Canvas);
+
+#[pymethods]
+impl PyCanvas {
+    #[getter]
+    fn width(&self) -> usize {
+        self.0.width
+    }
+
+    #[getter]
+    fn height(&self) -> usize {
+        self.0.height
+    }
+
+    fn pixel_at(&self, x: usize, y: usize) -> PyResult<Triple> {
+        if x >= self.0.width || y >= self.0.height {
+            return Err(PyValueError::new_err("pixel is outside the canvas"));
+        }
+        let pixel = self.0.pixel_at(x, y);
+        Ok((pixel.red(), pixel.green(), pixel.blue()))
+    }
+
+    /// The pixels as RGBA bytes, ready for `PIL.Image.frombytes("RGBA",
+    /// (width, height), data)` or a numpy array.
+    fn to_rgba8<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new_bound(py, &self.0.to_rgba8())
+    }
+
+    /// Write the image to `path`, in the format given by its extension.
+    fn save(&self, path: &str) -> PyResult<()> {
+        Ok(self.0.save(path)?)
+    }
+}
+
+/// Render `world` as seen by `camera`, without holding the GIL so other
+/// Python threads can run meanwhile. `threads` limits how many threads trace
+/// rays; by default there's one per CPU.
+#[pyfunction]
+#[pyo3(signature = (camera, world, threads=None))]
+fn render(py: Python<'_>, camera: &PyCamera, world: &PyWorld, threads: Option<usize>) -> PyCanvas {
+    let (camera, world) = (&camera.0, &world.0);
+    let options = RenderOptions { threads };
+    PyCanvas(py.allow_threads(|| camera.render_with_options(world, &options)))
+}
+
+#[pymodule]
+fn ray_tracer_challenge_2(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyMatrix>()?;
+    m.add_class::<PyMaterial>()?;
+    m.add_class::<PyShape>()?;
+    m.add_class::<PyPointLight>()?;
+    m.add_class::<PyWorld>()?;
+    m.add_class::<PyCamera>()?;
+    m.add_class::<PyCanvas>()?;
+    m.add_function(wrap_pyfunction!(render, m)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::consts::PI;
+
+    use super::*;
+
+    fn run(script: &str) -> Py<PyAny> {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let module = PyModule::new_bound(py, "ray_tracer_challenge_2").unwrap();
+            ray_tracer_challenge_2(&module).unwrap();
+            let globals = module.dict();
+            py.run_bound(script, Some(&globals), None).unwrap();
+            globals.get_item("result").unwrap().unwrap().unbind()
+        })
+    }
+
+    #[test]
+    fn test_render_from_python() {
+        let result = run(&format!(
+            "world = World()\n\
+             world.set_light(PointLight((-10, 10, -10), (1, 1, 1)))\n\
+             world.add(Shape.sphere(material=Material(color=(0.8, 1.0, 0.6), diffuse=0.7, specular=0.2)))\n\
+             world.add(Shape.sphere(Matrix.scaling(0.5, 0.5, 0.5)))\n\
+             camera = Camera(11, 11, {}, Matrix.view_transform((0, 0, -5), (0, 0, 0), (0, 1, 0)))\n\
+             image = render(camera, world)\n\
+             result = (image.width, len(image.to_rgba8()), image.pixel_at(5, 5))",
+            PI / 2.0
+        ));
+        Python::with_gil(|py| {
+            let (width, bytes, pixel): (usize, usize, Triple) = result.extract(py).unwrap();
+            assert_eq!(width, 11);
+            assert_eq!(bytes, 11 * 11 * 4);
+            assert_eq!(color(pixel), Color::new(0.38066, 0.47583, 0.2855));
+        });
+    }
+
+    #[test]
+    fn test_named_group_from_python() {
+        let result = run("group = Shape.group([Shape.sphere(name='ball')], Matrix.translation(1, 0, 0), name='outer')\n\
+             world = World()\n\
+             world.add(group)\n\
+             result = (group.name, world.find('ball').name, world.find('missing'))");
+        Python::with_gil(|py| {
+            let names: (String, String, Option<String>) = result.extract(py).unwrap();
+            assert_eq!(names, ("outer".to_string(), "ball".to_string(), None));
+        });
+    }
+
+    #[test]
+    fn test_singular_transform_from_python() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let result =
+                PyShape::sphere(Some(&PyMatrix(Matrix4::scaling(0.0, 1.0, 1.0))), None, None);
+            assert!(result.err().unwrap().is_instance_of::<PyValueError>(py));
+        });
+    }
+}
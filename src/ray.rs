@@ -1,33 +1,93 @@
 use std::cmp::Ordering;
-use std::collections::BinaryHeap;
+use std::ops::Index;
 
-use crate::matrix::Matrix;
+use crate::matrix4::Matrix4;
 use crate::shape::Shape;
 use crate::space::{Point, Vector};
+use crate::Float;
+use crate::EPSILON;
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Ray {
     pub origin: Point,
     pub direction: Vector,
+    /// When the ray was cast, within the camera's shutter interval. Moving
+    /// shapes are intersected where they were at this time.
+    pub time: Float,
 }
 
 impl Ray {
     pub fn new(origin: Point, direction: Vector) -> Self {
-        Self { origin, direction }
+        Self::with_time(origin, direction, 0.0)
     }
 
-    pub fn position(&self, d: f64) -> Point {
+    pub fn with_time(origin: Point, direction: Vector, time: Float) -> Self {
+        Self {
+            origin,
+            direction,
+            time,
+        }
+    }
+
+    pub fn position(&self, d: Float) -> Point {
         self.origin + self.direction * d
     }
 
-    pub fn transform(&self, matrix: &Matrix) -> Ray {
-        Ray::new((matrix * (*self.origin)).into(), matrix * self.direction)
+    pub fn transform(&self, matrix: &Matrix4) -> Ray {
+        Ray::with_time(
+            (matrix * (*self.origin)).into(),
+            matrix * self.direction,
+            self.time,
+        )
+    }
+}
+
+/// The distances along a ray at which it enters and leaves the slab between
+/// `min` and `max` on a single axis, given the ray's origin and direction on
+/// that axis. A ray parallel to the slab is either always inside it or
+/// never, giving infinite distances. Only a direction of exactly zero is
+/// parallel: a tiny one still crosses the slab, just a long way off.
+pub fn check_axis(origin: Float, direction: Float, min: Float, max: Float) -> (Float, Float) {
+    let tmin_numerator = min - origin;
+    let tmax_numerator = max - origin;
+
+    let (tmin, tmax) = if direction != 0.0 {
+        (tmin_numerator / direction, tmax_numerator / direction)
+    } else {
+        (
+            tmin_numerator * Float::INFINITY,
+            tmax_numerator * Float::INFINITY,
+        )
+    };
+
+    if tmin > tmax {
+        (tmax, tmin)
+    } else {
+        (tmin, tmax)
+    }
+}
+
+/// The distances at which `ray` enters and leaves the axis-aligned box from
+/// `min` to `max`, if it passes through at all.
+pub fn intersect_box(ray: &Ray, min: &Point, max: &Point) -> Option<(Float, Float)> {
+    let mut tmin = Float::NEG_INFINITY;
+    let mut tmax = Float::INFINITY;
+    for i in 0..3 {
+        let (axis_min, axis_max) = check_axis(
+            ray.origin.get(i),
+            ray.direction.get(i),
+            min.get(i),
+            max.get(i),
+        );
+        tmin = tmin.max(axis_min);
+        tmax = tmax.min(axis_max);
     }
+    (tmin <= tmax).then_some((tmin, tmax))
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Intersection<'a> {
-    pub t: f64,
+    pub t: Float,
     pub shape: &'a Shape,
 }
 
@@ -38,50 +98,187 @@ impl<'a> PartialOrd for Intersection<'a> {
 }
 
 impl<'a> Intersection<'a> {
-    pub fn new(t: f64, shape: &'a Shape) -> Self {
+    pub fn new(t: Float, shape: &'a Shape) -> Self {
         Self { t, shape }
     }
+
+    /// Whether `ray` enters the shape here, travelling against its outward
+    /// normal, rather than leaving it. A ray grazing the surface does
+    /// neither, and counts as leaving.
+    pub fn is_entering(&self, ray: &Ray) -> bool {
+        let normal = self.shape.normal_at_time(&ray.position(self.t), ray.time);
+        ray.direction.dot(&normal) < 0.0
+    }
+
+    pub fn prepare_computations(&self, ray: &Ray, xs: &Intersections<'a>) -> Computations<'a> {
+        let point = ray.position(self.t);
+        let eyev = -ray.direction;
+        let mut normalv = self.shape.normal_at_time(&point, ray.time);
+        let entering = ray.direction.dot(&normalv) < 0.0;
+        let inside = normalv.dot(&eyev) < 0.0;
+        if inside {
+            normalv = -normalv;
+        }
+        let reflectv = ray.direction.reflect(&normalv);
+
+        // Walk the intersections in order, tracking which objects the ray is
+        // currently inside, to find the refractive indices either side of
+        // this hit.
+        let mut n1 = 1.0;
+        let mut n2 = 1.0;
+        let mut containers: Vec<&Shape> = vec![];
+        for i in xs {
+            let is_hit = i.t == self.t && std::ptr::eq(i.shape, self.shape);
+            if is_hit {
+                n1 = containers
+                    .last()
+                    .map_or(1.0, |s| s.material().refractive_index);
+            }
+
+            match containers.iter().position(|&s| std::ptr::eq(s, i.shape)) {
+                Some(index) => {
+                    containers.remove(index);
+                }
+                None => containers.push(i.shape),
+            }
+
+            if is_hit {
+                n2 = containers
+                    .last()
+                    .map_or(1.0, |s| s.material().refractive_index);
+                break;
+            }
+        }
+
+        Computations {
+            t: self.t,
+            shape: self.shape,
+            point,
+            over_point: point + normalv * EPSILON,
+            under_point: point - normalv * EPSILON,
+            eyev,
+            normalv,
+            reflectv,
+            inside,
+            entering,
+            n1,
+            n2,
+            time: ray.time,
+        }
+    }
+}
+
+/// The precomputed state of a ray hitting a shape, used for shading.
+#[derive(Debug, Clone)]
+pub struct Computations<'a> {
+    pub t: Float,
+    pub shape: &'a Shape,
+    pub point: Point,
+    pub over_point: Point,
+    pub under_point: Point,
+    pub eyev: Vector,
+    pub normalv: Vector,
+    pub reflectv: Vector,
+    pub inside: bool,
+    /// Whether the ray enters the shape here, as for
+    /// [`Intersection::is_entering`].
+    pub entering: bool,
+    pub n1: Float,
+    pub n2: Float,
+    pub time: Float,
+}
+
+impl<'a> Computations<'a> {
+    /// Schlick's approximation of the Fresnel equations: the fraction of light
+    /// reflected (rather than refracted) at this hit.
+    pub fn schlick(&self) -> Float {
+        let mut cos = self.eyev.dot(&self.normalv);
+
+        if self.n1 > self.n2 {
+            let n = self.n1 / self.n2;
+            let sin2_t = n * n * (1.0 - cos * cos);
+            if sin2_t > 1.0 {
+                // Total internal reflection
+                return 1.0;
+            }
+            cos = (1.0 - sin2_t).sqrt();
+        }
+
+        let r0 = ((self.n1 - self.n2) / (self.n1 + self.n2)).powi(2);
+        r0 + (1.0 - r0) * (1.0 - cos).powi(5)
+    }
 }
 
 impl<'a> Eq for Intersection<'a> {}
 
 impl<'a> Ord for Intersection<'a> {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        match self.t.partial_cmp(&other.t).unwrap() {
-            Ordering::Greater => Ordering::Less,
-            Ordering::Less => Ordering::Greater,
-            Ordering::Equal => Ordering::Equal,
-        }
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.t.total_cmp(&other.t)
     }
 }
 
+/// A collection of intersections, kept sorted by increasing `t`.
 #[derive(Debug, Default, Clone)]
 pub struct Intersections<'a> {
-    items: BinaryHeap<Intersection<'a>>,
+    items: Vec<Intersection<'a>>,
 }
 
-//impl<'a> IntoIterator for Intersections<'a> {
 impl<'a> IntoIterator for Intersections<'a> {
     type Item = Intersection<'a>;
-    type IntoIter = std::collections::binary_heap::IntoIter<Self::Item>;
+    type IntoIter = std::vec::IntoIter<Self::Item>;
     fn into_iter(self) -> Self::IntoIter {
         self.items.into_iter()
     }
 }
 
+impl<'a, 'b> IntoIterator for &'b Intersections<'a> {
+    type Item = &'b Intersection<'a>;
+    type IntoIter = std::slice::Iter<'b, Intersection<'a>>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.iter()
+    }
+}
+
+impl<'a> Index<usize> for Intersections<'a> {
+    type Output = Intersection<'a>;
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.items[index]
+    }
+}
+
 impl<'a> Intersections<'a> {
     pub fn new() -> Self {
-        Self {
-            items: BinaryHeap::new(),
-        }
+        Self { items: vec![] }
     }
 
+    /// Add an intersection, keeping the collection in order. Intersections
+    /// with equal `t` stay in the order they were added.
     pub fn add(&mut self, i: Intersection<'a>) {
-        self.items.push(i);
+        let index = self.items.partition_point(|x| x.t <= i.t);
+        self.items.insert(index, i);
     }
 
+    /// The visible intersection: the one with the lowest non-negative `t`.
     pub fn hit(&self) -> Option<&Intersection<'a>> {
-        self.items.iter().find(|&i| i.t.is_sign_positive())
+        self.hit_where(|_| true)
+    }
+
+    /// The visible intersection out of those `predicate` accepts, such as
+    /// with shapes that cast shadows.
+    pub fn hit_where(
+        &self,
+        mut predicate: impl FnMut(&Intersection<'a>) -> bool,
+    ) -> Option<&Intersection<'a>> {
+        self.items
+            .iter()
+            .find(|&i| i.t.is_sign_positive() && predicate(i))
+    }
+
+    /// The visible intersection with anything but `shape`, such as the
+    /// object a ray starts from. Shapes are told apart by address, so an
+    /// identical copy of `shape` can still be hit.
+    pub fn hit_excluding(&self, shape: &Shape) -> Option<&Intersection<'a>> {
+        self.hit_where(|i| !std::ptr::eq(i.shape, shape))
     }
 
     pub fn len(&self) -> usize {
@@ -91,14 +288,67 @@ impl<'a> Intersections<'a> {
     pub fn is_empty(&self) -> bool {
         self.items.is_empty()
     }
+
+    /// Remove all the intersections, keeping the allocated space so the
+    /// collection can be reused for another ray.
+    pub fn clear(&mut self) {
+        self.items.clear();
+    }
+
+    /// The intersections in order of increasing `t`.
+    pub fn iter(&self) -> std::slice::Iter<'_, Intersection<'a>> {
+        self.items.iter()
+    }
+
+    /// The intersection at `index` in order of `t`, if there are that many.
+    pub fn get(&self, index: usize) -> Option<&Intersection<'a>> {
+        self.items.get(index)
+    }
+
+    /// The intersection with the lowest `t`, even if it's negative.
+    pub fn first(&self) -> Option<&Intersection<'a>> {
+        self.items.first()
+    }
+
+    /// The intersection with the highest `t`.
+    pub fn last(&self) -> Option<&Intersection<'a>> {
+        self.items.last()
+    }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::shape::{Shape, Sphere};
+    use crate::shape::{glass_sphere, Shape, Sphere};
 
     use super::*;
 
+    #[test]
+    fn test_check_axis() {
+        assert_eq!(check_axis(-5.0, 1.0, -1.0, 1.0), (4.0, 6.0));
+        assert_eq!(check_axis(5.0, -2.0, -1.0, 1.0), (2.0, 3.0));
+        assert_eq!(
+            check_axis(0.5, 0.0, -1.0, 1.0),
+            (Float::NEG_INFINITY, Float::INFINITY)
+        );
+        assert_eq!(
+            check_axis(2.0, 0.0, -1.0, 1.0),
+            (Float::NEG_INFINITY, Float::NEG_INFINITY)
+        );
+        let (tmin, tmax) = check_axis(2.0, 1e-6, -1.0, 1.0);
+        assert!(tmin.is_finite() && tmax.is_finite() && tmin < tmax);
+    }
+
+    #[test]
+    fn test_intersect_box() {
+        let (min, max) = (Point::new(-1.0, -1.0, -1.0), Point::new(1.0, 1.0, 1.0));
+        let r = Ray::new(Point::new(5.0, 0.5, 0.0), Vector::new(-1.0, 0.0, 0.0));
+        assert_eq!(intersect_box(&r, &min, &max), Some((4.0, 6.0)));
+        let r = Ray::new(Point::new(0.0, 0.5, 0.0), Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(intersect_box(&r, &min, &max), Some((-1.0, 1.0)));
+        let r = Ray::new(Point::new(2.0, 2.0, 0.0), Vector::new(-1.0, 0.0, 0.0));
+        assert_eq!(intersect_box(&r, &min, &max), None);
+    }
+
     #[test]
     fn test_ray_construction() {
         let p = Point::new(1., 2., 3.);
@@ -107,6 +357,7 @@ mod test {
         let r = Ray::new(p, v);
         assert_eq!(r.origin, Point::new(1., 2., 3.));
         assert_eq!(r.direction, Vector::new(4., 5., 6.));
+        assert_eq!(r.time, 0.0);
     }
 
     #[test]
@@ -165,6 +416,46 @@ mod test {
         assert_eq!(xs.hit(), None);
     }
 
+    #[test]
+    fn test_intersections_sorted() {
+        let s: Shape = Sphere::new().into();
+        let mut xs = Intersections::new();
+        for t in [3.0, -1.0, 7.0, 0.5, 2.0, -4.0, 6.0, 1.0] {
+            xs.add(Intersection::new(t, &s));
+        }
+
+        let ts: Vec<Float> = xs.iter().map(|i| i.t).collect();
+        assert_eq!(ts, vec![-4.0, -1.0, 0.5, 1.0, 2.0, 3.0, 6.0, 7.0]);
+        assert_eq!(xs[0].t, -4.0);
+        assert_eq!(xs[7].t, 7.0);
+        assert_eq!(xs.hit().unwrap().t, 0.5);
+
+        // Iterating by reference leaves the collection intact.
+        assert_eq!((&xs).into_iter().count(), 8);
+        assert_eq!(xs.len(), 8);
+
+        assert_eq!(xs.get(2).unwrap().t, 0.5);
+        assert_eq!(xs.get(8), None);
+        assert_eq!(xs.first().unwrap().t, -4.0);
+        assert_eq!(xs.last().unwrap().t, 7.0);
+        assert_eq!(Intersections::new().first(), None);
+    }
+
+    #[test]
+    fn test_filtered_hits() {
+        let a: Shape = Sphere::new().into();
+        let b: Shape = Sphere::new().into();
+        let mut xs = Intersections::new();
+        xs.add(Intersection::new(-1.0, &b));
+        xs.add(Intersection::new(1.0, &a));
+        xs.add(Intersection::new(2.0, &b));
+
+        assert_eq!(xs.hit_excluding(&a).unwrap().t, 2.0);
+        assert_eq!(xs.hit_excluding(&b).unwrap().t, 1.0);
+        assert_eq!(xs.hit_where(|i| i.t > 1.5).unwrap().t, 2.0);
+        assert_eq!(xs.hit_where(|i| i.t < 0.0), None);
+    }
+
     #[test]
     fn test_intersections_more_values() {
         let s: Shape = Sphere::new().into();
@@ -184,7 +475,7 @@ mod test {
     #[test]
     fn test_ray_translation() {
         let r = Ray::new(Point::new(1.0, 2.0, 3.0), Vector::new(0.0, 1.0, 0.0));
-        let m = Matrix::translation(3.0, 4.0, 5.0);
+        let m = Matrix4::translation(3.0, 4.0, 5.0);
         let r2 = r.transform(&m);
         assert_eq!(r2.origin, Point::new(4.0, 6.0, 8.0));
         assert_eq!(r2.direction, Vector::new(0.0, 1.0, 0.0));
@@ -193,9 +484,183 @@ mod test {
     #[test]
     fn test_ray_scaling() {
         let r = Ray::new(Point::new(1.0, 2.0, 3.0), Vector::new(0.0, 1.0, 0.0));
-        let m = Matrix::scaling(2.0, 3.0, 4.0);
+        let m = Matrix4::scaling(2.0, 3.0, 4.0);
         let r2 = r.transform(&m);
         assert_eq!(r2.origin, Point::new(2.0, 6.0, 12.0));
         assert_eq!(r2.direction, Vector::new(0.0, 3.0, 0.0));
     }
+
+    #[test]
+    fn test_ray_transform_keeps_time() {
+        let r = Ray::with_time(Point::new(1.0, 2.0, 3.0), Vector::new(0.0, 1.0, 0.0), 0.25);
+        let r2 = r.transform(&Matrix4::translation(3.0, 4.0, 5.0));
+        assert_eq!(r2.time, 0.25);
+    }
+
+    #[test]
+    fn test_precompute_intersection_state() {
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let s: Shape = Sphere::new().into();
+        let i = Intersection::new(4.0, &s);
+        let mut xs = Intersections::new();
+        xs.add(i.clone());
+        let comps = i.prepare_computations(&r, &xs);
+
+        assert_eq!(comps.t, i.t);
+        assert_eq!(comps.shape, &s);
+        assert_eq!(comps.point, Point::new(0.0, 0.0, -1.0));
+        assert_eq!(comps.eyev, Vector::new(0.0, 0.0, -1.0));
+        assert_eq!(comps.normalv, Vector::new(0.0, 0.0, -1.0));
+        assert!(!comps.inside);
+        assert!(comps.entering);
+    }
+
+    #[test]
+    fn test_precompute_hit_inside() {
+        let r = Ray::new(Point::origin(), Vector::new(0.0, 0.0, 1.0));
+        let s: Shape = Sphere::new().into();
+        let i = Intersection::new(1.0, &s);
+        let mut xs = Intersections::new();
+        xs.add(i.clone());
+        let comps = i.prepare_computations(&r, &xs);
+
+        assert_eq!(comps.point, Point::new(0.0, 0.0, 1.0));
+        assert_eq!(comps.eyev, Vector::new(0.0, 0.0, -1.0));
+        assert!(comps.inside);
+        assert!(!comps.entering);
+        // Normal is inverted to point back towards the eye
+        assert_eq!(comps.normalv, Vector::new(0.0, 0.0, -1.0));
+    }
+
+    #[test]
+    fn test_intersections_enter_and_exit() {
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let s: Shape = Sphere::new().into();
+        let mut xs = Intersections::new();
+        s.intersect(&r, &mut xs);
+        let entering: Vec<bool> = xs.iter().map(|i| i.is_entering(&r)).collect();
+        assert_eq!(entering, [true, false]);
+
+        // Grazing the sphere neither enters nor leaves it.
+        let r = Ray::new(Point::new(0.0, 1.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        assert!(!Intersection::new(5.0, &s).is_entering(&r));
+    }
+
+    #[test]
+    fn test_precompute_reflectv() {
+        let hsq = (2.0 as Float).sqrt() / 2.0;
+        let r = Ray::new(Point::new(0.0, hsq, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let s: Shape = Sphere::new().into();
+        let i = Intersection::new(5.0 - hsq, &s);
+        let mut xs = Intersections::new();
+        xs.add(i.clone());
+        let comps = i.prepare_computations(&r, &xs);
+
+        assert_eq!(comps.reflectv, Vector::new(0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn test_precompute_n1_n2() {
+        let mut a = glass_sphere();
+        a.set_transform(Matrix4::scaling(2.0, 2.0, 2.0));
+        a.material_mut().refractive_index = 1.5;
+        let mut b = glass_sphere();
+        b.set_transform(Matrix4::translation(0.0, 0.0, -0.25));
+        b.material_mut().refractive_index = 2.0;
+        let mut c = glass_sphere();
+        c.set_transform(Matrix4::translation(0.0, 0.0, 0.25));
+        c.material_mut().refractive_index = 2.5;
+        let (a, b, c): (Shape, Shape, Shape) = (a.into(), b.into(), c.into());
+
+        let r = Ray::new(Point::new(0.0, 0.0, -4.0), Vector::new(0.0, 0.0, 1.0));
+        let hits = [
+            Intersection::new(2.0, &a),
+            Intersection::new(2.75, &b),
+            Intersection::new(3.25, &c),
+            Intersection::new(4.75, &b),
+            Intersection::new(5.25, &c),
+            Intersection::new(6.0, &a),
+        ];
+        let mut xs = Intersections::new();
+        for i in hits.iter() {
+            xs.add(i.clone());
+        }
+
+        let expected = [
+            (1.0, 1.5),
+            (1.5, 2.0),
+            (2.0, 2.5),
+            (2.5, 2.5),
+            (2.5, 1.5),
+            (1.5, 1.0),
+        ];
+        for (i, (n1, n2)) in hits.iter().zip(expected) {
+            let comps = i.prepare_computations(&r, &xs);
+            assert_eq!((comps.n1, comps.n2), (n1, n2), "at t = {}", i.t);
+        }
+    }
+
+    #[test]
+    fn test_precompute_under_point() {
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let mut s = glass_sphere();
+        s.set_transform(Matrix4::translation(0.0, 0.0, 1.0));
+        let s: Shape = s.into();
+        let i = Intersection::new(5.0, &s);
+        let mut xs = Intersections::new();
+        xs.add(i.clone());
+        let comps = i.prepare_computations(&r, &xs);
+
+        assert!(comps.under_point.z() > EPSILON / 2.0);
+        assert!(comps.point.z() < comps.under_point.z());
+    }
+
+    #[test]
+    fn test_precompute_over_point() {
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let s: Shape = Sphere::with_transform(Matrix4::translation(0.0, 0.0, 1.0)).into();
+        let i = Intersection::new(5.0, &s);
+        let mut xs = Intersections::new();
+        xs.add(i.clone());
+        let comps = i.prepare_computations(&r, &xs);
+
+        assert!(comps.over_point.z() < -EPSILON / 2.0);
+        assert!(comps.point.z() > comps.over_point.z());
+    }
+
+    #[test]
+    fn test_schlick_total_internal_reflection() {
+        let hsq = (2.0 as Float).sqrt() / 2.0;
+        let s: Shape = glass_sphere().into();
+        let r = Ray::new(Point::new(0.0, 0.0, hsq), Vector::new(0.0, 1.0, 0.0));
+        let mut xs = Intersections::new();
+        xs.add(Intersection::new(-hsq, &s));
+        xs.add(Intersection::new(hsq, &s));
+        let comps = Intersection::new(hsq, &s).prepare_computations(&r, &xs);
+
+        assert_eq!(comps.schlick(), 1.0);
+    }
+
+    #[test]
+    fn test_schlick_perpendicular() {
+        let s: Shape = glass_sphere().into();
+        let r = Ray::new(Point::origin(), Vector::new(0.0, 1.0, 0.0));
+        let mut xs = Intersections::new();
+        xs.add(Intersection::new(-1.0, &s));
+        xs.add(Intersection::new(1.0, &s));
+        let comps = Intersection::new(1.0, &s).prepare_computations(&r, &xs);
+
+        assert!((comps.schlick() - 0.04).abs() < EPSILON);
+    }
+
+    #[test]
+    fn test_schlick_small_angle_n2_greater() {
+        let s: Shape = glass_sphere().into();
+        let r = Ray::new(Point::new(0.0, 0.99, -2.0), Vector::new(0.0, 0.0, 1.0));
+        let mut xs = Intersections::new();
+        xs.add(Intersection::new(1.8589, &s));
+        let comps = Intersection::new(1.8589, &s).prepare_computations(&r, &xs);
+
+        assert!((comps.schlick() - 0.48873).abs() < EPSILON);
+    }
 }
@@ -0,0 +1,162 @@
+use std::panic;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+
+use crate::camera::Camera;
+use crate::canvas::Canvas;
+use crate::color::Color;
+use crate::world::World;
+use crate::Float;
+
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+/// A render running on a background thread, started by
+/// [`Camera::render_async`].
+#[derive(Debug)]
+pub struct RenderHandle {
+    state: Arc<State>,
+    thread: JoinHandle<Canvas>,
+}
+
+/// Shared between the handle and the rendering thread.
+#[derive(Debug)]
+struct State {
+    rows: usize,
+    rows_done: AtomicUsize,
+    cancelled: AtomicBool,
+}
+
+impl RenderHandle {
+    /// The fraction of the image's rows finished so far, from 0.0 to 1.0.
+    pub fn progress(&self) -> Float {
+        if self.state.rows == 0 {
+            return 1.0;
+        }
+        self.state.rows_done.load(Ordering::Relaxed) as Float / self.state.rows as Float
+    }
+
+    /// Stop the render as soon as the rows in progress are finished. Call
+    /// [`RenderHandle::join`] to wait for it to stop.
+    pub fn cancel(&self) {
+        self.state.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.state.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Whether the render has stopped, because it's complete or cancelled.
+    pub fn is_finished(&self) -> bool {
+        self.thread.is_finished()
+    }
+
+    /// Wait for the render to stop and return the image. Rows that weren't
+    /// rendered before the render was cancelled are left black.
+    pub fn join(self) -> Canvas {
+        self.thread
+            .join()
+            .unwrap_or_else(|payload| panic::resume_unwind(payload))
+    }
+}
+
+impl Camera {
+    /// Start rendering `world` on a background thread, returning a handle to
+    /// follow its progress, cancel it or wait for the image. Rows are traced
+    /// in parallel if possible. The image matches [`Camera::render`] unless
+    /// the render is cancelled.
+    pub fn render_async(&self, world: World) -> RenderHandle {
+        let camera = self.clone();
+        let state = Arc::new(State {
+            rows: camera.vsize(),
+            rows_done: AtomicUsize::new(0),
+            cancelled: AtomicBool::new(false),
+        });
+
+        let thread_state = Arc::clone(&state);
+        let thread = thread::spawn(move || {
+            let state = thread_state;
+            let mut image = Canvas::new(camera.hsize(), camera.vsize());
+            let render_row = |(y, row): (usize, &mut [Color])| {
+                if state.cancelled.load(Ordering::Relaxed) {
+                    return;
+                }
+                for (x, pixel) in row.iter_mut().enumerate() {
+                    *pixel = camera.color_at_pixel(&world, x, y);
+                }
+                state.rows_done.fetch_add(1, Ordering::Relaxed);
+            };
+
+            #[cfg(feature = "rayon")]
+            image.par_rows_mut().enumerate().for_each(render_row);
+            #[cfg(not(feature = "rayon"))]
+            image.rows_mut().enumerate().for_each(render_row);
+            image
+        });
+
+        RenderHandle { state, thread }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::consts::PI;
+
+    use super::*;
+    use crate::fixtures::{default_camera, default_world};
+
+    /// The default camera, with a different image size.
+    fn sized_camera(hsize: usize, vsize: usize) -> Camera {
+        let mut c = Camera::new(hsize, vsize, PI / 2.0);
+        c.set_transform(*default_camera().transformation());
+        c
+    }
+
+    #[test]
+    fn test_render_async_matches_render() {
+        let c = default_camera();
+        let handle = c.render_async(default_world());
+        let image = handle.join();
+        assert_eq!(image, c.render(&default_world()));
+    }
+
+    #[test]
+    fn test_render_async_progress() {
+        let c = default_camera();
+        let handle = c.render_async(default_world());
+        let progress = handle.progress();
+        assert!((0.0..=1.0).contains(&progress));
+        while !handle.is_finished() {
+            thread::yield_now();
+        }
+        assert_eq!(handle.progress(), 1.0);
+        assert!(!handle.is_cancelled());
+    }
+
+    #[test]
+    fn test_render_async_cancel() {
+        let c = sized_camera(40, 30);
+        let full = c.render(&default_world());
+        let handle = c.render_async(default_world());
+        handle.cancel();
+        assert!(handle.is_cancelled());
+        let image = handle.join();
+
+        // Each row is either finished or untouched.
+        let black = Color::new(0.0, 0.0, 0.0);
+        for y in 0..30 {
+            let finished = (0..40).all(|x| image.pixel_at(x, y) == full.pixel_at(x, y));
+            let untouched = (0..40).all(|x| image.pixel_at(x, y) == black);
+            assert!(finished || untouched, "row {y} is partly rendered");
+        }
+    }
+
+    #[test]
+    fn test_render_async_empty_image() {
+        let c = sized_camera(0, 0);
+        let handle = c.render_async(default_world());
+        assert_eq!(handle.progress(), 1.0);
+        assert_eq!(handle.join(), Canvas::new(0, 0));
+    }
+}
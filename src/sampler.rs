@@ -0,0 +1,313 @@
+use crate::Float;
+
+/// Strategy for placing multiple samples within a pixel when antialiasing.
+///
+/// Each sampler produces `n * n` points for a sample count of `n`, as offsets
+/// from the pixel's top left corner in the range 0.0 to 1.0. With a single
+/// sample every strategy uses the pixel's center, as in the book.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Sampler {
+    /// The center of each cell of an `n` by `n` grid. Cheap and smooth, but
+    /// regular patterns in the scene can alias against the grid.
+    Grid,
+    /// Points scattered uniformly at random over the pixel. Avoids structured
+    /// artifacts at the cost of clumping, and so more noise.
+    Random,
+    /// One random point within each cell of an `n` by `n` grid: the regular
+    /// coverage of [`Sampler::Grid`] without its structured artifacts.
+    #[default]
+    Jittered,
+}
+
+impl Sampler {
+    /// Sample offsets for the pixel at (`px`, `py`). Random choices are
+    /// seeded from the pixel's coordinates, so rendering is repeatable and
+    /// doesn't depend on the order pixels are traced in.
+    pub fn offsets(&self, n: usize, px: usize, py: usize) -> Vec<(Float, Float)> {
+        self.offsets_from(n, &mut Sequence::random(0).fork(pixel_key(px, py)))
+    }
+
+    /// Like [`Sampler::offsets`], with the random choices taken from
+    /// `sequence`.
+    pub fn offsets_from(&self, n: usize, sequence: &mut Sequence) -> Vec<(Float, Float)> {
+        if n <= 1 {
+            return vec![(0.5, 0.5)];
+        }
+
+        let cell = 1.0 / n as Float;
+        let mut offsets = Vec::with_capacity(n * n);
+        for j in 0..n {
+            for i in 0..n {
+                let (x, y) = (i as Float * cell, j as Float * cell);
+                offsets.push(match self {
+                    Sampler::Grid => (x + cell / 2.0, y + cell / 2.0),
+                    Sampler::Random => (sequence.next(), sequence.next()),
+                    Sampler::Jittered => (x + sequence.next() * cell, y + sequence.next() * cell),
+                });
+            }
+        }
+        offsets
+    }
+}
+
+impl Sampler {
+    /// Times for each of the `n * n` samples in the pixel at (`px`, `py`), as
+    /// fractions of the shutter interval from 0.0 to 1.0. With a single
+    /// sample the time is the middle of the interval.
+    ///
+    /// Jittered times are stratified, then shuffled so they aren't correlated
+    /// with where the sample falls in the pixel.
+    pub fn times(&self, n: usize, px: usize, py: usize) -> Vec<Float> {
+        // Seed differently from the offsets, so time and position aren't linked.
+        self.times_from(n, &mut Sequence::random(0).fork(!pixel_key(px, py)))
+    }
+
+    /// Like [`Sampler::times`], with the random choices taken from
+    /// `sequence`.
+    pub fn times_from(&self, n: usize, sequence: &mut Sequence) -> Vec<Float> {
+        if n <= 1 {
+            return vec![0.5];
+        }
+
+        let count = n * n;
+        let cell = 1.0 / count as Float;
+        let mut times: Vec<Float> = (0..count)
+            .map(|k| match self {
+                Sampler::Grid => (k as Float + 0.5) * cell,
+                Sampler::Random => sequence.next(),
+                Sampler::Jittered => (k as Float + sequence.next()) * cell,
+            })
+            .collect();
+
+        if *self == Sampler::Jittered {
+            for i in (1..count).rev() {
+                let j = (sequence.next() * (i + 1) as Float) as usize;
+                times.swap(i, j);
+            }
+        }
+        times
+    }
+}
+
+/// A key identifying the pixel at (`px`, `py`), for [`Sequence::fork`].
+pub(crate) fn pixel_key(px: usize, py: usize) -> u64 {
+    ((px as u64) << 32) ^ py as u64
+}
+
+/// Where the random choices made while rendering come from: soft shadow
+/// and antialiasing sample positions and motion blur times.
+///
+/// Sequences are either pseudo-random from a seed, so renders are repeatable
+/// but different seeds give different noise, or a fixed list of numbers
+/// repeated over and over, so tests can control exactly where samples fall.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Sequence {
+    source: Source,
+    // How many numbers have been taken.
+    position: u64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+enum Source {
+    Random(u64),
+    Fixed(Vec<Float>),
+}
+
+/// SplitMix64's increment, which steps through every 64-bit state.
+const GOLDEN_GAMMA: u64 = 0x9e3779b97f4a7c15;
+
+/// SplitMix64's output function: a small, fast hash good enough for placing
+/// samples, though not for anything that needs real randomness.
+fn mix(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+impl Sequence {
+    /// Pseudo-random numbers determined by `seed`.
+    pub fn random(seed: u64) -> Self {
+        Self {
+            source: Source::Random(seed),
+            position: 0,
+        }
+    }
+
+    /// `values` in order, starting again from the first after the last.
+    ///
+    /// Panics if `values` is empty or any of them are outside the range 0.0
+    /// to 1.0, excluding 1.0.
+    pub fn fixed(values: impl Into<Vec<Float>>) -> Self {
+        let values = values.into();
+        assert!(
+            !values.is_empty(),
+            "a fixed sequence needs at least one value"
+        );
+        assert!(
+            values.iter().all(|v| (0.0..1.0).contains(v)),
+            "sequence values must be in the range [0.0, 1.0)"
+        );
+        Self {
+            source: Source::Fixed(values),
+            position: 0,
+        }
+    }
+
+    /// The next number, in the range 0.0 to 1.0, excluding 1.0.
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Float {
+        self.position += 1;
+        match &self.source {
+            Source::Random(seed) => {
+                let bits = mix(seed.wrapping_add(self.position.wrapping_mul(GOLDEN_GAMMA)));
+                // As many bits as fit exactly in the mantissa.
+                let digits = Float::MANTISSA_DIGITS;
+                (bits >> (64 - digits)) as Float / (1u64 << digits) as Float
+            }
+            Source::Fixed(values) => values[((self.position - 1) % values.len() as u64) as usize],
+        }
+    }
+
+    /// A sequence for one of many separate uses, such as a pixel, identified
+    /// by `key`. Random sequences give unrelated numbers for each key, so
+    /// work can be split up in any order and still come out the same. Fixed
+    /// sequences start again from their first value.
+    pub fn fork(&self, key: u64) -> Self {
+        match &self.source {
+            // A seed of 0 keeps the key itself, as samplers seeded before
+            // sequences could be chosen.
+            Source::Random(0) => Self::random(key),
+            Source::Random(seed) => Self::random(mix(*seed) ^ key),
+            Source::Fixed(values) => Self::fixed(values.clone()),
+        }
+    }
+}
+
+impl Default for Sequence {
+    fn default() -> Self {
+        Self::random(0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_single_sample_is_pixel_center() {
+        for sampler in [Sampler::Grid, Sampler::Random, Sampler::Jittered] {
+            assert_eq!(sampler.offsets(1, 3, 4), vec![(0.5, 0.5)]);
+        }
+    }
+
+    #[test]
+    fn test_grid_offsets() {
+        assert_eq!(
+            Sampler::Grid.offsets(2, 0, 0),
+            vec![(0.25, 0.25), (0.75, 0.25), (0.25, 0.75), (0.75, 0.75)]
+        );
+    }
+
+    #[test]
+    fn test_random_offsets_within_pixel() {
+        let offsets = Sampler::Random.offsets(4, 7, 2);
+        assert_eq!(offsets.len(), 16);
+        for (x, y) in offsets {
+            assert!((0.0..1.0).contains(&x) && (0.0..1.0).contains(&y));
+        }
+    }
+
+    #[test]
+    fn test_jittered_offsets_one_per_cell() {
+        let n = 4;
+        let offsets = Sampler::Jittered.offsets(n, 7, 2);
+        assert_eq!(offsets.len(), n * n);
+        for (k, (x, y)) in offsets.into_iter().enumerate() {
+            let (i, j) = (k % n, k / n);
+            assert_eq!((x * n as Float) as usize, i);
+            assert_eq!((y * n as Float) as usize, j);
+        }
+    }
+
+    #[test]
+    fn test_single_sample_time_is_mid_shutter() {
+        for sampler in [Sampler::Grid, Sampler::Random, Sampler::Jittered] {
+            assert_eq!(sampler.times(1, 3, 4), vec![0.5]);
+        }
+    }
+
+    #[test]
+    fn test_grid_times() {
+        assert_eq!(
+            Sampler::Grid.times(2, 0, 0),
+            vec![0.125, 0.375, 0.625, 0.875]
+        );
+    }
+
+    #[test]
+    fn test_jittered_times_one_per_stratum() {
+        let mut strata: Vec<usize> = Sampler::Jittered
+            .times(3, 1, 2)
+            .into_iter()
+            .map(|t| (t * 9.0) as usize)
+            .collect();
+        strata.sort();
+        assert_eq!(strata, (0..9).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_fixed_sequence_repeats() {
+        let mut s = Sequence::fixed([0.1, 0.5, 0.9]);
+        let taken: Vec<_> = (0..5).map(|_| s.next()).collect();
+        assert_eq!(taken, [0.1, 0.5, 0.9, 0.1, 0.5]);
+        assert_eq!(s.fork(7).next(), 0.1);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_fixed_sequence_values_in_range() {
+        Sequence::fixed([0.5, 1.0]);
+    }
+
+    #[test]
+    fn test_random_sequences_are_repeatable() {
+        let take = |mut s: Sequence| (0..4).map(|_| s.next()).collect::<Vec<_>>();
+        assert_eq!(take(Sequence::random(3)), take(Sequence::random(3)));
+        assert_ne!(take(Sequence::random(3)), take(Sequence::random(4)));
+        assert_ne!(
+            take(Sequence::random(3).fork(1)),
+            take(Sequence::random(3).fork(2))
+        );
+        assert_ne!(
+            take(Sequence::random(3).fork(1)),
+            take(Sequence::random(4).fork(1))
+        );
+        for v in take(Sequence::random(5)) {
+            assert!((0.0..1.0).contains(&v));
+        }
+    }
+
+    #[test]
+    fn test_offsets_from_fixed_sequence() {
+        let mut s = Sequence::fixed([0.5, 0.0]);
+        assert_eq!(
+            Sampler::Jittered.offsets_from(2, &mut s),
+            vec![(0.25, 0.0), (0.75, 0.0), (0.25, 0.5), (0.75, 0.5)]
+        );
+    }
+
+    #[test]
+    fn test_offsets_are_repeatable_per_pixel() {
+        assert_eq!(
+            Sampler::Jittered.offsets(3, 5, 9),
+            Sampler::Jittered.offsets(3, 5, 9)
+        );
+        assert_ne!(
+            Sampler::Jittered.offsets(3, 5, 9),
+            Sampler::Jittered.offsets(3, 9, 5)
+        );
+    }
+}
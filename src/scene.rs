@@ -0,0 +1,283 @@
+use crate::color::Color;
+use crate::lighting::PointLight;
+use crate::materials::Material;
+use crate::matrix4::Matrix4;
+use crate::patterns::Pattern;
+use crate::shape::{Shape, Sphere};
+use crate::space::Point;
+use crate::world::{Acceleration, Background, World};
+use crate::Float;
+
+/// Builds a [`World`] by chaining calls, configuring each shape in a closure:
+///
+/// ```
+/// use ray_tracer_challenge_2::color::Color;
+/// use ray_tracer_challenge_2::scene::SceneBuilder;
+/// use ray_tracer_challenge_2::space::Point;
+///
+/// let world = SceneBuilder::new()
+///     .light(Point::new(-10.0, 10.0, -10.0), Color::new(1.0, 1.0, 1.0))
+///     .sphere(|s| {
+///         s.scaled(0.5, 0.5, 0.5)
+///             .at(0.0, 1.0, 0.0)
+///             .colored(Color::new(1.0, 0.2, 0.2))
+///     })
+///     .build();
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct SceneBuilder {
+    world: World,
+}
+
+impl SceneBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a point light. Lights get ids for [`ShapeBuilder::light_mask`]
+    /// in the order they're added, starting from 0.
+    pub fn light(mut self, position: Point, intensity: Color) -> Self {
+        self.world.add_light(PointLight::new(position, intensity));
+        self
+    }
+
+    /// Set the background to a [`Background`] or a solid [`Color`].
+    pub fn background(mut self, background: impl Into<Background>) -> Self {
+        self.world.set_background(background);
+        self
+    }
+
+    /// Set how many times rays may be reflected or refracted, as for
+    /// [`World::set_max_depth`].
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.world.set_max_depth(max_depth);
+        self
+    }
+
+    /// Choose how rays find objects, as for [`World::set_acceleration`].
+    pub fn acceleration(mut self, acceleration: Acceleration) -> Self {
+        self.world.set_acceleration(acceleration);
+        self
+    }
+
+    /// Add a unit sphere, configured by `build`.
+    pub fn sphere(self, build: impl FnOnce(ShapeBuilder) -> ShapeBuilder) -> Self {
+        self.shape(Sphere::new(), build)
+    }
+
+    /// Add `shape`, configured by `build`. Transformations given to the
+    /// builder apply on top of any the shape already has.
+    pub fn shape(
+        mut self,
+        shape: impl Into<Shape>,
+        build: impl FnOnce(ShapeBuilder) -> ShapeBuilder,
+    ) -> Self {
+        self.world
+            .add_object(build(ShapeBuilder::new(shape)).build());
+        self
+    }
+
+    /// Add `shape` as it is.
+    pub fn object(mut self, shape: impl Into<Shape>) -> Self {
+        self.world.add_object(shape);
+        self
+    }
+
+    pub fn build(self) -> World {
+        self.world
+    }
+}
+
+/// Configures a shape's placement and material for [`SceneBuilder`].
+///
+/// Transformations apply in the order they're called, so
+/// `s.scaled(2.0, 2.0, 2.0).at(0.0, 1.0, 0.0)` doubles the shape's size and
+/// then moves it up, while calling them the other way round would move it up
+/// twice as far.
+#[derive(Clone, Debug)]
+pub struct ShapeBuilder {
+    shape: Shape,
+}
+
+impl ShapeBuilder {
+    pub fn new(shape: impl Into<Shape>) -> Self {
+        Self {
+            shape: shape.into(),
+        }
+    }
+
+    /// Apply `transformation`, a [`Matrix4`] or [`Transform`], after those
+    /// given so far.
+    ///
+    /// Panics if the result isn't invertible.
+    ///
+    /// [`Transform`]: crate::transform::Transform
+    pub fn transformed(mut self, transformation: impl Into<Matrix4>) -> Self {
+        let combined = transformation.into() * self.shape.transformation();
+        self.shape.set_transform(combined);
+        self
+    }
+
+    /// Move the shape by (`x`, `y`, `z`).
+    pub fn at(self, x: Float, y: Float, z: Float) -> Self {
+        self.transformed(Matrix4::translation(x, y, z))
+    }
+
+    pub fn scaled(self, x: Float, y: Float, z: Float) -> Self {
+        self.transformed(Matrix4::scaling(x, y, z))
+    }
+
+    pub fn rotated_x(self, radians: Float) -> Self {
+        self.transformed(Matrix4::rotation_x(radians))
+    }
+
+    pub fn rotated_y(self, radians: Float) -> Self {
+        self.transformed(Matrix4::rotation_y(radians))
+    }
+
+    pub fn rotated_z(self, radians: Float) -> Self {
+        self.transformed(Matrix4::rotation_z(radians))
+    }
+
+    /// Replace the shape's whole material.
+    pub fn material(mut self, material: Material) -> Self {
+        *self.shape.material_mut() = material;
+        self
+    }
+
+    pub fn colored(mut self, color: Color) -> Self {
+        self.shape.material_mut().color = color;
+        self
+    }
+
+    pub fn pattern(mut self, pattern: impl Into<Pattern>) -> Self {
+        self.shape.material_mut().pattern = Some(pattern.into());
+        self
+    }
+
+    pub fn ambient(mut self, ambient: Float) -> Self {
+        self.shape.material_mut().ambient = ambient;
+        self
+    }
+
+    pub fn diffuse(mut self, diffuse: Float) -> Self {
+        self.shape.material_mut().diffuse = diffuse;
+        self
+    }
+
+    pub fn specular(mut self, specular: Float) -> Self {
+        self.shape.material_mut().specular = specular;
+        self
+    }
+
+    pub fn shininess(mut self, shininess: Float) -> Self {
+        self.shape.material_mut().shininess = shininess;
+        self
+    }
+
+    pub fn reflective(mut self, reflective: Float) -> Self {
+        self.shape.material_mut().reflective = reflective;
+        self
+    }
+
+    pub fn transparent(mut self, transparency: Float, refractive_index: Float) -> Self {
+        let material = self.shape.material_mut();
+        material.transparency = transparency;
+        material.refractive_index = refractive_index;
+        self
+    }
+
+    pub fn casts_shadow(mut self, casts_shadow: bool) -> Self {
+        self.shape.set_casts_shadow(casts_shadow);
+        self
+    }
+
+    pub fn receives_shadow(mut self, receives_shadow: bool) -> Self {
+        self.shape.set_receives_shadow(receives_shadow);
+        self
+    }
+
+    /// Light the shape with only some of the scene's lights, as described
+    /// for [`Shape::set_light_mask`].
+    pub fn light_mask(mut self, mask: u64) -> Self {
+        self.shape.set_light_mask(mask);
+        self
+    }
+
+    pub fn named(mut self, name: impl Into<String>) -> Self {
+        self.shape.set_name(name);
+        self
+    }
+
+    pub fn build(self) -> Shape {
+        self.shape
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::patterns::StripePattern;
+    use crate::ray::Ray;
+    use crate::shape::Triangle;
+    use crate::space::Vector;
+
+    #[test]
+    fn test_empty_scene() {
+        assert_eq!(SceneBuilder::new().build(), World::new());
+    }
+
+    #[test]
+    fn test_build_sphere() {
+        let world = SceneBuilder::new()
+            .sphere(|s| {
+                s.scaled(2.0, 2.0, 2.0)
+                    .at(0.0, 1.0, 0.0)
+                    .colored(Color::new(1.0, 0.0, 0.0))
+                    .reflective(0.5)
+                    .named("ball")
+            })
+            .build();
+
+        let ball = world.find_object("ball").unwrap();
+        assert_eq!(
+            ball.transformation(),
+            &(Matrix4::translation(0.0, 1.0, 0.0) * Matrix4::scaling(2.0, 2.0, 2.0))
+        );
+        assert_eq!(ball.material().color, Color::new(1.0, 0.0, 0.0));
+        assert_eq!(ball.material().reflective, 0.5);
+        assert_eq!(ball.material().diffuse, Material::new().diffuse);
+    }
+
+    #[test]
+    fn test_build_lit_scene() {
+        let world = SceneBuilder::new()
+            .light(Point::new(-10.0, 10.0, -10.0), Color::new(1.0, 1.0, 1.0))
+            .background(Color::new(0.0, 0.0, 0.2))
+            .sphere(|s| {
+                s.pattern(StripePattern::new(
+                    Color::new(1.0, 1.0, 1.0),
+                    Color::new(0.0, 0.0, 0.0),
+                ))
+            })
+            .shape(
+                Triangle::new(
+                    Point::new(0.0, 1.0, 0.0),
+                    Point::new(-1.0, 0.0, 0.0),
+                    Point::new(1.0, 0.0, 0.0),
+                ),
+                |t| t.at(0.0, 0.0, -2.0).transparent(1.0, 1.5),
+            )
+            .build();
+
+        let r = Ray::new(Point::new(0.0, 0.5, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = world.intersect(&r);
+        assert_eq!(xs.len(), 3);
+        assert_eq!(xs[0].t, 3.0);
+        assert_eq!(xs[0].shape.material().refractive_index, 1.5);
+        assert!(xs[1].shape.material().pattern.is_some());
+
+        let r = Ray::new(Point::new(0.0, 5.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(world.color_at(&r), Color::new(0.0, 0.0, 0.2));
+    }
+}
@@ -0,0 +1,241 @@
+use std::fmt;
+use std::sync::Arc;
+
+use crate::bounds::BoundingBox;
+use crate::ray::Ray;
+use crate::shape::{CustomShape, LocalShape, Shape};
+use crate::space::{Point, Vector};
+use crate::Float;
+use crate::EPSILON;
+
+/// How close to the surface a ray has to get to count as hitting it. This is
+/// well inside `EPSILON`, so points nudged off the surface for shadow and
+/// reflection rays don't immediately hit it again.
+const SURFACE_DISTANCE: Float = EPSILON / 10.0;
+
+/// The default limit on marching steps per ray.
+const DEFAULT_MAX_STEPS: usize = 1000;
+
+type DistanceFn = dyn Fn(&Point) -> Float + Send + Sync;
+
+/// An implicit surface defined by a signed distance function: negative inside
+/// the shape, positive outside, and never more than the true distance to the
+/// surface. Rays are intersected by sphere tracing, stepping along the ray by
+/// the distance to the nearest surface until they reach it.
+///
+/// Tracing is confined to `bounds`, which must enclose the whole surface.
+/// Add the field to a world via `Shape::from`, which wraps it in a
+/// [`CustomShape`] to give it a transformation and material.
+#[derive(Clone)]
+pub struct DistanceField {
+    distance: Arc<DistanceFn>,
+    bounds: BoundingBox,
+    max_steps: usize,
+}
+
+impl DistanceField {
+    /// Panics if `bounds` isn't finite.
+    pub fn new(
+        distance: impl Fn(&Point) -> Float + Send + Sync + 'static,
+        bounds: BoundingBox,
+    ) -> Self {
+        assert!(bounds.is_finite(), "distance field bounds must be finite");
+        Self {
+            distance: Arc::new(distance),
+            bounds,
+            max_steps: DEFAULT_MAX_STEPS,
+        }
+    }
+
+    /// The signed distance from `point` to the surface.
+    pub fn distance(&self, point: &Point) -> Float {
+        (self.distance)(point)
+    }
+
+    pub fn max_steps(&self) -> usize {
+        self.max_steps
+    }
+
+    /// Limit how many steps a ray may take through the bounds, trading
+    /// missed detail in complex surfaces for speed.
+    pub fn set_max_steps(&mut self, max_steps: usize) {
+        self.max_steps = max_steps;
+    }
+}
+
+impl fmt::Debug for DistanceField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DistanceField")
+            .field("bounds", &self.bounds)
+            .field("max_steps", &self.max_steps)
+            .finish_non_exhaustive()
+    }
+}
+
+impl LocalShape for DistanceField {
+    fn local_intersect(&self, ray: &Ray) -> Vec<Float> {
+        let Some((start, end)) = self.bounds.intersect(ray) else {
+            return vec![];
+        };
+        // The distance function is in object space, but t is measured in
+        // multiples of the ray's direction, which needn't be normalized.
+        let speed = ray.direction.magnitude();
+
+        let mut ts = vec![];
+        let mut t = start;
+        // Only count a hit once the ray has moved clear of the last one, so
+        // crossing a surface isn't recorded on every step through it.
+        let mut clear = true;
+        for _ in 0..self.max_steps {
+            if t > end {
+                break;
+            }
+            let d = self.distance(&ray.position(t)).abs();
+            if d < SURFACE_DISTANCE {
+                if clear {
+                    ts.push(t);
+                    clear = false;
+                }
+                t += 2.0 * SURFACE_DISTANCE / speed;
+            } else {
+                clear = true;
+                t += d / speed;
+            }
+        }
+        ts
+    }
+
+    fn local_normal_at(&self, point: &Point) -> Vector {
+        // The gradient of the distance function, by central differences.
+        let h = SURFACE_DISTANCE;
+        let gradient = |dx, dy, dz| {
+            self.distance(&point.translate(dx, dy, dz))
+                - self.distance(&point.translate(-dx, -dy, -dz))
+        };
+        Vector::new(
+            gradient(h, 0.0, 0.0),
+            gradient(0.0, h, 0.0),
+            gradient(0.0, 0.0, h),
+        )
+    }
+
+    fn local_bounds(&self) -> BoundingBox {
+        self.bounds
+    }
+}
+
+impl From<DistanceField> for Shape {
+    fn from(value: DistanceField) -> Self {
+        CustomShape::new(value).into()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::assert_approx_eq;
+    use crate::matrix4::Matrix4;
+    use crate::ray::Intersections;
+    use crate::testlib::approx_equals_fail;
+
+    fn unit_sphere() -> DistanceField {
+        DistanceField::new(
+            |p| p.subtract_origin().magnitude() - 1.0,
+            BoundingBox::new(Point::new(-1.0, -1.0, -1.0), Point::new(1.0, 1.0, 1.0)),
+        )
+    }
+
+    fn torus(major: Float, minor: Float) -> DistanceField {
+        DistanceField::new(
+            move |p| {
+                let ring = (p.x() * p.x() + p.z() * p.z()).sqrt() - major;
+                (ring * ring + p.y() * p.y()).sqrt() - minor
+            },
+            BoundingBox::new(
+                Point::new(-major - minor, -minor, -major - minor),
+                Point::new(major + minor, minor, major + minor),
+            ),
+        )
+    }
+
+    #[test]
+    fn test_intersect_sphere_field() {
+        let s = unit_sphere();
+        let ts = s.local_intersect(&Ray::new(
+            Point::new(0.0, 0.0, -5.0),
+            Vector::new(0.0, 0.0, 1.0),
+        ));
+        assert_eq!(ts.len(), 2);
+        assert_approx_eq!(ts[0], 4.0);
+        assert_approx_eq!(ts[1], 6.0);
+    }
+
+    #[test]
+    fn test_intersect_sphere_field_from_inside() {
+        let s = unit_sphere();
+        let ts = s.local_intersect(&Ray::new(Point::origin(), Vector::new(0.0, 0.0, 1.0)));
+        assert_eq!(ts.len(), 2);
+        assert_approx_eq!(ts[0], -1.0);
+        assert_approx_eq!(ts[1], 1.0);
+    }
+
+    #[test]
+    fn test_intersect_sphere_field_miss() {
+        let s = unit_sphere();
+        let ts = s.local_intersect(&Ray::new(
+            Point::new(0.0, 2.0, -5.0),
+            Vector::new(0.0, 0.0, 1.0),
+        ));
+        assert!(ts.is_empty());
+    }
+
+    #[test]
+    fn test_intersect_torus_through_hole() {
+        let t = torus(1.0, 0.25);
+        let ts = t.local_intersect(&Ray::new(
+            Point::new(-5.0, 0.0, 0.0),
+            Vector::new(1.0, 0.0, 0.0),
+        ));
+        assert_eq!(ts.len(), 4);
+        for (t, expected) in ts.iter().zip([3.75, 4.25, 5.75, 6.25]) {
+            assert_approx_eq!(*t, expected);
+        }
+
+        let ts = t.local_intersect(&Ray::new(
+            Point::new(0.0, -5.0, 0.0),
+            Vector::new(0.0, 1.0, 0.0),
+        ));
+        assert!(ts.is_empty());
+    }
+
+    #[test]
+    fn test_sphere_field_normal() {
+        let s = unit_sphere();
+        let trt = (3.0 as Float).sqrt() / 3.0;
+        let n = s.local_normal_at(&Point::new(trt, trt, trt)).normalize();
+        assert_eq!(n, Vector::new(trt, trt, trt));
+    }
+
+    #[test]
+    fn test_transformed_field_in_shape() {
+        let mut custom = CustomShape::new(unit_sphere());
+        custom.set_transform(Matrix4::scaling(2.0, 2.0, 2.0));
+        let s: Shape = custom.into();
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let mut xs = Intersections::new();
+        s.intersect(&r, &mut xs);
+        assert_eq!(xs.len(), 2);
+        assert_approx_eq!(xs[0].t, 3.0);
+        assert_approx_eq!(xs[1].t, 7.0);
+        assert_eq!(
+            s.normal_at(&Point::new(0.0, 0.0, -2.0)),
+            Vector::new(0.0, 0.0, -1.0)
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_unbounded_field() {
+        DistanceField::new(|p| p.y(), BoundingBox::infinite());
+    }
+}
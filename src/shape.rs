@@ -1,335 +1,2728 @@
+use crate::bounds::BoundingBox;
+use crate::error::Error;
+use crate::kdtree::{self, KdTree};
 use crate::materials::Material;
-use crate::matrix::{identity_matrix, Matrix};
+use crate::matrix3::Matrix3;
+use crate::matrix4::Matrix4;
 use crate::ray::Ray;
 use crate::ray::{Intersection, Intersections};
 use crate::space::{Point, Vector};
+use crate::EPSILON;
+use crate::Float;
+use std::fmt;
+use std::sync::Arc;
 
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Shape {
     Sphere(Sphere),
+    Triangle(Triangle),
+    Quad(Quad),
+    /// Arbitrary user geometry, which can't be serialized: serializing a
+    /// world or group containing one fails with an error saying so.
+    #[cfg_attr(
+        feature = "serde",
+        serde(skip_deserializing, serialize_with = "serialize_custom")
+    )]
+    Custom(CustomShape),
+    Group(Group),
 }
 
 impl Shape {
+    pub fn intersect<'a>(&'a self, ray: &Ray, intersections: &mut Intersections<'a>) {
+        if let Self::Group(group) = self {
+            return group.intersect(ray, intersections);
+        }
+
+        // The built-in shapes are intersected without collecting their
+        // results in a Vec, as this is done for every shape on every ray.
+        let mut add = |t| intersections.add(Intersection::new(t, self));
+        match self {
+            Self::Sphere(sphere) => {
+                if let Some((t1, t2)) = sphere.roots(ray) {
+                    add(t1);
+                    add(t2);
+                }
+            }
+            Self::Triangle(triangle) => triangle.intersection_t(ray).into_iter().for_each(add),
+            Self::Quad(quad) => quad.intersection_t(ray).into_iter().for_each(add),
+            Self::Custom(custom) => custom.intersect(ray).into_iter().for_each(add),
+            Self::Group(_) => unreachable!(),
+        }
+    }
+
+    pub fn material(&self) -> &Material {
+        match self {
+            Self::Sphere(sphere) => sphere.material(),
+            Self::Triangle(triangle) => &triangle.material,
+            Self::Quad(quad) => &quad.material,
+            Self::Custom(custom) => custom.material(),
+            Self::Group(group) => &group.material,
+        }
+    }
+
+    pub fn material_mut(&mut self) -> &mut Material {
+        match self {
+            Self::Sphere(sphere) => sphere.material_mut(),
+            Self::Triangle(triangle) => &mut triangle.material,
+            Self::Quad(quad) => &mut quad.material,
+            Self::Custom(custom) => custom.material_mut(),
+            Self::Group(group) => &mut group.material,
+        }
+    }
+
+    pub fn normal_at(&self, p: &Point) -> Vector {
+        match self {
+            Self::Sphere(sphere) => sphere.normal_at(p),
+            Self::Triangle(triangle) => triangle.normal_at(p),
+            Self::Quad(quad) => quad.normal_at(p),
+            Self::Custom(custom) => custom.normal_at(p),
+            Self::Group(_) => panic!("groups have no surface of their own"),
+        }
+    }
+
+    /// The normal at `p` for a ray cast at `time`, for shapes in motion.
+    pub fn normal_at_time(&self, p: &Point, time: Float) -> Vector {
+        match self {
+            Self::Sphere(sphere) => sphere.normal_at_time(p, time),
+            Self::Triangle(triangle) => triangle.normal_at(p),
+            Self::Quad(quad) => quad.normal_at(p),
+            Self::Custom(custom) => custom.normal_at(p),
+            Self::Group(_) => panic!("groups have no surface of their own"),
+        }
+    }
+
+    /// The name given to the shape with [`Shape::set_name`], if any.
+    pub fn name(&self) -> Option<&str> {
+        match self {
+            Self::Sphere(sphere) => sphere.name.as_deref(),
+            Self::Triangle(triangle) => triangle.name.as_deref(),
+            Self::Quad(quad) => quad.name.as_deref(),
+            Self::Custom(custom) => custom.name.as_deref(),
+            Self::Group(group) => group.name.as_deref(),
+        }
+    }
+
+    /// Name the shape, so it can be found with `World::find_object` and
+    /// identified in debug output. Names needn't be unique.
+    pub fn set_name(&mut self, name: impl Into<String>) {
+        let slot = match self {
+            Self::Sphere(sphere) => &mut sphere.name,
+            Self::Triangle(triangle) => &mut triangle.name,
+            Self::Quad(quad) => &mut quad.name,
+            Self::Custom(custom) => &mut custom.name,
+            Self::Group(group) => &mut group.name,
+        };
+        *slot = Some(name.into());
+    }
+
+    /// Whether the shape blocks light from reaching other shapes. On by
+    /// default.
+    pub fn casts_shadow(&self) -> bool {
+        match self {
+            Self::Sphere(sphere) => sphere.casts_shadow,
+            Self::Triangle(triangle) => triangle.casts_shadow,
+            Self::Quad(quad) => quad.casts_shadow,
+            Self::Custom(custom) => custom.casts_shadow,
+            Self::Group(group) => group.casts_shadow,
+        }
+    }
+
+    /// Set whether the shape blocks light, leaving it out of shadow tests
+    /// when not. Useful for glass, which would otherwise cast a solid black
+    /// shadow, and for floors and walls that shouldn't shade anything. For a
+    /// group, this applies to everything already in it.
+    pub fn set_casts_shadow(&mut self, casts_shadow: bool) {
+        match self {
+            Self::Sphere(sphere) => sphere.casts_shadow = casts_shadow,
+            Self::Triangle(triangle) => triangle.casts_shadow = casts_shadow,
+            Self::Quad(quad) => quad.casts_shadow = casts_shadow,
+            Self::Custom(custom) => custom.casts_shadow = casts_shadow,
+            Self::Group(group) => {
+                group.casts_shadow = casts_shadow;
+                for child in &mut group.children {
+                    child.set_casts_shadow(casts_shadow);
+                }
+            }
+        }
+    }
+
+    /// Whether other shapes can shadow this one. On by default.
+    pub fn receives_shadow(&self) -> bool {
+        match self {
+            Self::Sphere(sphere) => sphere.receives_shadow,
+            Self::Triangle(triangle) => triangle.receives_shadow,
+            Self::Quad(quad) => quad.receives_shadow,
+            Self::Custom(custom) => custom.receives_shadow,
+            Self::Group(group) => group.receives_shadow,
+        }
+    }
+
+    /// Set whether other shapes can shadow this one. When not, it's lit as
+    /// if nothing stood between it and the light, which suits backdrops and
+    /// sky domes. For a group, this applies to everything already in it.
+    pub fn set_receives_shadow(&mut self, receives_shadow: bool) {
+        match self {
+            Self::Sphere(sphere) => sphere.receives_shadow = receives_shadow,
+            Self::Triangle(triangle) => triangle.receives_shadow = receives_shadow,
+            Self::Quad(quad) => quad.receives_shadow = receives_shadow,
+            Self::Custom(custom) => custom.receives_shadow = receives_shadow,
+            Self::Group(group) => {
+                group.receives_shadow = receives_shadow;
+                for child in &mut group.children {
+                    child.set_receives_shadow(receives_shadow);
+                }
+            }
+        }
+    }
+
+    /// Which lights shine on the shape, as set by [`Shape::set_light_mask`].
+    pub fn light_mask(&self) -> u64 {
+        match self {
+            Self::Sphere(sphere) => sphere.light_mask,
+            Self::Triangle(triangle) => triangle.light_mask,
+            Self::Quad(quad) => quad.light_mask,
+            Self::Custom(custom) => custom.light_mask,
+            Self::Group(group) => group.light_mask,
+        }
+    }
+
+    /// Choose which of the world's lights shine on the shape, so a fill
+    /// light can brighten just one object. Bit `n` of `mask` is for the light
+    /// with id `n`, as returned by `World::add_light`; lights with ids of 64
+    /// or more shine on every shape. All lights shine on a shape by default.
+    /// For a group, this applies to everything already in it.
+    pub fn set_light_mask(&mut self, mask: u64) {
+        match self {
+            Self::Sphere(sphere) => sphere.light_mask = mask,
+            Self::Triangle(triangle) => triangle.light_mask = mask,
+            Self::Quad(quad) => quad.light_mask = mask,
+            Self::Custom(custom) => custom.light_mask = mask,
+            Self::Group(group) => {
+                group.light_mask = mask;
+                for child in &mut group.children {
+                    child.set_light_mask(mask);
+                }
+            }
+        }
+    }
+
+    /// Whether the light with id `light` shines on the shape.
+    pub fn is_lit_by(&self, light: usize) -> bool {
+        light >= u64::BITS as usize || self.light_mask() & (1 << light) != 0
+    }
+
+    /// The first shape named `name` within this one, searching depth first
+    /// through groups, starting with the shape itself.
+    pub fn find(&self, name: &str) -> Option<&Shape> {
+        if self.name() == Some(name) {
+            return Some(self);
+        }
+        match self {
+            Self::Group(group) => group.children.iter().find_map(|child| child.find(name)),
+            _ => None,
+        }
+    }
+
+    /// Call `update` with the first shape named `name` within this one, as
+    /// found by [`Shape::find`], returning whether there was one. The groups
+    /// it's nested in then fit their bounds to the change.
+    pub fn update(&mut self, name: &str, update: impl FnOnce(&mut Shape)) -> bool {
+        self.try_update(name, update).is_ok()
+    }
+
+    /// Like [`Shape::update`], but handing `update` back if nothing is named
+    /// `name`, so the search can go on elsewhere.
+    pub(crate) fn try_update<F: FnOnce(&mut Shape)>(
+        &mut self,
+        name: &str,
+        update: F,
+    ) -> Result<(), F> {
+        if self.name() == Some(name) {
+            update(self);
+            return Ok(());
+        }
+        let Self::Group(group) = self else {
+            return Err(update);
+        };
+        let mut update = update;
+        for child in &mut group.children {
+            match child.try_update(name, update) {
+                Ok(()) => {
+                    group.update_bounds();
+                    return Ok(());
+                }
+                Err(unused) => update = unused,
+            }
+        }
+        Err(update)
+    }
+
+    fn placement(&self) -> &Placement {
+        match self {
+            Self::Sphere(sphere) => &sphere.placement,
+            Self::Triangle(triangle) => &triangle.placement,
+            Self::Quad(quad) => &quad.placement,
+            Self::Custom(custom) => &custom.placement,
+            Self::Group(group) => &group.placement,
+        }
+    }
+
+    /// The shape's own transformation, relative to any group it's in.
+    pub fn transformation(&self) -> &Matrix4 {
+        &self.placement().transformation
+    }
+
+    /// Panics if `transformation` isn't invertible.
+    pub fn set_transform(&mut self, transformation: Matrix4) {
+        self.try_set_transform(transformation)
+            .expect("shape transformation must be invertible");
+    }
+
+    /// Like [`Shape::set_transform`], but fails with
+    /// [`MatrixError::Singular`] rather than panicking, leaving the shape as
+    /// it was.
+    ///
+    /// [`MatrixError::Singular`]: crate::error::MatrixError::Singular
+    pub fn try_set_transform(&mut self, transformation: Matrix4) -> Result<(), Error> {
+        (self.placement().parent * transformation).inverse()?;
+        match self {
+            Self::Sphere(sphere) => sphere.set_transform(transformation),
+            Self::Triangle(triangle) => triangle.set_transform(transformation),
+            Self::Quad(quad) => quad.set_transform(transformation),
+            Self::Custom(custom) => custom.set_transform(transformation),
+            Self::Group(group) => group.set_transform(transformation),
+        }
+        Ok(())
+    }
+
+    /// The inverse of the shape's transformation combined with those of all
+    /// the groups it's nested in, mapping world space to object space.
+    pub fn inverse_transformation(&self) -> &Matrix4 {
+        &self.placement().inverse
+    }
+
+    /// Convert a world-space point to the shape's object space, through the
+    /// transformations of any groups it's nested in.
+    pub fn world_to_object(&self, point: &Point) -> Point {
+        self.placement().world_to_object(point)
+    }
+
+    /// Convert an object-space normal to a normalized world-space one,
+    /// through the transformations of any groups the shape is nested in.
+    pub fn normal_to_world(&self, normal: &Vector) -> Vector {
+        self.placement().normal_to_world(normal)
+    }
+
+    /// The box enclosing the shape in its own object space.
+    pub fn bounds(&self) -> BoundingBox {
+        match self {
+            Self::Sphere(_) => {
+                BoundingBox::new(Point::new(-1.0, -1.0, -1.0), Point::new(1.0, 1.0, 1.0))
+            }
+            Self::Triangle(triangle) => triangle.bounds(),
+            Self::Quad(quad) => quad.bounds(),
+            Self::Custom(custom) => custom.local.local_bounds(),
+            Self::Group(group) => group.bounds,
+        }
+    }
+
+    /// The box enclosing the shape in the space of the group it's in, which
+    /// for a moving sphere covers its whole path.
+    pub fn parent_space_bounds(&self) -> BoundingBox {
+        let mut bounds = self.bounds().transform(self.transformation());
+        if let Self::Sphere(Sphere {
+            motion: Some(motion),
+            ..
+        }) = self
+        {
+            bounds.add_box(&self.bounds().transform(&motion.transformation));
+        }
+        bounds
+    }
+
+    /// Split any groups in this shape into nested sub-groups with no more
+    /// than about `threshold` children each. See [`Group::divide`].
+    pub fn divide(&mut self, threshold: usize) {
+        if let Self::Group(group) = self {
+            group.divide(threshold);
+        }
+    }
+
+    /// Turn smoothing on or off for a triangle, or every triangle in a
+    /// group. See [`Triangle::set_smooth`].
+    pub fn set_smooth(&mut self, smooth: bool) {
+        match self {
+            Self::Triangle(triangle) => triangle.set_smooth(smooth),
+            Self::Group(group) => {
+                for child in &mut group.children {
+                    child.set_smooth(smooth);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Index the children of a group with a kd-tree. See
+    /// [`Group::build_kd_tree`].
+    pub fn build_kd_tree(&mut self, leaf_size: usize) {
+        if let Self::Group(group) = self {
+            group.build_kd_tree(leaf_size);
+        }
+    }
+
+    /// Tell the shape the combined transformation of the groups it's nested
+    /// in, when it's added to a group or that group moves.
+    fn set_parent(&mut self, parent: Matrix4) {
+        match self {
+            Self::Sphere(sphere) => sphere.set_parent(parent),
+            Self::Triangle(triangle) => triangle.placement.set_parent(parent),
+            Self::Quad(quad) => quad.placement.set_parent(parent),
+            Self::Custom(custom) => custom.placement.set_parent(parent),
+            Self::Group(group) => group.set_parent(parent),
+        }
+    }
+}
+
+impl From<Sphere> for Shape {
+    fn from(value: Sphere) -> Self {
+        Self::Sphere(value)
+    }
+}
+
+impl From<Triangle> for Shape {
+    fn from(value: Triangle) -> Self {
+        Self::Triangle(value)
+    }
+}
+
+impl From<Quad> for Shape {
+    fn from(value: Quad) -> Self {
+        Self::Quad(value)
+    }
+}
+
+impl From<CustomShape> for Shape {
+    fn from(value: CustomShape) -> Self {
+        Self::Custom(value)
+    }
+}
+
+impl From<Group> for Shape {
+    fn from(value: Group) -> Self {
+        Self::Group(value)
+    }
+}
+
+/// Where a shape sits in the world: its own transformation and the combined
+/// transformation of the groups it's nested in, with the inverses needed to
+/// move between world and object space cached, as they're needed for every
+/// ray.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "PlacementData", try_from = "PlacementData")
+)]
+struct Placement {
+    transformation: Matrix4,
+    parent: Matrix4,
+    inverse: Matrix4,
+    /// Transforms normals to world space.
+    normal: Matrix3,
+}
+
+impl Placement {
+    fn new() -> Self {
+        Self {
+            transformation: Matrix4::identity(),
+            parent: Matrix4::identity(),
+            inverse: Matrix4::identity(),
+            normal: Matrix3::identity(),
+        }
+    }
+
+    /// Panics if `transformation` isn't invertible.
+    fn set_transform(&mut self, transformation: Matrix4) {
+        self.update(self.parent, transformation);
+    }
+
+    fn set_parent(&mut self, parent: Matrix4) {
+        self.update(parent, self.transformation);
+    }
+
+    fn update(&mut self, parent: Matrix4, transformation: Matrix4) {
+        let inverse = (parent * transformation)
+            .inverse()
+            .expect("shape transformation must be invertible");
+        self.transformation = transformation;
+        self.parent = parent;
+        self.inverse = inverse;
+        self.normal = inverse.submatrix(3, 3).transpose();
+    }
+
+    /// The transformation from object space all the way to world space.
+    fn to_world(&self) -> Matrix4 {
+        self.parent * self.transformation
+    }
+
+    fn world_to_object(&self, point: &Point) -> Point {
+        self.inverse * (*point)
+    }
+
+    fn normal_to_world(&self, normal: &Vector) -> Vector {
+        (self.normal * (*normal)).normalize()
+    }
+}
+
+/// A [`Placement`] as serialized, without the inverses, which are recomputed
+/// when it's read back in.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct PlacementData {
+    transformation: Matrix4,
+    parent: Matrix4,
+}
+
+#[cfg(feature = "serde")]
+impl From<Placement> for PlacementData {
+    fn from(value: Placement) -> Self {
+        Self {
+            transformation: value.transformation,
+            parent: value.parent,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<PlacementData> for Placement {
+    type Error = &'static str;
+
+    fn try_from(value: PlacementData) -> Result<Self, Self::Error> {
+        let inverse = (value.parent * value.transformation)
+            .inverse()
+            .map_err(|_| "shape transformation must be invertible")?;
+        Ok(Self {
+            transformation: value.transformation,
+            parent: value.parent,
+            inverse,
+            normal: inverse.submatrix(3, 3).transpose(),
+        })
+    }
+}
+
+/// Movement of a shape while the camera's shutter is open, for motion blur.
+///
+/// The shape's own transformation applies at `start`, and `transformation`
+/// at `end`. In between the two matrices are interpolated element by element,
+/// and rays are cast through their inverses interpolated the same way, which
+/// is exact for translation but only approximate for scaling and rotation.
+/// Outside the interval the shape stays at the nearest end.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Motion {
+    pub transformation: Matrix4,
+    pub start: Float,
+    pub end: Float,
+}
+
+impl Motion {
+    pub fn new(transformation: Matrix4, start: Float, end: Float) -> Self {
+        Self {
+            transformation,
+            start,
+            end,
+        }
+    }
+
+    /// How far through the motion `time` is, from 0.0 to 1.0.
+    fn progress(&self, time: Float) -> Float {
+        if self.end > self.start {
+            ((time - self.start) / (self.end - self.start)).clamp(0.0, 1.0)
+        } else if time < self.start {
+            0.0
+        } else {
+            1.0
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "SphereData", try_from = "SphereData")
+)]
+pub struct Sphere {
+    placement: Placement,
+    material: Material,
+    name: Option<String>,
+    casts_shadow: bool,
+    receives_shadow: bool,
+    light_mask: u64,
+    motion: Option<Motion>,
+    // The inverse at the end of the motion, through the groups the sphere is
+    // in, if it's moving.
+    end_inverse: Matrix4,
+}
+
+impl Sphere {
+    pub fn new() -> Self {
+        Self::with_transform(Matrix4::identity())
+    }
+
+    pub fn intersect(&self, ray: &Ray) -> Vec<Float> {
+        match self.roots(ray) {
+            Some((t1, t2)) => vec![t1, t2],
+            None => vec![],
+        }
+    }
+
+    /// Where `ray` enters and leaves the sphere, if it hits it at all.
+    fn roots(&self, ray: &Ray) -> Option<(Float, Float)> {
+        let ray2 = ray.transform(&self.inverse_at(ray.time));
+
+        let sphere_to_ray = ray2.origin - Point::new(0., 0., 0.);
+        let a = ray2.direction.dot(&ray2.direction);
+        let b = 2. * ray2.direction.dot(&sphere_to_ray);
+        let c = sphere_to_ray.dot(&sphere_to_ray) - 1.0;
+        let discriminant = b * b - 4. * a * c;
+
+        if discriminant >= 0.0 {
+            Some(((-b - discriminant.sqrt()) / (2. * a),
+            (-b + discriminant.sqrt()) / (2. * a)))
+        } else {
+            None
+        }
+    }
+
+    pub fn with_transform(transformation: Matrix4) -> Self {
+        let mut sphere = Self {
+            placement: Placement::new(),
+            material: Material::new(),
+            name: None,
+            casts_shadow: true,
+            receives_shadow: true,
+            light_mask: u64::MAX,
+            motion: None,
+            end_inverse: Matrix4::identity(),
+        };
+        sphere.set_transform(transformation);
+        sphere
+    }
+
+    /// A sphere of `radius` centred on `center`, built by baking the
+    /// equivalent scaling and translation into its transformation.
+    ///
+    /// Panics if `radius` is zero.
+    pub fn with_geometry(center: Point, radius: Float) -> Self {
+        Self::with_transform(
+            Matrix4::translation(center.x(), center.y(), center.z())
+                * Matrix4::scaling(radius, radius, radius),
+        )
+    }
+
+    pub fn transformation(&self) -> &Matrix4 {
+        &self.placement.transformation
+    }
+
+    /// Set the sphere's transformation, precomputing its inverse.
+    ///
+    /// Panics if `transformation` isn't invertible.
+    pub fn set_transform(&mut self, transformation: Matrix4) {
+        self.placement.set_transform(transformation);
+    }
+
+    fn set_parent(&mut self, parent: Matrix4) {
+        self.placement.set_parent(parent);
+        if let Some(motion) = &self.motion {
+            self.end_inverse = (parent * motion.transformation)
+                .inverse()
+                .expect("motion transformation must be invertible");
+        }
+    }
+
+    pub fn motion(&self) -> Option<&Motion> {
+        self.motion.as_ref()
+    }
+
+    /// Set the sphere moving, precomputing the inverse of where it ends up.
+    /// Use [`Shape::update`] to change a sphere within a group, so the
+    /// group's bounds cover its new path.
+    ///
+    /// Panics if the motion's transformation isn't invertible.
+    pub fn set_motion(&mut self, motion: Motion) {
+        self.try_set_motion(motion)
+            .expect("motion transformation must be invertible");
+    }
+
+    /// Like [`Sphere::set_motion`], but failing with
+    /// [`MatrixError::Singular`] rather than panicking, leaving the sphere
+    /// as it was.
+    ///
+    /// [`MatrixError::Singular`]: crate::error::MatrixError::Singular
+    pub fn try_set_motion(&mut self, motion: Motion) -> Result<(), Error> {
+        self.end_inverse = (self.placement.parent * motion.transformation).inverse()?;
+        self.motion = Some(motion);
+        Ok(())
+    }
+
+    /// Stop the sphere moving.
+    pub fn clear_motion(&mut self) {
+        self.motion = None;
+        self.end_inverse = Matrix4::identity();
+    }
+
+    /// The sphere's transformation at `time`, taking any motion into account.
+    pub fn transformation_at(&self, time: Float) -> Matrix4 {
+        match &self.motion {
+            Some(motion) => self
+                .placement
+                .transformation
+                .lerp(&motion.transformation, motion.progress(time)),
+            None => self.placement.transformation,
+        }
+    }
+
+    fn inverse_at(&self, time: Float) -> Matrix4 {
+        match &self.motion {
+            Some(motion) => self
+                .placement
+                .inverse
+                .lerp(&self.end_inverse, motion.progress(time)),
+            None => self.placement.inverse,
+        }
+    }
+
+    pub fn material(&self) -> &Material {
+        &self.material
+    }
+
+    pub fn material_mut(&mut self) -> &mut Material {
+        &mut self.material
+    }
+
+    pub fn normal_at(&self, p: &Point) -> Vector {
+        self.normal_at_time(p, 0.0)
+    }
+
+    pub fn normal_at_time(&self, p: &Point, time: Float) -> Vector {
+        let (it, itt) = match &self.motion {
+            Some(_) => {
+                let it = self.inverse_at(time);
+                (it, it.submatrix(3, 3).transpose())
+            }
+            None => (self.placement.inverse, self.placement.normal),
+        };
+        let op = it * (*p);
+        let on = op.subtract_origin();
+        let wn = itt * on;
+        wn.normalize()
+    }
+}
+
+impl Default for Sphere {
+    fn default() -> Self {
+        Sphere::new()
+    }
+}
+
+/// A [`Sphere`] as serialized, without the inverse at the end of its
+/// motion, which is recomputed when it's read back in.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct SphereData {
+    placement: Placement,
+    material: Material,
+    name: Option<String>,
+    casts_shadow: bool,
+    receives_shadow: bool,
+    light_mask: u64,
+    motion: Option<Motion>,
+}
+
+#[cfg(feature = "serde")]
+impl From<Sphere> for SphereData {
+    fn from(value: Sphere) -> Self {
+        Self {
+            placement: value.placement,
+            material: value.material,
+            name: value.name,
+            casts_shadow: value.casts_shadow,
+            receives_shadow: value.receives_shadow,
+            light_mask: value.light_mask,
+            motion: value.motion,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<SphereData> for Sphere {
+    type Error = Error;
+
+    fn try_from(value: SphereData) -> Result<Self, Self::Error> {
+        let mut sphere = Self {
+            placement: value.placement,
+            material: value.material,
+            name: value.name,
+            casts_shadow: value.casts_shadow,
+            receives_shadow: value.receives_shadow,
+            light_mask: value.light_mask,
+            motion: None,
+            end_inverse: Matrix4::identity(),
+        };
+        if let Some(motion) = value.motion {
+            sphere.try_set_motion(motion)?;
+        }
+        Ok(sphere)
+    }
+}
+
+/// A unit sphere of clear glass: fully transparent, with a refractive index
+/// of 1.5.
+pub fn glass_sphere() -> Sphere {
+    let mut s = Sphere::new();
+    let material = s.material_mut();
+    material.transparency = 1.0;
+    material.refractive_index = 1.5;
+    s
+}
+
+/// A flat triangle between three points, the building block of meshes.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Triangle {
+    placement: Placement,
+    material: Material,
+    name: Option<String>,
+    casts_shadow: bool,
+    receives_shadow: bool,
+    light_mask: u64,
+    p1: Point,
+    p2: Point,
+    p3: Point,
+    // Precomputed, as they're needed for every ray.
+    e1: Vector,
+    e2: Vector,
+    normal: Vector,
+    /// At `p1`, `p2` and `p3`, blended across the triangle when smooth.
+    #[cfg_attr(feature = "serde", serde(default))]
+    normals: Option<[Vector; 3]>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    smooth: bool,
+}
+
+impl Triangle {
+    pub fn new(p1: Point, p2: Point, p3: Point) -> Self {
+        let e1 = p2 - p1;
+        let e2 = p3 - p1;
+        Self {
+            placement: Placement::new(),
+            material: Material::new(),
+            name: None,
+            casts_shadow: true,
+            receives_shadow: true,
+            light_mask: u64::MAX,
+            p1,
+            p2,
+            p3,
+            e1,
+            e2,
+            normal: e2.cross(e1).normalize(),
+            normals: None,
+            smooth: false,
+        }
+    }
+
+    /// A smooth triangle, with the normals at `p1`, `p2` and `p3` blended
+    /// across it so a mesh of them looks curved.
+    pub fn with_normals(
+        p1: Point,
+        p2: Point,
+        p3: Point,
+        n1: Vector,
+        n2: Vector,
+        n3: Vector,
+    ) -> Self {
+        let mut triangle = Self::new(p1, p2, p3);
+        triangle.set_normals(n1, n2, n3);
+        triangle
+    }
+
+    pub fn points(&self) -> (Point, Point, Point) {
+        (self.p1, self.p2, self.p3)
+    }
+
+    /// The normals at each point, if they've been set.
+    pub fn normals(&self) -> Option<(Vector, Vector, Vector)> {
+        self.normals.map(|[n1, n2, n3]| (n1, n2, n3))
+    }
+
+    /// Set the normals at each point, and make the triangle smooth.
+    pub fn set_normals(&mut self, n1: Vector, n2: Vector, n3: Vector) {
+        self.normals = Some([n1.normalize(), n2.normalize(), n3.normalize()]);
+        self.smooth = true;
+    }
+
+    /// Whether the normals at each point are blended across the triangle.
+    /// Without them, it's flat either way.
+    pub fn smooth(&self) -> bool {
+        self.smooth
+    }
+
+    pub fn set_smooth(&mut self, smooth: bool) {
+        self.smooth = smooth;
+    }
+
+    pub fn transformation(&self) -> &Matrix4 {
+        &self.placement.transformation
+    }
+
+    /// Set the triangle's transformation, precomputing its inverse.
+    ///
+    /// Panics if `transformation` isn't invertible.
+    pub fn set_transform(&mut self, transformation: Matrix4) {
+        self.placement.set_transform(transformation);
+    }
+
+    pub fn material(&self) -> &Material {
+        &self.material
+    }
+
+    pub fn material_mut(&mut self) -> &mut Material {
+        &mut self.material
+    }
+
+    pub fn intersect(&self, ray: &Ray) -> Vec<Float> {
+        self.intersection_t(ray).into_iter().collect()
+    }
+
+    /// Intersect using the Möller–Trumbore algorithm.
+    fn intersection_t(&self, ray: &Ray) -> Option<Float> {
+        let ray = ray.transform(&self.placement.inverse);
+        let dir_cross_e2 = ray.direction.cross(self.e2);
+        let det = self.e1.dot(&dir_cross_e2);
+        if det.abs() < EPSILON {
+            return None;
+        }
+
+        let f = 1.0 / det;
+        let p1_to_origin = ray.origin - self.p1;
+        let u = f * p1_to_origin.dot(&dir_cross_e2);
+        if !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+
+        let origin_cross_e1 = p1_to_origin.cross(self.e1);
+        let v = f * ray.direction.dot(&origin_cross_e1);
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+
+        Some(f * self.e2.dot(&origin_cross_e1))
+    }
+
+    /// The same everywhere on a flat triangle. On a smooth one, the normals
+    /// at each point weighted by how close `p` is to them.
+    pub fn normal_at(&self, p: &Point) -> Vector {
+        match self.normals {
+            Some([n1, n2, n3]) if self.smooth => {
+                let (u, v) = self.barycentric(&self.placement.world_to_object(p));
+                self.placement
+                    .normal_to_world(&(n2 * u + n3 * v + n1 * (1.0 - u - v)))
+            }
+            _ => self.placement.normal_to_world(&self.normal),
+        }
+    }
+
+    /// How far `p` is towards `p2` and `p3`, as the weights of those points.
+    fn barycentric(&self, p: &Point) -> (Float, Float) {
+        let to_p = *p - self.p1;
+        let (d11, d12, d22) = (
+            self.e1.dot(&self.e1),
+            self.e1.dot(&self.e2),
+            self.e2.dot(&self.e2),
+        );
+        let (dp1, dp2) = (to_p.dot(&self.e1), to_p.dot(&self.e2));
+        let denominator = d11 * d22 - d12 * d12;
+        (
+            (d22 * dp1 - d12 * dp2) / denominator,
+            (d11 * dp2 - d12 * dp1) / denominator,
+        )
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        let mut bounds = BoundingBox::empty();
+        for p in [&self.p1, &self.p2, &self.p3] {
+            bounds.add_point(p);
+        }
+        bounds
+    }
+}
+
+/// A flat parallelogram with one corner at `corner` and its sides along the
+/// `u` and `v` edges, so the opposite corner is at `corner + u + v`. Use
+/// perpendicular edges for a rectangle.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Quad {
+    placement: Placement,
+    material: Material,
+    name: Option<String>,
+    casts_shadow: bool,
+    receives_shadow: bool,
+    light_mask: u64,
+    corner: Point,
+    u: Vector,
+    v: Vector,
+    // Precomputed, as they're needed for every ray.
+    normal: Vector,
+    // The plane's normal scaled so that dotting it with a cross product of
+    // an edge and a point's offset gives that point's edge coordinates.
+    w: Vector,
+}
+
+impl Quad {
+    /// The quad faces along `u × v`.
+    ///
+    /// Panics if `u` and `v` are parallel, as the quad would have no area.
+    /// That's judged by the angle between them rather than the area, so
+    /// small quads are fine.
+    pub fn new(corner: Point, u: Vector, v: Vector) -> Self {
+        Self::try_new(corner, u, v).expect("quad edges must not be parallel")
+    }
+
+    /// Like [`Quad::new`], but fails with [`Error::ParallelQuadEdges`]
+    /// rather than panicking.
+    pub fn try_new(corner: Point, u: Vector, v: Vector) -> Result<Self, Error> {
+        let n = u.cross(v);
+        let area_squared = n.dot(&n);
+        // The squared sine of the angle between the edges, or NaN if either
+        // has no length.
+        let sin_squared = area_squared / (u.dot(&u) * v.dot(&v));
+        if sin_squared.is_nan() || sin_squared <= EPSILON * EPSILON {
+            return Err(Error::ParallelQuadEdges);
+        }
+        Ok(Self {
+            placement: Placement::new(),
+            material: Material::new(),
+            name: None,
+            casts_shadow: true,
+            receives_shadow: true,
+            light_mask: u64::MAX,
+            corner,
+            u,
+            v,
+            normal: n.normalize(),
+            w: n * (1.0 / area_squared),
+        })
+    }
+
+    pub fn corner(&self) -> Point {
+        self.corner
+    }
+
+    pub fn edges(&self) -> (Vector, Vector) {
+        (self.u, self.v)
+    }
+
+    pub fn transformation(&self) -> &Matrix4 {
+        &self.placement.transformation
+    }
+
+    /// Set the quad's transformation, precomputing its inverse.
+    ///
+    /// Panics if `transformation` isn't invertible.
+    pub fn set_transform(&mut self, transformation: Matrix4) {
+        self.placement.set_transform(transformation);
+    }
+
+    pub fn material(&self) -> &Material {
+        &self.material
+    }
+
+    pub fn material_mut(&mut self) -> &mut Material {
+        &mut self.material
+    }
+
+    pub fn intersect(&self, ray: &Ray) -> Vec<Float> {
+        self.intersection_t(ray).into_iter().collect()
+    }
+
+    fn intersection_t(&self, ray: &Ray) -> Option<Float> {
+        let ray = ray.transform(&self.placement.inverse);
+        let denominator = self.normal.dot(&ray.direction);
+        if denominator.abs() < EPSILON {
+            return None;
+        }
+
+        let t = self.normal.dot(&(self.corner - ray.origin)) / denominator;
+        let offset = ray.position(t) - self.corner;
+        let alpha = self.w.dot(&offset.cross(self.v));
+        let beta = self.w.dot(&self.u.cross(offset));
+        if (0.0..=1.0).contains(&alpha) && (0.0..=1.0).contains(&beta) {
+            Some(t)
+        } else {
+            None
+        }
+    }
+
+    /// The same everywhere on the quad, so `_p` is ignored.
+    pub fn normal_at(&self, _p: &Point) -> Vector {
+        self.placement.normal_to_world(&self.normal)
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        let mut bounds = BoundingBox::empty();
+        for p in [
+            self.corner,
+            self.corner + self.u,
+            self.corner + self.v,
+            self.corner + self.u + self.v,
+        ] {
+            bounds.add_point(&p);
+        }
+        bounds
+    }
+}
+
+/// The geometry of a primitive defined outside this crate, in its own object
+/// space. Wrap it in a [`CustomShape`] to give it a transformation and a
+/// material and add it to a world.
+pub trait LocalShape: fmt::Debug + Send + Sync {
+    /// The distances along `ray`, already in object space, at which it
+    /// crosses the shape's surface.
+    fn local_intersect(&self, ray: &Ray) -> Vec<Float>;
+
+    /// The object-space normal at `point`, which lies on the surface. It
+    /// needn't be normalized.
+    fn local_normal_at(&self, point: &Point) -> Vector;
+
+    /// The box enclosing the shape in object space. Unbounded by default,
+    /// which stops any group containing the shape from being culled.
+    fn local_bounds(&self) -> BoundingBox {
+        BoundingBox::infinite()
+    }
+}
+
+/// A user-defined primitive, placed in the world like a [`Sphere`].
+///
+/// Custom shapes don't support motion blur.
+#[derive(Clone, Debug)]
+pub struct CustomShape {
+    local: Arc<dyn LocalShape>,
+    placement: Placement,
+    material: Material,
+    name: Option<String>,
+    casts_shadow: bool,
+    receives_shadow: bool,
+    light_mask: u64,
+}
+
+impl CustomShape {
+    pub fn new(local: impl LocalShape + 'static) -> Self {
+        Self {
+            local: Arc::new(local),
+            placement: Placement::new(),
+            material: Material::new(),
+            name: None,
+            casts_shadow: true,
+            receives_shadow: true,
+            light_mask: u64::MAX,
+        }
+    }
+
+    pub fn with_transform(local: impl LocalShape + 'static, transformation: Matrix4) -> Self {
+        let mut shape = Self::new(local);
+        shape.set_transform(transformation);
+        shape
+    }
+
+    pub fn local(&self) -> &dyn LocalShape {
+        self.local.as_ref()
+    }
+
+    pub fn transformation(&self) -> &Matrix4 {
+        &self.placement.transformation
+    }
+
+    /// Set the shape's transformation, precomputing its inverse.
+    ///
+    /// Panics if `transformation` isn't invertible.
+    pub fn set_transform(&mut self, transformation: Matrix4) {
+        self.placement.set_transform(transformation);
+    }
+
+    pub fn material(&self) -> &Material {
+        &self.material
+    }
+
+    pub fn material_mut(&mut self) -> &mut Material {
+        &mut self.material
+    }
+
+    pub fn intersect(&self, ray: &Ray) -> Vec<Float> {
+        self.local
+            .local_intersect(&ray.transform(&self.placement.inverse))
+    }
+
+    pub fn normal_at(&self, p: &Point) -> Vector {
+        let local_point = self.placement.world_to_object(p);
+        let local_normal = self.local.local_normal_at(&local_point);
+        self.placement.normal_to_world(&local_normal)
+    }
+}
+
+impl PartialEq for CustomShape {
+    /// Custom shapes are equal if they share the same geometry, not merely
+    /// geometry that compares equal, as `LocalShape` has no way to compare.
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.local, &other.local)
+            && self.placement == other.placement
+            && self.material == other.material
+            && self.name == other.name
+            && self.casts_shadow == other.casts_shadow
+            && self.receives_shadow == other.receives_shadow
+            && self.light_mask == other.light_mask
+    }
+}
+
+/// A collection of shapes transformed as one. The group's transformation
+/// applies on top of each child's own.
+///
+/// Groups are never hit themselves, only their children, so a group's
+/// material is never used for shading. Rays that miss the box around all
+/// the children skip testing them individually.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "GroupData", from = "GroupData")
+)]
+pub struct Group {
+    placement: Placement,
+    material: Material,
+    name: Option<String>,
+    casts_shadow: bool,
+    receives_shadow: bool,
+    light_mask: u64,
+    children: Vec<Shape>,
+    // In the group's object space, grown as children are added.
+    bounds: BoundingBox,
+    // Over the children, in the group's object space, if one's been built.
+    kd_tree: Option<KdTree>,
+}
+
+impl Group {
+    pub fn new() -> Self {
+        Self {
+            placement: Placement::new(),
+            material: Material::new(),
+            name: None,
+            casts_shadow: true,
+            receives_shadow: true,
+            light_mask: u64::MAX,
+            children: vec![],
+            bounds: BoundingBox::empty(),
+            kd_tree: None,
+        }
+    }
+
+    pub fn with_transform(transformation: Matrix4) -> Self {
+        let mut group = Self::new();
+        group.set_transform(transformation);
+        group
+    }
+
+    pub fn transformation(&self) -> &Matrix4 {
+        &self.placement.transformation
+    }
+
+    /// Set the group's transformation, moving all its children with it.
+    ///
+    /// Panics if `transformation` isn't invertible.
+    pub fn set_transform(&mut self, transformation: Matrix4) {
+        self.placement.set_transform(transformation);
+        self.update_children();
+    }
+
+    fn set_parent(&mut self, parent: Matrix4) {
+        self.placement.set_parent(parent);
+        self.update_children();
+    }
+
+    fn update_children(&mut self) {
+        let to_world = self.placement.to_world();
+        for child in &mut self.children {
+            child.set_parent(to_world);
+        }
+    }
+
+    pub fn add_child(&mut self, child: impl Into<Shape>) {
+        let mut child = child.into();
+        child.set_parent(self.placement.to_world());
+        self.bounds.add_box(&child.parent_space_bounds());
+        self.children.push(child);
+        self.kd_tree = None;
+    }
+
+    pub fn children(&self) -> &[Shape] {
+        &self.children
+    }
+
+    /// Fit the bounds to the children again after one has changed, and
+    /// rebuild the kd-tree over them if there was one.
+    fn update_bounds(&mut self) {
+        self.bounds = BoundingBox::empty();
+        for child in &self.children {
+            self.bounds.add_box(&child.parent_space_bounds());
+        }
+        if let Some(leaf_size) = self.kd_tree.as_ref().map(KdTree::leaf_size) {
+            self.build_kd_tree(leaf_size);
+        }
+    }
+
+    /// Give each triangle in the group, including those in sub-groups, the
+    /// normals at its points, and make them smooth. The normal at a point is
+    /// the average of the triangles meeting there, weighted by their area,
+    /// so a mesh imported without normals can still be shaded smoothly.
+    /// Triangles only meet where they have exactly the same point once
+    /// transformed into the group's object space, and where the ones meeting
+    /// cancel out, as back to back faces do, each keeps its own normal.
+    pub fn compute_normals(&mut self) {
+        let mut triangles = vec![];
+        self.collect_triangles(&Matrix4::identity(), &mut triangles);
+
+        let key = |p: &Point| p.to_array().map(Float::to_bits);
+        let points: Vec<[Point; 3]> = triangles
+            .iter()
+            .map(|(to_group, triangle)| {
+                let (p1, p2, p3) = triangle.points();
+                [p1, p2, p3].map(|p| to_group * p)
+            })
+            .collect();
+        // The sum at each point, and the total length of what was summed.
+        let mut sums = std::collections::HashMap::new();
+        for &[p1, p2, p3] in &points {
+            // Its length is twice the triangle's area.
+            let weighted = (p3 - p1).cross(p2 - p1);
+            for p in [p1, p2, p3] {
+                let (sum, total) = sums
+                    .entry(key(&p))
+                    .or_insert((Vector::new(0.0, 0.0, 0.0), 0.0));
+                *sum += weighted;
+                *total += weighted.magnitude();
+            }
+        }
+        for ((to_group, triangle), points) in triangles.into_iter().zip(points) {
+            // Normals go back to the triangle's object space by the
+            // transpose, as they come out by the inverse transpose.
+            let to_object = to_group.submatrix(3, 3).transpose();
+            let [n1, n2, n3] = points.map(|p| {
+                let (sum, total) = sums[&key(&p)];
+                if sum.magnitude() <= EPSILON * total {
+                    triangle.normal
+                } else {
+                    to_object * sum
+                }
+            });
+            triangle.set_normals(n1, n2, n3);
+        }
+    }
+
+    /// Collect the triangles within the group, each with the transformation
+    /// from its object space to the space `to_group` leads to.
+    fn collect_triangles<'a>(
+        &'a mut self,
+        to_group: &Matrix4,
+        triangles: &mut Vec<(Matrix4, &'a mut Triangle)>,
+    ) {
+        for child in &mut self.children {
+            match child {
+                Shape::Triangle(triangle) => {
+                    triangles.push((to_group * triangle.transformation(), triangle))
+                }
+                Shape::Group(group) => {
+                    let to_group = to_group * group.transformation();
+                    group.collect_triangles(&to_group, triangles)
+                }
+                _ => {}
+            }
+        }
+    }
+
+    pub fn intersect<'a>(&'a self, ray: &Ray, intersections: &mut Intersections<'a>) {
+        let local = ray.transform(&self.placement.inverse);
+        if !self.bounds.intersects(&local) {
+            return;
+        }
+        match &self.kd_tree {
+            Some(kd_tree) => {
+                kd_tree.visit(&local, |index| {
+                    self.children[index].intersect(ray, intersections)
+                });
+            }
+            None => {
+                for child in &self.children {
+                    child.intersect(ray, intersections);
+                }
+            }
+        }
+    }
+
+    /// Index the children with a [`KdTree`], so rays only test those near
+    /// them. This suits a large mesh of small triangles better than
+    /// [`Group::divide`]. Adding another child drops the index, and
+    /// changing one with [`Shape::update`] rebuilds it.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip(self), fields(children = self.children.len()))
+    )]
+    pub fn build_kd_tree(&mut self, leaf_size: usize) {
+        let bounds = self.children.iter().map(Shape::parent_space_bounds);
+        self.kd_tree = Some(KdTree::new(bounds, kdtree::DEFAULT_MAX_DEPTH, leaf_size));
+    }
+
+    /// Take out the children that fit entirely within the left or right half
+    /// of the group's bounds, leaving those that straddle both.
+    fn partition_children(&mut self) -> (Vec<Shape>, Vec<Shape>) {
+        let (left_bounds, right_bounds) = self.bounds.split();
+        let (mut left, mut right) = (vec![], vec![]);
+        for child in std::mem::take(&mut self.children) {
+            let bounds = child.parent_space_bounds();
+            if left_bounds.contains_box(&bounds) {
+                left.push(child);
+            } else if right_bounds.contains_box(&bounds) {
+                right.push(child);
+            } else {
+                self.children.push(child);
+            }
+        }
+        (left, right)
+    }
+
+    fn make_subgroup(&mut self, children: Vec<Shape>) {
+        let mut subgroup = Group::new();
+        for child in children {
+            subgroup.add_child(child);
+        }
+        self.add_child(subgroup);
+    }
+
+    /// Recursively split the children into nested sub-groups by position,
+    /// until no group has `threshold` or more children or they can't be
+    /// split any further. Rays can then skip most of a large group, such as
+    /// an imported mesh, after a few bounding box tests.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip(self), fields(children = self.children.len()))
+    )]
+    pub fn divide(&mut self, threshold: usize) {
+        self.kd_tree = None;
+        if threshold <= self.children.len() {
+            let (left, right) = self.partition_children();
+            if !left.is_empty() {
+                self.make_subgroup(left);
+            }
+            if !right.is_empty() {
+                self.make_subgroup(right);
+            }
+        }
+        for child in &mut self.children {
+            child.divide(threshold);
+        }
+    }
+}
+
+impl Default for Group {
+    fn default() -> Self {
+        Group::new()
+    }
+}
+
+/// A [`Group`] as serialized, without its bounds or kd-tree, which are
+/// rebuilt from the children when it's read back in. An empty group's
+/// bounds are infinite, which JSON can't represent.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct GroupData {
+    placement: Placement,
+    material: Material,
+    name: Option<String>,
+    casts_shadow: bool,
+    receives_shadow: bool,
+    light_mask: u64,
+    children: Vec<Shape>,
+    /// The leaf size of the group's kd-tree, if it had one.
+    #[serde(default)]
+    kd_tree_leaf_size: Option<usize>,
+}
+
+#[cfg(feature = "serde")]
+impl From<Group> for GroupData {
+    fn from(value: Group) -> Self {
+        Self {
+            placement: value.placement,
+            material: value.material,
+            name: value.name,
+            casts_shadow: value.casts_shadow,
+            receives_shadow: value.receives_shadow,
+            light_mask: value.light_mask,
+            children: value.children,
+            kd_tree_leaf_size: value.kd_tree.as_ref().map(KdTree::leaf_size),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<GroupData> for Group {
+    fn from(value: GroupData) -> Self {
+        let mut bounds = BoundingBox::empty();
+        for child in &value.children {
+            bounds.add_box(&child.parent_space_bounds());
+        }
+        let mut group = Self {
+            placement: value.placement,
+            material: value.material,
+            name: value.name,
+            casts_shadow: value.casts_shadow,
+            receives_shadow: value.receives_shadow,
+            light_mask: value.light_mask,
+            children: value.children,
+            bounds,
+            kd_tree: None,
+        };
+        if let Some(leaf_size) = value.kd_tree_leaf_size {
+            group.build_kd_tree(leaf_size);
+        }
+        group
+    }
+}
+
+#[cfg(feature = "serde")]
+fn serialize_custom<S: serde::Serializer>(
+    _custom: &CustomShape,
+    _serializer: S,
+) -> Result<S::Ok, S::Error> {
+    Err(serde::ser::Error::custom(
+        "custom shapes can't be serialized",
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::consts::{FRAC_1_SQRT_2, PI};
+
+    use crate::error::MatrixError;
+    use crate::{ray::Ray, space::Vector};
+
+    use std::sync::Mutex;
+
+    use super::*;
+
+    /// A shape that never gets hit, but remembers the last ray it was asked to
+    /// intersect, for testing how shapes transform rays. Clones share the same
+    /// record, so keep a clone to inspect after handing one to a
+    /// [`CustomShape`].
+    #[derive(Clone, Debug, Default)]
+    struct TestShape {
+        saved_ray: Arc<Mutex<Option<Ray>>>,
+    }
+
+    impl TestShape {
+        fn new() -> Self {
+            Self::default()
+        }
+
+        /// The object-space ray most recently passed to `local_intersect`.
+        fn saved_ray(&self) -> Option<Ray> {
+            self.saved_ray.lock().unwrap().clone()
+        }
+    }
+
+    impl LocalShape for TestShape {
+        fn local_intersect(&self, ray: &Ray) -> Vec<Float> {
+            *self.saved_ray.lock().unwrap() = Some(ray.clone());
+            vec![]
+        }
+
+        fn local_normal_at(&self, point: &Point) -> Vector {
+            Vector::new(point.x(), point.y(), point.z())
+        }
+
+        fn local_bounds(&self) -> BoundingBox {
+            BoundingBox::new(Point::new(-1.0, -1.0, -1.0), Point::new(1.0, 1.0, 1.0))
+        }
+    }
+
+    #[test]
+    fn test_intersect_sphere() {
+        let r = Ray::new(Point::new(0., 0., -5.), Vector::new(0., 0., 1.));
+        let s: Shape = Sphere::new().into();
+
+
+        let mut is = Intersections::new();
+        s.intersect(&r, &mut is);
+        assert_eq!(is.len(), 2);
+        let mut is_iter = is.into_iter();
+        let i = is_iter.next().expect("First intersection");
+        assert_eq!(i.t, 4.0);
+        assert_eq!(i.shape, &s);
+        let i = is_iter.next().expect("Second intersection");
+        assert_eq!(i.t, 6.0);
+        assert_eq!(i.shape, &s);
+    }
+
+    #[test]
+    fn test_intersect_sphere_tangent() {
+        let r = Ray::new(Point::new(0., 1., -5.), Vector::new(0., 0., 1.));
+        let s: Shape = Sphere::new().into();
+
+        let mut is = Intersections::new();
+        s.intersect(&r, &mut is);
+        assert_eq!(is.len(), 2);
+
+        let mut is_iter = is.into_iter();
+        let i = is_iter.next().expect("First intersection");
+        assert_eq!(i.t, 5.0);
+        assert_eq!(i.shape, &s);
+        let i = is_iter.next().expect("Second intersection");
+        assert_eq!(i.t, 5.0);
+        assert_eq!(i.shape, &s);
+    }
+
+    #[test]
+    fn test_intersect_sphere_miss() {
+        let r = Ray::new(Point::new(0., 2., -5.), Vector::new(0., 0., 1.));
+        let s: Shape = Sphere::new().into();
+
+
+        let mut is = Intersections::new();
+        s.intersect(&r, &mut is);
+        assert_eq!(is.len(), 0);
+    }
+
+    #[test]
+    fn test_intersect_sphere_from_inside() {
+        let r = Ray::new(Point::new(0., 0., 0.), Vector::new(0., 0., 1.));
+        let s: Shape = Sphere::new().into();
+        let mut is = Intersections::new();
+        s.intersect(&r, &mut is);
+        assert_eq!(is.len(), 2);
+
+        let mut is_iter = is.into_iter();
+        let i = is_iter.next().expect("First intersection");
+        assert_eq!(i.t, -1.0);
+        assert_eq!(i.shape, &s);
+
+        let i = is_iter.next().expect("Second intersection");
+        assert_eq!(i.t, 1.0);
+        assert_eq!(i.shape, &s);
+    }
+
+    #[test]
+    fn test_intersect_sphere_from_behind() {
+        let r = Ray::new(Point::new(0., 0., 5.), Vector::new(0., 0., 1.));
+        let s: Shape = Sphere::new().into();
+
+        let mut is = Intersections::new();
+        s.intersect(&r, &mut is);
+        assert_eq!(is.len(), 2);
+
+        let mut is_iter = is.into_iter();
+        let i = is_iter.next().expect("First intersection");
+        assert_eq!(i.t, -6.0);
+        assert_eq!(i.shape, &s);
+
+        let i = is_iter.next().expect("Second intersection");
+        assert_eq!(i.t, -4.0);
+        assert_eq!(i.shape, &s);
+    }
+
+    #[test]
+    fn test_sphere_default_transformation() {
+        let s = Sphere::new();
+        assert_eq!(s.placement.transformation, Matrix4::identity());
+    }
+
+    #[test]
+    fn test_new_transformation() {
+        let mut s = Sphere::new();
+        let t = Matrix4::translation(2.0, 3.0, 4.0);
+        s.set_transform(t);
+        assert_eq!(s.placement.transformation, t);
+        assert_eq!(s.placement.inverse, t.inverse().unwrap());
+    }
+
+    #[test]
+    fn test_with_transformation() {
+        let t = Matrix4::translation(2.0, 3.0, 4.0);
+        let s = Sphere::with_transform(t);
+        assert_eq!(s.placement.transformation, t);
+        assert_eq!(s.placement.inverse, t.inverse().unwrap());
+    }
+
+    #[test]
+    fn test_with_geometry() {
+        let s = Sphere::with_geometry(Point::new(1.0, 2.0, 3.0), 2.0);
+        assert_eq!(
+            s.placement.transformation,
+            Matrix4::translation(1.0, 2.0, 3.0) * Matrix4::scaling(2.0, 2.0, 2.0)
+        );
+
+        let r = Ray::new(Point::new(1.0, 2.0, -5.0), Vector::new(0., 0., 1.));
+        assert_eq!(s.intersect(&r), vec![6.0, 10.0]);
+        assert_eq!(
+            s.normal_at(&Point::new(3.0, 2.0, 3.0)),
+            Vector::new(1.0, 0.0, 0.0)
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_non_invertible_transformation() {
+        let mut s = Sphere::new();
+        s.set_transform(Matrix4::scaling(0.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn test_try_non_invertible_transformation() {
+        use crate::error::MatrixError;
+
+        let mut s: Shape = Sphere::new().into();
+        s.set_transform(Matrix4::translation(1.0, 0.0, 0.0));
+        assert!(matches!(
+            s.try_set_transform(Matrix4::scaling(0.0, 1.0, 1.0)),
+            Err(Error::Matrix(MatrixError::Singular))
+        ));
+        assert_eq!(s.transformation(), &Matrix4::translation(1.0, 0.0, 0.0));
+        assert!(s.try_set_transform(Matrix4::scaling(2.0, 1.0, 1.0)).is_ok());
+    }
+
+    #[test]
+    fn test_intersect_scaled_sphere() {
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let mut s = Sphere::new();
+        s.set_transform(Matrix4::scaling(2.0, 2.0, 2.0));
+        let s: Shape = s.into();
+
+        let mut is = Intersections::new();
+        s.intersect(&r, &mut is);
+        assert_eq!(is.len(), 2);
+
+        let mut is_iter = is.into_iter();
+        let i = is_iter.next().expect("First intersection");
+        assert_eq!(i.t, 3.0);
+        let i = is_iter.next().expect("Second intersection");
+        assert_eq!(i.t, 7.0);
+    }
+
+    #[test]
+    fn test_intersect_translated_sphere() {
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let mut s = Sphere::new();
+        s.set_transform(Matrix4::translation(5.0, 0.0, 0.0));
+        let s: Shape = s.into();
+
+        let mut is = Intersections::new();
+        s.intersect(&r, &mut is);
+
+        assert_eq!(is.len(), 0);
+    }
+
+    #[test]
+    fn test_sphere_normal_x() {
+        let s = Sphere::new();
+        let n = s.normal_at(&Point::new(1.0, 0.0, 0.0));
+        assert_eq!(n, Vector::new(1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_sphere_normal_y() {
+        let s = Sphere::new();
+        let n = s.normal_at(&Point::new(0.0, 1.0, 0.0));
+        assert_eq!(n, Vector::new(0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn test_sphere_normal_z() {
+        let s = Sphere::new();
+        let n = s.normal_at(&Point::new(0.0, 0.0, 1.0));
+        assert_eq!(n, Vector::new(0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn test_sphere_normal_nonaxial() {
+        // Third root three
+        let trt = (3.0 as Float).sqrt() / 3.0;
+        let s = Sphere::new();
+        let n = s.normal_at(&Point::new(trt, trt, trt));
+        assert_eq!(n, Vector::new(trt, trt, trt));
+    }
+
+    #[test]
+    fn test_sphere_normal_is_normalized() {
+        let trt = (3.0 as Float).sqrt() / 3.0;
+        let s = Sphere::new();
+        let n = s.normal_at(&Point::new(trt, trt, trt));
+        assert_eq!(n, n.normalize());
+    }
+
+    #[test]
+    fn test_sphere_translated_normal() {
+        let s = Sphere::with_transform(Matrix4::translation(0.0, 1.0, 0.0));
+        let n = s.normal_at(&Point::new(0.0, 1.0 + FRAC_1_SQRT_2, -FRAC_1_SQRT_2));
+        assert_eq!(n, Vector::new(0.0, FRAC_1_SQRT_2, -FRAC_1_SQRT_2));
+    }
+
+    #[test]
+    fn test_sphere_transformed() {
+        let s =
+            Sphere::with_transform(Matrix4::scaling(1.0, 0.5, 1.0) * Matrix4::rotation_z(PI / 5.0));
+        let n = s.normal_at(&Point::new(
+            0.0,
+            (2.0 as Float).sqrt() / 2.0,
+            -(2.0 as Float).sqrt() / 2.0,
+        ));
+        assert_eq!(n, Vector::new(0.0, 0.97014, -0.24254));
+    }
+
+    #[test]
+    fn test_sphere_default_material() {
+        let s = Sphere::new();
+        assert_eq!(s.material, Material::new())
+    }
+
+    #[test]
+    fn test_sphere_assign_material() {
+        let mut s = Sphere::new();
+        let mut m = Material::new();
+        m.ambient = 1.0;
+        s.material = m.clone();
+
+        assert_eq!(s.material, m)
+    }
+
+    fn moving_sphere() -> Sphere {
+        let mut s = Sphere::new();
+        s.set_motion(Motion::new(Matrix4::translation(4.0, 0.0, 0.0), 0.0, 1.0));
+        s
+    }
+
+    #[test]
+    fn test_motion_interpolates_transformation() {
+        let s = moving_sphere();
+        assert_eq!(
+            s.transformation_at(0.0),
+            Matrix4::translation(0.0, 0.0, 0.0)
+        );
+        assert_eq!(
+            s.transformation_at(0.5),
+            Matrix4::translation(2.0, 0.0, 0.0)
+        );
+        assert_eq!(
+            s.transformation_at(1.0),
+            Matrix4::translation(4.0, 0.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn test_motion_clamps_outside_interval() {
+        let s = moving_sphere();
+        assert_eq!(
+            s.transformation_at(-1.0),
+            Matrix4::translation(0.0, 0.0, 0.0)
+        );
+        assert_eq!(
+            s.transformation_at(2.0),
+            Matrix4::translation(4.0, 0.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn test_intersect_moving_sphere() {
+        let s: Shape = moving_sphere().into();
+        let direction = Vector::new(0.0, 0.0, 1.0);
+
+        let mut is = Intersections::new();
+        s.intersect(
+            &Ray::with_time(Point::new(2.0, 0.0, -5.0), direction, 0.0),
+            &mut is,
+        );
+        assert_eq!(is.len(), 0);
+
+        let mut is = Intersections::new();
+        s.intersect(
+            &Ray::with_time(Point::new(2.0, 0.0, -5.0), direction, 0.5),
+            &mut is,
+        );
+        assert_eq!(is.len(), 2);
+    }
+
+    #[test]
+    fn test_try_set_singular_motion() {
+        let mut s = moving_sphere();
+        let motion = Motion::new(Matrix4::scaling(0.0, 1.0, 1.0), 0.0, 1.0);
+        assert!(matches!(
+            s.try_set_motion(motion),
+            Err(Error::Matrix(MatrixError::Singular))
+        ));
+        assert_eq!(
+            s.transformation_at(1.0),
+            Matrix4::translation(4.0, 0.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn test_update_motion_in_group() {
+        let mut ball: Shape = Sphere::new().into();
+        ball.set_name("ball");
+        let mut g = Group::with_transform(Matrix4::translation(0.0, 5.0, 0.0));
+        g.add_child(ball);
+        let mut g: Shape = g.into();
+
+        let r = Ray::with_time(Point::new(4.0, 5.0, -5.0), Vector::new(0.0, 0.0, 1.0), 1.0);
+        let hits = |g: &Shape| {
+            let mut xs = Intersections::new();
+            g.intersect(&r, &mut xs);
+            xs.len()
+        };
+        assert_eq!(hits(&g), 0);
+        assert!(g.update("ball", |ball| {
+            let Shape::Sphere(ball) = ball else {
+                unreachable!()
+            };
+            ball.set_motion(Motion::new(Matrix4::translation(4.0, 0.0, 0.0), 0.0, 1.0));
+        }));
+        // The group's bounds now cover the ball's path.
+        assert_eq!(hits(&g), 2);
+    }
+
+    #[test]
+    fn test_normal_moving_sphere() {
+        let s: Shape = moving_sphere().into();
+        let n = s.normal_at_time(&Point::new(2.0, 0.0, -1.0), 0.5);
+        assert_eq!(n, Vector::new(0.0, 0.0, -1.0));
+    }
+
+    #[test]
+    fn test_glass_sphere() {
+        let s = glass_sphere();
+        assert_eq!(s.placement.transformation, Matrix4::identity());
+        assert_eq!(s.material().transparency, 1.0);
+        assert_eq!(s.material().refractive_index, 1.5);
+    }
+
+    #[test]
+    fn test_intersect_scaled_custom_shape() {
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let local = TestShape::new();
+        let s: Shape =
+            CustomShape::with_transform(local.clone(), Matrix4::scaling(2.0, 2.0, 2.0)).into();
+
+        let mut is = Intersections::new();
+        s.intersect(&r, &mut is);
+        let saved = local.saved_ray().expect("ray should be recorded");
+        assert_eq!(saved.origin, Point::new(0.0, 0.0, -2.5));
+        assert_eq!(saved.direction, Vector::new(0.0, 0.0, 0.5));
+    }
+
+    #[test]
+    fn test_intersect_translated_custom_shape() {
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let local = TestShape::new();
+        let s: Shape =
+            CustomShape::with_transform(local.clone(), Matrix4::translation(5.0, 0.0, 0.0)).into();
+
+        let mut is = Intersections::new();
+        s.intersect(&r, &mut is);
+        let saved = local.saved_ray().expect("ray should be recorded");
+        assert_eq!(saved.origin, Point::new(-5.0, 0.0, -5.0));
+        assert_eq!(saved.direction, Vector::new(0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn test_custom_shape_normals() {
+        let s = CustomShape::with_transform(TestShape::new(), Matrix4::translation(0.0, 1.0, 0.0));
+        let n = s.normal_at(&Point::new(0.0, 1.0 + FRAC_1_SQRT_2, -FRAC_1_SQRT_2));
+        assert_eq!(n, Vector::new(0.0, FRAC_1_SQRT_2, -FRAC_1_SQRT_2));
+
+        let s = CustomShape::with_transform(
+            TestShape::new(),
+            Matrix4::scaling(1.0, 0.5, 1.0) * Matrix4::rotation_z(PI / 5.0),
+        );
+        let n = s.normal_at(&Point::new(0.0, FRAC_1_SQRT_2, -FRAC_1_SQRT_2));
+        assert_eq!(n, Vector::new(0.0, 0.97014, -0.24254));
+    }
+
+    #[test]
+    fn test_custom_shape_equality() {
+        let a = CustomShape::new(TestShape::new());
+        let b = a.clone();
+        assert_eq!(a, b);
+        assert_ne!(a, CustomShape::new(TestShape::new()));
+    }
+
+    fn only_group(shape: &Shape) -> &Group {
+        match shape {
+            Shape::Group(group) => group,
+            _ => panic!("expected a group, got {:?}", shape),
+        }
+    }
+
+    #[test]
+    fn test_new_group() {
+        let g = Group::new();
+        assert_eq!(g.placement.transformation, Matrix4::identity());
+        assert!(g.children().is_empty());
+    }
+
+    #[test]
+    fn test_add_child_to_group() {
+        let mut g = Group::new();
+        g.add_child(Sphere::new());
+        assert_eq!(g.children(), &[Sphere::new().into()]);
+    }
+
+    #[test]
+    fn test_intersect_empty_group() {
+        let g: Shape = Group::new().into();
+        let r = Ray::new(Point::new(0.0, 0.0, 0.0), Vector::new(0.0, 0.0, 1.0));
+        let mut xs = Intersections::new();
+        g.intersect(&r, &mut xs);
+        assert_eq!(xs.len(), 0);
+    }
+
+    #[test]
+    fn test_intersect_nonempty_group() {
+        let mut g = Group::new();
+        g.add_child(Sphere::new());
+        g.add_child(Sphere::with_transform(Matrix4::translation(0.0, 0.0, -3.0)));
+        g.add_child(Sphere::with_transform(Matrix4::translation(5.0, 0.0, 0.0)));
+        let g: Shape = g.into();
+        let children = only_group(&g).children();
+
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let mut xs = Intersections::new();
+        g.intersect(&r, &mut xs);
+        assert_eq!(xs.len(), 4);
+        assert!(std::ptr::eq(xs[0].shape, &children[1]));
+        assert!(std::ptr::eq(xs[1].shape, &children[1]));
+        assert!(std::ptr::eq(xs[2].shape, &children[0]));
+        assert!(std::ptr::eq(xs[3].shape, &children[0]));
+    }
+
+    #[test]
+    fn test_intersect_transformed_group() {
+        let mut g = Group::with_transform(Matrix4::scaling(2.0, 2.0, 2.0));
+        g.add_child(Sphere::with_transform(Matrix4::translation(5.0, 0.0, 0.0)));
+        let g: Shape = g.into();
+
+        let r = Ray::new(Point::new(10.0, 0.0, -10.0), Vector::new(0.0, 0.0, 1.0));
+        let mut xs = Intersections::new();
+        g.intersect(&r, &mut xs);
+        assert_eq!(xs.len(), 2);
+    }
 
-    pub fn intersect<'a>(&'a self, ray: &Ray, intersections: &mut Intersections<'a>) {
-        let ts = match self {
-            Self::Sphere(sphere) => sphere.intersect(ray),
-        };
+    #[test]
+    fn test_group_transform_applies_to_existing_children() {
+        let mut g = Group::new();
+        g.add_child(Sphere::with_transform(Matrix4::translation(5.0, 0.0, 0.0)));
+        g.set_transform(Matrix4::scaling(2.0, 2.0, 2.0));
 
-        for t in ts {
-            intersections.add(Intersection::new(
-                t,
-                self,
-            ));
-        }
+        let s = &g.children()[0];
+        assert_eq!(s.transformation(), &Matrix4::translation(5.0, 0.0, 0.0));
+        assert_eq!(
+            s.world_to_object(&Point::new(10.0, 0.0, -10.0)),
+            Point::new(0.0, 0.0, -5.0)
+        );
     }
 
-    pub fn material(&self) -> &Material {
-        match self {
-            Self::Sphere(sphere) => sphere.material(),
-        }
+    fn nested_sphere(outer: Matrix4, inner: Matrix4) -> Group {
+        let mut g2 = Group::with_transform(inner);
+        g2.add_child(Sphere::with_transform(Matrix4::translation(5.0, 0.0, 0.0)));
+        let mut g1 = Group::with_transform(outer);
+        g1.add_child(g2);
+        g1
     }
 
-    pub fn material_mut(&mut self) -> &mut Material {
-        match self {
-            Self::Sphere(sphere) => sphere.material_mut(),
-        }
+    #[test]
+    fn test_world_to_object() {
+        let g1 = nested_sphere(
+            Matrix4::rotation_y(PI / 2.0),
+            Matrix4::scaling(2.0, 2.0, 2.0),
+        );
+        let s = &only_group(&g1.children()[0]).children()[0];
+        let p = s.world_to_object(&Point::new(-2.0, 0.0, -10.0));
+        assert_eq!(p, Point::new(0.0, 0.0, -1.0));
     }
 
-    pub fn normal_at(&self, p: &Point) -> Vector {
-        match self {
-            Self::Sphere(sphere) => sphere.normal_at(p)
-        }
+    #[test]
+    fn test_normal_to_world() {
+        let g1 = nested_sphere(
+            Matrix4::rotation_y(PI / 2.0),
+            Matrix4::scaling(1.0, 2.0, 3.0),
+        );
+        let s = &only_group(&g1.children()[0]).children()[0];
+        let trt = (3.0 as Float).sqrt() / 3.0;
+        let n = s.normal_to_world(&Vector::new(trt, trt, trt));
+        assert_eq!(n, Vector::new(0.28571, 0.42857, -0.85714));
     }
-}
 
-impl From<Sphere> for Shape {
-    fn from(value: Sphere) -> Self {
-        Self::Sphere(value)
+    #[test]
+    fn test_normal_on_child() {
+        let g1 = nested_sphere(
+            Matrix4::rotation_y(PI / 2.0),
+            Matrix4::scaling(1.0, 2.0, 3.0),
+        );
+        let s = &only_group(&g1.children()[0]).children()[0];
+        let n = s.normal_at(&Point::new(1.7321, 1.1547, -5.5774));
+        assert_eq!(n, Vector::new(0.28570, 0.42854, -0.85716));
     }
-}
 
-#[derive(Clone, Debug, PartialEq)]
-pub struct Sphere {
-    transformation: Matrix,
-    material: Material,
-}
+    #[test]
+    fn test_parent_space_bounds() {
+        let s: Shape = Sphere::with_transform(
+            Matrix4::translation(1.0, -3.0, 5.0) * Matrix4::scaling(0.5, 2.0, 4.0),
+        )
+        .into();
+        let b = s.parent_space_bounds();
+        assert_eq!(b.min, Point::new(0.5, -5.0, 1.0));
+        assert_eq!(b.max, Point::new(1.5, -1.0, 9.0));
+    }
 
-impl Sphere {
-    pub fn new() -> Self {
-        Self {
-            transformation: identity_matrix().to_owned(),
-            material: Material::new(),
-        }
+    #[test]
+    fn test_moving_sphere_bounds_cover_path() {
+        let mut s = Sphere::new();
+        s.set_motion(Motion::new(Matrix4::translation(4.0, 0.0, 0.0), 0.0, 1.0));
+        let b = Shape::from(s).parent_space_bounds();
+        assert_eq!(b.min, Point::new(-1.0, -1.0, -1.0));
+        assert_eq!(b.max, Point::new(5.0, 1.0, 1.0));
     }
 
-    pub fn intersect(&self, ray: &Ray) -> Vec<f64> {
-        let ray2 = ray.transform(&self.transformation.inverse().unwrap());
+    #[test]
+    fn test_group_bounds_contain_children() {
+        let mut g = Group::new();
+        g.add_child(Sphere::with_transform(
+            Matrix4::translation(2.0, 5.0, -3.0) * Matrix4::scaling(2.0, 2.0, 2.0),
+        ));
+        g.add_child(Sphere::with_transform(Matrix4::translation(
+            -4.0, -1.0, 4.0,
+        )));
+        let b = Shape::from(g).bounds();
+        assert_eq!(b.min, Point::new(-5.0, -2.0, -5.0));
+        assert_eq!(b.max, Point::new(4.0, 7.0, 5.0));
+    }
 
-        let sphere_to_ray = ray2.origin - Point::new(0., 0., 0.);
-        let a = ray2.direction.dot(&ray2.direction);
-        let b = 2. * ray2.direction.dot(&sphere_to_ray);
-        let c = sphere_to_ray.dot(&sphere_to_ray) - 1.0;
-        let discriminant = b * b - 4. * a * c;
+    #[test]
+    fn test_group_skips_children_when_box_missed() {
+        let child = TestShape::new();
+        let mut g = Group::new();
+        g.add_child(CustomShape::new(child.clone()));
+        let g: Shape = g.into();
+        let mut xs = Intersections::new();
 
-        if discriminant >= 0.0 {
-            vec![(-b - discriminant.sqrt()) / (2. * a),
-            (-b + discriminant.sqrt()) / (2. * a),]
-        } else {
-            vec![]
-        }
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 1.0, 0.0));
+        g.intersect(&r, &mut xs);
+        assert!(child.saved_ray().is_none());
+
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        g.intersect(&r, &mut xs);
+        assert!(child.saved_ray().is_some());
     }
 
-    pub fn with_transform(transformation: Matrix) -> Self {
-        Self {
-            transformation,
-            material: Material::new(),
+    #[test]
+    fn test_partition_children() {
+        let s1: Shape = Sphere::with_transform(Matrix4::translation(-2.0, 0.0, 0.0)).into();
+        let s2: Shape = Sphere::with_transform(Matrix4::translation(2.0, 0.0, 0.0)).into();
+        let s3: Shape = Sphere::new().into();
+        let mut g = Group::new();
+        for s in [&s1, &s2, &s3] {
+            g.add_child(s.clone());
         }
+
+        let (left, right) = g.partition_children();
+        assert_eq!(g.children(), &[s3]);
+        assert_eq!(left, vec![s1]);
+        assert_eq!(right, vec![s2]);
     }
 
-    pub fn transformation(&mut self) -> &mut Matrix {
-        &mut self.transformation
+    #[test]
+    fn test_make_subgroup() {
+        let s1: Shape = Sphere::new().into();
+        let s2: Shape = Sphere::with_transform(Matrix4::translation(3.0, 0.0, 0.0)).into();
+        let mut g = Group::new();
+        g.make_subgroup(vec![s1.clone(), s2.clone()]);
+        assert_eq!(g.children().len(), 1);
+        assert_eq!(only_group(&g.children()[0]).children(), &[s1, s2]);
     }
 
-    pub fn material(&self) -> &Material {
-        &self.material
+    #[test]
+    fn test_divide_group() {
+        let s1: Shape = Sphere::with_transform(Matrix4::translation(-2.0, -2.0, 0.0)).into();
+        let s2: Shape = Sphere::with_transform(Matrix4::translation(-2.0, 2.0, 0.0)).into();
+        let s3: Shape = Sphere::with_transform(Matrix4::scaling(4.0, 4.0, 4.0)).into();
+        let mut g = Group::new();
+        for s in [&s1, &s2, &s3] {
+            g.add_child(s.clone());
+        }
+
+        g.divide(1);
+        assert_eq!(g.children()[0], s3);
+        let subgroup = only_group(&g.children()[1]);
+        assert_eq!(subgroup.children().len(), 2);
+        assert_eq!(only_group(&subgroup.children()[0]).children(), &[s1]);
+        assert_eq!(only_group(&subgroup.children()[1]).children(), &[s2]);
     }
 
-    pub fn material_mut(&mut self) -> &mut Material {
-        &mut self.material
+    #[test]
+    fn test_divide_group_with_too_few_children() {
+        let s1: Shape = Sphere::with_transform(Matrix4::translation(-2.0, 0.0, 0.0)).into();
+        let s2: Shape = Sphere::with_transform(Matrix4::translation(2.0, 1.0, 0.0)).into();
+        let s3: Shape = Sphere::with_transform(Matrix4::translation(2.0, -1.0, 0.0)).into();
+        let mut subgroup = Group::new();
+        for s in [&s1, &s2, &s3] {
+            subgroup.add_child(s.clone());
+        }
+        let s4: Shape = Sphere::new().into();
+        let mut g = Group::new();
+        g.add_child(subgroup);
+        g.add_child(s4.clone());
+
+        g.divide(3);
+        assert_eq!(g.children()[1], s4);
+        let subgroup = only_group(&g.children()[0]);
+        assert_eq!(subgroup.children().len(), 2);
+        assert_eq!(only_group(&subgroup.children()[0]).children(), &[s1]);
+        assert_eq!(only_group(&subgroup.children()[1]).children(), &[s2, s3]);
     }
 
-    
+    #[test]
+    fn test_divided_group_intersects_the_same() {
+        let mut g = Group::new();
+        for i in 0..8 {
+            g.add_child(Sphere::with_transform(Matrix4::translation(
+                i as Float * 3.0,
+                0.0,
+                0.0,
+            )));
+        }
+        let r = Ray::new(Point::new(-5.0, 0.5, 0.0), Vector::new(1.0, 0.0, 0.0));
+        let before: Shape = g.clone().into();
+        let mut divided = g;
+        divided.divide(2);
+        let after: Shape = divided.into();
 
-    pub fn normal_at(&self, p: &Point) -> Vector {
-        let it = self.transformation.inverse().unwrap();
-        let op = &it * (*p);
-        let on = op.subtract_origin();
-        let wn = it.transpose() * on;
-        wn.normalize()
+        let (mut xs1, mut xs2) = (Intersections::new(), Intersections::new());
+        before.intersect(&r, &mut xs1);
+        after.intersect(&r, &mut xs2);
+        let ts1: Vec<Float> = xs1.iter().map(|i| i.t).collect();
+        let ts2: Vec<Float> = xs2.iter().map(|i| i.t).collect();
+        assert_eq!(ts1.len(), 16);
+        assert_eq!(ts1, ts2);
     }
-}
 
-impl Default for Sphere {
-    fn default() -> Self {
-        Sphere::new()
+    #[test]
+    fn test_kd_tree_group_intersects_the_same() {
+        // A 20x20 grid of triangles in the xy plane.
+        let mut g = Group::new();
+        for i in 0..400 {
+            let (x, y) = ((i % 20) as Float, (i / 20) as Float);
+            g.add_child(Triangle::new(
+                Point::new(x, y, 0.0),
+                Point::new(x + 1.0, y, 0.0),
+                Point::new(x, y + 1.0, 0.0),
+            ));
+        }
+        let plain: Shape = g.clone().into();
+        let mut indexed: Shape = g.into();
+        indexed.build_kd_tree(4);
+
+        let rays = [
+            Ray::new(Point::new(3.2, 7.3, -5.0), Vector::new(0.0, 0.0, 1.0)),
+            Ray::new(Point::new(-1.0, -1.0, -5.0), Vector::new(0.3, 0.4, 1.0)),
+            Ray::new(Point::new(10.5, 10.5, 5.0), Vector::new(0.0, 0.0, -1.0)),
+            Ray::new(Point::new(25.0, 25.0, -5.0), Vector::new(0.0, 0.0, 1.0)),
+        ];
+        for r in &rays {
+            let (mut xs1, mut xs2) = (Intersections::new(), Intersections::new());
+            plain.intersect(r, &mut xs1);
+            indexed.intersect(r, &mut xs2);
+            let ts1: Vec<Float> = xs1.iter().map(|i| i.t).collect();
+            let ts2: Vec<Float> = xs2.iter().map(|i| i.t).collect();
+            assert_eq!(ts1, ts2);
+        }
     }
-}
 
-#[cfg(test)]
-mod test {
-    use std::f64::consts::PI;
+    #[test]
+    fn test_group_compute_normals() {
+        // Two faces of a ridge, mirrored in x, with the same area.
+        let mut g = Group::new();
+        g.add_child(Triangle::new(
+            Point::new(0.0, 1.0, 0.0),
+            Point::new(-1.0, 0.0, 0.0),
+            Point::new(0.0, 1.0, 1.0),
+        ));
+        let mut sub = Group::new();
+        sub.add_child(Triangle::new(
+            Point::new(0.0, 1.0, 0.0),
+            Point::new(0.0, 1.0, 1.0),
+            Point::new(1.0, 0.0, 0.0),
+        ));
+        g.add_child(sub);
+        g.compute_normals();
 
-    use crate::{ray::Ray, space::Vector};
+        let left = match &g.children()[0] {
+            Shape::Triangle(triangle) => triangle.clone(),
+            _ => unreachable!(),
+        };
+        let (n1, n2, n3) = left.normals().unwrap();
+        assert_eq!(n1, Vector::new(0.0, -1.0, 0.0));
+        assert_eq!(n2, Vector::new(FRAC_1_SQRT_2, -FRAC_1_SQRT_2, 0.0));
+        assert_eq!(n3, Vector::new(0.0, -1.0, 0.0));
+        assert!(left.smooth());
 
-    use super::*;
+        let mut g: Shape = g.into();
+        g.set_smooth(false);
+        let left = match &g {
+            Shape::Group(group) => &group.children()[0],
+            _ => unreachable!(),
+        };
+        assert_eq!(
+            left.normal_at(&Point::new(0.0, 1.0, 0.5)),
+            Vector::new(FRAC_1_SQRT_2, -FRAC_1_SQRT_2, 0.0)
+        );
+    }
 
     #[test]
-    fn test_intersect_sphere() {
-        let r = Ray::new(Point::new(0., 0., -5.), Vector::new(0., 0., 1.));
-        let s: Shape = Sphere::new().into();
+    fn test_group_compute_normals_across_transformed_groups() {
+        // The ridge again, with the right face stretched into place.
+        let mut g = Group::new();
+        g.add_child(Triangle::new(
+            Point::new(0.0, 1.0, 0.0),
+            Point::new(-1.0, 0.0, 0.0),
+            Point::new(0.0, 1.0, 1.0),
+        ));
+        let mut sub = Group::with_transform(Matrix4::scaling(2.0, 1.0, 1.0));
+        sub.add_child(Triangle::new(
+            Point::new(0.0, 1.0, 0.0),
+            Point::new(0.0, 1.0, 1.0),
+            Point::new(0.5, 0.0, 0.0),
+        ));
+        g.add_child(sub);
+        g.compute_normals();
 
+        let Shape::Triangle(left) = &g.children()[0] else {
+            unreachable!()
+        };
+        let (n1, _, n3) = left.normals().unwrap();
+        assert_eq!(n1, Vector::new(0.0, -1.0, 0.0));
+        assert_eq!(n3, Vector::new(0.0, -1.0, 0.0));
 
-        let mut is = Intersections::new();
-        s.intersect(&r, &mut is);
-        assert_eq!(is.len(), 2);
-        let mut is_iter = is.into_iter();
-        let i = is_iter.next().expect("First intersection");
-        assert_eq!(i.t, 4.0);
-        assert_eq!(i.shape, &s);
-        let i = is_iter.next().expect("Second intersection");
-        assert_eq!(i.t, 6.0);
-        assert_eq!(i.shape, &s);
+        let Shape::Group(sub) = &g.children()[1] else {
+            unreachable!()
+        };
+        let Shape::Triangle(right) = &sub.children()[0] else {
+            unreachable!()
+        };
+        // In the triangle's object space, so only the corner it shares with
+        // nothing keeps the flat normal.
+        let (n1, n2, n3) = right.normals().unwrap();
+        assert_eq!(n1, Vector::new(0.0, -1.0, 0.0));
+        assert_eq!(n2, Vector::new(0.0, -1.0, 0.0));
+        assert_eq!(n3, right.normal);
     }
 
     #[test]
-    fn test_intersect_sphere_tangent() {
-        let r = Ray::new(Point::new(0., 1., -5.), Vector::new(0., 0., 1.));
-        let s: Shape = Sphere::new().into();
+    fn test_group_compute_normals_back_to_back() {
+        let (p1, p2, p3) = book_triangle().points();
+        let mut g = Group::new();
+        g.add_child(Triangle::new(p1, p2, p3));
+        g.add_child(Triangle::new(p1, p3, p2));
+        g.compute_normals();
 
-        let mut is = Intersections::new();
-        s.intersect(&r, &mut is);
-        assert_eq!(is.len(), 2);
+        for child in g.children() {
+            let Shape::Triangle(triangle) = child else {
+                unreachable!()
+            };
+            let normal = triangle.normal;
+            assert_eq!(triangle.normals(), Some((normal, normal, normal)));
+        }
+    }
 
-        let mut is_iter = is.into_iter();
-        let i = is_iter.next().expect("First intersection");
-        assert_eq!(i.t, 5.0);
-        assert_eq!(i.shape, &s);
-        let i = is_iter.next().expect("Second intersection");
-        assert_eq!(i.t, 5.0);
-        assert_eq!(i.shape, &s);
+    fn book_triangle() -> Triangle {
+        Triangle::new(
+            Point::new(0.0, 1.0, 0.0),
+            Point::new(-1.0, 0.0, 0.0),
+            Point::new(1.0, 0.0, 0.0),
+        )
     }
 
     #[test]
-    fn test_intersect_sphere_miss() {
-        let r = Ray::new(Point::new(0., 2., -5.), Vector::new(0., 0., 1.));
-        let s: Shape = Sphere::new().into();
-
+    fn test_construct_triangle() {
+        let t = book_triangle();
+        assert_eq!(t.e1, Vector::new(-1.0, -1.0, 0.0));
+        assert_eq!(t.e2, Vector::new(1.0, -1.0, 0.0));
+        assert_eq!(t.normal, Vector::new(0.0, 0.0, -1.0));
+    }
 
-        let mut is = Intersections::new();
-        s.intersect(&r, &mut is);
-        assert_eq!(is.len(), 0);
+    #[test]
+    fn test_triangle_normal() {
+        let t = book_triangle();
+        for p in [
+            Point::new(0.0, 0.5, 0.0),
+            Point::new(-0.5, 0.75, 0.0),
+            Point::new(0.5, 0.25, 0.0),
+        ] {
+            assert_eq!(t.normal_at(&p), t.normal);
+        }
     }
 
     #[test]
-    fn test_intersect_sphere_from_inside() {
-        let r = Ray::new(Point::new(0., 0., 0.), Vector::new(0., 0., 1.));
-        let s: Shape = Sphere::new().into();
-        let mut is = Intersections::new();
-        s.intersect(&r, &mut is);
-        assert_eq!(is.len(), 2);
+    fn test_smooth_triangle_normal() {
+        let mut t = Triangle::with_normals(
+            Point::new(0.0, 1.0, 0.0),
+            Point::new(-1.0, 0.0, 0.0),
+            Point::new(1.0, 0.0, 0.0),
+            Vector::new(0.0, 1.0, 0.0),
+            Vector::new(-1.0, 0.0, 0.0),
+            Vector::new(1.0, 0.0, 0.0),
+        );
+        assert!(t.smooth());
+        // A quarter of the way to p3 and almost half way to p2.
+        let p = Point::new(-0.2, 0.3, 0.0);
+        assert_eq!(t.normal_at(&p), Vector::new(-0.5547, 0.83205, 0.0));
+        assert_eq!(
+            t.normal_at(&Point::new(0.0, 1.0, 0.0)),
+            Vector::new(0.0, 1.0, 0.0)
+        );
 
-        let mut is_iter = is.into_iter();
-        let i = is_iter.next().expect("First intersection");
-        assert_eq!(i.t, -1.0);
-        assert_eq!(i.shape, &s);
+        t.set_smooth(false);
+        assert_eq!(t.normal_at(&p), Vector::new(0.0, 0.0, -1.0));
+    }
 
-        let i = is_iter.next().expect("Second intersection");
-        assert_eq!(i.t, 1.0);
-        assert_eq!(i.shape, &s);
+    #[test]
+    fn test_intersect_triangle_misses() {
+        let t = book_triangle();
+        let cases = [
+            // Parallel to the triangle
+            (Point::new(0.0, -1.0, -2.0), Vector::new(0.0, 1.0, 0.0)),
+            // Past each edge
+            (Point::new(1.0, 1.0, -2.0), Vector::new(0.0, 0.0, 1.0)),
+            (Point::new(-1.0, 1.0, -2.0), Vector::new(0.0, 0.0, 1.0)),
+            (Point::new(0.0, -1.0, -2.0), Vector::new(0.0, 0.0, 1.0)),
+        ];
+        for (origin, direction) in cases {
+            assert!(t.intersect(&Ray::new(origin, direction)).is_empty());
+        }
     }
 
     #[test]
-    fn test_intersect_sphere_from_behind() {
-        let r = Ray::new(Point::new(0., 0., 5.), Vector::new(0., 0., 1.));
-        let s: Shape = Sphere::new().into();
+    fn test_intersect_triangle_hit() {
+        let t = book_triangle();
+        let r = Ray::new(Point::new(0.0, 0.5, -2.0), Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(t.intersect(&r), vec![2.0]);
+    }
 
-        let mut is = Intersections::new();
-        s.intersect(&r, &mut is);
-        assert_eq!(is.len(), 2);
+    #[test]
+    fn test_triangle_bounds() {
+        let t: Shape = Triangle::new(
+            Point::new(-3.0, 7.0, 2.0),
+            Point::new(6.0, 2.0, -4.0),
+            Point::new(2.0, -1.0, -1.0),
+        )
+        .into();
+        let b = t.bounds();
+        assert_eq!(b.min, Point::new(-3.0, -1.0, -4.0));
+        assert_eq!(b.max, Point::new(6.0, 7.0, 2.0));
+    }
 
-        let mut is_iter = is.into_iter();
-        let i = is_iter.next().expect("First intersection");
-        assert_eq!(i.t, -6.0);
-        assert_eq!(i.shape, &s);
+    fn unit_square() -> Quad {
+        Quad::new(
+            Point::new(-1.0, -1.0, 0.0),
+            Vector::new(2.0, 0.0, 0.0),
+            Vector::new(0.0, 2.0, 0.0),
+        )
+    }
 
-        let i = is_iter.next().expect("Second intersection");
-        assert_eq!(i.t, -4.0);
-        assert_eq!(i.shape, &s);
+    #[test]
+    fn test_quad_normal() {
+        let q = unit_square();
+        assert_eq!(q.normal_at(&Point::origin()), Vector::new(0.0, 0.0, 1.0));
     }
 
     #[test]
-    fn test_sphere_default_transformation() {
-        let s = Sphere::new();
-        assert_eq!(&s.transformation, identity_matrix());
+    fn test_intersect_quad_hit() {
+        let q = unit_square();
+        let r = Ray::new(Point::new(0.5, -0.9, -3.0), Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(q.intersect(&r), vec![3.0]);
+        let r = Ray::new(Point::new(1.0, 1.0, 2.0), Vector::new(0.0, 0.0, -1.0));
+        assert_eq!(q.intersect(&r), vec![2.0]);
     }
 
     #[test]
-    fn test_new_transformation() {
-        let mut s = Sphere::new();
-        let t = Matrix::translation(2.0, 3.0, 4.0);
-        s.transformation = t.clone();
-        assert_eq!(s.transformation, t);
+    fn test_intersect_quad_misses() {
+        let q = unit_square();
+        let cases = [
+            // Parallel to the quad
+            (Point::new(0.0, 0.0, -1.0), Vector::new(1.0, 0.0, 0.0)),
+            // Past each edge
+            (Point::new(-1.1, 0.0, -3.0), Vector::new(0.0, 0.0, 1.0)),
+            (Point::new(1.1, 0.0, -3.0), Vector::new(0.0, 0.0, 1.0)),
+            (Point::new(0.0, -1.1, -3.0), Vector::new(0.0, 0.0, 1.0)),
+            (Point::new(0.0, 1.1, -3.0), Vector::new(0.0, 0.0, 1.0)),
+        ];
+        for (origin, direction) in cases {
+            assert!(q.intersect(&Ray::new(origin, direction)).is_empty());
+        }
     }
 
     #[test]
-    fn test_with_transformation() {
-        let t = Matrix::translation(2.0, 3.0, 4.0);
-        let s = Sphere::with_transform(t.clone());
-        assert_eq!(s.transformation, t);
+    fn test_intersect_skewed_quad() {
+        // A parallelogram leaning to the right
+        let q = Quad::new(
+            Point::origin(),
+            Vector::new(1.0, 0.0, 0.0),
+            Vector::new(1.0, 1.0, 0.0),
+        );
+        let down = Vector::new(0.0, 0.0, 1.0);
+        assert_eq!(
+            q.intersect(&Ray::new(Point::new(1.9, 0.9, -1.0), down)),
+            vec![1.0]
+        );
+        assert!(q
+            .intersect(&Ray::new(Point::new(0.1, 0.9, -1.0), down))
+            .is_empty());
     }
 
     #[test]
-    fn test_intersect_scaled_sphere() {
-        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
-        let mut s = Sphere::new();
-        s.transformation = Matrix::scaling(2.0, 2.0, 2.0);
-        let s: Shape = s.into();
+    fn test_transformed_quad() {
+        let mut q = unit_square();
+        q.set_transform(Matrix4::rotation_x(PI / 2.0));
+        let q: Shape = q.into();
+        let r = Ray::new(Point::new(0.5, 5.0, 0.5), Vector::new(0.0, -1.0, 0.0));
+        let mut xs = Intersections::new();
+        q.intersect(&r, &mut xs);
+        assert_eq!(xs.len(), 1);
+        assert_eq!(xs[0].t, 5.0);
+        assert_eq!(
+            q.normal_at(&Point::new(0.5, 0.0, 0.5)),
+            Vector::new(0.0, -1.0, 0.0)
+        );
+    }
 
-        let mut is = Intersections::new();
-        s.intersect(&r, &mut is);
-        assert_eq!(is.len(), 2);
+    #[test]
+    fn test_quad_bounds() {
+        let b = Shape::from(unit_square()).bounds();
+        assert_eq!(b.min, Point::new(-1.0, -1.0, 0.0));
+        assert_eq!(b.max, Point::new(1.0, 1.0, 0.0));
+    }
 
-        let mut is_iter = is.into_iter();
-        let i = is_iter.next().expect("First intersection");
-        assert_eq!(i.t, 3.0);
-        let i = is_iter.next().expect("Second intersection");
-        assert_eq!(i.t, 7.0);
+    #[test]
+    #[should_panic]
+    fn test_degenerate_quad() {
+        Quad::new(
+            Point::origin(),
+            Vector::new(1.0, 0.0, 0.0),
+            Vector::new(2.0, 0.0, 0.0),
+        );
     }
 
     #[test]
-    fn test_intersect_translated_sphere() {
-        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
-        let mut s = Sphere::new();
-        s.transformation = Matrix::translation(5.0, 0.0, 0.0);
-        let s: Shape = s.into();
+    fn test_try_new_quad() {
+        let result = Quad::try_new(
+            Point::origin(),
+            Vector::new(1.0, 0.0, 0.0),
+            Vector::new(2.0, 0.0, 0.0),
+        );
+        assert!(matches!(result, Err(Error::ParallelQuadEdges)));
+        let result = Quad::try_new(
+            Point::origin(),
+            Vector::new(0.0, 0.0, 0.0),
+            Vector::new(0.0, 1.0, 0.0),
+        );
+        assert!(matches!(result, Err(Error::ParallelQuadEdges)));
+    }
 
-        let mut is = Intersections::new();
-        s.intersect(&r, &mut is);
+    #[test]
+    fn test_small_quad() {
+        let q = Quad::new(
+            Point::origin(),
+            Vector::new(0.05, 0.0, 0.0),
+            Vector::new(0.0, 0.05, 0.0),
+        );
+        assert_eq!(q.normal_at(&Point::origin()), Vector::new(0.0, 0.0, 1.0));
+    }
 
-        assert_eq!(is.len(), 0);
+    #[test]
+    fn test_shape_names() {
+        let mut s: Shape = Sphere::new().into();
+        assert_eq!(s.name(), None);
+        s.set_name("ball");
+        assert_eq!(s.name(), Some("ball"));
+        assert_ne!(s, Sphere::new().into());
     }
 
     #[test]
-    fn test_sphere_normal_x() {
-        let s = Sphere::new();
-        let n = s.normal_at(&Point::new(1.0, 0.0, 0.0));
-        assert_eq!(n, Vector::new(1.0, 0.0, 0.0));
+    fn test_group_shadow_flags_set_children() {
+        let mut g = Group::new();
+        g.add_child(Sphere::new());
+        let mut g: Shape = g.into();
+        assert!(g.casts_shadow());
+        g.set_casts_shadow(false);
+        assert!(!g.casts_shadow());
+        assert!(g.receives_shadow());
+        g.set_receives_shadow(false);
+        let Shape::Group(group) = &g else {
+            unreachable!()
+        };
+        assert!(!group.children()[0].casts_shadow());
+        assert!(!group.children()[0].receives_shadow());
     }
 
     #[test]
-    fn test_sphere_normal_y() {
-        let s = Sphere::new();
-        let n = s.normal_at(&Point::new(0.0, 1.0, 0.0));
-        assert_eq!(n, Vector::new(0.0, 1.0, 0.0));
+    fn test_light_mask() {
+        let mut g = Group::new();
+        g.add_child(Sphere::new());
+        let mut g: Shape = g.into();
+        assert!(g.is_lit_by(0));
+        assert!(g.is_lit_by(63));
+
+        g.set_light_mask(0b10);
+        assert!(!g.is_lit_by(0));
+        assert!(g.is_lit_by(1));
+        assert!(!g.is_lit_by(2));
+        assert!(g.is_lit_by(64));
+        let Shape::Group(group) = &g else {
+            unreachable!()
+        };
+        assert_eq!(group.children()[0].light_mask(), 0b10);
     }
 
     #[test]
-    fn test_sphere_normal_z() {
-        let s = Sphere::new();
-        let n = s.normal_at(&Point::new(0.0, 0.0, 1.0));
-        assert_eq!(n, Vector::new(0.0, 0.0, 1.0));
+    fn test_find_named_shape_in_groups() {
+        let mut inner = Group::new();
+        let mut s: Shape = Sphere::with_transform(Matrix4::translation(1.0, 0.0, 0.0)).into();
+        s.set_name("ball");
+        inner.add_child(s);
+        let mut inner: Shape = inner.into();
+        inner.set_name("inner");
+        let mut outer = Group::new();
+        outer.add_child(Sphere::new());
+        outer.add_child(inner);
+        let outer: Shape = outer.into();
+
+        let ball = outer.find("ball").expect("ball should be found");
+        assert_eq!(ball.transformation(), &Matrix4::translation(1.0, 0.0, 0.0));
+        assert!(matches!(outer.find("inner"), Some(Shape::Group(_))));
+        assert!(outer.find("missing").is_none());
     }
 
     #[test]
-    fn test_sphere_normal_nonaxial() {
-        // Third root three
-        let trt = (3.0_f64).sqrt() / 3.0;
-        let s = Sphere::new();
-        let n = s.normal_at(&Point::new(trt, trt, trt));
-        assert_eq!(n, Vector::new(trt, trt, trt));
+    fn test_update_rebuilds_kd_tree() {
+        let mut g = Group::new();
+        for i in 0..20 {
+            let mut s: Shape =
+                Sphere::with_transform(Matrix4::translation(2.0 * i as Float, 0.0, 0.0)).into();
+            s.set_name(format!("s{i}"));
+            g.add_child(s);
+        }
+        let mut g: Shape = g.into();
+        g.build_kd_tree(2);
+
+        let r = Ray::new(Point::new(30.0, 0.0, -10.0), Vector::new(0.0, 0.0, 1.0));
+        let ts = |g: &Shape| {
+            let mut xs = Intersections::new();
+            g.intersect(&r, &mut xs);
+            let mut ts: Vec<Float> = xs.iter().map(|i| i.t).collect();
+            ts.sort_by(Float::total_cmp);
+            ts
+        };
+        assert_eq!(ts(&g), [9.0, 11.0]);
+
+        assert!(g.update("s0", |s| {
+            s.set_transform(Matrix4::translation(30.0, 0.0, 3.0))
+        }));
+        assert_eq!(ts(&g), [9.0, 11.0, 12.0, 14.0]);
+        let Shape::Group(group) = &g else {
+            unreachable!()
+        };
+        assert_eq!(group.kd_tree.as_ref().map(KdTree::leaf_size), Some(2));
     }
 
+    #[cfg(feature = "serde")]
     #[test]
-    fn test_sphere_normal_is_normalized() {
-        let trt = (3.0_f64).sqrt() / 3.0;
-        let s = Sphere::new();
-        let n = s.normal_at(&Point::new(trt, trt, trt));
-        assert_eq!(n, n.normalize());
+    fn test_group_json_round_trip() {
+        let mut group = Group::with_transform(Matrix4::scaling(2.0, 2.0, 2.0));
+        group.add_child(Sphere::with_transform(Matrix4::translation(5.0, 0.0, 0.0)));
+        let shape: Shape = group.into();
+
+        let json = serde_json::to_string(&shape).unwrap();
+        let restored: Shape = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, shape);
+
+        let child = &only_group(&restored).children()[0];
+        let n = child.normal_at(&Point::new(10.0, 0.0, -2.0));
+        assert_eq!(n, Vector::new(0.0, 0.0, -1.0));
     }
 
+    #[cfg(feature = "serde")]
     #[test]
-    fn test_sphere_translated_normal() {
-        let s = Sphere::with_transform(Matrix::translation(0.0, 1.0, 0.0));
-        let n = s.normal_at(&Point::new(0.0, 1.70711, -0.70711));
-        assert_eq!(n, Vector::new(0.0, 0.70711, -0.70711));
+    fn test_empty_group_json_round_trip() {
+        let mut group = Group::new();
+        group.add_child(Group::new());
+        let shape: Shape = group.into();
+
+        let json = serde_json::to_string(&shape).unwrap();
+        let restored: Shape = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, shape);
     }
 
+    #[cfg(feature = "serde")]
     #[test]
-    fn test_sphere_transformed() {
-        let s =
-            Sphere::with_transform(Matrix::scaling(1.0, 0.5, 1.0) * Matrix::rotation_z(PI / 5.0));
-        let n = s.normal_at(&Point::new(
-            0.0,
-            (2.0_f64).sqrt() / 2.0,
-            -(2.0_f64).sqrt() / 2.0,
-        ));
-        assert_eq!(n, Vector::new(0.0, 0.97014, -0.24254));
+    fn test_kd_tree_json_round_trip() {
+        let mut group = Group::new();
+        for i in 0..10 {
+            let x = i as Float * 3.0;
+            group.add_child(Sphere::with_transform(Matrix4::translation(x, 0.0, 0.0)));
+        }
+        group.build_kd_tree(2);
+
+        let json = serde_json::to_string(&group).unwrap();
+        let restored: Group = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, group);
+        assert!(restored.kd_tree.is_some());
     }
 
+    #[cfg(feature = "serde")]
     #[test]
-    fn test_sphere_default_material() {
-        let s = Sphere::new();
-        assert_eq!(s.material, Material::new())
+    fn test_custom_shape_json_error() {
+        let mut group = Group::new();
+        group.add_child(CustomShape::new(TestShape::new()));
+        let error = serde_json::to_string(&group).unwrap_err();
+        assert_eq!(error.to_string(), "custom shapes can't be serialized");
     }
 
+    #[cfg(feature = "serde")]
     #[test]
-    fn test_sphere_assign_material() {
+    fn test_moving_sphere_json_round_trip() {
+        let s = moving_sphere();
+        let json = serde_json::to_string(&s).unwrap();
+        assert_eq!(serde_json::from_str::<Sphere>(&json).unwrap(), s);
+
         let mut s = Sphere::new();
-        let mut m = Material::new();
-        m.ambient = 1.0;
-        s.material = m.clone();
+        s.set_motion(Motion::new(Matrix4::scaling(2.0, 2.0, 2.0), 0.0, 1.0));
+        let json = serde_json::to_string(&s).unwrap().replace("2.0", "0.0");
+        assert!(serde_json::from_str::<Sphere>(&json).is_err());
+    }
 
-        assert_eq!(s.material, m)
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_deserialize_singular_transformation() {
+        let sphere = Sphere::with_transform(Matrix4::scaling(2.0, 2.0, 2.0));
+        let json = serde_json::to_string(&sphere)
+            .unwrap()
+            .replace("2.0", "0.0");
+        assert!(serde_json::from_str::<Sphere>(&json).is_err());
     }
 }
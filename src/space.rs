@@ -1,12 +1,19 @@
-use crate::{approx_equal, matrix::Matrix};
+use crate::Float;
+use crate::{approx_equal, fmt_components, matrix4::Matrix4};
 
-use std::ops::{Add, Deref, DerefMut, Div, Mul, Neg, Sub};
+use std::fmt;
+
+use std::ops::{
+    Add, AddAssign, Deref, DerefMut, Div, DivAssign, Index, IndexMut, Mul, MulAssign, Neg, Sub,
+    SubAssign,
+};
 
 #[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Point(Tuple);
 
 impl Point {
-    pub fn new(x: f64, y: f64, z: f64) -> Self {
+    pub fn new(x: Float, y: Float, z: Float) -> Self {
         Self(Tuple::new(x, y, z, 1.0))
     }
 
@@ -20,29 +27,34 @@ impl Point {
         Vector(t)
     }
 
-    pub fn translate(&self, x: f64, y: f64, z: f64) -> Point {
+    pub fn translate(&self, x: Float, y: Float, z: Float) -> Point {
         Point((**self).translate(x, y, z))
     }
 
-    pub fn scale(&self, x: f64, y: f64, z: f64) -> Point {
+    pub fn scale(&self, x: Float, y: Float, z: Float) -> Point {
         Point((**self).scale(x, y, z))
     }
 
-    pub fn shear(&self, xy: f64, xz: f64, yx: f64, yz: f64, zx: f64, zy: f64) -> Point {
+    pub fn shear(&self, xy: Float, xz: Float, yx: Float, yz: Float, zx: Float, zy: Float) -> Point {
         Point((**self).shear(xy, xz, yx, yz, zx, zy))
     }
 
-    pub fn rotate_x(&self, x: f64) -> Point {
+    pub fn rotate_x(&self, x: Float) -> Point {
         Point((**self).rotate_x(x))
     }
 
-    pub fn rotate_y(&self, y: f64) -> Point {
+    pub fn rotate_y(&self, y: Float) -> Point {
         Point((**self).rotate_y(y))
     }
 
-    pub fn rotate_z(&self, z: f64) -> Point {
+    pub fn rotate_z(&self, z: Float) -> Point {
         Point((**self).rotate_z(z))
     }
+
+    /// The x, y and z coordinates.
+    pub fn to_array(&self) -> [Float; 3] {
+        [self.x, self.y, self.z]
+    }
 }
 
 impl From<Tuple> for Point {
@@ -51,6 +63,18 @@ impl From<Tuple> for Point {
     }
 }
 
+impl From<[Float; 3]> for Point {
+    fn from([x, y, z]: [Float; 3]) -> Self {
+        Self::new(x, y, z)
+    }
+}
+
+impl From<(Float, Float, Float)> for Point {
+    fn from((x, y, z): (Float, Float, Float)) -> Self {
+        Self::new(x, y, z)
+    }
+}
+
 impl Add<Vector> for Point {
     type Output = Point;
     fn add(self, rhs: Vector) -> Self::Output {
@@ -106,39 +130,70 @@ impl DerefMut for Point {
     }
 }
 
+impl fmt::Display for Point {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_components(f, "point", &[self.x, self.y, self.z])
+    }
+}
+
+impl Index<usize> for Point {
+    type Output = Float;
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.0[index]
+    }
+}
+
+/// Only x, y and z can be set: changing w would turn a point into something
+/// else.
+impl IndexMut<usize> for Point {
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        assert!(index < 3, "point index out of range: {index}");
+        &mut self.0[index]
+    }
+}
+
 #[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Vector(Tuple);
 
 impl Vector {
-    pub fn new(x: f64, y: f64, z: f64) -> Self {
+    pub fn new(x: Float, y: Float, z: Float) -> Self {
         Self(Tuple::new(x, y, z, 0.0))
     }
 
-    pub fn translate(&self, x: f64, y: f64, z: f64) -> Vector {
+    pub fn translate(&self, x: Float, y: Float, z: Float) -> Vector {
         Vector((**self).translate(x, y, z))
     }
 
-    pub fn scale(&self, x: f64, y: f64, z: f64) -> Vector {
+    pub fn scale(&self, x: Float, y: Float, z: Float) -> Vector {
         Vector((**self).scale(x, y, z))
     }
 
-    pub fn rotate_x(&self, x: f64) -> Vector {
+    pub fn rotate_x(&self, x: Float) -> Vector {
         Vector((**self).rotate_x(x))
     }
 
-    pub fn rotate_y(&self, y: f64) -> Vector {
+    pub fn rotate_y(&self, y: Float) -> Vector {
         Vector((**self).rotate_y(y))
     }
 
-    pub fn rotate_z(&self, z: f64) -> Vector {
+    pub fn rotate_z(&self, z: Float) -> Vector {
         Vector((**self).rotate_z(z))
     }
 
-    pub fn shear(&self, xy: f64, xz: f64, yx: f64, yz: f64, zx: f64, zy: f64) -> Vector {
+    pub fn shear(
+        &self,
+        xy: Float,
+        xz: Float,
+        yx: Float,
+        yz: Float,
+        zx: Float,
+        zy: Float,
+    ) -> Vector {
         Vector((**self).shear(xy, xz, yx, yz, zx, zy))
     }
 
-    pub fn magnitude(&self) -> f64 {
+    pub fn magnitude(&self) -> Float {
         (self.x.powf(2.) + self.y.powf(2.) + self.z.powf(2.) + self.w.powf(2.)).sqrt()
     }
 
@@ -147,7 +202,7 @@ impl Vector {
         Vector::new(self.x / m, self.y / m, self.z / m)
     }
 
-    pub fn dot(&self, other: &Self) -> f64 {
+    pub fn dot(&self, other: &Self) -> Float {
         self.x * other.x + self.y * other.y + self.z * other.z + self.w + other.w
     }
 
@@ -162,6 +217,28 @@ impl Vector {
     pub fn reflect(&self, normal: &Vector) -> Vector {
         *self - normal * 2.0 * self.dot(normal)
     }
+
+    /// The angle between the two vectors in radians, from 0 to π.
+    pub fn angle_between(&self, other: &Vector) -> Float {
+        // More accurate than the acos of the dot product for nearly parallel
+        // vectors.
+        self.cross(*other).magnitude().atan2(self.dot(other))
+    }
+
+    /// The component of this vector in the direction of `other`.
+    pub fn project_onto(&self, other: &Vector) -> Vector {
+        other * (self.dot(other) / other.dot(other))
+    }
+
+    /// Interpolate linearly towards `other`, as `t` goes from 0.0 to 1.0.
+    pub fn lerp(&self, other: &Vector, t: Float) -> Vector {
+        *self + (other - self) * t
+    }
+
+    /// The x, y and z components.
+    pub fn to_array(&self) -> [Float; 3] {
+        [self.x, self.y, self.z]
+    }
 }
 
 impl From<Tuple> for Vector {
@@ -172,6 +249,18 @@ impl From<Tuple> for Vector {
     }
 }
 
+impl From<[Float; 3]> for Vector {
+    fn from([x, y, z]: [Float; 3]) -> Self {
+        Self::new(x, y, z)
+    }
+}
+
+impl From<(Float, Float, Float)> for Vector {
+    fn from((x, y, z): (Float, Float, Float)) -> Self {
+        Self::new(x, y, z)
+    }
+}
+
 impl Add<Vector> for Vector {
     type Output = Vector;
     fn add(self, rhs: Vector) -> Self::Output {
@@ -200,20 +289,48 @@ impl Sub<&Vector> for &Vector {
     }
 }
 
-impl Mul<f64> for Vector {
+impl Mul<Float> for Vector {
     type Output = Vector;
-    fn mul(self, rhs: f64) -> Self::Output {
+    fn mul(self, rhs: Float) -> Self::Output {
         Vector(*self * rhs)
     }
 }
 
-impl Mul<f64> for &Vector {
+impl Mul<Float> for &Vector {
     type Output = Vector;
-    fn mul(self, rhs: f64) -> Self::Output {
+    fn mul(self, rhs: Float) -> Self::Output {
         Vector(**self * rhs)
     }
 }
 
+impl Div<Float> for Vector {
+    type Output = Vector;
+    fn div(self, rhs: Float) -> Self::Output {
+        Vector(*self / rhs)
+    }
+}
+
+impl Div<Float> for &Vector {
+    type Output = Vector;
+    fn div(self, rhs: Float) -> Self::Output {
+        Vector(**self / rhs)
+    }
+}
+
+impl Neg for Vector {
+    type Output = Vector;
+    fn neg(self) -> Self::Output {
+        Vector(-*self)
+    }
+}
+
+impl Neg for &Vector {
+    type Output = Vector;
+    fn neg(self) -> Self::Output {
+        Vector(-**self)
+    }
+}
+
 impl Deref for Vector {
     type Target = Tuple;
     fn deref(&self) -> &Self::Target {
@@ -227,51 +344,71 @@ impl DerefMut for Vector {
     }
 }
 
+impl fmt::Display for Vector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_components(f, "vector", &[self.x, self.y, self.z])
+    }
+}
+
+impl Index<usize> for Vector {
+    type Output = Float;
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.0[index]
+    }
+}
+
+/// Only x, y and z can be set: changing w would turn a vector into something
+/// else.
+impl IndexMut<usize> for Vector {
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        assert!(index < 3, "vector index out of range: {index}");
+        &mut self.0[index]
+    }
+}
+
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Tuple {
-    x: f64,
-    y: f64,
-    z: f64,
-    w: f64,
+    x: Float,
+    y: Float,
+    z: Float,
+    w: Float,
 }
 
 impl Tuple {
-    pub fn new(x: f64, y: f64, z: f64, w: f64) -> Self {
+    pub fn new(x: Float, y: Float, z: Float, w: Float) -> Self {
         Self { x, y, z, w }
     }
 
-    pub fn point(x: f64, y: f64, z: f64) -> Point {
+    pub fn point(x: Float, y: Float, z: Float) -> Point {
         Point::new(x, y, z)
     }
 
-    pub fn vector(x: f64, y: f64, z: f64) -> Vector {
+    pub fn vector(x: Float, y: Float, z: Float) -> Vector {
         Vector::new(x, y, z)
     }
 
-    pub fn x(&self) -> f64 {
+    pub fn x(&self) -> Float {
         self.x
     }
 
-    pub fn y(&self) -> f64 {
+    pub fn y(&self) -> Float {
         self.y
     }
 
-    pub fn z(&self) -> f64 {
+    pub fn z(&self) -> Float {
         self.z
     }
 
-    pub fn get(&self, index: usize) -> f64 {
-        match index {
-            0 => self.x,
-            1 => self.y,
-            2 => self.z,
-            3 => self.w,
-            4_usize.. => todo!(),
-        }
+    /// The component at `index`: x, y, z then w.
+    ///
+    /// Panics if `index` is 4 or more.
+    pub fn get(&self, index: usize) -> Float {
+        self[index]
     }
 
     #[allow(unused)]
-    fn w(&self) -> f64 {
+    fn w(&self) -> Float {
         self.w
     }
 
@@ -283,28 +420,59 @@ impl Tuple {
         approx_equal(self.w, 0.0)
     }
 
-    pub fn translate(&self, x: f64, y: f64, z: f64) -> Tuple {
-        Matrix::translation(x, y, z) * self
+    pub fn translate(&self, x: Float, y: Float, z: Float) -> Tuple {
+        Matrix4::translation(x, y, z) * self
     }
 
-    pub fn scale(&self, x: f64, y: f64, z: f64) -> Tuple {
-        Matrix::scaling(x, y, z) * self
+    pub fn scale(&self, x: Float, y: Float, z: Float) -> Tuple {
+        Matrix4::scaling(x, y, z) * self
     }
 
-    pub fn shear(&self, xy: f64, xz: f64, yx: f64, yz: f64, zx: f64, zy: f64) -> Tuple {
-        Matrix::shearing(xy, xz, yx, yz, zx, zy) * self
+    pub fn shear(&self, xy: Float, xz: Float, yx: Float, yz: Float, zx: Float, zy: Float) -> Tuple {
+        Matrix4::shearing(xy, xz, yx, yz, zx, zy) * self
     }
 
-    pub fn rotate_x(&self, radians: f64) -> Tuple {
-        Matrix::rotation_x(radians) * self
+    pub fn rotate_x(&self, radians: Float) -> Tuple {
+        Matrix4::rotation_x(radians) * self
     }
 
-    pub fn rotate_y(&self, radians: f64) -> Tuple {
-        Matrix::rotation_y(radians) * self
+    pub fn rotate_y(&self, radians: Float) -> Tuple {
+        Matrix4::rotation_y(radians) * self
     }
 
-    pub fn rotate_z(&self, radians: f64) -> Tuple {
-        Matrix::rotation_z(radians) * self
+    pub fn rotate_z(&self, radians: Float) -> Tuple {
+        Matrix4::rotation_z(radians) * self
+    }
+}
+
+impl fmt::Display for Tuple {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_components(f, "tuple", &[self.x, self.y, self.z, self.w])
+    }
+}
+
+impl Index<usize> for Tuple {
+    type Output = Float;
+    fn index(&self, index: usize) -> &Self::Output {
+        match index {
+            0 => &self.x,
+            1 => &self.y,
+            2 => &self.z,
+            3 => &self.w,
+            _ => panic!("tuple index out of range: {index}"),
+        }
+    }
+}
+
+impl IndexMut<usize> for Tuple {
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        match index {
+            0 => &mut self.x,
+            1 => &mut self.y,
+            2 => &mut self.z,
+            3 => &mut self.w,
+            _ => panic!("tuple index out of range: {index}"),
+        }
     }
 }
 
@@ -352,9 +520,9 @@ impl Neg for Tuple {
     }
 }
 
-impl Mul<f64> for Tuple {
+impl Mul<Float> for Tuple {
     type Output = Tuple;
-    fn mul(self, rhs: f64) -> Self::Output {
+    fn mul(self, rhs: Float) -> Self::Output {
         Self::Output {
             x: self.x * rhs,
             y: self.y * rhs,
@@ -364,9 +532,9 @@ impl Mul<f64> for Tuple {
     }
 }
 
-impl Div<f64> for Tuple {
+impl Div<Float> for Tuple {
     type Output = Tuple;
-    fn div(self, rhs: f64) -> Self::Output {
+    fn div(self, rhs: Float) -> Self::Output {
         Self::Output {
             x: self.x / rhs,
             y: self.y / rhs,
@@ -376,8 +544,72 @@ impl Div<f64> for Tuple {
     }
 }
 
+// Assignment operators, delegating to the binary operators above.
+
+impl AddAssign for Tuple {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl SubAssign for Tuple {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+impl MulAssign<Float> for Tuple {
+    fn mul_assign(&mut self, rhs: Float) {
+        *self = *self * rhs;
+    }
+}
+
+impl DivAssign<Float> for Tuple {
+    fn div_assign(&mut self, rhs: Float) {
+        *self = *self / rhs;
+    }
+}
+
+impl AddAssign<Vector> for Point {
+    fn add_assign(&mut self, rhs: Vector) {
+        *self = *self + rhs;
+    }
+}
+
+impl SubAssign<Vector> for Point {
+    fn sub_assign(&mut self, rhs: Vector) {
+        *self = *self - rhs;
+    }
+}
+
+impl AddAssign for Vector {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl SubAssign for Vector {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+impl MulAssign<Float> for Vector {
+    fn mul_assign(&mut self, rhs: Float) {
+        *self = *self * rhs;
+    }
+}
+
+impl DivAssign<Float> for Vector {
+    fn div_assign(&mut self, rhs: Float) {
+        *self = *self / rhs;
+    }
+}
+
 #[cfg(test)]
 mod test {
+    use crate::consts::PI;
+
     use super::*;
     use crate::{assert_approx_eq, testlib::approx_equals_fail, EPSILON};
 
@@ -424,6 +656,68 @@ mod test {
         // assert_ne!(a, b);
     }
 
+    #[test]
+    fn test_tuple_index() {
+        let mut t = Tuple::new(4.3, -4.2, 3.1, 1.0);
+        assert_eq!((t[0], t[1], t[2], t[3]), (4.3, -4.2, 3.1, 1.0));
+        assert_eq!(t.get(2), 3.1);
+
+        t[1] = 2.0;
+        t[3] = 0.0;
+        assert_eq!(t, Tuple::new(4.3, 2.0, 3.1, 0.0));
+    }
+
+    #[test]
+    #[should_panic(expected = "tuple index out of range: 4")]
+    fn test_tuple_index_out_of_range() {
+        Tuple::new(0.0, 0.0, 0.0, 0.0).get(4);
+    }
+
+    #[test]
+    fn test_point_and_vector_index() {
+        fn sum<T: Index<usize, Output = Float>>(t: &T) -> Float {
+            (0..3).map(|i| t[i]).sum()
+        }
+
+        let mut p = Point::new(1.0, 2.0, 3.0);
+        p[0] = 4.0;
+        assert_eq!(p, Point::new(4.0, 2.0, 3.0));
+        assert_eq!(sum(&p), 9.0);
+
+        let mut v = Vector::new(1.0, 2.0, 3.0);
+        for i in 0..3 {
+            v[i] *= 2.0;
+        }
+        assert_eq!(v, Vector::new(2.0, 4.0, 6.0));
+        assert_eq!(v[3], 0.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "point index out of range: 3")]
+    fn test_point_index_mut_w() {
+        Point::origin()[3] = 0.0;
+    }
+
+    #[test]
+    #[should_panic(expected = "vector index out of range: 3")]
+    fn test_vector_index_mut_w() {
+        Vector::new(1.0, 0.0, 0.0)[3] = 1.0;
+    }
+
+    #[test]
+    fn test_array_and_tuple_conversions() {
+        let p = Point::from([1.0, 2.0, 3.0]);
+        assert_eq!(p, Point::new(1.0, 2.0, 3.0));
+        assert_eq!(Point::from((1.0, 2.0, 3.0)), p);
+        assert_eq!(p.to_array(), [1.0, 2.0, 3.0]);
+
+        let v = Vector::from([1.0, 2.0, 3.0]);
+        assert_eq!(v, Vector::new(1.0, 2.0, 3.0));
+        assert_eq!(Vector::from((1.0, 2.0, 3.0)), v);
+        assert_eq!(v.to_array(), [1.0, 2.0, 3.0]);
+        assert_eq!(v[3], 0.0);
+    }
+
     #[test]
     fn test_tuple_add() {
         let a = Point::new(3., -2., 5.);
@@ -520,8 +814,11 @@ mod test {
         assert_approx_eq!(Vector::new(1.0, 0.0, 0.0).magnitude(), 1.0);
         assert_approx_eq!(Vector::new(0.0, 1.0, 0.0).magnitude(), 1.0);
         assert_approx_eq!(Vector::new(0.0, 0.0, 1.0).magnitude(), 1.0);
-        assert_approx_eq!(Vector::new(1.0, 2.0, 3.0).magnitude(), 14_f64.sqrt());
-        assert_approx_eq!(Vector::new(-1.0, -2.0, -3.0).magnitude(), 14_f64.sqrt());
+        assert_approx_eq!(Vector::new(1.0, 2.0, 3.0).magnitude(), (14 as Float).sqrt());
+        assert_approx_eq!(
+            Vector::new(-1.0, -2.0, -3.0).magnitude(),
+            (14 as Float).sqrt()
+        );
     }
 
     #[test]
@@ -565,10 +862,99 @@ mod test {
 
     #[test]
     fn test_reflect_slanted() {
-        let hsq = (2.0_f64).sqrt() / 2.0;
+        let hsq = (2.0 as Float).sqrt() / 2.0;
         let v = Vector::new(0.0, -1.0, 0.0);
         let n = Vector::new(hsq, hsq, 0.0);
 
         assert_eq!(v.reflect(&n), Vector::new(1.0, 0.0, 0.0));
     }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(Point::new(1.0, 2.0, 3.0).to_string(), "point(1, 2, 3)");
+        assert_eq!(
+            Vector::new(0.5, -2.0, 0.0).to_string(),
+            "vector(0.5, -2, 0)"
+        );
+        assert_eq!(
+            Tuple::new(1.0, 2.0, 3.0, 1.0).to_string(),
+            "tuple(1, 2, 3, 1)"
+        );
+        assert_eq!(
+            format!("{:.2}", Point::new(1.0, 0.125, 1.0 / 3.0)),
+            "point(1.00, 0.12, 0.33)"
+        );
+    }
+
+    #[test]
+    fn test_assignment_operators() {
+        let mut t = Tuple::new(1.0, 2.0, 3.0, 0.0);
+        t += Tuple::new(1.0, 1.0, 1.0, 1.0);
+        t -= Tuple::new(0.0, 1.0, 0.0, 0.0);
+        t *= 2.0;
+        t /= 4.0;
+        assert_eq!(t, Tuple::new(1.0, 1.0, 2.0, 0.5));
+
+        let mut p = Point::new(1.0, 2.0, 3.0);
+        p += Vector::new(1.0, 0.0, -1.0);
+        assert_eq!(p, Point::new(2.0, 2.0, 2.0));
+        p -= Vector::new(2.0, 2.0, 2.0);
+        assert_eq!(p, Point::origin());
+
+        let mut v = Vector::new(1.0, 2.0, 3.0);
+        v += Vector::new(1.0, 0.0, 0.0);
+        v -= Vector::new(0.0, 0.0, 1.0);
+        v *= 3.0;
+        v /= 2.0;
+        assert_eq!(v, Vector::new(3.0, 3.0, 3.0));
+    }
+
+    #[test]
+    fn test_vector_neg() {
+        let v = Vector::new(1.0, -2.0, 3.0);
+        assert_eq!(-v, Vector::new(-1.0, 2.0, -3.0));
+        assert_eq!(-&v, v * -1.0);
+        assert!((-v).is_vector());
+    }
+
+    #[test]
+    fn test_vector_div() {
+        let v = Vector::new(1.0, -2.0, 3.0);
+        assert_eq!(v / 2.0, Vector::new(0.5, -1.0, 1.5));
+        assert_eq!(&v / 2.0, v * 0.5);
+    }
+
+    #[test]
+    fn test_angle_between() {
+        let x = Vector::new(1.0, 0.0, 0.0);
+        assert_approx_eq!(x.angle_between(&Vector::new(0.0, 3.0, 0.0)), PI / 2.0);
+        assert_approx_eq!(x.angle_between(&Vector::new(2.0, 2.0, 0.0)), PI / 4.0);
+        assert_approx_eq!(x.angle_between(&Vector::new(-1.0, 0.0, 0.0)), PI);
+        assert_eq!(x.angle_between(&(x * 3.0)), 0.0);
+
+        let v = Vector::new(0.1, 0.2, 0.3);
+        assert_approx_eq!(v.angle_between(&v), 0.0);
+    }
+
+    #[test]
+    fn test_project_onto() {
+        let v = Vector::new(2.0, 3.0, 4.0);
+        assert_eq!(
+            v.project_onto(&Vector::new(0.0, 5.0, 0.0)),
+            Vector::new(0.0, 3.0, 0.0)
+        );
+        assert_eq!(
+            v.project_onto(&Vector::new(1.0, 1.0, 0.0)),
+            Vector::new(2.5, 2.5, 0.0)
+        );
+    }
+
+    #[test]
+    fn test_vector_lerp() {
+        let a = Vector::new(1.0, 0.0, -2.0);
+        let b = Vector::new(3.0, 4.0, 2.0);
+        assert_eq!(a.lerp(&b, 0.0), a);
+        assert_eq!(a.lerp(&b, 1.0), b);
+        assert_eq!(a.lerp(&b, 0.25), Vector::new(1.5, 1.0, -1.0));
+    }
 }
@@ -0,0 +1,120 @@
+use crate::camera::Camera;
+use crate::canvas::Canvas;
+use crate::color::Color;
+use crate::matrix4::Matrix4;
+use crate::world::World;
+use crate::Float;
+
+/// How the two views of a stereo pair are combined into one image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StereoLayout {
+    /// The left eye's view on the left and the right eye's on the right, in
+    /// an image twice the camera's width, for VR headsets and 3D displays.
+    SideBySide,
+    /// A red-cyan anaglyph for viewing with colored glasses: red from the left
+    /// eye, green and blue from the right.
+    Anaglyph,
+}
+
+impl Camera {
+    /// The camera for one eye of a stereo pair: this camera moved sideways by
+    /// `offset`, to the left for positive values, keeping the same direction.
+    fn eye(&self, offset: Float) -> Camera {
+        // The camera looks toward -z, so +x is to the left.
+        let mut eye = self.clone();
+        let view = Matrix4::translation(-offset, 0.0, 0.0) * *eye.transformation();
+        eye.set_transform(view);
+        eye
+    }
+
+    /// Render `world` from two eyes `eye_separation` apart, centred on the
+    /// camera's position, and combine the views according to `layout`.
+    pub fn render_stereo(
+        &self,
+        world: &World,
+        eye_separation: Float,
+        layout: StereoLayout,
+    ) -> Canvas {
+        let left = self.eye(eye_separation / 2.0).render(world);
+        let right = self.eye(-eye_separation / 2.0).render(world);
+
+        match layout {
+            StereoLayout::SideBySide => {
+                let mut image = Canvas::new(self.hsize() * 2, self.vsize());
+                for y in 0..self.vsize() {
+                    for x in 0..self.hsize() {
+                        image.write_pixel(x, y, left.pixel_at(x, y));
+                        image.write_pixel(x + self.hsize(), y, right.pixel_at(x, y));
+                    }
+                }
+                image
+            }
+            StereoLayout::Anaglyph => {
+                let mut image = Canvas::new(self.hsize(), self.vsize());
+                for y in 0..self.vsize() {
+                    for x in 0..self.hsize() {
+                        let (l, r) = (left.pixel_at(x, y), right.pixel_at(x, y));
+                        image.write_pixel(x, y, Color::new(l.red(), r.green(), r.blue()));
+                    }
+                }
+                image
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::consts::PI;
+
+    use super::*;
+    use crate::fixtures::{default_camera, default_world};
+    use crate::space::{Point, Vector};
+
+    fn camera_from(x: Float) -> Camera {
+        let mut c = Camera::new(11, 11, PI / 2.0);
+        c.set_transform(Matrix4::view_transform(
+            Point::new(x, 0.0, -5.0),
+            Point::new(x, 0.0, 0.0),
+            Vector::new(0.0, 1.0, 0.0),
+        ));
+        c
+    }
+
+    #[test]
+    fn test_eyes_are_offset_sideways() {
+        // Looking toward +z from z = -5, the viewer's left is -x.
+        let w = default_world();
+        let c = default_camera();
+        assert_eq!(c.eye(0.5).render(&w), camera_from(-0.5).render(&w));
+        assert_eq!(c.eye(-0.5).render(&w), camera_from(0.5).render(&w));
+    }
+
+    #[test]
+    fn test_side_by_side() {
+        let w = default_world();
+        let image = default_camera().render_stereo(&w, 1.0, StereoLayout::SideBySide);
+        let left = camera_from(-0.5).render(&w);
+        let right = camera_from(0.5).render(&w);
+
+        assert_eq!(image.width, 22);
+        assert_eq!(image.height, 11);
+        assert_eq!(image.pixel_at(3, 5), left.pixel_at(3, 5));
+        assert_eq!(image.pixel_at(11 + 3, 5), right.pixel_at(3, 5));
+    }
+
+    #[test]
+    fn test_anaglyph() {
+        let w = default_world();
+        let image = default_camera().render_stereo(&w, 1.0, StereoLayout::Anaglyph);
+        let left = camera_from(-0.5).render(&w).pixel_at(4, 5);
+        let right = camera_from(0.5).render(&w).pixel_at(4, 5);
+
+        assert_eq!(image.width, 11);
+        assert_eq!(
+            image.pixel_at(4, 5),
+            Color::new(left.red(), right.green(), right.blue())
+        );
+    }
+}
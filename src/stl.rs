@@ -0,0 +1,199 @@
+//! Import meshes from STL files, as used for 3D printing.
+
+use std::fs::File;
+use std::io::{prelude::*, Error, ErrorKind, Result};
+use std::path::Path;
+
+use crate::shape::{Group, Triangle};
+use crate::space::Point;
+use crate::Float;
+
+const HEADER_SIZE: usize = 80;
+/// A normal and three vertices of three f32s each, then two attribute bytes.
+const FACET_SIZE: usize = 50;
+
+/// Read a mesh from an ASCII or binary STL file, as a group with a triangle
+/// for each facet. The facets' normals are ignored in favour of the ones
+/// their vertices give.
+///
+/// Binary files are recognised by their length, as some begin with `solid`
+/// like ASCII ones. Build a kd-tree for the group with
+/// [`Group::build_kd_tree`] before rendering a large mesh.
+pub fn read(source: &mut impl Read) -> Result<Group> {
+    let mut data = vec![];
+    source.read_to_end(&mut data)?;
+
+    let binary_count = data
+        .get(HEADER_SIZE..HEADER_SIZE + 4)
+        .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()) as usize);
+    match binary_count {
+        Some(count) if data.len() == HEADER_SIZE + 4 + count * FACET_SIZE => {
+            Ok(read_binary(&data[HEADER_SIZE + 4..]))
+        }
+        _ if data.starts_with(b"solid") => {
+            let text = std::str::from_utf8(&data).map_err(|_| invalid("ASCII STL isn't UTF-8"))?;
+            read_ascii(text)
+        }
+        Some(count) => Err(invalid(format!(
+            "STL file is the wrong size for its {count} facets"
+        ))),
+        None => Err(invalid("STL file is too short")),
+    }
+}
+
+/// Load a mesh from the STL file at `path`. See [`read`].
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(skip_all, fields(path = %path.as_ref().display()))
+)]
+pub fn load(path: impl AsRef<Path>) -> Result<Group> {
+    read(&mut File::open(path)?)
+}
+
+fn read_binary(facets: &[u8]) -> Group {
+    let mut group = Group::new();
+    for facet in facets.chunks_exact(FACET_SIZE) {
+        let value = |i: usize| {
+            let offset = 4 * i;
+            f32::from_le_bytes(facet[offset..offset + 4].try_into().unwrap()) as Float
+        };
+        // Skipping the normal, in the first three values.
+        let point = |vertex: usize| {
+            let i = 3 + 3 * vertex;
+            Point::new(value(i), value(i + 1), value(i + 2))
+        };
+        group.add_child(Triangle::new(point(0), point(1), point(2)));
+    }
+    group
+}
+
+fn read_ascii(text: &str) -> Result<Group> {
+    let mut group = Group::new();
+    let mut points = vec![];
+    for (number, line) in text.lines().enumerate() {
+        let invalid_line = || invalid(format!("invalid STL on line {}: {line:?}", number + 1));
+        match line.split_whitespace().collect::<Vec<_>>().as_slice() {
+            ["vertex", x, y, z] => {
+                let [x, y, z] = [x, y, z].map(|value| value.parse::<Float>());
+                points.push(Point::new(
+                    x.map_err(|_| invalid_line())?,
+                    y.map_err(|_| invalid_line())?,
+                    z.map_err(|_| invalid_line())?,
+                ));
+            }
+            ["endloop"] => {
+                let [p1, p2, p3] = points[..] else {
+                    return Err(invalid_line());
+                };
+                group.add_child(Triangle::new(p1, p2, p3));
+                points.clear();
+            }
+            _ => {}
+        }
+    }
+    Ok(group)
+}
+
+fn invalid(message: impl Into<String>) -> Error {
+    Error::new(ErrorKind::InvalidData, message.into())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::shape::Shape;
+
+    const TWO_FACETS: &str = "solid face
+  facet normal 0 0 -1
+    outer loop
+      vertex 0 1 0
+      vertex -1 0 0
+      vertex 1 0 0
+    endloop
+  endfacet
+  facet normal 0 -1 0
+    outer loop
+      vertex 0 0 0
+      vertex 1 0 0
+      vertex 0 0 1
+    endloop
+  endfacet
+endsolid face
+";
+
+    fn points(group: &Group) -> Vec<(Point, Point, Point)> {
+        group
+            .children()
+            .iter()
+            .map(|child| match child {
+                Shape::Triangle(triangle) => triangle.points(),
+                _ => unreachable!(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_read_ascii() {
+        let group = read(&mut TWO_FACETS.as_bytes()).unwrap();
+        assert_eq!(
+            points(&group),
+            [
+                (
+                    Point::new(0.0, 1.0, 0.0),
+                    Point::new(-1.0, 0.0, 0.0),
+                    Point::new(1.0, 0.0, 0.0)
+                ),
+                (
+                    Point::new(0.0, 0.0, 0.0),
+                    Point::new(1.0, 0.0, 0.0),
+                    Point::new(0.0, 0.0, 1.0)
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_read_binary() {
+        // Headers may start with "solid" too.
+        let mut stl = b"solid but binary".to_vec();
+        stl.resize(HEADER_SIZE, 0);
+        stl.extend_from_slice(&1u32.to_le_bytes());
+        for value in [
+            0.0f32, 0.0, -1.0, 0.0, 1.0, 0.0, -1.0, 0.0, 0.0, 1.0, 0.0, 0.0,
+        ] {
+            stl.extend_from_slice(&value.to_le_bytes());
+        }
+        stl.extend_from_slice(&[0, 0]);
+
+        let group = read(&mut stl.as_slice()).unwrap();
+        assert_eq!(
+            points(&group),
+            [(
+                Point::new(0.0, 1.0, 0.0),
+                Point::new(-1.0, 0.0, 0.0),
+                Point::new(1.0, 0.0, 0.0)
+            )]
+        );
+    }
+
+    #[test]
+    fn test_read_invalid() {
+        let error = |stl: &[u8]| read(&mut &stl[..]).unwrap_err().to_string();
+        assert_eq!(error(b"not an stl"), "STL file is too short");
+        let mut stl = vec![0; HEADER_SIZE];
+        stl.extend_from_slice(&2u32.to_le_bytes());
+        assert_eq!(error(&stl), "STL file is the wrong size for its 2 facets");
+        assert_eq!(
+            error(
+                TWO_FACETS
+                    .replace("vertex 0 0 1", "vertex 0 0 z")
+                    .as_bytes()
+            ),
+            "invalid STL on line 13: \"      vertex 0 0 z\""
+        );
+        assert_eq!(
+            error(TWO_FACETS.replace("vertex 0 0 1", "").as_bytes()),
+            "invalid STL on line 14: \"    endloop\""
+        );
+    }
+}
@@ -0,0 +1,109 @@
+use crate::canvas::Canvas;
+use crate::shape::{Group, Triangle};
+use crate::space::Point;
+use crate::Float;
+
+/// How many triangles the terrain's groups are divided down to, so rays
+/// only test the few near where they pass.
+const DIVIDE_THRESHOLD: usize = 8;
+
+/// Build terrain from a heightfield, with a vertex for each pixel raised by
+/// the pixel's luminance, so black is at height 0.0 and white at 1.0.
+///
+/// The terrain covers -1.0 to 1.0 in x and z, laid out like a map viewed
+/// from above: the image's columns run along +x and its top row is at +z.
+/// Transform the group to size and place it. Each grid square is split
+/// into two triangles, grouped so rays skip most of them.
+///
+/// Panics if `heights` is less than 2 pixels in either direction.
+pub fn heightmap(heights: &Canvas) -> Group {
+    assert!(
+        heights.width >= 2 && heights.height >= 2,
+        "a heightmap needs at least 2x2 pixels"
+    );
+
+    let vertex = |col: usize, row: usize| {
+        Point::new(
+            -1.0 + 2.0 * col as Float / (heights.width - 1) as Float,
+            heights.pixel_at(col, row).luminance(),
+            1.0 - 2.0 * row as Float / (heights.height - 1) as Float,
+        )
+    };
+
+    let mut group = Group::new();
+    for row in 0..heights.height - 1 {
+        for col in 0..heights.width - 1 {
+            let top_left = vertex(col, row);
+            let top_right = vertex(col + 1, row);
+            let bottom_left = vertex(col, row + 1);
+            let bottom_right = vertex(col + 1, row + 1);
+            // Wound so the flat terrain's normals point up.
+            group.add_child(Triangle::new(top_left, bottom_left, top_right));
+            group.add_child(Triangle::new(top_right, bottom_left, bottom_right));
+        }
+    }
+    group.divide(DIVIDE_THRESHOLD);
+    group
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::color::Color;
+    use crate::ray::{Intersections, Ray};
+    use crate::shape::Shape;
+    use crate::space::Vector;
+
+    fn count_triangles(shape: &Shape) -> usize {
+        match shape {
+            Shape::Triangle(_) => 1,
+            Shape::Group(group) => group.children().iter().map(count_triangles).sum(),
+            _ => 0,
+        }
+    }
+
+    fn height_at(terrain: &Shape, x: Float, z: Float) -> Option<Float> {
+        let r = Ray::new(Point::new(x, 10.0, z), Vector::new(0.0, -1.0, 0.0));
+        let mut xs = Intersections::new();
+        terrain.intersect(&r, &mut xs);
+        xs.hit().map(|hit| 10.0 - hit.t)
+    }
+
+    #[test]
+    fn test_heightmap_triangle_count() {
+        let terrain: Shape = heightmap(&Canvas::new(5, 4)).into();
+        assert_eq!(count_triangles(&terrain), 4 * 3 * 2);
+    }
+
+    #[test]
+    fn test_heightmap_heights() {
+        let mut heights = Canvas::new(3, 3);
+        heights.write_pixel(1, 1, Color::new(1.0, 1.0, 1.0));
+        heights.write_pixel(0, 0, Color::new(0.5, 0.5, 0.5));
+        let terrain: Shape = heightmap(&heights).into();
+
+        assert_eq!(height_at(&terrain, 0.0, 0.0), Some(1.0));
+        assert!(height_at(&terrain, -0.999, 0.999).is_some_and(|h| h > 0.49));
+        assert!(height_at(&terrain, 0.999, -0.999).is_some_and(|h| h < 0.01));
+        assert_eq!(height_at(&terrain, 1.5, 0.0), None);
+    }
+
+    #[test]
+    fn test_flat_heightmap_faces_up() {
+        let terrain: Shape = heightmap(&Canvas::new(2, 2)).into();
+        let triangle = match &terrain {
+            Shape::Group(group) => &group.children()[0],
+            _ => unreachable!(),
+        };
+        assert_eq!(
+            triangle.normal_at(&Point::origin()),
+            Vector::new(0.0, 1.0, 0.0)
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_heightmap_too_small() {
+        heightmap(&Canvas::new(1, 5));
+    }
+}
@@ -5,7 +5,7 @@ macro_rules! assert_approx_eq {
         ($left:expr, $right:expr $(,)?) => {
             match (&$left, &$right) {
                 (left_val, right_val) => {
-                    if !((*left_val - *right_val).abs() < crate::EPSILON) {
+                    if (*left_val - *right_val).abs() >= $crate::EPSILON {
                         approx_equals_fail(left_val, right_val, None);
                     }
                 }
@@ -0,0 +1,173 @@
+use crate::canvas::Canvas;
+use crate::tonemap::OutputOptions;
+use std::io::{prelude::*, Error, ErrorKind, Result};
+
+/// Settings controlling how a TGA is written.
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TgaOptions {
+    /// Compress the image with run-length encoding, which shrinks flat areas
+    /// such as backgrounds a lot and is understood by nearly all readers.
+    pub rle: bool,
+    /// Exposure and tone mapping, applied before colors are clamped.
+    pub output: OutputOptions,
+}
+
+/// The most pixels one packet can hold.
+const MAX_PACKET: usize = 128;
+
+/// Run-length encode one row of pixels as TGA packets: runs of two or more
+/// identical pixels as a count (with the high bit set) and the pixel,
+/// everything else as literal spans. Packets never cross rows.
+fn write_rle_row(sink: &mut impl Write, pixels: &[[u8; 3]]) -> Result<()> {
+    let mut cur = 0;
+    while cur < pixels.len() {
+        let run = pixels[cur..]
+            .iter()
+            .take(MAX_PACKET)
+            .take_while(|&&p| p == pixels[cur])
+            .count();
+        if run >= 2 {
+            sink.write_all(&[0x80 | (run - 1) as u8])?;
+            sink.write_all(&pixels[cur])?;
+            cur += run;
+            continue;
+        }
+
+        // Gather literals up to the start of the next run.
+        let mut end = cur + 1;
+        while end < pixels.len()
+            && end - cur < MAX_PACKET
+            && (end + 1 == pixels.len() || pixels[end] != pixels[end + 1])
+        {
+            end += 1;
+        }
+        sink.write_all(&[(end - cur - 1) as u8])?;
+        for pixel in &pixels[cur..end] {
+            sink.write_all(pixel)?;
+        }
+        cur = end;
+    }
+    Ok(())
+}
+
+impl Canvas {
+    /// Write the canvas as an uncompressed 24-bit TGA image, clamping colors
+    /// to the range 0.0 to 1.0 as for PPM.
+    pub fn write_tga(&self, sink: &mut impl Write) -> Result<()> {
+        self.write_tga_with_options(sink, &TgaOptions::default())
+    }
+
+    /// Fails with [`ErrorKind::InvalidInput`] if the canvas is more than
+    /// 65535 pixels wide or high, the most TGA can describe.
+    pub fn write_tga_with_options(
+        &self,
+        sink: &mut impl Write,
+        options: &TgaOptions,
+    ) -> Result<()> {
+        let too_large = || Error::new(ErrorKind::InvalidInput, "canvas too large for TGA");
+        let width = u16::try_from(self.width).map_err(|_| too_large())?;
+        let height = u16::try_from(self.height).map_err(|_| too_large())?;
+
+        let mut header = [0; 18];
+        // Truecolor, with no image ID or color map.
+        header[2] = if options.rle { 10 } else { 2 };
+        header[12..14].copy_from_slice(&width.to_le_bytes());
+        header[14..16].copy_from_slice(&height.to_le_bytes());
+        header[16] = 24;
+        // Rows are stored from the top down.
+        header[17] = 0x20;
+        sink.write_all(&header)?;
+
+        let canvas = self.for_output(&options.output);
+        for row in 0..self.height {
+            let pixels: Vec<[u8; 3]> = (0..self.width)
+                .map(|col| {
+                    let (r, g, b) = canvas.pixel_at(col, row).to_u8();
+                    [b, g, r]
+                })
+                .collect();
+            if options.rle {
+                write_rle_row(sink, &pixels)?;
+            } else {
+                sink.write_all(pixels.as_flattened())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::color::Color;
+
+    #[test]
+    fn test_tga() {
+        let mut c = Canvas::new(2, 2);
+        c.write_pixel(0, 0, Color::new(1.0, 0.0, 0.2));
+        c.write_pixel(1, 1, Color::new(1.5, 0.5, -0.5));
+        let mut bytes = Vec::new();
+        c.write_tga(&mut bytes).unwrap();
+
+        assert_eq!(
+            &bytes[..18],
+            &[0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 2, 0, 24, 0x20]
+        );
+        assert_eq!(&bytes[18..], &[51, 0, 255, 0, 0, 0, 0, 0, 0, 0, 128, 255]);
+    }
+
+    #[test]
+    fn test_tga_rle() {
+        let mut c = Canvas::new(6, 1);
+        c.fill(Color::new(0.0, 0.0, 1.0));
+        c.write_pixel(0, 0, Color::new(1.0, 0.0, 0.0));
+        c.write_pixel(5, 0, Color::new(0.0, 1.0, 0.0));
+        let mut bytes = Vec::new();
+        let options = TgaOptions {
+            rle: true,
+            ..TgaOptions::default()
+        };
+        c.write_tga_with_options(&mut bytes, &options).unwrap();
+
+        assert_eq!(bytes[2], 10);
+        assert_eq!(&bytes[18..], &[0, 0, 0, 255, 0x83, 255, 0, 0, 0, 0, 255, 0]);
+    }
+
+    #[test]
+    fn test_tga_exposure() {
+        let mut c = Canvas::new(1, 1);
+        c.write_pixel(0, 0, Color::new(2.0, 1.0, 0.0));
+        let mut bytes = Vec::new();
+        let options = TgaOptions {
+            output: OutputOptions {
+                exposure: -1.0,
+                ..OutputOptions::default()
+            },
+            ..TgaOptions::default()
+        };
+        c.write_tga_with_options(&mut bytes, &options).unwrap();
+        assert_eq!(&bytes[18..], &[0, 128, 255]);
+    }
+
+    #[test]
+    fn test_rle_splits_long_runs_and_literals() {
+        let mut pixels = vec![[1, 1, 1]; 130];
+        pixels.extend((0..130).map(|i| [i as u8, 0, 0]));
+        let mut bytes = Vec::new();
+        write_rle_row(&mut bytes, &pixels).unwrap();
+
+        assert_eq!(&bytes[..4], &[0xff, 1, 1, 1]);
+        assert_eq!(&bytes[4..8], &[0x81, 1, 1, 1]);
+        assert_eq!(bytes[8], 127);
+        assert_eq!(bytes[9 + 128 * 3], 1);
+        assert_eq!(bytes.len(), 8 + 1 + 128 * 3 + 1 + 2 * 3);
+    }
+
+    #[test]
+    fn test_tga_rejects_huge_canvas() {
+        let c = Canvas::new(70000, 1);
+        let err = c.write_tga(&mut Vec::new()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    }
+}
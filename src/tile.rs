@@ -0,0 +1,93 @@
+/// A rectangular block of pixels within an image, rendered as one unit of
+/// work.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Tile {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Tile {
+    pub fn new(x: usize, y: usize, width: usize, height: usize) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// Split a `width` by `height` image into tiles of at most `tile_size`
+    /// pixels square, in rows from the top left. Tiles along the right and
+    /// bottom edges are cropped to fit.
+    pub fn grid(width: usize, height: usize, tile_size: usize) -> Vec<Tile> {
+        assert!(tile_size > 0, "tile size must be positive");
+
+        let mut tiles = vec![];
+        for y in (0..height).step_by(tile_size) {
+            for x in (0..width).step_by(tile_size) {
+                tiles.push(Tile::new(
+                    x,
+                    y,
+                    usize::min(tile_size, width - x),
+                    usize::min(tile_size, height - y),
+                ));
+            }
+        }
+        tiles
+    }
+
+    /// The image coordinates of every pixel in the tile, row by row.
+    pub fn pixels(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (self.y..self.y + self.height)
+            .flat_map(move |y| (self.x..self.x + self.width).map(move |x| (x, y)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_grid_exact_fit() {
+        let tiles = Tile::grid(4, 4, 2);
+        assert_eq!(
+            tiles,
+            vec![
+                Tile::new(0, 0, 2, 2),
+                Tile::new(2, 0, 2, 2),
+                Tile::new(0, 2, 2, 2),
+                Tile::new(2, 2, 2, 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_grid_crops_edges() {
+        let tiles = Tile::grid(5, 3, 2);
+        assert_eq!(
+            tiles,
+            vec![
+                Tile::new(0, 0, 2, 2),
+                Tile::new(2, 0, 2, 2),
+                Tile::new(4, 0, 1, 2),
+                Tile::new(0, 2, 2, 1),
+                Tile::new(2, 2, 2, 1),
+                Tile::new(4, 2, 1, 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_grid_covers_every_pixel_once() {
+        let mut seen = vec![0; 37 * 23];
+        for tile in Tile::grid(37, 23, 8) {
+            for (x, y) in tile.pixels() {
+                seen[y * 37 + x] += 1;
+            }
+        }
+        assert!(seen.iter().all(|&n| n == 1));
+    }
+}
@@ -0,0 +1,117 @@
+use std::borrow::Cow;
+
+use crate::canvas::Canvas;
+use crate::color::Color;
+use crate::Float;
+
+/// How colors brighter than white are brought into the displayable range
+/// when writing an image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ToneMap {
+    /// Leave colors as they are, so anything over 1.0 is clipped to white.
+    #[default]
+    None,
+    /// Compress each component with `c / (1 + c)`, which never quite reaches
+    /// white and darkens mid-tones noticeably.
+    Reinhard,
+    /// An approximation of the ACES filmic curve, which keeps mid-tones
+    /// bright and rolls highlights off smoothly.
+    Aces,
+}
+
+impl ToneMap {
+    pub fn apply(self, color: Color) -> Color {
+        match self {
+            // Keep out-of-range values for writers that clamp them anyway.
+            Self::None => color,
+            Self::Reinhard => per_component(color, |c| c / (1.0 + c)),
+            // Krzysztof Narkowicz's fit to the ACES reference transform.
+            Self::Aces => per_component(color, |c| {
+                ((c * (2.51 * c + 0.03)) / (c * (2.43 * c + 0.59) + 0.14)).min(1.0)
+            }),
+        }
+    }
+}
+
+/// Apply `f` to each component of `color`, treating negatives as 0.0.
+fn per_component(color: Color, f: impl Fn(Float) -> Float) -> Color {
+    Color::new(
+        f(color.red().max(0.0)),
+        f(color.green().max(0.0)),
+        f(color.blue().max(0.0)),
+    )
+}
+
+/// Exposure and tone mapping, applied to a canvas before any of the 8-bit
+/// image writers, such as [`Canvas::write_png_with_options`], convert its
+/// colors.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OutputOptions {
+    /// How many stops to brighten the image by before tone mapping. Each
+    /// stop doubles the colors; negative values darken.
+    pub exposure: Float,
+    /// How to bring colors brighter than white into range.
+    pub tone_map: ToneMap,
+}
+
+impl Canvas {
+    /// The canvas as `options` would have it written: tone mapped, or as it
+    /// is if they leave it alone.
+    pub(crate) fn for_output(&self, options: &OutputOptions) -> Cow<'_, Canvas> {
+        if options.exposure == 0.0 && options.tone_map == ToneMap::None {
+            Cow::Borrowed(self)
+        } else {
+            Cow::Owned(self.tone_mapped(options.exposure, options.tone_map))
+        }
+    }
+
+    /// A copy of the canvas brightened by `exposure` stops, doubling its
+    /// colors for each, then tone mapped with `operator` to fit the range 0.0
+    /// to 1.0. Negative exposures darken.
+    pub fn tone_mapped(&self, exposure: Float, operator: ToneMap) -> Canvas {
+        let scale = exposure.exp2();
+        let pixels = self
+            .pixels()
+            .map(|&pixel| operator.apply(pixel * scale))
+            .collect();
+        Canvas::from_pixels(self.width, self.height, pixels)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_no_tone_mapping() {
+        let c = Color::new(2.0, 0.5, -0.5);
+        assert_eq!(ToneMap::None.apply(c), c);
+    }
+
+    #[test]
+    fn test_reinhard() {
+        let c = ToneMap::Reinhard.apply(Color::new(1.0, 3.0, 0.0));
+        assert_eq!(c, Color::new(0.5, 0.75, 0.0));
+    }
+
+    #[test]
+    fn test_aces() {
+        let c = ToneMap::Aces.apply(Color::new(0.0, 0.18, 100.0));
+        assert_eq!(c.red(), 0.0);
+        assert!(c.green() > 0.18 && c.green() < 0.3);
+        assert_eq!(c.blue(), 1.0);
+    }
+
+    #[test]
+    fn test_exposure() {
+        let c = Canvas::from_pixels(2, 1, vec![Color::new(0.25, 0.5, 1.0); 2]);
+        let brighter = c.tone_mapped(1.0, ToneMap::None);
+        assert_eq!(brighter.pixel_at(1, 0), Color::new(0.5, 1.0, 2.0));
+        let darker = c.tone_mapped(-2.0, ToneMap::None);
+        assert_eq!(darker.pixel_at(0, 0), Color::new(0.0625, 0.125, 0.25));
+        let mapped = c.tone_mapped(1.0, ToneMap::Reinhard);
+        assert_eq!(mapped.pixel_at(0, 0), Color::new(1.0 / 3.0, 0.5, 2.0 / 3.0));
+    }
+}
@@ -0,0 +1,122 @@
+use crate::matrix4::Matrix4;
+use crate::Float;
+
+/// Composes a transformation from a sequence of steps, applied in the order
+/// they're called. Multiplying matrices directly needs them written in
+/// reverse, so
+///
+/// ```
+/// # use ray_tracer_challenge_2::consts::PI;
+/// # use ray_tracer_challenge_2::matrix4::Matrix4;
+/// # use ray_tracer_challenge_2::transform::Transform;
+/// let m = Transform::new()
+///     .rotate_x(PI / 2.0)
+///     .scale(5.0, 5.0, 5.0)
+///     .translate(10.0, 5.0, 7.0)
+///     .matrix();
+/// assert_eq!(
+///     m,
+///     Matrix4::translation(10.0, 5.0, 7.0)
+///         * Matrix4::scaling(5.0, 5.0, 5.0)
+///         * Matrix4::rotation_x(PI / 2.0)
+/// );
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub struct Transform {
+    matrix: Matrix4,
+}
+
+impl Transform {
+    /// The identity transformation, which leaves everything where it is.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Follow the transformation so far with `matrix`.
+    pub fn then(self, matrix: Matrix4) -> Self {
+        Self {
+            matrix: matrix * self.matrix,
+        }
+    }
+
+    pub fn translate(self, x: Float, y: Float, z: Float) -> Self {
+        self.then(Matrix4::translation(x, y, z))
+    }
+
+    pub fn scale(self, x: Float, y: Float, z: Float) -> Self {
+        self.then(Matrix4::scaling(x, y, z))
+    }
+
+    pub fn rotate_x(self, radians: Float) -> Self {
+        self.then(Matrix4::rotation_x(radians))
+    }
+
+    pub fn rotate_y(self, radians: Float) -> Self {
+        self.then(Matrix4::rotation_y(radians))
+    }
+
+    pub fn rotate_z(self, radians: Float) -> Self {
+        self.then(Matrix4::rotation_z(radians))
+    }
+
+    pub fn shear(self, xy: Float, xz: Float, yx: Float, yz: Float, zx: Float, zy: Float) -> Self {
+        self.then(Matrix4::shearing(xy, xz, yx, yz, zx, zy))
+    }
+
+    /// The combined transformation.
+    pub fn matrix(&self) -> Matrix4 {
+        self.matrix
+    }
+}
+
+impl From<Transform> for Matrix4 {
+    fn from(value: Transform) -> Self {
+        value.matrix
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::consts::PI;
+
+    use super::*;
+    use crate::space::Point;
+
+    #[test]
+    fn test_empty_transform_is_identity() {
+        assert_eq!(Transform::new().matrix(), Matrix4::identity());
+    }
+
+    #[test]
+    fn test_transform_applies_in_call_order() {
+        let p = Point::new(1.0, 0.0, 1.0);
+        let m = Transform::new()
+            .rotate_x(PI / 2.0)
+            .scale(5.0, 5.0, 5.0)
+            .translate(10.0, 5.0, 7.0)
+            .matrix();
+        assert_eq!(m * p, Point::new(15.0, 0.0, 7.0));
+
+        let m: Matrix4 = Transform::new()
+            .translate(1.0, 0.0, 0.0)
+            .rotate_z(PI / 2.0)
+            .into();
+        assert_eq!(m * Point::origin(), Point::new(0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn test_transform_then_matches_point_methods() {
+        let p = Point::new(2.0, 3.0, 4.0);
+        let m = Transform::new()
+            .shear(0.0, 0.0, 0.0, 0.0, 0.0, 1.0)
+            .rotate_y(PI / 4.0)
+            .then(Matrix4::scaling(2.0, 1.0, 1.0))
+            .matrix();
+        assert_eq!(
+            m * p,
+            p.shear(0.0, 0.0, 0.0, 0.0, 0.0, 1.0)
+                .rotate_y(PI / 4.0)
+                .scale(2.0, 1.0, 1.0)
+        );
+    }
+}
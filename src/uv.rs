@@ -0,0 +1,406 @@
+use crate::consts::PI;
+
+use crate::canvas::Canvas;
+use crate::color::Color;
+use crate::space::Point;
+use crate::Float;
+
+/// A way of flattening a 3D point on a surface into 2D (u, v) texture
+/// coordinates, each in the range 0.0 to 1.0.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum UvMapping {
+    Spherical,
+    Planar,
+    Cylindrical,
+}
+
+impl UvMapping {
+    pub fn map(&self, point: &Point) -> (Float, Float) {
+        match self {
+            Self::Spherical => spherical_map(point),
+            Self::Planar => planar_map(point),
+            Self::Cylindrical => cylindrical_map(point),
+        }
+    }
+}
+
+/// Map a point on a sphere centred on the origin to (u, v), with u running
+/// around the equator and v running from the south pole to the north.
+pub fn spherical_map(point: &Point) -> (Float, Float) {
+    let theta = point.x().atan2(point.z());
+    let radius = point.subtract_origin().magnitude();
+    let phi = (point.y() / radius).acos();
+
+    let raw_u = theta / (2.0 * PI);
+    // Flip u so it increases counter-clockwise when viewed from above.
+    let u = 1.0 - (raw_u + 0.5);
+    let v = 1.0 - phi / PI;
+    (u, v)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CubeFace {
+    Left,
+    Front,
+    Right,
+    Back,
+    Up,
+    Down,
+}
+
+impl CubeFace {
+    /// The face of an axis-aligned cube centred on the origin that lies in
+    /// the direction of `point`.
+    pub fn from_point(point: &Point) -> Self {
+        let (x, y, z) = (point.x(), point.y(), point.z());
+        let coord = x.abs().max(y.abs()).max(z.abs());
+
+        if coord == x {
+            Self::Right
+        } else if coord == -x {
+            Self::Left
+        } else if coord == y {
+            Self::Up
+        } else if coord == -y {
+            Self::Down
+        } else if coord == z {
+            Self::Front
+        } else {
+            Self::Back
+        }
+    }
+
+    /// Map a point to (u, v) on this face.
+    ///
+    /// The point is first projected out onto the surface of the unit cube, so
+    /// this works for any point in the face's direction: handy for skyboxes,
+    /// which usually aren't cubes.
+    pub fn map(&self, point: &Point) -> (Float, Float) {
+        let coord = point.x().abs().max(point.y().abs()).max(point.z().abs());
+        let (x, y, z) = (point.x() / coord, point.y() / coord, point.z() / coord);
+        let wrap = |a: Float| a.rem_euclid(2.0) / 2.0;
+
+        match self {
+            Self::Front => (wrap(x + 1.0), wrap(y + 1.0)),
+            Self::Back => (wrap(1.0 - x), wrap(y + 1.0)),
+            Self::Left => (wrap(z + 1.0), wrap(y + 1.0)),
+            Self::Right => (wrap(1.0 - z), wrap(y + 1.0)),
+            Self::Up => (wrap(x + 1.0), wrap(1.0 - z)),
+            Self::Down => (wrap(x + 1.0), wrap(z + 1.0)),
+        }
+    }
+}
+
+/// Map a point on the xz plane to (u, v), repeating every unit along x and z.
+pub fn planar_map(point: &Point) -> (Float, Float) {
+    (point.x().rem_euclid(1.0), point.z().rem_euclid(1.0))
+}
+
+/// Map a point on a unit cylinder around the y axis to (u, v), with u running
+/// around the cylinder and v repeating every unit along y.
+pub fn cylindrical_map(point: &Point) -> (Float, Float) {
+    let theta = point.x().atan2(point.z());
+    let raw_u = theta / (2.0 * PI);
+    let u = 1.0 - (raw_u + 0.5);
+    let v = point.y().rem_euclid(1.0);
+    (u, v)
+}
+
+/// A two-dimensional pattern, looked up by (u, v) texture coordinates.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum UvPattern {
+    Checkers(UvCheckers),
+    AlignCheck(UvAlignCheck),
+    Image(UvImage),
+}
+
+impl UvPattern {
+    pub fn uv_pattern_at(&self, u: Float, v: Float) -> Color {
+        match self {
+            Self::Checkers(checkers) => checkers.uv_pattern_at(u, v),
+            Self::AlignCheck(align_check) => align_check.uv_pattern_at(u, v),
+            Self::Image(image) => image.uv_pattern_at(u, v),
+        }
+    }
+}
+
+impl From<UvAlignCheck> for UvPattern {
+    fn from(value: UvAlignCheck) -> Self {
+        Self::AlignCheck(value)
+    }
+}
+
+impl From<UvCheckers> for UvPattern {
+    fn from(value: UvCheckers) -> Self {
+        Self::Checkers(value)
+    }
+}
+
+impl From<UvImage> for UvPattern {
+    fn from(value: UvImage) -> Self {
+        Self::Image(value)
+    }
+}
+
+/// A `width` by `height` checkerboard of two colors.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UvCheckers {
+    width: Float,
+    height: Float,
+    a: Color,
+    b: Color,
+}
+
+impl UvCheckers {
+    pub fn new(width: Float, height: Float, a: Color, b: Color) -> Self {
+        Self {
+            width,
+            height,
+            a,
+            b,
+        }
+    }
+
+    pub fn uv_pattern_at(&self, u: Float, v: Float) -> Color {
+        let u2 = (u * self.width).floor();
+        let v2 = (v * self.height).floor();
+        if (u2 + v2).rem_euclid(2.0) == 0.0 {
+            self.a
+        } else {
+            self.b
+        }
+    }
+}
+
+/// A solid color with a differently colored square in each corner, which
+/// makes it easy to see how a mapping is oriented.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UvAlignCheck {
+    main: Color,
+    ul: Color,
+    ur: Color,
+    bl: Color,
+    br: Color,
+}
+
+impl UvAlignCheck {
+    pub fn new(main: Color, ul: Color, ur: Color, bl: Color, br: Color) -> Self {
+        Self {
+            main,
+            ul,
+            ur,
+            bl,
+            br,
+        }
+    }
+
+    pub fn uv_pattern_at(&self, u: Float, v: Float) -> Color {
+        if v > 0.8 {
+            if u < 0.2 {
+                return self.ul;
+            }
+            if u > 0.8 {
+                return self.ur;
+            }
+        } else if v < 0.2 {
+            if u < 0.2 {
+                return self.bl;
+            }
+            if u > 0.8 {
+                return self.br;
+            }
+        }
+        self.main
+    }
+}
+
+/// An image stretched over the whole (u, v) range.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UvImage {
+    canvas: Canvas,
+}
+
+impl UvImage {
+    pub fn new(canvas: Canvas) -> Self {
+        Self { canvas }
+    }
+
+    pub fn uv_pattern_at(&self, u: Float, v: Float) -> Color {
+        // Images are stored top row first, but v increases upwards.
+        let v = 1.0 - v;
+        let x = (u * (self.canvas.width - 1) as Float).round() as usize;
+        let y = (v * (self.canvas.height - 1) as Float).round() as usize;
+        self.canvas.pixel_at(x, y)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::consts::FRAC_1_SQRT_2;
+
+    use super::*;
+    use crate::assert_approx_eq;
+    use crate::testlib::approx_equals_fail;
+
+    #[test]
+    fn test_uv_checkers() {
+        let black = Color::new(0.0, 0.0, 0.0);
+        let white = Color::new(1.0, 1.0, 1.0);
+        let checkers = UvCheckers::new(2.0, 2.0, black, white);
+
+        assert_eq!(checkers.uv_pattern_at(0.0, 0.0), black);
+        assert_eq!(checkers.uv_pattern_at(0.5, 0.0), white);
+        assert_eq!(checkers.uv_pattern_at(0.0, 0.5), white);
+        assert_eq!(checkers.uv_pattern_at(0.5, 0.5), black);
+        assert_eq!(checkers.uv_pattern_at(1.0, 1.0), black);
+    }
+
+    #[test]
+    fn test_spherical_map() {
+        let cases = [
+            (Point::new(0.0, 0.0, -1.0), 0.0, 0.5),
+            (Point::new(1.0, 0.0, 0.0), 0.25, 0.5),
+            (Point::new(0.0, 0.0, 1.0), 0.5, 0.5),
+            (Point::new(-1.0, 0.0, 0.0), 0.75, 0.5),
+            (Point::new(0.0, 1.0, 0.0), 0.5, 1.0),
+            (Point::new(0.0, -1.0, 0.0), 0.5, 0.0),
+            (Point::new(FRAC_1_SQRT_2, FRAC_1_SQRT_2, 0.0), 0.25, 0.75),
+        ];
+        for (point, u, v) in cases {
+            let (mu, mv) = spherical_map(&point);
+            assert_approx_eq!(mu, u);
+            assert_approx_eq!(mv, v);
+        }
+    }
+
+    #[test]
+    fn test_planar_map() {
+        let cases = [
+            (Point::new(0.25, 0.0, 0.5), 0.25, 0.5),
+            (Point::new(0.25, 0.0, -0.25), 0.25, 0.75),
+            (Point::new(0.25, 0.5, -0.25), 0.25, 0.75),
+            (Point::new(1.25, 0.0, 0.5), 0.25, 0.5),
+            (Point::new(0.25, 0.0, -1.75), 0.25, 0.25),
+            (Point::new(1.0, 0.0, -1.0), 0.0, 0.0),
+            (Point::new(0.0, 0.0, 0.0), 0.0, 0.0),
+        ];
+        for (point, u, v) in cases {
+            let (mu, mv) = planar_map(&point);
+            assert_approx_eq!(mu, u);
+            assert_approx_eq!(mv, v);
+        }
+    }
+
+    #[test]
+    fn test_cylindrical_map() {
+        let cases = [
+            (Point::new(0.0, 0.0, -1.0), 0.0, 0.0),
+            (Point::new(0.0, 0.5, -1.0), 0.0, 0.5),
+            (Point::new(0.0, 1.0, -1.0), 0.0, 0.0),
+            (Point::new(FRAC_1_SQRT_2, 0.5, -FRAC_1_SQRT_2), 0.125, 0.5),
+            (Point::new(1.0, 0.5, 0.0), 0.25, 0.5),
+            (Point::new(FRAC_1_SQRT_2, 0.5, FRAC_1_SQRT_2), 0.375, 0.5),
+            (Point::new(0.0, -0.25, 1.0), 0.5, 0.75),
+            (Point::new(-FRAC_1_SQRT_2, 0.5, FRAC_1_SQRT_2), 0.625, 0.5),
+            (Point::new(-1.0, 1.25, 0.0), 0.75, 0.25),
+            (Point::new(-FRAC_1_SQRT_2, 0.5, -FRAC_1_SQRT_2), 0.875, 0.5),
+        ];
+        for (point, u, v) in cases {
+            let (mu, mv) = cylindrical_map(&point);
+            assert_approx_eq!(mu, u);
+            assert_approx_eq!(mv, v);
+        }
+    }
+
+    #[test]
+    fn test_uv_image() {
+        let mut canvas = Canvas::new(10, 10);
+        for y in 0..10 {
+            for x in 0..10 {
+                let v = (x + y) as Float / 20.0;
+                canvas.write_pixel(x, y, Color::new(v, v, v));
+            }
+        }
+        let image = UvImage::new(canvas);
+
+        let cases = [
+            (0.0, 0.0, 0.45),
+            (0.3, 0.0, 0.6),
+            (0.6, 0.3, 0.55),
+            (1.0, 1.0, 0.45),
+        ];
+        for (u, v, expected) in cases {
+            assert_eq!(
+                image.uv_pattern_at(u, v),
+                Color::new(expected, expected, expected)
+            );
+        }
+    }
+
+    #[test]
+    fn test_uv_align_check() {
+        let main = Color::new(1.0, 1.0, 1.0);
+        let ul = Color::new(1.0, 0.0, 0.0);
+        let ur = Color::new(1.0, 1.0, 0.0);
+        let bl = Color::new(0.0, 1.0, 0.0);
+        let br = Color::new(0.0, 1.0, 1.0);
+        let pattern = UvAlignCheck::new(main, ul, ur, bl, br);
+
+        assert_eq!(pattern.uv_pattern_at(0.5, 0.5), main);
+        assert_eq!(pattern.uv_pattern_at(0.1, 0.9), ul);
+        assert_eq!(pattern.uv_pattern_at(0.9, 0.9), ur);
+        assert_eq!(pattern.uv_pattern_at(0.1, 0.1), bl);
+        assert_eq!(pattern.uv_pattern_at(0.9, 0.1), br);
+    }
+
+    #[test]
+    fn test_cube_face_from_point() {
+        let cases = [
+            (Point::new(-1.0, 0.5, -0.25), CubeFace::Left),
+            (Point::new(1.1, -0.75, 0.8), CubeFace::Right),
+            (Point::new(0.1, 0.6, 0.9), CubeFace::Front),
+            (Point::new(-0.7, 0.0, -2.0), CubeFace::Back),
+            (Point::new(0.5, 1.0, 0.9), CubeFace::Up),
+            (Point::new(-0.2, -1.3, 1.1), CubeFace::Down),
+        ];
+        for (point, face) in cases {
+            assert_eq!(CubeFace::from_point(&point), face);
+        }
+    }
+
+    #[test]
+    fn test_cube_face_map() {
+        let cases = [
+            (CubeFace::Front, Point::new(-0.5, 0.5, 1.0), 0.25, 0.75),
+            (CubeFace::Front, Point::new(0.5, -0.5, 1.0), 0.75, 0.25),
+            (CubeFace::Back, Point::new(0.5, 0.5, -1.0), 0.25, 0.75),
+            (CubeFace::Back, Point::new(-0.5, -0.5, -1.0), 0.75, 0.25),
+            (CubeFace::Left, Point::new(-1.0, 0.5, -0.5), 0.25, 0.75),
+            (CubeFace::Left, Point::new(-1.0, -0.5, 0.5), 0.75, 0.25),
+            (CubeFace::Right, Point::new(1.0, 0.5, 0.5), 0.25, 0.75),
+            (CubeFace::Right, Point::new(1.0, -0.5, -0.5), 0.75, 0.25),
+            (CubeFace::Up, Point::new(-0.5, 1.0, -0.5), 0.25, 0.75),
+            (CubeFace::Up, Point::new(0.5, 1.0, 0.5), 0.75, 0.25),
+            (CubeFace::Down, Point::new(-0.5, -1.0, 0.5), 0.25, 0.75),
+            (CubeFace::Down, Point::new(0.5, -1.0, -0.5), 0.75, 0.25),
+        ];
+        for (face, point, u, v) in cases {
+            let (mu, mv) = face.map(&point);
+            assert_approx_eq!(mu, u);
+            assert_approx_eq!(mv, v);
+        }
+    }
+
+    #[test]
+    fn test_cube_face_map_projects_onto_cube() {
+        let p = Point::new(-0.5, 0.5, 1.0);
+        let far = Point::new(-50.0, 50.0, 100.0);
+        assert_eq!(CubeFace::Front.map(&p), CubeFace::Front.map(&far));
+    }
+}
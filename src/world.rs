@@ -1,19 +1,435 @@
-use crate::{lighting::PointLight, shape::Shape};
+use crate::Float;
+use crate::{
+    color::Color,
+    lighting::{Light, PointLight},
+    octree::{self, Octree},
+    ray::{Computations, Intersections, Ray},
+    shape::Shape,
+    space::{Point, Vector},
+};
+use once_cell::sync::OnceCell;
+use std::fmt;
 
+/// How many times reflected and refracted rays may bounce before giving up,
+/// unless set otherwise with [`World::set_max_depth`].
+pub const DEFAULT_MAX_DEPTH: usize = 5;
+
+/// What rays that don't hit anything see.
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Background {
+    Solid(Color),
+    /// Blends from `bottom`, looking straight down, to `top`, looking
+    /// straight up, like a simple sky.
+    Gradient {
+        bottom: Color,
+        top: Color,
+    },
+}
+
+impl Background {
+    /// The color seen looking along `direction`.
+    pub fn color_for(&self, direction: &Vector) -> Color {
+        match *self {
+            Self::Solid(color) => color,
+            Self::Gradient { bottom, top } => {
+                bottom.lerp(top, 0.5 * (direction.normalize().y() + 1.0))
+            }
+        }
+    }
+}
+
+impl From<Color> for Background {
+    fn from(value: Color) -> Self {
+        Self::Solid(value)
+    }
+}
+
+/// How a [`World`] finds the objects a ray might hit.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Acceleration {
+    /// Test every object in turn. Groups still skip their children when
+    /// rays miss their bounds, which [`Group::divide`] turns into a
+    /// bounding volume hierarchy.
+    ///
+    /// [`Group::divide`]: crate::shape::Group::divide
+    #[default]
+    None,
+    /// Only test the objects an [`Octree`] finds near the ray.
+    Octree { max_depth: usize, leaf_size: usize },
+}
+
+impl Acceleration {
+    /// An octree with the default depth and leaf size.
+    pub fn octree() -> Self {
+        Self::Octree {
+            max_depth: octree::DEFAULT_MAX_DEPTH,
+            leaf_size: octree::DEFAULT_LEAF_SIZE,
+        }
+    }
+}
+
+/// The index [`Acceleration`] asks for, built on first use and dropped
+/// whenever the objects change. It's derived from the objects, so worlds
+/// compare equal without it and it isn't serialized. Nor is it printed, so
+/// a world's debug output doesn't change once it's built.
+#[derive(Clone, Default)]
+struct Index(OnceCell<Octree>);
+
+impl PartialEq for Index {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl fmt::Debug for Index {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Index")
+    }
+}
 
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct World {
-    light: Option<PointLight>,
-    objects: Vec<Shape>
+    lights: Vec<Light>,
+    objects: Vec<Shape>,
+    background: Background,
+    max_depth: usize,
+    #[cfg_attr(feature = "serde", serde(default))]
+    acceleration: Acceleration,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    index: Index,
 }
 
 impl World {
     pub fn new() -> Self {
         Self {
-            light: None,
+            lights: vec![],
             objects: vec![],
+            background: Background::Solid(Color::new(0.0, 0.0, 0.0)),
+            max_depth: DEFAULT_MAX_DEPTH,
+            acceleration: Acceleration::None,
+            index: Index::default(),
+        }
+    }
+
+    /// A world lit by `light` containing `objects`, with a black background.
+    pub fn with(
+        light: impl Into<Light>,
+        objects: impl IntoIterator<Item = impl Into<Shape>>,
+    ) -> Self {
+        let mut world = Self::new();
+        world.set_light(light);
+        for object in objects {
+            world.add_object(object);
+        }
+        world
+    }
+
+    /// Make `light` the world's only light.
+    pub fn set_light(&mut self, light: impl Into<Light>) {
+        self.lights = vec![light.into()];
+    }
+
+    /// Add another light, returning its id for use in a shape's
+    /// [light mask]. Ids count up from 0 in the order lights are added.
+    ///
+    /// [light mask]: Shape::set_light_mask
+    pub fn add_light(&mut self, light: impl Into<Light>) -> usize {
+        self.lights.push(light.into());
+        self.lights.len() - 1
+    }
+
+    pub fn lights(&self) -> &[Light] {
+        &self.lights
+    }
+
+    /// The color seen by rays that don't hit anything.
+    pub fn background(&self) -> Background {
+        self.background
+    }
+
+    /// Set the background to a [`Background`] or a solid [`Color`].
+    pub fn set_background(&mut self, background: impl Into<Background>) {
+        self.background = background.into();
+    }
+
+    /// How many times rays may be reflected or refracted.
+    pub fn max_depth(&self) -> usize {
+        self.max_depth
+    }
+
+    /// Set how many times rays may be reflected or refracted before giving
+    /// up. Facing mirrors need more bounces to look right; fewer make
+    /// renders of shiny scenes faster. At 0 surfaces are lit but reflect and
+    /// refract nothing.
+    pub fn set_max_depth(&mut self, max_depth: usize) {
+        self.max_depth = max_depth;
+    }
+
+    pub fn acceleration(&self) -> Acceleration {
+        self.acceleration
+    }
+
+    /// Choose how rays find the objects they might hit. An octree helps
+    /// most with many separate objects; a few large groups are better off
+    /// [divided](crate::shape::Group::divide).
+    pub fn set_acceleration(&mut self, acceleration: Acceleration) {
+        self.acceleration = acceleration;
+        self.index = Index::default();
+    }
+
+    pub fn add_object(&mut self, object: impl Into<Shape>) {
+        self.objects.push(object.into());
+        self.index = Index::default();
+    }
+
+    /// The first object named `name`, including those nested in groups.
+    pub fn find_object(&self, name: &str) -> Option<&Shape> {
+        self.objects.iter().find_map(|object| object.find(name))
+    }
+
+    /// Call `update` with the first object named `name`, including those
+    /// nested in groups, returning whether there was one. The groups it's
+    /// in and the world's index are then brought up to date, in case it
+    /// moved.
+    pub fn update_object(&mut self, name: &str, update: impl FnOnce(&mut Shape)) -> bool {
+        let mut update = update;
+        for object in &mut self.objects {
+            match object.try_update(name, update) {
+                Ok(()) => {
+                    self.index = Index::default();
+                    return true;
+                }
+                Err(unused) => update = unused,
+            }
+        }
+        false
+    }
+
+    pub fn intersect(&self, ray: &Ray) -> Intersections<'_> {
+        let mut xs = Intersections::new();
+        self.intersect_into(ray, &mut xs);
+        xs
+    }
+
+    /// Like [`World::intersect`], but replacing the contents of `xs` rather
+    /// than allocating a new collection, so one buffer can be reused across
+    /// many rays.
+    pub fn intersect_into<'a>(&'a self, ray: &Ray, xs: &mut Intersections<'a>) {
+        xs.clear();
+        match self.acceleration {
+            Acceleration::None => {
+                for object in &self.objects {
+                    object.intersect(ray, xs);
+                }
+            }
+            Acceleration::Octree {
+                max_depth,
+                leaf_size,
+            } => {
+                let octree = self.index.0.get_or_init(|| {
+                    #[cfg(feature = "tracing")]
+                    let _span =
+                        tracing::info_span!("build_index", objects = self.objects.len()).entered();
+                    let bounds = self.objects.iter().map(Shape::parent_space_bounds);
+                    Octree::new(bounds, max_depth, leaf_size)
+                });
+                octree.visit(ray, |index| self.objects[index].intersect(ray, xs));
+            }
+        }
+    }
+
+    /// The index, in the order they were added, of the object `ray` hits
+    /// first. Shapes nested in a group count as part of the group.
+    pub fn object_at(&self, ray: &Ray) -> Option<usize> {
+        let mut xs = Intersections::new();
+        let mut nearest = None;
+        for (index, object) in self.objects.iter().enumerate() {
+            xs.clear();
+            object.intersect(ray, &mut xs);
+            if let Some(hit) = xs.hit() {
+                if nearest.is_none_or(|(_, t)| hit.t < t) {
+                    nearest = Some((index, hit.t));
+                }
+            }
+        }
+        nearest.map(|(index, _)| index)
+    }
+
+    pub fn color_at(&self, ray: &Ray) -> Color {
+        self.color_at_with(ray, &mut Intersections::new())
+    }
+
+    /// Like [`World::color_at`], but using `xs` for the intersections of
+    /// every ray cast along the way, including reflected, refracted and
+    /// shadow rays, so one buffer can be reused across many pixels.
+    pub fn color_at_with<'a>(&'a self, ray: &Ray, xs: &mut Intersections<'a>) -> Color {
+        self.color_at_remaining(ray, self.max_depth, xs)
+    }
+
+    fn color_at_remaining<'a>(
+        &'a self,
+        ray: &Ray,
+        remaining: usize,
+        xs: &mut Intersections<'a>,
+    ) -> Color {
+        self.intersect_into(ray, xs);
+        match xs.hit() {
+            Some(hit) => {
+                let comps = hit.prepare_computations(ray, xs);
+                self.shade_hit_with(&comps, remaining, xs)
+            }
+            None => self.background.color_for(&ray.direction),
+        }
+    }
+
+    pub fn shade_hit(&self, comps: &Computations, remaining: usize) -> Color {
+        self.shade_hit_with(comps, remaining, &mut Intersections::new())
+    }
+
+    fn shade_hit_with<'a>(
+        &'a self,
+        comps: &Computations,
+        remaining: usize,
+        xs: &mut Intersections<'a>,
+    ) -> Color {
+        let material = comps.shape.material();
+        let mut surface = Color::new(0.0, 0.0, 0.0);
+        if material.unlit {
+            // Shown as it is, rather than once per light.
+            surface = material.color_at(comps.shape, &comps.point);
+        } else {
+            for (id, light) in self.lights.iter().enumerate() {
+                if !comps.shape.is_lit_by(id) {
+                    continue;
+                }
+                match light {
+                    Light::Point(light) => surface += self.lighting(comps, light, xs),
+                    // Shade as if lit by a point light at each sample in turn.
+                    Light::Area(light) => {
+                        for (position, share) in light.samples_for(&comps.over_point) {
+                            let sample = PointLight::new(position, light.intensity());
+                            surface += self.lighting(comps, &sample, xs) * share;
+                        }
+                    }
+                }
+            }
+        }
+        let reflected = self.reflected_color_with(comps, remaining, xs);
+        let refracted = self.refracted_color_with(comps, remaining, xs);
+
+        if material.reflective > 0.0 && material.transparency > 0.0 {
+            let reflectance = comps.schlick();
+            surface + reflected * reflectance + refracted * (1.0 - reflectance)
+        } else {
+            surface + reflected + refracted
         }
     }
+
+    /// The surface color at `comps` lit by `light` alone.
+    fn lighting<'a>(
+        &'a self,
+        comps: &Computations,
+        light: &PointLight,
+        xs: &mut Intersections<'a>,
+    ) -> Color {
+        let in_shadow = comps.shape.receives_shadow()
+            && self.is_shadowed_from(&comps.over_point, light.position(), comps.time, xs);
+        comps.shape.material().lighting(
+            comps.shape,
+            light,
+            &comps.over_point,
+            &comps.eyev,
+            &comps.normalv,
+            in_shadow,
+        )
+    }
+
+    /// Whether anything lies between `point` and the first light, or the
+    /// middle of it for an area light. A world without a light leaves
+    /// everything in shadow.
+    pub fn is_shadowed(&self, point: &Point) -> bool {
+        self.is_shadowed_at(point, 0.0)
+    }
+
+    /// Like [`World::is_shadowed`], with the shadow ray cast at `time` so
+    /// moving shapes cast their shadows from the right place.
+    fn is_shadowed_at(&self, point: &Point, time: Float) -> bool {
+        match self.lights.first() {
+            Some(light) => {
+                self.is_shadowed_from(point, light.position(), time, &mut Intersections::new())
+            }
+            None => true,
+        }
+    }
+
+    /// Whether anything that casts shadows lies between `point` and
+    /// `light_position`, using `xs` for the shadow ray's intersections.
+    fn is_shadowed_from<'a>(
+        &'a self,
+        point: &Point,
+        light_position: Point,
+        time: Float,
+        xs: &mut Intersections<'a>,
+    ) -> bool {
+        let v = light_position - *point;
+        let distance = v.magnitude();
+        let r = Ray::with_time(*point, v.normalize(), time);
+        self.intersect_into(&r, xs);
+        xs.hit_where(|i| i.shape.casts_shadow())
+            .is_some_and(|hit| hit.t < distance)
+    }
+
+    pub fn reflected_color(&self, comps: &Computations, remaining: usize) -> Color {
+        self.reflected_color_with(comps, remaining, &mut Intersections::new())
+    }
+
+    fn reflected_color_with<'a>(
+        &'a self,
+        comps: &Computations,
+        remaining: usize,
+        xs: &mut Intersections<'a>,
+    ) -> Color {
+        let reflective = comps.shape.material().reflective;
+        if remaining == 0 || reflective == 0.0 {
+            return Color::new(0.0, 0.0, 0.0);
+        }
+
+        let reflect_ray = Ray::with_time(comps.over_point, comps.reflectv, comps.time);
+        self.color_at_remaining(&reflect_ray, remaining - 1, xs) * reflective
+    }
+
+    pub fn refracted_color(&self, comps: &Computations, remaining: usize) -> Color {
+        self.refracted_color_with(comps, remaining, &mut Intersections::new())
+    }
+
+    fn refracted_color_with<'a>(
+        &'a self,
+        comps: &Computations,
+        remaining: usize,
+        xs: &mut Intersections<'a>,
+    ) -> Color {
+        let transparency = comps.shape.material().transparency;
+        if remaining == 0 || transparency == 0.0 {
+            return Color::new(0.0, 0.0, 0.0);
+        }
+
+        // Snell's law
+        let n_ratio = comps.n1 / comps.n2;
+        let cos_i = comps.eyev.dot(&comps.normalv);
+        let sin2_t = n_ratio * n_ratio * (1.0 - cos_i * cos_i);
+        if sin2_t > 1.0 {
+            // Total internal reflection
+            return Color::new(0.0, 0.0, 0.0);
+        }
+
+        let cos_t = (1.0 - sin2_t).sqrt();
+        let direction = comps.normalv * (n_ratio * cos_i - cos_t) - comps.eyev * n_ratio;
+        let refract_ray = Ray::with_time(comps.under_point, direction, comps.time);
+        self.color_at_remaining(&refract_ray, remaining - 1, xs) * transparency
+    }
 }
 
 impl Default for World {
@@ -24,7 +440,17 @@ impl Default for World {
 
 #[cfg(test)]
 mod test {
-    use crate::{color::Color, matrix::Matrix, shape::Sphere, space::Point};
+    use crate::{
+        assert_approx_eq,
+        fixtures::default_world,
+        lighting::AreaLight,
+        materials::Material,
+        matrix4::Matrix4,
+        ray::Intersection,
+        shape::{Group, Quad, Sphere},
+        space::{Point, Vector},
+        testlib::approx_equals_fail,
+    };
 
     use super::*;
 
@@ -32,47 +458,513 @@ mod test {
     fn test_world_init() {
         let w = World::new();
 
-        assert_eq!(w.light, None);
+        assert!(w.lights().is_empty());
         assert_eq!(w.objects.len(), 0);
+        assert_eq!(w.background(), Color::new(0.0, 0.0, 0.0).into());
     }
 
-    fn default_world() -> World {
-        let mut world = World::new();
-        let light = PointLight::new(Point::new(-10.0, 10.0, -10.0), Color::new(1.0, 1.0, 1.0));
-        let mut s1 = Sphere::new();
-        let material = s1.material_mut();
-        material.color = Color::new(0.8, 1.0, 0.6);
-        material.diffuse = 0.7;
-        material.specular = 0.2;
-        let mut s2 = Sphere::new();
-        *s2.transformation() = Matrix::scaling(0.5, 0.5, 0.5);
-        world.light = Some(light);
-        world.objects.push(s1.into());
-        world.objects.push(s2.into());
-
-        world
+    #[test]
+    fn test_world_with() {
+        let light = PointLight::new(Point::new(0.0, 5.0, 0.0), Color::new(1.0, 1.0, 1.0));
+        let w = World::with(light.clone(), [Sphere::new(), Sphere::new()]);
+        assert_eq!(w.lights(), [light.into()]);
+        assert_eq!(w.objects.len(), 2);
     }
 
     #[test]
     fn test_default_world() {
         let w = default_world();
 
-        assert!(w.light.is_some());
         assert_eq!(w.objects.len(), 2);
 
         let light = PointLight::new(Point::new(-10.0, 10.0, -10.0), Color::new(1.0, 1.0, 1.0));
-        assert_eq!(Some(light), w.light);
+        assert_eq!(w.lights(), [light.into()]);
 
         let mut s1 = Sphere::new();
         let material = s1.material_mut();
         material.color = Color::new(0.8, 1.0, 0.6);
         material.diffuse = 0.7;
         material.specular = 0.2;
-        
+
         let mut s2 = Sphere::new();
-        *s2.transformation() = Matrix::scaling(0.5, 0.5, 0.5);
+        s2.set_transform(Matrix4::scaling(0.5, 0.5, 0.5));
 
         assert_eq!(<Sphere as Into<Shape>>::into(s1), w.objects[0]);
         assert_eq!(<Sphere as Into<Shape>>::into(s2), w.objects[1]);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_find_object() {
+        let mut w = default_world();
+        let mut floor: Shape = Sphere::with_transform(Matrix4::scaling(10.0, 0.01, 10.0)).into();
+        floor.set_name("floor");
+        w.add_object(floor);
+
+        let found = w.find_object("floor").expect("floor should be found");
+        assert_eq!(found.transformation(), &Matrix4::scaling(10.0, 0.01, 10.0));
+        assert!(w.find_object("ceiling").is_none());
+    }
+
+    #[test]
+    fn test_update_object_in_group() {
+        let mut ball: Shape = Sphere::new().into();
+        ball.set_name("ball");
+        let mut group = Group::new();
+        group.add_child(ball);
+        let mut w = World::new();
+        w.add_object(group);
+        w.set_acceleration(Acceleration::octree());
+
+        let r = Ray::new(Point::new(10.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        assert!(w.intersect(&r).is_empty());
+        let moved = w.update_object("ball", |ball| {
+            ball.set_transform(Matrix4::translation(10.0, 0.0, 0.0))
+        });
+        assert!(moved);
+        // The group's bounds and the world's index both follow the ball.
+        assert_eq!(w.intersect(&r).len(), 2);
+
+        assert!(!w.update_object("missing", |_| unreachable!()));
+    }
+
+    #[test]
+    fn test_intersect_world() {
+        let w = default_world();
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = w.intersect(&r);
+        assert_eq!(xs.len(), 4);
+
+        let mut ts: Vec<Float> = xs.into_iter().map(|i| i.t).collect();
+        ts.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(ts, vec![4.0, 4.5, 5.5, 6.0]);
+    }
+
+    #[test]
+    fn test_intersect_into_reused_buffer() {
+        let w = default_world();
+        let mut xs = Intersections::new();
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        w.intersect_into(&r, &mut xs);
+        assert_eq!(xs.len(), 4);
+
+        let r = Ray::new(Point::new(0.0, 0.9, -5.0), Vector::new(0.0, 0.0, 1.0));
+        w.intersect_into(&r, &mut xs);
+        assert_eq!(xs.len(), 2);
+        assert!(xs.iter().all(|i| i.shape == &w.objects[0]));
+
+        let r = Ray::new(Point::new(0.0, 5.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        w.intersect_into(&r, &mut xs);
+        assert!(xs.is_empty());
+    }
+
+    #[test]
+    fn test_color_at_with_reused_buffer() {
+        let mut w = default_world();
+        w.objects[0].material_mut().reflective = 0.5;
+        let mut xs = Intersections::new();
+        for y in [0.0, 0.5, 0.9, 5.0] {
+            let r = Ray::new(Point::new(0.0, y, -5.0), Vector::new(0.0, 0.0, 1.0));
+            assert_eq!(w.color_at_with(&r, &mut xs), w.color_at(&r));
+        }
+    }
+
+    #[test]
+    fn test_octree_acceleration() {
+        let mut w = World::new();
+        for i in 0..125 {
+            let (x, y, z) = ((i % 5) as Float, (i / 5 % 5) as Float, (i / 25) as Float);
+            w.add_object(Sphere::with_transform(
+                Matrix4::translation(x * 2.0, y * 2.0, z * 2.0) * Matrix4::scaling(0.5, 0.5, 0.5),
+            ));
+        }
+        let mut accelerated = w.clone();
+        accelerated.set_acceleration(Acceleration::octree());
+
+        let ts = |w: &World, r: &Ray| w.intersect(r).iter().map(|i| i.t).collect::<Vec<_>>();
+        for (origin, direction) in [
+            (Point::new(-5.0, 2.0, 4.0), Vector::new(1.0, 0.0, 0.0)),
+            (Point::new(-5.0, -5.0, -5.0), Vector::new(1.0, 1.0, 1.0)),
+            (Point::new(3.0, 20.0, 3.1), Vector::new(0.1, -1.0, 0.0)),
+            (Point::new(-5.0, 2.0, 4.0), Vector::new(-1.0, 0.0, 0.0)),
+        ] {
+            let r = Ray::new(origin, direction);
+            assert_eq!(ts(&accelerated, &r), ts(&w, &r));
+        }
+        assert_eq!(
+            accelerated
+                .intersect(&Ray::new(
+                    Point::new(-5.0, 2.0, 4.0),
+                    Vector::new(1.0, 0.0, 0.0)
+                ))
+                .len(),
+            10
+        );
+
+        // Adding an object rebuilds the index.
+        let r = Ray::new(Point::new(20.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        assert!(accelerated.intersect(&r).is_empty());
+        accelerated.add_object(Sphere::with_transform(Matrix4::translation(20.0, 0.0, 0.0)));
+        assert_eq!(accelerated.intersect(&r).len(), 2);
+    }
+
+    #[test]
+    fn test_object_at() {
+        let w = default_world();
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(w.object_at(&r), Some(0));
+
+        // Inside the outer sphere, the inner one is hit first.
+        let r = Ray::new(Point::new(0.0, 0.0, -0.75), Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(w.object_at(&r), Some(1));
+
+        let r = Ray::new(Point::new(0.0, 5.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(w.object_at(&r), None);
+    }
+
+    #[test]
+    fn test_shade_hit() {
+        let w = default_world();
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let shape = &w.objects[0];
+        let i = Intersection::new(4.0, shape);
+        let mut xs = Intersections::new();
+        xs.add(i.clone());
+        let comps = i.prepare_computations(&r, &xs);
+
+        assert_eq!(w.shade_hit(&comps, 1), Color::new(0.38066, 0.47583, 0.2855));
+    }
+
+    #[test]
+    fn test_shade_hit_skybox_ignores_lights() {
+        let mut sky: Shape = Sphere::with_transform(Matrix4::scaling(100.0, 100.0, 100.0)).into();
+        *sky.material_mut() = Material::skybox(Color::new(0.2, 0.4, 0.6));
+        let mut w = World::new();
+        w.add_object(sky);
+        let r = Ray::new(Point::origin(), Vector::new(0.0, 0.0, 1.0));
+
+        w.set_light(PointLight::new(Point::origin(), Color::new(1.0, 0.0, 0.5)));
+        assert_eq!(w.color_at(&r), Color::new(0.2, 0.4, 0.6));
+        w.add_light(PointLight::new(Point::origin(), Color::new(1.0, 1.0, 1.0)));
+        assert_eq!(w.color_at(&r), Color::new(0.2, 0.4, 0.6));
+    }
+
+    #[test]
+    fn test_shade_hit_from_inside() {
+        let mut w = default_world();
+        w.set_light(PointLight::new(
+            Point::new(0.0, 0.25, 0.0),
+            Color::new(1.0, 1.0, 1.0),
+        ));
+        let r = Ray::new(Point::origin(), Vector::new(0.0, 0.0, 1.0));
+        let shape = &w.objects[1];
+        let i = Intersection::new(0.5, shape);
+        let mut xs = Intersections::new();
+        xs.add(i.clone());
+        let comps = i.prepare_computations(&r, &xs);
+
+        assert_eq!(
+            w.shade_hit(&comps, 1),
+            Color::new(0.90498, 0.90498, 0.90498)
+        );
+    }
+
+    #[test]
+    fn test_color_at_miss() {
+        let w = default_world();
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 1.0, 0.0));
+        assert_eq!(w.color_at(&r), Color::new(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_color_at_miss_shows_background() {
+        let mut w = default_world();
+        w.set_background(Color::new(0.2, 0.4, 0.8));
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 1.0, 0.0));
+        assert_eq!(w.color_at(&r), Color::new(0.2, 0.4, 0.8));
+    }
+
+    #[test]
+    fn test_color_at_miss_shows_background_gradient() {
+        let mut w = default_world();
+        w.set_background(Background::Gradient {
+            bottom: Color::new(1.0, 1.0, 1.0),
+            top: Color::new(0.0, 0.0, 1.0),
+        });
+        let color_towards =
+            |x, y, z| w.color_at(&Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(x, y, z)));
+        assert_eq!(color_towards(0.0, 2.0, 0.0), Color::new(0.0, 0.0, 1.0));
+        assert_eq!(color_towards(0.0, -1.0, 0.0), Color::new(1.0, 1.0, 1.0));
+        assert_eq!(color_towards(0.0, 0.0, -1.0), Color::new(0.5, 0.5, 1.0));
+    }
+
+    #[test]
+    fn test_color_at_limits_bounces_to_max_depth() {
+        let mut w = default_world();
+        w.set_background(Color::new(0.0, 0.0, 1.0));
+        w.objects[0].material_mut().reflective = 0.5;
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(w.max_depth(), DEFAULT_MAX_DEPTH);
+        let reflecting = w.color_at(&r);
+
+        w.set_max_depth(0);
+        let unreflecting = w.color_at(&r);
+        assert_eq!(reflecting - unreflecting, Color::new(0.0, 0.0, 0.5));
+    }
+
+    #[test]
+    fn test_color_at_hit() {
+        let w = default_world();
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(w.color_at(&r), Color::new(0.38066, 0.47583, 0.2855));
+    }
+
+    #[test]
+    fn test_color_at_intersection_behind_ray() {
+        let mut w = default_world();
+        w.objects[0].material_mut().ambient = 1.0;
+        w.objects[1].material_mut().ambient = 1.0;
+        let r = Ray::new(Point::new(0.0, 0.0, 0.75), Vector::new(0.0, 0.0, -1.0));
+        assert_eq!(w.color_at(&r), w.objects[1].material().color);
+    }
+
+    #[test]
+    fn test_reflected_color_nonreflective() {
+        let mut w = default_world();
+        w.objects[1].material_mut().ambient = 1.0;
+        let r = Ray::new(Point::origin(), Vector::new(0.0, 0.0, 1.0));
+        let shape = &w.objects[1];
+        let i = Intersection::new(1.0, shape);
+        let mut xs = Intersections::new();
+        xs.add(i.clone());
+        let comps = i.prepare_computations(&r, &xs);
+
+        assert_eq!(w.reflected_color(&comps, 1), Color::new(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_refracted_color_opaque() {
+        let w = default_world();
+        let shape = &w.objects[0];
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let mut xs = Intersections::new();
+        xs.add(Intersection::new(4.0, shape));
+        xs.add(Intersection::new(6.0, shape));
+        let comps = Intersection::new(4.0, shape).prepare_computations(&r, &xs);
+
+        assert_eq!(w.refracted_color(&comps, 5), Color::new(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_refracted_color_max_depth() {
+        let mut w = default_world();
+        let material = w.objects[0].material_mut();
+        material.transparency = 1.0;
+        material.refractive_index = 1.5;
+        let shape = &w.objects[0];
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let mut xs = Intersections::new();
+        xs.add(Intersection::new(4.0, shape));
+        xs.add(Intersection::new(6.0, shape));
+        let comps = Intersection::new(4.0, shape).prepare_computations(&r, &xs);
+
+        assert_eq!(w.refracted_color(&comps, 0), Color::new(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_refracted_color_total_internal_reflection() {
+        let mut w = default_world();
+        let material = w.objects[0].material_mut();
+        material.transparency = 1.0;
+        material.refractive_index = 1.5;
+        let shape = &w.objects[0];
+        let hsq = (2.0 as Float).sqrt() / 2.0;
+        let r = Ray::new(Point::new(0.0, 0.0, hsq), Vector::new(0.0, 1.0, 0.0));
+        let mut xs = Intersections::new();
+        xs.add(Intersection::new(-hsq, shape));
+        xs.add(Intersection::new(hsq, shape));
+        // Inside the sphere, so the second intersection is the one that matters.
+        let comps = Intersection::new(hsq, shape).prepare_computations(&r, &xs);
+
+        assert_eq!(w.refracted_color(&comps, 5), Color::new(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_shade_hit_blends_with_schlick() {
+        let mut w = default_world();
+        // A wide quad standing in for the book's plane.
+        let mut floor = Quad::new(
+            Point::new(-50.0, 0.0, -50.0),
+            Vector::new(0.0, 0.0, 100.0),
+            Vector::new(100.0, 0.0, 0.0),
+        );
+        floor.set_transform(Matrix4::translation(0.0, -1.0, 0.0));
+        let material = floor.material_mut();
+        material.reflective = 0.5;
+        material.transparency = 0.5;
+        material.refractive_index = 1.5;
+        w.add_object(floor);
+        let mut ball = Sphere::with_transform(Matrix4::translation(0.0, -3.5, -0.5));
+        let material = ball.material_mut();
+        material.color = Color::new(1.0, 0.0, 0.0);
+        material.ambient = 0.5;
+        w.add_object(ball);
+
+        let hsq = (2.0 as Float).sqrt() / 2.0;
+        let r = Ray::new(Point::new(0.0, 0.0, -3.0), Vector::new(0.0, -hsq, hsq));
+        let mut xs = Intersections::new();
+        let i = Intersection::new((2.0 as Float).sqrt(), &w.objects[2]);
+        xs.add(i.clone());
+        let comps = i.prepare_computations(&r, &xs);
+
+        assert_eq!(
+            w.shade_hit(&comps, 5),
+            Color::new(0.93391, 0.69643, 0.69243)
+        );
+    }
+
+    #[test]
+    fn test_no_shadow_when_nothing_collinear() {
+        let w = default_world();
+        assert!(!w.is_shadowed(&Point::new(0.0, 10.0, 0.0)));
+    }
+
+    #[test]
+    fn test_shadow_when_object_between_point_and_light() {
+        let w = default_world();
+        assert!(w.is_shadowed(&Point::new(10.0, -10.0, 10.0)));
+    }
+
+    #[test]
+    fn test_no_shadow_when_object_behind_light() {
+        let w = default_world();
+        assert!(!w.is_shadowed(&Point::new(-20.0, 20.0, -20.0)));
+    }
+
+    #[test]
+    fn test_no_shadow_when_object_behind_point() {
+        let w = default_world();
+        assert!(!w.is_shadowed(&Point::new(-2.0, 2.0, -2.0)));
+    }
+
+    #[test]
+    fn test_no_shadow_from_objects_that_cast_none() {
+        let mut w = default_world();
+        let p = Point::new(10.0, -10.0, 10.0);
+        w.objects[0].set_casts_shadow(false);
+        assert!(w.is_shadowed(&p));
+        w.objects[1].set_casts_shadow(false);
+        assert!(!w.is_shadowed(&p));
+    }
+
+    #[test]
+    fn test_shade_hit_ignores_shadow_on_objects_that_receive_none() {
+        let mut w = World::new();
+        w.set_light(PointLight::new(
+            Point::new(0.0, 0.0, -10.0),
+            Color::new(1.0, 1.0, 1.0),
+        ));
+        w.add_object(Sphere::new());
+        let mut backdrop: Shape =
+            Sphere::with_transform(Matrix4::translation(0.0, 0.0, 10.0)).into();
+        backdrop.set_receives_shadow(false);
+        w.add_object(backdrop);
+
+        let r = Ray::new(Point::new(0.0, 0.0, 5.0), Vector::new(0.0, 0.0, 1.0));
+        let i = Intersection::new(4.0, &w.objects[1]);
+        let mut xs = Intersections::new();
+        xs.add(i.clone());
+        let comps = i.prepare_computations(&r, &xs);
+        assert_eq!(w.shade_hit(&comps, 5), Color::new(1.9, 1.9, 1.9));
+    }
+
+    #[test]
+    fn test_shade_hit_with_linked_lights() {
+        let mut w = World::new();
+        let key = w.add_light(PointLight::new(
+            Point::new(0.0, 0.0, -10.0),
+            Color::new(1.0, 1.0, 1.0),
+        ));
+        let fill = w.add_light(PointLight::new(
+            Point::new(0.0, 0.0, -10.0),
+            Color::new(0.5, 0.5, 0.5),
+        ));
+        assert_eq!((key, fill), (0, 1));
+        w.add_object(Sphere::new());
+
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let color = |w: &World| {
+            let xs = w.intersect(&r);
+            w.shade_hit(&xs.hit().unwrap().prepare_computations(&r, &xs), 5)
+        };
+        let both = color(&w);
+        w.objects[0].set_light_mask(1 << key);
+        let key_only = color(&w);
+        w.objects[0].set_light_mask(1 << fill);
+        let fill_only = color(&w);
+
+        assert_eq!(key_only, Color::new(1.9, 1.9, 1.9));
+        assert_eq!(fill_only, Color::new(0.95, 0.95, 0.95));
+        assert_eq!(both, key_only + fill_only);
+    }
+
+    #[test]
+    fn test_shade_hit_in_shadow() {
+        let mut w = World::new();
+        w.set_light(PointLight::new(
+            Point::new(0.0, 0.0, -10.0),
+            Color::new(1.0, 1.0, 1.0),
+        ));
+        w.add_object(Sphere::new());
+        w.add_object(Sphere::with_transform(Matrix4::translation(0.0, 0.0, 10.0)));
+
+        let r = Ray::new(Point::new(0.0, 0.0, 5.0), Vector::new(0.0, 0.0, 1.0));
+        let i = Intersection::new(4.0, &w.objects[1]);
+        let mut xs = Intersections::new();
+        xs.add(i.clone());
+        let comps = i.prepare_computations(&r, &xs);
+        assert_eq!(w.shade_hit(&comps, 5), Color::new(0.1, 0.1, 0.1));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_world_json_round_trip() {
+        let w = default_world();
+        let json = serde_json::to_string(&w).unwrap();
+        let restored: World = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, w);
+
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(restored.color_at(&r), w.color_at(&r));
+    }
+
+    #[test]
+    fn test_area_light_casts_soft_shadows() {
+        let mut w = World::new();
+        w.set_light(AreaLight::new(
+            Point::new(-1.0, 5.0, -1.0),
+            Vector::new(2.0, 0.0, 0.0),
+            Vector::new(0.0, 0.0, 2.0),
+            Color::new(1.0, 1.0, 1.0),
+        ));
+        w.add_object(Quad::new(
+            Point::new(-5.0, 0.0, -5.0),
+            Vector::new(10.0, 0.0, 0.0),
+            Vector::new(0.0, 0.0, 10.0),
+        ));
+        let mut ball = Sphere::with_transform(Matrix4::translation(0.0, 1.5, 0.0));
+        ball.material_mut().color = Color::new(0.0, 0.0, 0.0);
+        w.add_object(ball);
+
+        let floor_at = |x: Float| {
+            let r = Ray::new(Point::new(x, 0.1, 0.0), Vector::new(0.0, -1.0, 0.0));
+            w.color_at(&r).red()
+        };
+        // Right under the ball no part of the light is visible, but beside
+        // it the shadow fades out gradually.
+        assert_approx_eq!(floor_at(0.0), 0.1);
+        let penumbra = [0.9, 1.2, 1.5, 2.0].map(floor_at);
+        assert_approx_eq!(penumbra[0], 0.1);
+        assert!(penumbra.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(penumbra[3] > 0.8);
+    }
+}